@@ -0,0 +1,7 @@
+import Guestbook from '@benchmark/shared/components/Guestbook'
+
+export const dynamic = 'force-dynamic'
+
+export default function GuestbookPage() {
+  return <Guestbook framework="Next.js" />
+}
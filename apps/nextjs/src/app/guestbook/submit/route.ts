@@ -0,0 +1,12 @@
+import { addGuestbookEntry } from '@benchmark/shared/functions'
+
+export async function POST(request: Request) {
+  const form = await request.formData()
+  const entry = form.get('entry')
+  if (typeof entry !== 'string' || entry.trim() === '')
+    return new Response('entry is required', { status: 400 })
+
+  await addGuestbookEntry(entry.trim())
+
+  return Response.redirect(new URL('/guestbook', request.url), 303)
+}
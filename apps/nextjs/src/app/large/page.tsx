@@ -0,0 +1,7 @@
+import LargePayload from '@benchmark/shared/components/LargePayload'
+
+export const revalidate = false
+
+export default function Large() {
+  return <LargePayload framework="Next.js" />
+}
@@ -0,0 +1,5 @@
+import Guestbook from '@benchmark/shared/components/Guestbook'
+
+export default function GuestbookPage() {
+  return <Guestbook framework="rari" />
+}
@@ -0,0 +1,5 @@
+import LargePayload from '@benchmark/shared/components/LargePayload'
+
+export default function Large() {
+  return <LargePayload framework="rari" />
+}
@@ -0,0 +1,36 @@
+import { getGuestbookEntries } from '../functions'
+
+// Posts to the app's /guestbook/submit route, which redirects back here.
+export default async function Guestbook({ framework }: { framework: string }) {
+  const entries = await getGuestbookEntries()
+
+  return (
+    <div className="max-w-2xl mx-auto py-8 px-4 space-y-6" data-component-id="guestbook">
+      <h1 className="text-2xl font-bold text-gray-900">
+        {framework}
+        {' '}
+        Guestbook
+      </h1>
+
+      <form method="post" action="/guestbook/submit" className="flex gap-2">
+        <input
+          name="entry"
+          required
+          maxLength={200}
+          className="flex-1 px-3 py-2 border border-gray-300 rounded"
+        />
+        <button type="submit" className="px-4 py-2 bg-blue-600 text-white rounded">
+          Sign
+        </button>
+      </form>
+
+      <ul className="space-y-2">
+        {entries.map(entry => (
+          <li key={entry.id} className="px-4 py-2 border-b border-gray-200 text-gray-800">
+            {entry.text}
+          </li>
+        ))}
+      </ul>
+    </div>
+  )
+}
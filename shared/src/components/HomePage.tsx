@@ -1,6 +1,7 @@
 import Counter from './Counter'
 import EnvTestComponent from './EnvTestComponent'
 import FetchExample from './FetchExample'
+import HydrationMarker from './HydrationMarker'
 import Markdown from './Markdown'
 import ServerWithClient from './ServerWithClient'
 import ShoppingList from './ShoppingList'
@@ -144,6 +145,7 @@ export default function HomePage({ framework }: HomePageProps) {
           </div>
         </div>
       </div>
+      <HydrationMarker />
     </div>
   )
 }
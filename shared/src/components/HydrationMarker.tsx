@@ -0,0 +1,12 @@
+'use client'
+
+import { useEffect } from 'react'
+
+// The browser benchmark reads this mark as the moment hydration completed.
+export default function HydrationMarker() {
+  useEffect(() => {
+    performance.mark('hydrated')
+  }, [])
+
+  return null
+}
@@ -0,0 +1,30 @@
+// Roughly 5 MB of HTML for the large-payload benchmark scenario.
+const ROWS = 20000
+
+function describe(index: number) {
+  return `Item ${index} of ${ROWS}: ${(index * 2654435761 % 4294967296).toString(16).padStart(8, '0')} `.repeat(4)
+}
+
+export default function LargePayload({ framework }: { framework: string }) {
+  const rows = Array.from({ length: ROWS }, (_, index) => index)
+
+  return (
+    <div className="max-w-6xl mx-auto py-8 px-4">
+      <h1 className="text-2xl font-bold text-gray-900 mb-4">
+        {framework}
+        {' '}
+        Large Payload
+      </h1>
+      <table className="w-full text-xs text-gray-700">
+        <tbody>
+          {rows.map(index => (
+            <tr key={index} className="border-b border-gray-100">
+              <td className="px-2 py-1 font-mono">{index}</td>
+              <td className="px-2 py-1">{describe(index)}</td>
+            </tr>
+          ))}
+        </tbody>
+      </table>
+    </div>
+  )
+}
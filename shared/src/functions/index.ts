@@ -23,3 +23,32 @@ export async function add(a: number, b: number): Promise<number> {
 
   return result
 }
+
+export interface GuestbookEntry {
+  id: number
+  text: string
+}
+
+// In memory on purpose: the mutation benchmark measures the framework's
+// write path, not a database. Only the latest entries are kept so the page
+// renders the same amount of work however long the benchmark runs. The list
+// lives on globalThis because a route handler and a page may be bundled with
+// separate copies of this module.
+const GUESTBOOK_LIMIT = 20
+const store = globalThis as typeof globalThis & {
+  __benchmarkGuestbook?: { entries: GuestbookEntry[], nextId: number }
+}
+store.__benchmarkGuestbook ??= { entries: [], nextId: 1 }
+const guestbook = store.__benchmarkGuestbook
+
+export async function getGuestbookEntries(): Promise<GuestbookEntry[]> {
+  return [...guestbook.entries]
+}
+
+export async function addGuestbookEntry(text: string): Promise<GuestbookEntry> {
+  const entry = { id: guestbook.nextId++, text: text.slice(0, 200) }
+  guestbook.entries.unshift(entry)
+  guestbook.entries.length = Math.min(guestbook.entries.length, GUESTBOOK_LIMIT)
+
+  return entry
+}
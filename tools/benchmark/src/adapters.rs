@@ -0,0 +1,158 @@
+use crate::targets::Target;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Commands of a target that names no framework and sets none itself.
+pub const DEFAULT_START: &str = "pnpm start";
+pub const DEFAULT_DEV: &str = "pnpm dev";
+pub const DEFAULT_BUILD: &str = "pnpm run build";
+
+/// What the benchmarks know about one framework. A target picks an adapter
+/// with `framework` in the targets file and takes from it every command and
+/// path it does not set itself; the built-in targets are the adapters'
+/// defaults.
+pub trait FrameworkAdapter: Sync {
+    /// The `framework` value in a targets file, and the built-in target's name.
+    fn key(&self) -> &'static str;
+
+    fn label(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn icon(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// The app directory of the built-in target, relative to the apps dir.
+    fn dir(&self) -> PathBuf;
+
+    /// The port of the built-in target.
+    fn port(&self) -> u16;
+
+    fn start_command(&self) -> &'static str {
+        DEFAULT_START
+    }
+
+    fn dev_command(&self) -> &'static str {
+        DEFAULT_DEV
+    }
+
+    fn build_command(&self) -> &'static str {
+        DEFAULT_BUILD
+    }
+
+    /// Where a production build puts its client chunks, relative to the app
+    /// directory. It is missing until the app has been built.
+    fn bundle_dir(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// The path that answers once the server is ready.
+    fn health_path(&self) -> &'static str {
+        "/"
+    }
+
+    /// The npm package an app depends on to use the framework.
+    fn package(&self) -> &'static str;
+
+    /// The framework's version among an app's resolved dependencies.
+    fn version<'a>(&self, dependencies: &'a BTreeMap<String, String>) -> Option<&'a String> {
+        dependencies.get(self.package())
+    }
+
+    /// The built-in target for this framework.
+    fn target(&self) -> Target {
+        Target {
+            name: self.key().to_string(),
+            label: self.label().map(str::to_string),
+            icon: self.icon().map(str::to_string),
+            framework: Some(self.key().to_string()),
+            dir: self.dir(),
+            port: self.port(),
+            start: self.start_command().to_string(),
+            dev: self.dev_command().to_string(),
+            build: self.build_command().to_string(),
+            bundle_dir: self.bundle_dir(),
+            health_path: Some(self.health_path().to_string()),
+            compose_service: None,
+            env: BTreeMap::new(),
+            cpus: None,
+            nice: None,
+            url: None,
+            dockerfile: None,
+            image: None,
+            assets: BTreeMap::new(),
+        }
+    }
+}
+
+pub struct RariAdapter;
+
+impl FrameworkAdapter for RariAdapter {
+    fn key(&self) -> &'static str {
+        "rari"
+    }
+
+    fn icon(&self) -> Option<&'static str> {
+        Some("🦀")
+    }
+
+    fn dir(&self) -> PathBuf {
+        PathBuf::from("apps/rari")
+    }
+
+    fn port(&self) -> u16 {
+        3000
+    }
+
+    fn bundle_dir(&self) -> Option<PathBuf> {
+        Some(PathBuf::from("dist/assets"))
+    }
+
+    fn package(&self) -> &'static str {
+        "rari"
+    }
+}
+
+pub struct NextJsAdapter;
+
+impl FrameworkAdapter for NextJsAdapter {
+    fn key(&self) -> &'static str {
+        "nextjs"
+    }
+
+    fn label(&self) -> Option<&'static str> {
+        Some("Next.js")
+    }
+
+    fn icon(&self) -> Option<&'static str> {
+        Some("🟢")
+    }
+
+    fn dir(&self) -> PathBuf {
+        PathBuf::from("apps/nextjs")
+    }
+
+    fn port(&self) -> u16 {
+        3001
+    }
+
+    fn bundle_dir(&self) -> Option<PathBuf> {
+        Some(PathBuf::from(".next/static/chunks"))
+    }
+
+    fn package(&self) -> &'static str {
+        "next"
+    }
+}
+
+/// Every known framework, in the order the built-in targets are compared.
+pub const ADAPTERS: [&dyn FrameworkAdapter; 2] = [&RariAdapter, &NextJsAdapter];
+
+pub fn find(key: &str) -> Option<&'static dyn FrameworkAdapter> {
+    ADAPTERS.into_iter().find(|adapter| adapter.key() == key)
+}
+
+pub fn keys() -> Vec<&'static str> {
+    ADAPTERS.iter().map(|adapter| adapter.key()).collect()
+}
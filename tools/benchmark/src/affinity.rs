@@ -0,0 +1,171 @@
+use crate::targets::Target;
+use anyhow::{Context, Result};
+
+/// Parses a CPU list such as `0-3,6` into sorted, de-duplicated CPU indices.
+pub fn parse_cpus(spec: &str) -> Result<Vec<usize>> {
+    let mut cpus = Vec::new();
+    for part in spec
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+    {
+        let (start, end) = part.split_once('-').unwrap_or((part, part));
+        let start: usize = start
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid CPU list '{}'", spec))?;
+        let end: usize = end
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid CPU list '{}'", spec))?;
+        if start > end {
+            anyhow::bail!("Invalid CPU range '{}' in '{}'", part, spec);
+        }
+        cpus.extend(start..=end);
+    }
+    if cpus.is_empty() {
+        anyhow::bail!("Empty CPU list '{}'", spec);
+    }
+
+    cpus.sort_unstable();
+    cpus.dedup();
+    #[cfg(target_os = "linux")]
+    if let Some(cpu) = cpus.iter().find(|&&cpu| !allowed(cpu)) {
+        anyhow::bail!("CPU {} in '{}' is not available on this machine", cpu, spec);
+    }
+    Ok(cpus)
+}
+
+/// Whether this process may run on `cpu`, i.e. it exists and is not excluded
+/// by a cgroup or an outer `taskset`.
+#[cfg(target_os = "linux")]
+fn allowed(cpu: usize) -> bool {
+    if cpu >= libc::CPU_SETSIZE as usize {
+        return false;
+    }
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    if unsafe { libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) } != 0
+    {
+        return true;
+    }
+    unsafe { libc::CPU_ISSET(cpu, &set) }
+}
+
+#[cfg(target_os = "linux")]
+fn cpu_set(cpus: &[usize]) -> libc::cpu_set_t {
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    for &cpu in cpus {
+        unsafe { libc::CPU_SET(cpu, &mut set) };
+    }
+    set
+}
+
+/// Makes `command` run on `cpus` (if any) with the given nice value. Both are
+/// inherited by everything the command starts.
+#[cfg(unix)]
+pub fn apply(command: &mut std::process::Command, cpus: &[usize], nice: Option<i32>) -> Result<()> {
+    use std::os::unix::process::CommandExt;
+
+    if cpus.is_empty() && nice.is_none() {
+        return Ok(());
+    }
+
+    #[cfg(target_os = "linux")]
+    let set = (!cpus.is_empty()).then(|| cpu_set(cpus));
+    #[cfg(not(target_os = "linux"))]
+    if !cpus.is_empty() {
+        anyhow::bail!("CPU pinning is only supported on Linux");
+    }
+
+    // Only async-signal-safe calls between fork and exec.
+    unsafe {
+        command.pre_exec(move || {
+            #[cfg(target_os = "linux")]
+            if let Some(set) = &set
+                && libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), set) != 0
+            {
+                return Err(std::io::Error::last_os_error());
+            }
+            if let Some(nice) = nice
+                && libc::setpriority(libc::PRIO_PROCESS, 0, nice) != 0
+            {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn apply(
+    _command: &mut std::process::Command,
+    cpus: &[usize],
+    nice: Option<i32>,
+) -> Result<()> {
+    if !cpus.is_empty() || nice.is_some() {
+        anyhow::bail!("CPU pinning and nice values are only supported on Unix");
+    }
+    Ok(())
+}
+
+/// Pins every thread of the current process to `cpus`; threads started later
+/// inherit the mask from the thread that spawns them.
+#[cfg(target_os = "linux")]
+pub fn pin_current_process(cpus: &[usize]) -> Result<()> {
+    let set = cpu_set(cpus);
+    let tasks = std::fs::read_dir("/proc/self/task").context("Failed to list threads")?;
+    for task in tasks.flatten() {
+        let Some(tid) = task
+            .file_name()
+            .to_str()
+            .and_then(|tid| tid.parse::<libc::pid_t>().ok())
+        else {
+            continue;
+        };
+        if unsafe { libc::sched_setaffinity(tid, std::mem::size_of::<libc::cpu_set_t>(), &set) }
+            != 0
+        {
+            return Err(std::io::Error::last_os_error()).context("Failed to set CPU affinity");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn pin_current_process(_cpus: &[usize]) -> Result<()> {
+    anyhow::bail!("CPU pinning is only supported on Linux")
+}
+
+/// Parses `--client-cpus` and warns about CPUs the load generator would
+/// share with a pinned server.
+pub fn client_cpus(spec: Option<&str>, targets: &[Target]) -> Result<Vec<usize>> {
+    let Some(spec) = spec else {
+        return Ok(Vec::new());
+    };
+    let client = parse_cpus(spec).context("--client-cpus")?;
+
+    for target in targets {
+        let Some(server_cpus) = target
+            .cpus
+            .as_deref()
+            .and_then(|spec| parse_cpus(spec).ok())
+        else {
+            continue;
+        };
+        let shared: Vec<String> = client
+            .iter()
+            .filter(|cpu| server_cpus.contains(cpu))
+            .map(usize::to_string)
+            .collect();
+        if !shared.is_empty() {
+            tracing::warn!(
+                "The load generator shares CPUs {} with {}",
+                shared.join(","),
+                target.label()
+            );
+        }
+    }
+
+    Ok(client)
+}
@@ -0,0 +1,102 @@
+use crate::config;
+use crate::errors::{Error, ErrorKind};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// How many times the target a response may take before it frustrates, as
+/// the Apdex specification sets it.
+const TOLERATING_FACTOR: f64 = 4.0;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ApdexArgs {
+    #[arg(
+        long,
+        value_name = "MS",
+        default_value_t = 100.0,
+        value_parser = config::positive,
+        help = "Apdex target: responses this fast satisfy users"
+    )]
+    pub apdex_target: f64,
+    #[arg(
+        long,
+        value_name = "MS",
+        value_parser = config::positive,
+        help = "Responses up to this slow are tolerated; slower ones, and errors, frustrate [default: 4 × --apdex-target]"
+    )]
+    pub apdex_tolerating: Option<f64>,
+}
+
+impl ApdexArgs {
+    pub fn tolerating(&self) -> f64 {
+        self.apdex_tolerating
+            .unwrap_or(self.apdex_target * TOLERATING_FACTOR)
+    }
+
+    /// Fails if the tolerated time is below the target, where no request could
+    /// be tolerated.
+    pub fn validate(&self) -> Result<()> {
+        if self.tolerating() < self.apdex_target {
+            return Err(Error::new(
+                ErrorKind::InvalidConfig,
+                format!(
+                    "--apdex-tolerating {} is below --apdex-target {}",
+                    self.tolerating(),
+                    self.apdex_target
+                ),
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// The score of `times` with `errors` failed requests on top, or `None`
+    /// for no requests at all.
+    pub fn score(&self, times: &[f64], errors: usize) -> Option<Apdex> {
+        let satisfied = times
+            .iter()
+            .filter(|&&time| time <= self.apdex_target)
+            .count();
+        let tolerating = times
+            .iter()
+            .filter(|&&time| time > self.apdex_target && time <= self.tolerating())
+            .count();
+        let frustrated = times.len() - satisfied - tolerating + errors;
+        let total = times.len() + errors;
+        (total > 0).then(|| Apdex {
+            score: (satisfied as f64 + tolerating as f64 / 2.0) / total as f64,
+            satisfied,
+            tolerating,
+            frustrated,
+        })
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "T = {}ms, tolerating up to {}ms",
+            self.apdex_target,
+            self.tolerating()
+        )
+    }
+}
+
+/// Satisfied requests plus half the tolerated ones, over all requests.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Apdex {
+    pub score: f64,
+    pub satisfied: usize,
+    pub tolerating: usize,
+    pub frustrated: usize,
+}
+
+impl Apdex {
+    /// The specification's rating for the score.
+    pub fn rating(&self) -> &'static str {
+        match self.score {
+            score if score >= 0.94 => "excellent",
+            score if score >= 0.85 => "good",
+            score if score >= 0.7 => "fair",
+            score if score >= 0.5 => "poor",
+            _ => "unacceptable",
+        }
+    }
+}
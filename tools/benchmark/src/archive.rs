@@ -0,0 +1,233 @@
+use crate::metrics::{self, ResultKind};
+use crate::run_metadata::RunMetadata;
+use crate::schema;
+use crate::time_utils;
+use crate::{charts, cpu_prof, flamegraph, outliers};
+use anyhow::{Result, bail};
+use colored::Colorize;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+pub const DEFAULT_NAME_TEMPLATE: &str = "{kind}-{date}";
+
+const PLACEHOLDERS: [&str; 6] = ["kind", "date", "time", "timestamp", "label", "sha"];
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ArchiveArgs {
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        default_value = DEFAULT_NAME_TEMPLATE,
+        help = "Result file name template ({kind}, {date}, {time}, {timestamp}, {label}, {sha})"
+    )]
+    pub name_template: String,
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Keep only the N most recent result files of this benchmark"
+    )]
+    pub keep: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexEntry {
+    file: String,
+    kind: &'static str,
+    timestamp: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    git_sha: Option<String>,
+}
+
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+fn placeholder_value(
+    name: &str,
+    kind: ResultKind,
+    timestamp: &str,
+    metadata: &RunMetadata,
+) -> Option<String> {
+    Some(match name {
+        "kind" => kind.prefix().to_string(),
+        "date" => timestamp.get(..10).unwrap_or(timestamp).to_string(),
+        "time" => timestamp.get(11..19).unwrap_or_default().replace(':', ""),
+        // Whole seconds, so names look the same with or without milliseconds.
+        "timestamp" => match time_utils::parse_timestamp(timestamp) {
+            Some(time) => time.format("%Y-%m-%dT%H-%M-%SZ").to_string(),
+            None => timestamp.replace(':', "-"),
+        },
+        "label" => metadata
+            .label
+            .as_deref()
+            .map_or("unlabeled".to_string(), sanitize),
+        "sha" => metadata
+            .git_sha
+            .as_deref()
+            .map_or("nogit".to_string(), |sha| {
+                sha[..sha.len().min(8)].to_string()
+            }),
+        _ => return None,
+    })
+}
+
+fn expand(template: &str, mut value_of: impl FnMut(&str) -> Option<String>) -> Result<String> {
+    let mut name = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        name.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('}') else {
+            bail!("Unterminated placeholder in name template '{}'", template);
+        };
+        let placeholder = &rest[start + 1..start + end];
+        match value_of(placeholder) {
+            Some(value) => name.push_str(&value),
+            None => bail!(
+                "Unknown placeholder '{{{}}}' in name template (expected one of {})",
+                placeholder,
+                PLACEHOLDERS.map(|p| format!("{{{}}}", p)).join(", ")
+            ),
+        }
+        rest = &rest[start + end + 1..];
+    }
+    name.push_str(rest);
+
+    if name.is_empty() || name.contains('/') || name.contains('\\') {
+        bail!(
+            "Name template '{}' must produce a plain file name",
+            template
+        );
+    }
+
+    Ok(format!("{}.json", name))
+}
+
+pub fn validate_template(template: &str) -> Result<()> {
+    expand(template, |name| {
+        PLACEHOLDERS.contains(&name).then(String::new)
+    })
+    .map(|_| ())
+}
+
+pub fn file_name(
+    template: &str,
+    kind: ResultKind,
+    timestamp: &str,
+    metadata: &RunMetadata,
+) -> Result<String> {
+    expand(template, |name| {
+        placeholder_value(name, kind, timestamp, metadata)
+    })
+}
+
+/// The files a run may have written next to its results file at `path`,
+/// by their exact names: matching on the stem alone would take in another
+/// run's files whenever one name template's output starts with another's.
+pub async fn artifacts(path: &Path) -> Result<Vec<PathBuf>> {
+    let json = schema::read_json(path).await?;
+    let mut files: Vec<PathBuf> = charts::SUFFIXES
+        .iter()
+        .map(|suffix| charts::chart_path(path, suffix))
+        .collect();
+    files.push(outliers::path(path));
+    for target in metrics::target_names(&json) {
+        files.push(flamegraph::path(path, &target));
+        files.push(cpu_prof::path(path, &target));
+    }
+
+    let mut existing = Vec::new();
+    for file in files {
+        if fs::try_exists(&file).await? {
+            existing.push(file);
+        }
+    }
+    Ok(existing)
+}
+
+async fn remove_run(path: &Path) -> Result<()> {
+    for artifact in artifacts(path).await? {
+        fs::remove_file(&artifact).await?;
+    }
+    fs::remove_file(path).await?;
+    Ok(())
+}
+
+async fn prune(results_dir: &Path, kind: ResultKind, keep: usize) -> Result<Vec<PathBuf>> {
+    let runs: Vec<PathBuf> = metrics::scan_results(results_dir)
+        .await?
+        .into_iter()
+        .filter(|r| r.kind == kind)
+        .map(|r| r.path)
+        .collect();
+
+    let excess = runs.len().saturating_sub(keep);
+    let removed: Vec<PathBuf> = runs.into_iter().take(excess).collect();
+    for path in &removed {
+        remove_run(path).await?;
+    }
+
+    Ok(removed)
+}
+
+async fn index_entry(result: &metrics::ResultFile) -> Result<IndexEntry> {
+    let json = schema::read_json(&result.path).await?;
+    let metadata = &json["metadata"];
+
+    Ok(IndexEntry {
+        file: result
+            .path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        kind: result.kind.prefix(),
+        timestamp: result.timestamp.clone(),
+        label: metadata["label"].as_str().map(str::to_string),
+        git_sha: metadata["gitSha"].as_str().map(str::to_string),
+    })
+}
+
+pub async fn write_index(results_dir: &Path) -> Result<PathBuf> {
+    let mut entries = Vec::new();
+    for result in metrics::scan_results(results_dir).await? {
+        entries.push(index_entry(&result).await?);
+    }
+
+    let path = results_dir.join("index.json");
+    let json = format!("{}\n", serde_json::to_string_pretty(&entries)?);
+    fs::write(&path, json).await?;
+
+    Ok(path)
+}
+
+pub async fn finalize(results_dir: &Path, kind: ResultKind, args: &ArchiveArgs) -> Result<()> {
+    if let Some(keep) = args.keep {
+        let removed = prune(results_dir, kind, keep as usize).await?;
+        if !removed.is_empty() {
+            println!(
+                "{} Removed {} old {} result file(s) (keeping {})",
+                "🧹".dimmed(),
+                removed.len(),
+                kind.title(),
+                keep
+            );
+        }
+    }
+
+    write_index(results_dir).await?;
+    Ok(())
+}
@@ -0,0 +1,226 @@
+use crate::targets::Target;
+use crate::tolerance;
+use crate::units;
+use colored::Colorize;
+use std::collections::{BTreeMap, HashMap};
+use tabled::builder::Builder;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AssetKind {
+    Js,
+    Css,
+    Image,
+}
+
+impl AssetKind {
+    pub const ALL: [AssetKind; 3] = [AssetKind::Js, AssetKind::Css, AssetKind::Image];
+
+    /// Key of the kind in a target's `assets`.
+    pub fn key(self) -> &'static str {
+        match self {
+            AssetKind::Js => "js",
+            AssetKind::Css => "css",
+            AssetKind::Image => "image",
+        }
+    }
+
+    pub fn scenario(self) -> &'static str {
+        match self {
+            AssetKind::Js => "Largest JS chunk",
+            AssetKind::Css => "Stylesheet",
+            AssetKind::Image => "Image",
+        }
+    }
+}
+
+/// Start tags in `html` as their lowercased name and attributes. Good enough
+/// for the `<script>`, `<link>`, and `<img>` tags of server-rendered pages.
+pub fn start_tags(html: &str) -> Vec<(String, HashMap<String, String>)> {
+    let bytes = html.as_bytes();
+    let mut tags = Vec::new();
+    let mut i = 0;
+    while let Some(offset) = html[i..].find('<') {
+        i += offset + 1;
+        let name_end = html[i..]
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .map_or(html.len(), |end| i + end);
+        let name = html[i..name_end].to_ascii_lowercase();
+        i = name_end;
+        if name.is_empty() {
+            continue;
+        }
+
+        let mut attributes = HashMap::new();
+        loop {
+            while i < bytes.len() && (bytes[i].is_ascii_whitespace() || bytes[i] == b'/') {
+                i += 1;
+            }
+            if i >= bytes.len() || bytes[i] == b'>' {
+                break;
+            }
+            let key_start = i;
+            while i < bytes.len() && !matches!(bytes[i], b'=' | b'>' | b'/') {
+                if bytes[i].is_ascii_whitespace() {
+                    break;
+                }
+                i += 1;
+            }
+            let key = html[key_start..i].to_ascii_lowercase();
+            let mut value = String::new();
+            if i < bytes.len() && bytes[i] == b'=' {
+                i += 1;
+                if i < bytes.len() && matches!(bytes[i], b'"' | b'\'') {
+                    let quote = bytes[i] as char;
+                    i += 1;
+                    let end = html[i..].find(quote).map_or(html.len(), |end| i + end);
+                    value = html[i..end].to_string();
+                    i = (end + 1).min(html.len());
+                } else {
+                    let start = i;
+                    while i < bytes.len() && !bytes[i].is_ascii_whitespace() && bytes[i] != b'>' {
+                        i += 1;
+                    }
+                    value = html[start..i].to_string();
+                }
+            }
+            if key.is_empty() {
+                i += 1;
+            } else {
+                attributes.insert(key, value);
+            }
+        }
+        tags.push((name, attributes));
+    }
+    tags
+}
+
+/// The path of `reference` when it points at the target itself.
+fn local_path(reference: &str, base_url: &str) -> Option<String> {
+    let reference = reference.replace("&amp;", "&");
+    if let Some(rest) = reference.strip_prefix(base_url) {
+        return Some(format!("/{}", rest.trim_start_matches('/')));
+    }
+    (reference.starts_with('/') && !reference.starts_with("//")).then_some(reference)
+}
+
+/// Candidate paths of each kind referenced by a page, in page order.
+fn candidates(html: &str, base_url: &str) -> BTreeMap<AssetKind, Vec<String>> {
+    let mut found: BTreeMap<AssetKind, Vec<String>> = BTreeMap::new();
+    for (name, attributes) in start_tags(html) {
+        let attribute = |key: &str| attributes.get(key).map(String::as_str).unwrap_or("");
+        let rel = attribute("rel").to_ascii_lowercase();
+        let (kind, reference) = match name.as_str() {
+            "script" => (AssetKind::Js, attribute("src")),
+            "link"
+                if rel == "modulepreload" || (rel == "preload" && attribute("as") == "script") =>
+            {
+                (AssetKind::Js, attribute("href"))
+            }
+            "link" if rel == "stylesheet" => (AssetKind::Css, attribute("href")),
+            "link" if rel.split_whitespace().any(|rel| rel == "icon") => {
+                (AssetKind::Image, attribute("href"))
+            }
+            "img" => (AssetKind::Image, attribute("src")),
+            _ => continue,
+        };
+        if let Some(path) = local_path(reference, base_url) {
+            let paths = found.entry(kind).or_default();
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+    }
+    found
+        .entry(AssetKind::Image)
+        .or_default()
+        .push("/favicon.ico".to_string());
+    found
+}
+
+/// Size of the file at `path`, or `None` when it cannot be fetched.
+async fn size(client: &reqwest::Client, base_url: &str, path: &str) -> Option<usize> {
+    let response = client
+        .get(format!("{}{}", base_url, path))
+        .send()
+        .await
+        .ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    Some(response.bytes().await.ok()?.len())
+}
+
+/// Picks one static file of each kind for `target`: the largest script the
+/// homepage loads, its first stylesheet, and its first image (or
+/// `/favicon.ico`). Paths in the target's `assets` take precedence.
+pub async fn discover(client: &reqwest::Client, target: &Target) -> BTreeMap<AssetKind, String> {
+    let base_url = target.base_url();
+    let html = match client.get(format!("{}/", base_url)).send().await {
+        Ok(response) => response.text().await.unwrap_or_default(),
+        Err(_) => String::new(),
+    };
+    let found = candidates(&html, &base_url);
+
+    let mut assets = BTreeMap::new();
+    for kind in AssetKind::ALL {
+        if let Some(path) = target.assets.get(kind.key()) {
+            assets.insert(kind, path.clone());
+            continue;
+        }
+        let mut best: Option<(usize, &String)> = None;
+        for path in found.get(&kind).into_iter().flatten() {
+            let Some(size) = size(client, &base_url, path).await else {
+                continue;
+            };
+            if kind != AssetKind::Js {
+                best = Some((size, path));
+                break;
+            }
+            if best.is_none_or(|(largest, _)| size > largest) {
+                best = Some((size, path));
+            }
+        }
+        match best {
+            Some((_, path)) => {
+                assets.insert(kind, path.clone());
+            }
+            None => tracing::warn!(
+                "No {} found on {}'s homepage; set assets.{} in the targets file",
+                kind.scenario().to_lowercase(),
+                target.label(),
+                kind.key()
+            ),
+        }
+    }
+    assets
+}
+
+/// One row per asset: each target's mean time to the last byte and its
+/// transfer rate.
+pub fn display(targets: &[Target], rows: &[(&str, Vec<(f64, f64)>)], tie_pct: f64) {
+    println!("\n{}", "📦 Static Asset Comparison".bold());
+
+    let mut header = vec!["Asset".to_string()];
+    header.extend(targets.iter().map(|target| target.label().to_string()));
+    header.push("Winner".to_string());
+
+    let mut builder = Builder::default();
+    builder.push_record(header);
+    for (name, measured) in rows {
+        let mut record = vec![name.to_string()];
+        record.extend(measured.iter().map(|(avg_ms, bytes_per_sec)| {
+            format!(
+                "{} ({}/s)",
+                units::duration_ms(*avg_ms),
+                units::bytes(*bytes_per_sec)
+            )
+        }));
+        let averages: Vec<f64> = measured.iter().map(|(avg_ms, _)| *avg_ms).collect();
+        record.push(match tolerance::best(&averages, true, tie_pct) {
+            Some(index) => targets[index].display(),
+            None => tolerance::TIE.to_string(),
+        });
+        builder.push_record(record);
+    }
+    println!("\n{}", builder.build());
+}
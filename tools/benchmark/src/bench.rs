@@ -0,0 +1,169 @@
+use anyhow::{Context, Result};
+use clap::{CommandFactory, Parser, Subcommand};
+use rari_benchmark::{
+    build_times, config, doctor, dry_run, errors, interrupt, load_test, logging, performance,
+    presets, sibling, targets,
+};
+use std::ffi::OsString;
+use std::path::PathBuf;
+use tokio::process::Command;
+
+#[derive(Parser, Debug)]
+#[command(name = "bench")]
+#[command(
+    about = "Run the load test, performance, or build time benchmark with one set of shared flags",
+    after_help = "Shared flags go before the subcommand and are passed on under the same names; everything after it goes to the benchmark as is, e.g. `bench --port nextjs=4001 perf --requests 100`."
+)]
+struct Cli {
+    #[command(flatten)]
+    targets: targets::TargetArgs,
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Where results are saved [default: results, or DIR/results for build]"
+    )]
+    results_dir: Option<PathBuf>,
+    #[arg(long, help = "Label recorded with the results")]
+    label: Option<String>,
+    #[command(flatten)]
+    config: config::ConfigArgs,
+    #[command(flatten)]
+    log: logging::LogArgs,
+    #[command(flatten)]
+    dry_run: dry_run::DryRunArgs,
+    #[command(flatten)]
+    preset: presets::PresetArgs,
+    #[command(subcommand)]
+    command: Bench,
+}
+
+#[derive(Subcommand, Debug)]
+enum Bench {
+    /// Sustained load with oha (the load-test binary)
+    Load(Forwarded),
+    /// Per-page latency (the performance binary)
+    Perf(Forwarded),
+    /// Build times and bundle sizes (the build-times binary)
+    Build(Forwarded),
+    /// Print a completion script for SHELL, with every benchmark's flags
+    Completions {
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+    /// Write man pages for bench and each of its commands
+    Man {
+        #[arg(long, value_name = "DIR", default_value = "man")]
+        out_dir: PathBuf,
+    },
+    /// Check tools, app directories, ports, and disk space, with how to fix each problem
+    Doctor(doctor::DoctorArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct Forwarded {
+    #[arg(
+        trailing_var_arg = true,
+        allow_hyphen_values = true,
+        value_name = "ARGS",
+        help = "Flags of the benchmark itself; see `bench <command> -- --help`"
+    )]
+    args: Vec<OsString>,
+}
+
+impl Cli {
+    /// The shared flags as the benchmarks spell them.
+    fn shared_args(&self) -> Vec<OsString> {
+        let mut args: Vec<OsString> = Vec::new();
+        if let Some(file) = &self.targets.targets_file {
+            args.extend(["--targets".into(), file.into()]);
+        }
+        for port in &self.targets.ports {
+            args.extend(["--port".into(), port.to_string().into()]);
+        }
+        for url in &self.targets.urls {
+            args.extend(["--url".into(), url.to_string().into()]);
+        }
+        if let Some(dir) = &self.results_dir {
+            args.extend(["--results-dir".into(), dir.into()]);
+        }
+        if let Some(label) = &self.label {
+            args.extend(["--label".into(), label.into()]);
+        }
+        args.extend(self.config.forward());
+        args.extend(self.log.forward());
+        args.extend(self.preset.forward());
+        if self.dry_run.dry_run {
+            args.push("--dry-run".into());
+        }
+        args
+    }
+}
+
+/// `bench` as completions and man pages describe it: each benchmark
+/// command takes that benchmark's own flags, rather than opaque arguments.
+fn documented() -> clap::Command {
+    let mut cli = Cli::command();
+    for (name, benchmark) in [
+        ("load", load_test::Args::command()),
+        ("perf", performance::Args::command()),
+        ("build", build_times::Args::command()),
+    ] {
+        cli = cli.mut_subcommand(name, |sub| {
+            let about = sub.get_about().cloned().unwrap_or_default();
+            benchmark.name(name).about(about)
+        });
+    }
+    cli
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run().await {
+        errors::exit(e);
+    }
+}
+
+async fn run() -> Result<()> {
+    let cli = Cli::parse();
+    let (bin, forwarded) = match &cli.command {
+        Bench::Load(forwarded) => ("load-test", forwarded),
+        Bench::Perf(forwarded) => ("performance", forwarded),
+        Bench::Build(forwarded) => ("build-times", forwarded),
+        Bench::Completions { shell } => {
+            clap_complete::generate(*shell, &mut documented(), "bench", &mut std::io::stdout());
+            return Ok(());
+        }
+        Bench::Man { out_dir } => {
+            std::fs::create_dir_all(out_dir)
+                .with_context(|| format!("Failed to create {}", out_dir.display()))?;
+            clap_mangen::generate_to(documented(), out_dir)
+                .with_context(|| format!("Failed to write man pages to {}", out_dir.display()))?;
+            println!("📖 Man pages written to {}", out_dir.display());
+            return Ok(());
+        }
+        Bench::Doctor(args) => {
+            let results_dir = cli.results_dir.as_deref().unwrap_or("results".as_ref());
+            return doctor::run(&cli.targets, results_dir, args).await;
+        }
+    };
+
+    let binary = sibling::binary(bin)?;
+    let mut command = Command::new(&binary);
+    command.args(cli.shared_args()).args(&forwarded.args);
+    #[cfg(unix)]
+    command.process_group(0);
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("Failed to run {}", binary.display()))?;
+    let _forwarding = interrupt::hand_over(child.id());
+    let status = match interrupt::or_interrupted(child.wait()).await {
+        Some(status) => status?,
+        None => {
+            // The benchmark got the Ctrl-C too and is saving its partial results.
+            interrupt::announce(&format!("Waiting for {} to finish", bin));
+            child.wait().await?
+        }
+    };
+    // Pass the exit code on, so a regression still fails CI.
+    std::process::exit(status.code().unwrap_or(1));
+}
@@ -0,0 +1,24 @@
+use anyhow::Result;
+use rari_benchmark::{build_times, config, errors, results};
+
+#[tokio::main]
+async fn main() {
+    match run().await {
+        Ok(Some(run)) => {
+            if let Some(code) = run.exit_code() {
+                std::process::exit(code);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => errors::exit(e),
+    }
+}
+
+async fn run() -> Result<Option<results::Run<build_times::BenchmarkResults>>> {
+    let args = config::parse::<build_times::Args>("build-times")?;
+    if args.dry_run.dry_run {
+        build_times::dry_run(&args).await?;
+        return Ok(None);
+    }
+    build_times::run_build_comparison(&args).await.map(Some)
+}
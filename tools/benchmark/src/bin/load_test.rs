@@ -0,0 +1,24 @@
+use anyhow::Result;
+use rari_benchmark::{config, errors, load_test, results};
+
+#[tokio::main]
+async fn main() {
+    match run().await {
+        Ok(Some(run)) => {
+            if let Some(code) = run.exit_code() {
+                std::process::exit(code);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => errors::exit(e),
+    }
+}
+
+async fn run() -> Result<Option<results::Run<load_test::BenchmarkResults>>> {
+    let args = config::parse::<load_test::Args>("load-test")?;
+    if args.dry_run.dry_run {
+        load_test::dry_run(&args).await?;
+        return Ok(None);
+    }
+    load_test::run_load_test(&args).await.map(Some)
+}
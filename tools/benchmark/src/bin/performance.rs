@@ -0,0 +1,24 @@
+use anyhow::Result;
+use rari_benchmark::{config, errors, performance, results};
+
+#[tokio::main]
+async fn main() {
+    match run().await {
+        Ok(Some(run)) => {
+            if let Some(code) = run.exit_code() {
+                std::process::exit(code);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => errors::exit(e),
+    }
+}
+
+async fn run() -> Result<Option<results::Run<performance::BenchmarkResults>>> {
+    let args = config::parse::<performance::Args>("performance")?;
+    if args.dry_run.dry_run {
+        performance::dry_run(&args).await?;
+        return Ok(None);
+    }
+    performance::run_performance(&args).await.map(Some)
+}
@@ -0,0 +1,104 @@
+use crate::random::Rng;
+use crate::{stats, tolerance};
+use serde::{Deserialize, Serialize};
+
+/// Resamples behind every interval, enough for steady 95% bounds.
+pub const RESAMPLES: usize = 1000;
+
+/// Keeps the bootstrap's draws apart from everything else on the run's seed.
+const STREAM: u64 = 0xb007_57a9;
+
+/// A 95% confidence interval.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Interval {
+    pub low: f64,
+    pub high: f64,
+}
+
+impl Interval {
+    /// The middle 95% of `estimates`.
+    fn of(mut estimates: Vec<f64>) -> Self {
+        estimates.sort_by(f64::total_cmp);
+        Interval {
+            low: stats::percentile(&estimates, 0.025),
+            high: stats::percentile(&estimates, 0.975),
+        }
+    }
+
+    pub fn contains(&self, value: f64) -> bool {
+        self.low <= value && value <= self.high
+    }
+}
+
+/// Intervals of the tail percentiles, or of the difference between two
+/// targets' tail percentiles.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tails {
+    pub p95: Interval,
+    pub p99: Interval,
+}
+
+/// The draws for one target and scenario, the same for every run with the
+/// same seed.
+pub fn rng(seed: u64, target: usize, scenario: usize) -> Rng {
+    Rng::stream(
+        Rng::stream(seed, STREAM).next_u64(),
+        ((target as u64) << 32) | scenario as u64,
+    )
+}
+
+/// `samples` drawn with replacement into `into`, sorted.
+fn resample(samples: &[f64], rng: &mut Rng, into: &mut Vec<f64>) {
+    into.clear();
+    into.extend((0..samples.len()).map(|_| samples[rng.up_to(samples.len() as u64 - 1) as usize]));
+    into.sort_by(f64::total_cmp);
+}
+
+/// 95% intervals of the p95 and p99 of `times`, or `None` with fewer than
+/// two times.
+pub fn tails(times: &[f64], rng: &mut Rng) -> Option<Tails> {
+    if times.len() < 2 {
+        return None;
+    }
+    let mut p95 = Vec::with_capacity(RESAMPLES);
+    let mut p99 = Vec::with_capacity(RESAMPLES);
+    let mut resampled = Vec::with_capacity(times.len());
+    for _ in 0..RESAMPLES {
+        resample(times, rng, &mut resampled);
+        p95.push(stats::percentile(&resampled, 0.95));
+        p99.push(stats::percentile(&resampled, 0.99));
+    }
+    Some(Tails {
+        p95: Interval::of(p95),
+        p99: Interval::of(p99),
+    })
+}
+
+/// 95% intervals of how much the p95 and p99 of `subject` differ from those
+/// of `other`, in percent of `other`'s as the comparison shows them, or
+/// `None` with fewer than two times on either side.
+pub fn tail_deltas(subject: &[f64], other: &[f64], rng: &mut Rng) -> Option<Tails> {
+    if subject.len() < 2 || other.len() < 2 {
+        return None;
+    }
+    let mut p95 = Vec::with_capacity(RESAMPLES);
+    let mut p99 = Vec::with_capacity(RESAMPLES);
+    let mut subject_resampled = Vec::with_capacity(subject.len());
+    let mut other_resampled = Vec::with_capacity(other.len());
+    for _ in 0..RESAMPLES {
+        resample(subject, rng, &mut subject_resampled);
+        resample(other, rng, &mut other_resampled);
+        p95.push(tolerance::difference_pct(
+            stats::percentile(&subject_resampled, 0.95),
+            stats::percentile(&other_resampled, 0.95),
+        ));
+        p99.push(tolerance::difference_pct(
+            stats::percentile(&subject_resampled, 0.99),
+            stats::percentile(&other_resampled, 0.99),
+        ));
+    }
+    Some(Tails {
+        p95: Interval::of(p95),
+        p99: Interval::of(p99),
+    })
+}
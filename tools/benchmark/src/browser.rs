@@ -0,0 +1,989 @@
+use anyhow::{Context, Result};
+use chromiumoxide::Page;
+use chromiumoxide::browser::{Browser, BrowserConfig};
+use chromiumoxide::cdp::browser_protocol::network::SetCacheDisabledParams;
+use clap::Parser;
+use colored::Colorize;
+use futures::StreamExt;
+use rari_benchmark::{
+    archive, config, errors, gate, hooks, influx, interrupt, lighthouse, metrics, notify, output,
+    preflight, presets, probe, publish, results, run_metadata, schema, servers, stats, store,
+    stream, targets, time_utils, tolerance, units, upload, versions,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime};
+use tracing::Instrument;
+
+/// Lighthouse's quiet window: a page is interactive once this long passes
+/// without a long task.
+const QUIET_WINDOW_MS: f64 = 5000.0;
+/// How long a page gets to paint and become interactive.
+const LOAD_TIMEOUT: Duration = Duration::from_secs(60);
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// A soft navigation is done once the DOM stops changing for this long.
+const NAVIGATION_QUIET_MS: u64 = 500;
+
+/// Runs before any page script and keeps the entries that are not buffered
+/// for `performance.getEntriesByType`.
+const OBSERVE: &str = r#"
+window.__rariBenchmark = { lcp: null, longTasks: [] };
+new PerformanceObserver((list) => {
+  for (const entry of list.getEntries())
+    window.__rariBenchmark.lcp = entry.startTime;
+}).observe({ type: 'largest-contentful-paint', buffered: true });
+new PerformanceObserver((list) => {
+  for (const entry of list.getEntries())
+    window.__rariBenchmark.longTasks.push([entry.startTime, entry.startTime + entry.duration]);
+}).observe({ type: 'longtask', buffered: true });
+"#;
+
+const COLLECT: &str = r#"(() => {
+  const observed = window.__rariBenchmark || { lcp: null, longTasks: [] };
+  const paint = performance.getEntriesByName('first-contentful-paint')[0];
+  const navigation = performance.getEntriesByType('navigation')[0];
+  const hydrated = performance.getEntriesByName('hydrated', 'mark')[0];
+  return {
+    now: performance.now(),
+    fcp: paint ? paint.startTime : null,
+    lcp: observed.lcp,
+    hydrated: hydrated ? hydrated.startTime : null,
+    responseStart: navigation ? navigation.responseStart : 0,
+    responseEnd: navigation ? navigation.responseEnd : 0,
+    domContentLoaded: navigation ? navigation.domContentLoadedEventEnd : 0,
+    longTasks: observed.longTasks,
+  };
+})()"#;
+
+const LOAD_EVENT_END: &str = r#"(() => {
+  const navigation = performance.getEntriesByType('navigation')[0];
+  return navigation ? navigation.loadEventEnd : 0;
+})()"#;
+
+/// Clicks the link to `to` and resolves with the milliseconds until the last
+/// DOM change once the URL is `to` and the DOM has been quiet for `quietMs`.
+const NAVIGATE: &str = r#"async (to, quietMs, timeoutMs) => {
+  const target = new URL(to, location.href).href;
+  const link = [...document.querySelectorAll('a[href]')].find((a) => a.href === target);
+  if (!link)
+    return { error: `no link to ${to} on ${location.pathname}` };
+  let changed = null;
+  const observer = new MutationObserver(() => { changed = performance.now(); });
+  observer.observe(document, { subtree: true, childList: true, characterData: true, attributes: true });
+  const start = performance.now();
+  link.click();
+  return await new Promise((resolve) => {
+    const check = () => {
+      const now = performance.now();
+      if (location.href === target && changed !== null && now - changed >= quietMs) {
+        observer.disconnect();
+        resolve({ ms: changed - start });
+      } else if (now - start >= timeoutMs) {
+        observer.disconnect();
+        resolve({ error: location.href === target ? 'the page never stopped changing' : `the URL never changed to ${to}` });
+      } else {
+        setTimeout(check, 20);
+      }
+    };
+    check();
+  });
+}"#;
+
+/// A client-side route change, given as `FROM=TO` paths.
+#[derive(Debug, Clone)]
+struct NavigationSpec {
+    from: String,
+    to: String,
+}
+
+impl NavigationSpec {
+    fn name(&self) -> String {
+        format!("{} → {}", self.from, self.to)
+    }
+}
+
+impl FromStr for NavigationSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (from, to) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected FROM=TO, got '{}'", s))?;
+        if !from.starts_with('/') || !to.starts_with('/') {
+            return Err(format!("expected two paths starting with /, got '{}'", s));
+        }
+        Ok(NavigationSpec {
+            from: from.to_string(),
+            to: to.to_string(),
+        })
+    }
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "browser")]
+#[command(
+    about = "Load pages in headless Chrome and compare First Contentful Paint, Largest Contentful Paint, and Time to Interactive"
+)]
+struct Args {
+    #[arg(long)]
+    label: Option<String>,
+    #[arg(
+        long = "page",
+        value_name = "PATH",
+        default_value = "/",
+        help = "Page to load; repeat for more scenarios"
+    )]
+    pages: Vec<String>,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value = "5",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Cold-cache loads per page or navigation and target"
+    )]
+    runs: u64,
+    #[arg(
+        long = "navigate",
+        value_name = "FROM=TO",
+        help = "Time a client-side navigation: load FROM, then click its link to TO; repeatable"
+    )]
+    navigations: Vec<NavigationSpec>,
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Also load every page from N browser tabs at once, with all its assets"
+    )]
+    sessions: Option<u64>,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value = "10",
+        value_parser = clap::value_parser!(u64).range(1..),
+        requires = "sessions",
+        help = "Page loads per session"
+    )]
+    session_loads: u64,
+    #[arg(
+        long,
+        value_name = "PATH",
+        env = "CHROME",
+        help = "Chrome or Chromium binary; found on PATH by default"
+    )]
+    chrome: Option<PathBuf>,
+    #[arg(long, default_value = "results")]
+    results_dir: PathBuf,
+    #[arg(
+        long,
+        value_name = "PCT",
+        default_value_t = tolerance::DEFAULT_TIE_PCT,
+        help = "Differences within ±PCT% are reported as a tie"
+    )]
+    tie_threshold: f64,
+    #[arg(long = "fail-if", value_name = "EXPR")]
+    fail_if: Vec<String>,
+    #[arg(long, value_name = "FILE")]
+    thresholds: Option<PathBuf>,
+    #[arg(long, value_name = "URL")]
+    notify_webhook: Option<String>,
+    #[arg(long, value_name = "URL")]
+    artifact_url: Option<String>,
+    #[arg(long, value_name = "URL")]
+    upload: Option<upload::Destination>,
+    #[arg(long, value_name = "FILE")]
+    store: Option<PathBuf>,
+    #[arg(long)]
+    no_store: bool,
+    #[command(flatten)]
+    targets: targets::TargetArgs,
+    #[command(flatten)]
+    hooks: hooks::HookArgs,
+    #[command(flatten)]
+    preflight: preflight::PreflightArgs,
+    #[command(flatten)]
+    servers: servers::ServerArgs,
+    #[command(flatten)]
+    probe: probe::ProbeArgs,
+    #[command(flatten)]
+    lighthouse: lighthouse::LighthouseArgs,
+    #[command(flatten)]
+    output: output::OutputArgs,
+    #[command(flatten)]
+    preset: presets::PresetArgs,
+    #[command(flatten)]
+    archive: archive::ArchiveArgs,
+    #[command(flatten)]
+    influx: influx::InfluxArgs,
+    #[command(flatten)]
+    github: publish::GitHubArgs,
+    #[command(flatten)]
+    config: config::ConfigArgs,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Distribution {
+    runs: Vec<f64>,
+    min: f64,
+    avg: f64,
+    p50: f64,
+    max: f64,
+}
+
+impl Distribution {
+    fn from_runs(runs: Vec<f64>) -> Self {
+        Distribution {
+            min: stats::min(&runs).unwrap_or_default(),
+            avg: stats::mean(&runs).unwrap_or_default(),
+            p50: stats::median(&runs).unwrap_or_default(),
+            max: stats::max(&runs).unwrap_or_default(),
+            runs,
+        }
+    }
+}
+
+/// Milliseconds from navigation start, per page load.
+#[derive(Debug, Serialize, Deserialize)]
+struct PageMetrics {
+    ttfb: Distribution,
+    fcp: Distribution,
+    lcp: Distribution,
+    tti: Distribution,
+    /// From the end of the HTML response to the page's `hydrated` mark; only
+    /// when every load set it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hydration: Option<Distribution>,
+    /// The `--lighthouse` run of this page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lighthouse: Option<lighthouse::LighthouseReport>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sessions: Option<SessionLoad>,
+}
+
+/// `--sessions` tabs loading a page over and over at the same time. Load time
+/// runs from navigation start to the end of the load event, so it covers the
+/// HTML and every asset.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionLoad {
+    concurrency: u64,
+    loads: usize,
+    errors: usize,
+    pages_per_sec: f64,
+    load_time: stats::Latency,
+}
+
+/// Milliseconds from the click to the new route's last DOM change.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Navigation {
+    soft_nav: Distribution,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TargetResults {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    navigations: BTreeMap<String, Navigation>,
+    #[serde(flatten)]
+    pages: BTreeMap<String, PageMetrics>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BenchmarkResults {
+    /// [`schema::VERSION`] when written; see [`schema::migrate`] for older files.
+    version: u32,
+    timestamp: String,
+    #[serde(default)]
+    metadata: run_metadata::RunMetadata,
+    targets: Vec<String>,
+    #[serde(flatten)]
+    results: BTreeMap<String, TargetResults>,
+    summary: TestSummary,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    partial: bool,
+    /// What cut the run short, if anything.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<errors::RunError>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TestSummary {
+    page_loads: u64,
+    pages: usize,
+    /// The `--lighthouse-preset`, when Lighthouse ran.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lighthouse: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NavigationOutcome {
+    ms: Option<f64>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageState {
+    now: f64,
+    fcp: Option<f64>,
+    lcp: Option<f64>,
+    hydrated: Option<f64>,
+    response_start: f64,
+    response_end: f64,
+    dom_content_loaded: f64,
+    long_tasks: Vec<(f64, f64)>,
+}
+
+type Metric = fn(&PageMetrics) -> f64;
+
+struct PageLoad {
+    ttfb: f64,
+    fcp: f64,
+    lcp: f64,
+    tti: f64,
+    hydration: Option<f64>,
+}
+
+/// Time to Interactive as Lighthouse computes it from long tasks: the end of
+/// the last long task before a quiet window, and no earlier than FCP or
+/// DOMContentLoaded. Lighthouse also wants at most two requests in flight
+/// during the window, which is not checked here. `None` while the window is
+/// still open.
+fn time_to_interactive(fcp: f64, state: &PageState) -> Option<f64> {
+    let mut long_tasks = state.long_tasks.clone();
+    long_tasks.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut interactive = fcp;
+    for (start, end) in long_tasks {
+        if start - interactive >= QUIET_WINDOW_MS {
+            return Some(interactive.max(state.dom_content_loaded));
+        }
+        interactive = interactive.max(end);
+    }
+    (state.now - interactive >= QUIET_WINDOW_MS).then(|| interactive.max(state.dom_content_loaded))
+}
+
+/// Loads `url` in a fresh tab with the cache disabled and waits until it is
+/// interactive.
+async fn load_page(browser: &Browser, url: &str) -> Result<PageLoad> {
+    let page = browser.new_page("about:blank").await?;
+    let measured = measure_page(&page, url).await;
+    let _ = page.close().await;
+    measured
+}
+
+async fn measure_page(page: &Page, url: &str) -> Result<PageLoad> {
+    page.execute(SetCacheDisabledParams::new(true)).await?;
+    page.evaluate_on_new_document(OBSERVE).await?;
+    page.goto(url)
+        .await
+        .with_context(|| format!("Failed to load {}", url))?;
+
+    let start = Instant::now();
+    loop {
+        let state: PageState = page.evaluate_expression(COLLECT).await?.into_value()?;
+        if let Some(fcp) = state.fcp
+            && let Some(tti) = time_to_interactive(fcp, &state)
+        {
+            let lcp = state
+                .lcp
+                .with_context(|| format!("{} reported no Largest Contentful Paint", url))?;
+            return Ok(PageLoad {
+                ttfb: state.response_start,
+                fcp,
+                lcp,
+                tti,
+                hydration: state.hydrated.map(|hydrated| hydrated - state.response_end),
+            });
+        }
+        if start.elapsed() > LOAD_TIMEOUT {
+            anyhow::bail!(
+                "{} did not {} within {:.0}s",
+                url,
+                if state.fcp.is_some() {
+                    "become interactive"
+                } else {
+                    "paint"
+                },
+                LOAD_TIMEOUT.as_secs_f64()
+            );
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Loads `from` in a fresh tab, waits until it is interactive, and clicks its
+/// link to `to`.
+async fn soft_navigation(browser: &Browser, base_url: &str, spec: &NavigationSpec) -> Result<f64> {
+    let page = browser.new_page("about:blank").await?;
+    let measured = async {
+        measure_page(&page, &format!("{}{}", base_url, spec.from)).await?;
+        let script = format!(
+            "({})({}, {}, {})",
+            NAVIGATE,
+            serde_json::to_string(&spec.to)?,
+            NAVIGATION_QUIET_MS,
+            LOAD_TIMEOUT.as_millis()
+        );
+        let outcome: NavigationOutcome = page
+            .evaluate_expression(script)
+            .await
+            .with_context(|| {
+                format!(
+                    "The page reloaded on the way to {}; is it a client-side route?",
+                    spec.to
+                )
+            })?
+            .into_value()?;
+        match outcome {
+            NavigationOutcome { ms: Some(ms), .. } => Ok(ms),
+            NavigationOutcome { error, .. } => anyhow::bail!(
+                "Navigating {} failed: {}",
+                spec.name(),
+                error.unwrap_or_default()
+            ),
+        }
+    }
+    .await;
+    let _ = page.close().await;
+    measured
+}
+
+async fn full_load(page: &Page, url: &str) -> Result<f64> {
+    page.goto(url).await?;
+    loop {
+        let load_event_end: f64 = page
+            .evaluate_expression(LOAD_EVENT_END)
+            .await?
+            .into_value()?;
+        if load_event_end > 0.0 {
+            return Ok(load_event_end);
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+}
+
+/// One tab loading `url` `loads` times with the cache disabled. Returns the
+/// load times and how many loads failed.
+async fn session(browser: &Browser, url: &str, loads: u64) -> Result<(Vec<f64>, usize)> {
+    let page = browser.new_page("about:blank").await?;
+    page.execute(SetCacheDisabledParams::new(true)).await?;
+    let mut times = Vec::new();
+    let mut errors = 0;
+    for _ in 0..loads {
+        match tokio::time::timeout(LOAD_TIMEOUT, full_load(&page, url)).await {
+            Ok(Ok(ms)) => times.push(ms),
+            _ => errors += 1,
+        }
+    }
+    let _ = page.close().await;
+    Ok((times, errors))
+}
+
+async fn session_load(
+    browser: &Browser,
+    url: &str,
+    concurrency: u64,
+    loads: u64,
+) -> Result<SessionLoad> {
+    let start = Instant::now();
+    let sessions =
+        futures::future::join_all((0..concurrency).map(|_| session(browser, url, loads))).await;
+    let elapsed = start.elapsed().as_secs_f64();
+
+    let mut times = Vec::new();
+    let mut errors = 0;
+    for session in sessions {
+        let (session_times, session_errors) = session?;
+        times.extend(session_times);
+        errors += session_errors;
+    }
+    let Some(load_time) = stats::Latency::from_times(&times) else {
+        return Err(errors::Error::new(
+            errors::ErrorKind::RequestsFailed,
+            format!("Every page load of {} failed or timed out", url),
+        )
+        .into());
+    };
+    Ok(SessionLoad {
+        concurrency,
+        loads: times.len(),
+        errors,
+        pages_per_sec: times.len() as f64 / elapsed,
+        load_time,
+    })
+}
+
+async fn launch(chrome: Option<&Path>) -> Result<Browser> {
+    // The apps are trusted, and Chrome's sandbox refuses to run as root in CI
+    // containers.
+    let mut config = BrowserConfig::builder().no_sandbox();
+    if let Some(chrome) = chrome {
+        config = config.chrome_executable(chrome);
+    }
+    let config = config.build().map_err(|e| {
+        errors::Error::new(
+            errors::ErrorKind::ToolMissing,
+            format!("{}; install Chrome or Chromium, or pass --chrome PATH", e),
+        )
+    })?;
+    let (browser, mut handler) = Browser::launch(config)
+        .await
+        .context("Failed to launch Chrome")?;
+    tokio::spawn(async move { while handler.next().await.is_some() {} });
+    Ok(browser)
+}
+
+async fn measure_target(
+    browser: &Browser,
+    target: &targets::Target,
+    args: &Args,
+) -> Result<TargetResults> {
+    let mut pages = BTreeMap::new();
+    for path in &args.pages {
+        let url = format!("{}{}", target.base_url(), path);
+        println!("\n🌐 {} · {}", target.label().bold(), path);
+
+        let mut loads = Vec::new();
+        for run in 1..=args.runs {
+            args.hooks.before_load(target).await?;
+            let load = load_page(browser, &url).await?;
+            let hydration = load
+                .hydration
+                .map(|ms| format!(", hydration {}", units::duration_ms(ms)))
+                .unwrap_or_default();
+            println!(
+                "  {} Load {}/{}: TTFB {}, FCP {}, LCP {}, TTI {}{}",
+                "⏱️".dimmed(),
+                run,
+                args.runs,
+                units::duration_ms(load.ttfb),
+                units::duration_ms(load.fcp),
+                units::duration_ms(load.lcp),
+                units::duration_ms(load.tti),
+                hydration
+            );
+            loads.push(load);
+        }
+
+        let lighthouse = if args.lighthouse.lighthouse {
+            args.hooks.before_load(target).await?;
+            let report = lighthouse::run(
+                &url,
+                args.lighthouse.lighthouse_preset,
+                args.chrome.as_deref(),
+            )
+            .await?;
+            println!("  {} Lighthouse: {}", "🔦".dimmed(), report.describe());
+            Some(report)
+        } else {
+            None
+        };
+
+        let sessions = match args.sessions {
+            Some(concurrency) => {
+                args.hooks.before_load(target).await?;
+                let load = session_load(browser, &url, concurrency, args.session_loads).await?;
+                println!(
+                    "  {} {} sessions: p50 {}, p95 {}, {:.1} pages/s, {} errors",
+                    "👥".dimmed(),
+                    concurrency,
+                    units::duration_ms(load.load_time.p50),
+                    units::duration_ms(load.load_time.p95),
+                    load.pages_per_sec,
+                    load.errors
+                );
+                Some(load)
+            }
+            None => None,
+        };
+
+        let metrics = PageMetrics {
+            ttfb: Distribution::from_runs(loads.iter().map(|load| load.ttfb).collect()),
+            fcp: Distribution::from_runs(loads.iter().map(|load| load.fcp).collect()),
+            lcp: Distribution::from_runs(loads.iter().map(|load| load.lcp).collect()),
+            tti: Distribution::from_runs(loads.iter().map(|load| load.tti).collect()),
+            hydration: loads
+                .iter()
+                .map(|load| load.hydration)
+                .collect::<Option<Vec<f64>>>()
+                .map(Distribution::from_runs),
+            lighthouse,
+            sessions,
+        };
+        stream::emit(
+            "page_finished",
+            json!({ "framework": &target.name, "page": path, "metrics": &metrics }),
+        );
+        pages.insert(path.clone(), metrics);
+    }
+
+    let mut navigations = BTreeMap::new();
+    for spec in &args.navigations {
+        println!("\n🧭 {} · {}", target.label().bold(), spec.name());
+
+        let mut runs = Vec::new();
+        for run in 1..=args.runs {
+            args.hooks.before_load(target).await?;
+            let elapsed_ms = soft_navigation(browser, &target.base_url(), spec).await?;
+            println!(
+                "  {} Navigation {}/{}: {}",
+                "⏱️".dimmed(),
+                run,
+                args.runs,
+                units::duration_ms(elapsed_ms)
+            );
+            runs.push(elapsed_ms);
+        }
+
+        let navigation = Navigation {
+            soft_nav: Distribution::from_runs(runs),
+        };
+        stream::emit(
+            "navigation_finished",
+            json!({ "framework": &target.name, "navigation": spec.name(), "metrics": &navigation }),
+        );
+        navigations.insert(spec.name(), navigation);
+    }
+    Ok(TargetResults { navigations, pages })
+}
+
+fn display_metric(title: &str, targets: &[targets::Target], values: &[f64], tie_pct: f64) {
+    println!("\n{}:", title);
+    for (heading, value) in targets::headings(targets).iter().zip(values) {
+        println!("  {}{}", heading, units::duration_ms(*value));
+    }
+
+    let subject = &targets[0];
+    for (other, value) in targets.iter().zip(values).skip(1) {
+        let diff = tolerance::difference_pct(values[0], *value);
+        match tolerance::classify(values[0], *value, true, tie_pct) {
+            tolerance::Outcome::SubjectBetter => println!(
+                "  {} {} is {:.1}% faster than {}",
+                "📈".green(),
+                subject.label(),
+                diff.abs(),
+                other.label()
+            ),
+            tolerance::Outcome::OtherBetter => println!(
+                "  {} {} is {:.1}% slower than {}",
+                "📉".red(),
+                subject.label(),
+                diff,
+                other.label()
+            ),
+            tolerance::Outcome::Tie => {
+                println!("{}", tolerance::tie_line(diff, tie_pct, other.label()))
+            }
+        }
+    }
+}
+
+fn display_comparison(
+    args: &Args,
+    targets: &[targets::Target],
+    results: &[TargetResults],
+    tie_pct: f64,
+) {
+    println!("\n{}", "📊 Browser Lab Metrics (median)".bold());
+
+    let metrics: [(&str, Metric); 4] = [
+        ("🖥️ Server Response (TTFB)", |m| m.ttfb.p50),
+        ("🎨 First Contentful Paint", |m| m.fcp.p50),
+        ("🖼️ Largest Contentful Paint", |m| m.lcp.p50),
+        ("👆 Time to Interactive", |m| m.tti.p50),
+    ];
+    for page in &args.pages {
+        println!("\n{}", page.bold());
+        for (title, metric) in metrics {
+            let values: Vec<f64> = results
+                .iter()
+                .map(|result| metric(&result.pages[page]))
+                .collect();
+            display_metric(title, targets, &values, tie_pct);
+        }
+        if let Some(values) = results
+            .iter()
+            .map(|result| Some(result.pages[page].hydration.as_ref()?.p50))
+            .collect::<Option<Vec<f64>>>()
+        {
+            display_metric("🌊 Hydration", targets, &values, tie_pct);
+        }
+        if let Some(concurrency) = args.sessions
+            && let Some(values) = results
+                .iter()
+                .map(|result| Some(result.pages[page].sessions.as_ref()?.load_time.p95))
+                .collect::<Option<Vec<f64>>>()
+        {
+            display_metric(
+                &format!("👥 Full Page Load, {} sessions (p95)", concurrency),
+                targets,
+                &values,
+                tie_pct,
+            );
+        }
+        if let Some(reports) = results
+            .iter()
+            .map(|result| result.pages[page].lighthouse.as_ref())
+            .collect::<Option<Vec<_>>>()
+        {
+            lighthouse::display(page, targets, &reports, tie_pct);
+        }
+    }
+
+    for spec in &args.navigations {
+        let values: Vec<f64> = results
+            .iter()
+            .map(|result| result.navigations[&spec.name()].soft_nav.p50)
+            .collect();
+        display_metric(
+            &format!("🧭 Soft Navigation {}", spec.name()),
+            targets,
+            &values,
+            tie_pct,
+        );
+    }
+}
+
+/// The median of `metric` averaged over the pages.
+fn page_average(pages: &BTreeMap<String, PageMetrics>, metric: Metric) -> f64 {
+    pages.values().map(metric).sum::<f64>() / pages.len().max(1) as f64
+}
+
+#[tokio::main]
+async fn main() {
+    let result = async {
+        let args = config::parse::<Args>("browser")?;
+        output::init(&args.output)?;
+        run(args)
+            .instrument(tracing::info_span!("benchmark", kind = "browser"))
+            .await
+    };
+    if let Err(e) = result.await {
+        errors::exit(e);
+    }
+}
+
+async fn run(args: Args) -> Result<()> {
+    let rules = gate::load_rules(&args.fail_if, args.thresholds.as_deref()).await?;
+    archive::validate_template(&args.archive.name_template)?;
+    // Pages share a target's results with its navigations.
+    if args.pages.iter().any(|page| page == "navigations") {
+        return Err(errors::Error::new(
+            errors::ErrorKind::InvalidConfig,
+            "'navigations' is reserved and cannot be a --page",
+        )
+        .into());
+    }
+    let mut targets = targets::load(&args.targets)?;
+    let mut metadata = run_metadata::collect(Path::new("."), args.label.clone()).await;
+    metadata.profile = args.preset.profile.map(|preset| preset.name().to_string());
+    metadata.mode = Some(args.servers.mode.name().to_string());
+    metadata.target_env = targets::env(&targets);
+    metadata.node_version = versions::node().await;
+    metadata.target_versions = versions::packages(&args.servers.apps_dir, &targets).await;
+    metadata.framework_versions = versions::frameworks(&targets, &metadata.target_versions);
+    metadata.target_urls = targets::remote_urls(&targets);
+    let title = format!("🌐 {} Browser Lab Metrics", targets::title(&targets));
+
+    println!("{}", title.cyan().bold());
+    println!("{}", metadata.describe().dimmed());
+    metadata.host_load = preflight::check(&args.preflight).await?;
+    stream::emit(
+        "run_started",
+        json!({ "benchmark": "browser", "metadata": &metadata, "config": { "runs": args.runs, "pages": &args.pages, "navigations": args.navigations.iter().map(NavigationSpec::name).collect::<Vec<_>>(), "lighthouse": args.lighthouse.lighthouse, "sessions": args.sessions } }),
+    );
+
+    let browser = launch(args.chrome.as_deref()).await?;
+
+    let managed_servers = if args.servers.manage_servers {
+        Some(servers::start(&args.servers, &args.probe, &args.hooks, &mut targets).await?)
+    } else {
+        servers::require_running(&targets, &args.probe, args.servers.mode).await?;
+        None
+    };
+
+    let mut page_results = Vec::new();
+    let measured = interrupt::or_interrupted(async {
+        for target in &targets {
+            // A target cut short is dropped; the finished ones are saved.
+            let Some(result) =
+                interrupt::until_cancelled(measure_target(&browser, target, &args)).await
+            else {
+                break;
+            };
+            page_results.push(result?);
+        }
+        Ok::<_, anyhow::Error>(())
+    })
+    .await;
+    let (partial, failures) = match measured {
+        Some(Ok(())) if !interrupt::cancelled() => (false, Vec::new()),
+        Some(Err(e)) if !interrupt::cancelled() => {
+            tracing::error!("{:#}", e);
+            (true, vec![errors::RunError::from_error(&e)])
+        }
+        _ => {
+            interrupt::announce("Saving the finished targets");
+            (true, Vec::new())
+        }
+    };
+    drop(browser);
+    drop(managed_servers);
+
+    if !partial {
+        display_comparison(&args, &targets, &page_results, args.tie_threshold);
+    }
+
+    let results = BenchmarkResults {
+        version: schema::VERSION,
+        timestamp: time_utils::format_timestamp(SystemTime::now()),
+        metadata,
+        targets: targets::names(&targets),
+        results: targets::names(&targets)
+            .into_iter()
+            .zip(page_results)
+            .collect(),
+        summary: TestSummary {
+            page_loads: args.runs,
+            pages: args.pages.len(),
+            lighthouse: args
+                .lighthouse
+                .lighthouse
+                .then(|| args.lighthouse.lighthouse_preset.name().to_string()),
+        },
+        partial,
+        errors: failures,
+    };
+
+    let file_name = archive::file_name(
+        &args.archive.name_template,
+        metrics::ResultKind::Browser,
+        &results.timestamp,
+        &results.metadata,
+    )?;
+    let saved_path = results::save(&results, &args.results_dir, &file_name).await?;
+    if partial {
+        // Partial results stay out of the archive, the store and the gate.
+        std::process::exit(errors::exit_code(&results.errors).unwrap_or(interrupt::EXIT_CODE));
+    }
+    if let Err(e) = archive::finalize(
+        &args.results_dir,
+        metrics::ResultKind::Browser,
+        &args.archive,
+    )
+    .await
+    {
+        tracing::warn!("Failed to update results archive: {:#}", e);
+    }
+
+    let document = serde_json::to_value(&results)?;
+    let store_path = (!args.no_store).then(|| {
+        args.store
+            .clone()
+            .unwrap_or_else(|| store::default_path(&args.results_dir))
+    });
+    store::record(
+        store_path.as_deref(),
+        metrics::ResultKind::Browser,
+        &saved_path,
+        &document,
+    );
+    influx::export(&args.influx, metrics::ResultKind::Browser, &document).await;
+
+    let result_metrics = metrics::flatten(&document);
+    let gate_passed = gate::check(&rules, &result_metrics)?;
+
+    let mut artifact = saved_path.display().to_string();
+    if let Some(destination) = &args.upload {
+        let date = time_utils::format_date(SystemTime::now());
+        match upload::upload(destination, &saved_path, &date).await {
+            Ok(uri) => artifact = uri,
+            Err(e) => tracing::warn!("{:#}", e),
+        }
+    }
+
+    let delta = |label: &str, metric: Metric| notify::Delta {
+        label: label.to_string(),
+        unit: units::Unit::Milliseconds,
+        values: targets
+            .iter()
+            .map(|target| page_average(&results.results[&target.name].pages, metric))
+            .collect(),
+        lower_is_better: true,
+    };
+    let mut deltas = vec![
+        delta("First Contentful Paint", |m| m.fcp.p50),
+        delta("Largest Contentful Paint", |m| m.lcp.p50),
+        delta("Time to Interactive", |m| m.tti.p50),
+    ];
+    if !args.navigations.is_empty() {
+        deltas.push(notify::Delta {
+            label: "Soft navigation".to_string(),
+            unit: units::Unit::Milliseconds,
+            values: targets
+                .iter()
+                .map(|target| {
+                    let navigations = &results.results[&target.name].navigations;
+                    navigations
+                        .values()
+                        .map(|navigation| navigation.soft_nav.p50)
+                        .sum::<f64>()
+                        / navigations.len().max(1) as f64
+                })
+                .collect(),
+            lower_is_better: true,
+        });
+    }
+    if args.lighthouse.lighthouse {
+        deltas.push(notify::Delta {
+            label: "Lighthouse score".to_string(),
+            unit: units::Unit::Count,
+            values: targets
+                .iter()
+                .map(|target| {
+                    page_average(&results.results[&target.name].pages, |m| {
+                        m.lighthouse.as_ref().map_or(0.0, |report| report.score)
+                    })
+                })
+                .collect(),
+            lower_is_better: false,
+        });
+    }
+    let summary = notify::Summary {
+        title,
+        timestamp: results.timestamp.clone(),
+        targets: targets.clone(),
+        deltas,
+        gate_passed: (!rules.is_empty()).then_some(gate_passed),
+        artifact: args.artifact_url.clone().unwrap_or(artifact),
+    };
+
+    notify::notify(args.notify_webhook.as_deref(), &summary).await;
+    if let Err(e) = publish::publish(&args.github, "benchmarks/browser", &summary).await {
+        tracing::warn!("{:#}", e);
+    }
+
+    args.hooks.after_run(&saved_path).await?;
+    output::finish(&args.output, &summary.to_text(), &document)?;
+    stream::emit(
+        "run_finished",
+        json!({
+            "results": saved_path,
+            "artifact": summary.artifact,
+            "gatePassed": gate_passed,
+        }),
+    );
+
+    if !gate_passed {
+        std::process::exit(gate::REGRESSION_EXIT_CODE);
+    }
+
+    println!("\n{}", "🎉 Browser benchmark completed!".green().bold());
+
+    Ok(())
+}
@@ -1,61 +1,173 @@
-mod time_utils;
-
+use crate::{
+    archive, cache, config, disk_io, dry_run, errors, gate, hooks, influx, interrupt, metrics,
+    notify, output, preflight, presets, progress, publish, results, run_metadata, schema, stats,
+    store, stream, targets, time_utils, tolerance, units, upload, versions,
+};
 use anyhow::{Context, Result};
 use clap::Parser;
 use colored::Colorize;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
 use std::future::Future;
 use std::path::{Path, PathBuf};
 use std::pin::Pin;
-use std::time::{Instant, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::fs;
 use tokio::process::Command;
+use tracing::Instrument;
 
 #[derive(Parser, Debug)]
 #[command(name = "build-times")]
-#[command(about = "Compare build times between rari and Next.js")]
-struct Args {
+#[command(about = "Compare build times between rari and Next.js (or any configured targets)")]
+pub struct Args {
     #[arg(short, long, default_value = ".")]
-    dir: PathBuf,
+    pub dir: PathBuf,
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Where results are saved [default: DIR/results]"
+    )]
+    pub results_dir: Option<PathBuf>,
+    #[arg(long)]
+    pub label: Option<String>,
+    #[arg(
+        long,
+        value_name = "PCT",
+        default_value_t = tolerance::DEFAULT_TIE_PCT,
+        help = "Differences within ±PCT% are reported as a tie"
+    )]
+    pub tie_threshold: f64,
+    #[arg(
+        long,
+        value_enum,
+        value_name = "POLICY",
+        help = "Warm or drop the OS file cache before each build"
+    )]
+    pub fs_cache: Option<cache::FsCache>,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value = "1",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Build every target N times and report the median build time"
+    )]
+    pub runs: u64,
+    #[arg(long = "fail-if", value_name = "EXPR")]
+    pub fail_if: Vec<String>,
+    #[arg(long, value_name = "FILE")]
+    pub thresholds: Option<PathBuf>,
+    #[arg(long, value_name = "URL")]
+    pub notify_webhook: Option<String>,
+    #[arg(long, value_name = "URL")]
+    pub artifact_url: Option<String>,
+    #[arg(long, value_name = "URL")]
+    pub upload: Option<upload::Destination>,
+    #[arg(long, value_name = "FILE")]
+    pub store: Option<PathBuf>,
+    #[arg(long)]
+    pub no_store: bool,
+    #[command(flatten)]
+    pub targets: targets::TargetArgs,
+    #[command(flatten)]
+    pub hooks: hooks::HookArgs,
+    #[command(flatten)]
+    pub preflight: preflight::PreflightArgs,
+    #[command(flatten)]
+    pub output: output::OutputArgs,
+    #[command(flatten)]
+    pub preset: presets::PresetArgs,
+    #[command(flatten)]
+    pub dry_run: dry_run::DryRunArgs,
+    #[command(flatten)]
+    pub archive: archive::ArchiveArgs,
+    #[command(flatten)]
+    pub influx: influx::InfluxArgs,
+    #[command(flatten)]
+    pub github: publish::GitHubArgs,
+    #[command(flatten)]
+    pub config: config::ConfigArgs,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct BuildResult {
-    success: bool,
-    duration_ms: f64,
-    bundle_size: Option<String>,
-    chunk_count: Option<usize>,
-    warnings: usize,
-    errors: usize,
+pub struct BuildResult {
+    pub success: bool,
+    /// The median of `runs_ms` when the target was built more than once.
+    pub duration_ms: units::Millis,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub runs_ms: Vec<units::Millis>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stddev_ms: Option<units::Millis>,
+    pub bundle_size: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bundle_bytes: Option<units::Bytes>,
+    pub chunk_count: Option<usize>,
+    pub warnings: usize,
+    pub errors: usize,
+    /// Storage I/O of the build and every process it waited for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disk_io: Option<disk_io::DiskIo>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct BenchmarkResults {
-    timestamp: String,
-    rari: BuildResult,
-    nextjs: BuildResult,
+pub struct BenchmarkResults {
+    /// [`schema::VERSION`] when written; see [`schema::migrate`] for older files.
+    pub version: u32,
+    pub timestamp: String,
+    #[serde(default)]
+    pub metadata: run_metadata::RunMetadata,
+    pub targets: Vec<String>,
+    #[serde(flatten)]
+    pub results: BTreeMap<String, BuildResult>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub partial: bool,
+    /// Failed builds, and what cut the run short, if anything.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<errors::RunError>,
 }
 
-async fn run_build(name: &str, directory: &Path, command: &str) -> Result<BuildResult> {
+#[tracing::instrument(name = "build", skip_all, fields(target = %target.name))]
+/// `expected` is how long an earlier build of the same target took, if any,
+/// for the progress bar.
+async fn run_build(
+    target: &targets::Target,
+    directory: &Path,
+    expected: Option<Duration>,
+) -> Result<BuildResult> {
+    let name = target.label();
+    let command = target.build.as_str();
     println!("\n{} Building {}...", "🔨".bold(), name.bold());
     println!("  {} {}", "Directory:".dimmed(), directory.display());
     println!("  {} {}", "Command:".dimmed(), command);
+    if !target.env.is_empty() {
+        println!("  {} {}", "Env:".dimmed(), target.describe_env());
+    }
 
     let start = Instant::now();
+    let io_before = disk_io::DiskIo::children();
 
     let parts: Vec<&str> = command.split_whitespace().collect();
     let (cmd, args) = parts.split_first().context("Empty command")?;
 
-    let output = Command::new(cmd)
+    let build = Command::new(cmd)
         .args(args)
         .current_dir(directory)
         .env("NODE_ENV", "production")
-        .output()
-        .await
-        .context("Failed to execute build command")?;
-
-    let duration = start.elapsed();
-    let duration_ms = duration.as_secs_f64() * 1000.0;
+        .envs(&target.env)
+        .kill_on_drop(true)
+        .output();
+    let build = progress::timed(build, "Building", expected);
+    let output = stream::with_progress(
+        build,
+        |elapsed_secs| json!({ "framework": &target.name, "elapsedSecs": elapsed_secs }),
+    )
+    .await
+    .map_err(|e| errors::spawn_error(e, "Failed to execute build command"))?;
+
+    let duration_ms = units::Millis::since(start);
+    let disk_io = disk_io::DiskIo::children()
+        .zip(io_before)
+        .map(|(after, before)| after.since(before));
 
     let stdout = String::from_utf8_lossy(&output.stdout);
     let stderr = String::from_utf8_lossy(&output.stderr);
@@ -63,9 +175,18 @@ async fn run_build(name: &str, directory: &Path, command: &str) -> Result<BuildR
 
     let success = output.status.success();
 
+    for line in combined.lines() {
+        let lower = line.to_lowercase();
+        if lower.contains("warn") || lower.contains("error") {
+            tracing::debug!("{}", line);
+        } else {
+            tracing::trace!("{}", line);
+        }
+    }
+
     if success {
         println!(
-            "  {} {} built successfully in {:.2}ms",
+            "  {} {} built successfully in {}",
             "✅".green(),
             name,
             duration_ms
@@ -94,8 +215,8 @@ async fn run_build(name: &str, directory: &Path, command: &str) -> Result<BuildR
         + combined.matches("Error").count()
         + combined.matches("ERROR").count();
 
-    let (bundle_size, chunk_count) = if success {
-        get_bundle_info(name, directory).await?
+    let (bundle_bytes, chunk_count) = if success {
+        get_bundle_info(target, directory).await?
     } else {
         (None, None)
     };
@@ -103,19 +224,26 @@ async fn run_build(name: &str, directory: &Path, command: &str) -> Result<BuildR
     Ok(BuildResult {
         success,
         duration_ms,
-        bundle_size,
+        runs_ms: Vec::new(),
+        stddev_ms: None,
+        bundle_size: bundle_bytes.map(|b| b.to_string()),
+        bundle_bytes,
         chunk_count,
         warnings,
         errors,
+        disk_io,
     })
 }
 
-async fn get_bundle_info(name: &str, directory: &Path) -> Result<(Option<String>, Option<usize>)> {
-    let (dist_dir, extensions): (PathBuf, Vec<&str>) = match name {
-        "Next.js" => (directory.join(".next/static/chunks"), vec!["js", "css"]),
-        "rari" => (directory.join("dist/assets"), vec!["js", "css"]),
-        _ => return Ok((None, None)),
+async fn get_bundle_info(
+    target: &targets::Target,
+    directory: &Path,
+) -> Result<(Option<units::Bytes>, Option<usize>)> {
+    let Some(bundle_dir) = &target.bundle_dir else {
+        return Ok((None, None));
     };
+    let dist_dir = directory.join(bundle_dir);
+    let extensions = ["js", "css"];
 
     if !dist_dir.exists() {
         return Ok((None, None));
@@ -126,13 +254,9 @@ async fn get_bundle_info(name: &str, directory: &Path) -> Result<(Option<String>
 
     scan_directory(&dist_dir, &extensions, &mut total_size, &mut chunk_count).await?;
 
-    let bundle_size = if total_size > 0 {
-        Some(format!("{:.2} kB", total_size as f64 / 1024.0))
-    } else {
-        None
-    };
+    let bundle_bytes = (total_size > 0).then_some(units::Bytes(total_size));
 
-    Ok((bundle_size, Some(chunk_count)))
+    Ok((bundle_bytes, Some(chunk_count)))
 }
 
 fn scan_directory<'a>(
@@ -160,79 +284,173 @@ fn scan_directory<'a>(
     })
 }
 
-fn display_comparison(rari: &BuildResult, nextjs: &BuildResult) {
+fn display_comparison(targets: &[targets::Target], results: &[BuildResult], tie_pct: f64) {
     println!("\n{}", "📊 Build Performance Comparison".bold());
 
     println!("\n⏱️  Build Times:");
-    println!("  🦀 rari:     {:.2}s", rari.duration_ms / 1000.0);
-    println!("  🟢 Next.js:  {:.2}s", nextjs.duration_ms / 1000.0);
+    for (heading, result) in targets::headings(targets).iter().zip(results) {
+        match result.stddev_ms {
+            Some(stddev_ms) => println!(
+                "  {}{} {}",
+                heading,
+                result.duration_ms,
+                format!("(median of {}, ±{})", result.runs_ms.len(), stddev_ms).dimmed()
+            ),
+            None => println!("  {}{}", heading, result.duration_ms),
+        }
+    }
 
-    let time_diff = ((rari.duration_ms - nextjs.duration_ms) / nextjs.duration_ms) * 100.0;
-    if time_diff < 0.0 {
-        println!(
-            "  {} rari builds {:.1}% faster",
-            "📈".green(),
-            time_diff.abs()
-        );
-    } else {
-        println!("  {} rari builds {:.1}% slower", "📉".red(), time_diff);
+    let subject = &targets[0];
+    for (other, result) in targets.iter().zip(results).skip(1) {
+        let subject_ms = results[0].duration_ms.0;
+        let time_diff = tolerance::difference_pct(subject_ms, result.duration_ms.0);
+        match tolerance::classify(subject_ms, result.duration_ms.0, true, tie_pct) {
+            tolerance::Outcome::SubjectBetter => println!(
+                "  {} {} builds {:.1}% faster than {}",
+                "📈".green(),
+                subject.label(),
+                time_diff.abs(),
+                other.label()
+            ),
+            tolerance::Outcome::OtherBetter => println!(
+                "  {} {} builds {:.1}% slower than {}",
+                "📉".red(),
+                subject.label(),
+                time_diff,
+                other.label()
+            ),
+            tolerance::Outcome::Tie => {
+                println!("{}", tolerance::tie_line(time_diff, tie_pct, other.label()))
+            }
+        }
     }
 
     println!("\n📦 Client Bundle Information:");
-    println!("  🦀 rari:");
-    println!(
-        "     Size: {}",
-        rari.bundle_size.as_deref().unwrap_or("Unknown")
-    );
-    println!(
-        "     Files: {}",
-        rari.chunk_count
-            .map_or("Unknown".to_string(), |c| c.to_string())
-    );
-    println!("     Warnings: {}", rari.warnings);
-    println!("     Errors: {}", rari.errors);
+    for (target, result) in targets.iter().zip(results) {
+        println!("  {}:", target.display());
+        println!(
+            "     Size: {}",
+            result.bundle_size.as_deref().unwrap_or("Unknown")
+        );
+        println!(
+            "     Files: {}",
+            result
+                .chunk_count
+                .map_or("Unknown".to_string(), |c| c.to_string())
+        );
+        println!("     Warnings: {}", result.warnings);
+        println!("     Errors: {}", result.errors);
+    }
 
-    println!("  🟢 Next.js:");
-    println!(
-        "     Size: {}",
-        nextjs.bundle_size.as_deref().unwrap_or("Unknown")
-    );
-    println!(
-        "     Files: {}",
-        nextjs
-            .chunk_count
-            .map_or("Unknown".to_string(), |c| c.to_string())
-    );
-    println!("     Warnings: {}", nextjs.warnings);
-    println!("     Errors: {}", nextjs.errors);
+    if results.iter().any(|result| result.disk_io.is_some()) {
+        println!("\n💽 Disk I/O:");
+        for (heading, result) in targets::headings(targets).iter().zip(results) {
+            match result.disk_io {
+                Some(io) => println!(
+                    "  {}{} read, {} written",
+                    heading, io.read_bytes, io.written_bytes
+                ),
+                None => println!("  {}{}", heading, "not measured".dimmed()),
+            }
+        }
+    }
 }
 
-async fn save_results(results: &BenchmarkResults, base_dir: &Path) -> Result<()> {
-    let results_dir = base_dir.join("results");
-    fs::create_dir_all(&results_dir).await?;
-
-    let date = time_utils::format_date(SystemTime::now());
-    let filename = results_dir.join(format!("buildtimes-{}.json", date));
-
-    let json = format!("{}\n", serde_json::to_string_pretty(results)?);
-    fs::write(&filename, json).await?;
+/// Runs the build time comparison as its binary does, from `Args::parse_from` or
+/// [`config::parse`], and returns the saved results. It exits neither on a
+/// failed gate nor on Ctrl-C, and failed builds, or a failure that ends the
+/// run early, are saved with the results; see [`results::Run::exit_code`].
+pub async fn run_build_comparison(args: &Args) -> Result<results::Run<BenchmarkResults>> {
+    output::init(&args.output)?;
+    run(args)
+        .instrument(tracing::info_span!("benchmark", kind = "build-times"))
+        .await
+}
 
-    println!(
-        "\n{} Results saved to {}",
-        "💾".dimmed(),
-        filename.display()
+/// Checks what a build comparison with `args` needs, the app directories and
+/// build commands among them, and prints what it would do, without building
+/// anything. Fails with the kind of the first failed check.
+pub async fn dry_run(args: &Args) -> Result<()> {
+    output::init(&args.output)?;
+    let mut checks = dry_run::Checks::new("build time comparison");
+    checks.record(
+        "Regression gate",
+        gate::load_rules(&args.fail_if, args.thresholds.as_deref())
+            .await
+            .map(|rules| format!("{} rule(s)", rules.len())),
     );
-
-    Ok(())
+    checks.record(
+        "Name template",
+        archive::validate_template(&args.archive.name_template)
+            .map(|()| args.archive.name_template.clone()),
+    );
+    let targets = match targets::load(&args.targets).and_then(targets::local) {
+        Ok(targets) => targets,
+        Err(e) => {
+            checks.record("Targets", Err(e));
+            return checks.finish();
+        }
+    };
+    checks.record("Targets", Ok(targets::names(&targets).join(", ")));
+    for target in &targets {
+        let app_dir = args.dir.join(&target.dir);
+        let found = if app_dir.is_dir() {
+            dry_run::command(&target.build)
+        } else {
+            Err(errors::Error::new(
+                errors::ErrorKind::InvalidConfig,
+                format!("{} not found", app_dir.display()),
+            )
+            .into())
+        };
+        checks.record(&format!("{} build", target.label()), found);
+    }
+    let results_dir = args
+        .results_dir
+        .clone()
+        .unwrap_or_else(|| args.dir.join("results"));
+    checks.record("Results", dry_run::output_dir(&results_dir).await);
+
+    checks.plan(&[
+        ("Targets", targets::title(&targets)),
+        (
+            "Builds",
+            format!(
+                "{} run(s) of each target, {} in total",
+                args.runs,
+                args.runs * targets.len() as u64
+            ),
+        ),
+        (
+            "File cache",
+            args.fs_cache
+                .map_or("left as is", |policy| policy.name())
+                .to_string(),
+        ),
+        ("Results", results_dir.display().to_string()),
+    ]);
+    checks.finish()
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let args = Args::parse();
-
-    println!(
-        "{}",
-        "🔨 rari vs Next.js Build Time Comparison".cyan().bold()
+async fn run(args: &Args) -> Result<results::Run<BenchmarkResults>> {
+    let rules = gate::load_rules(&args.fail_if, args.thresholds.as_deref()).await?;
+    archive::validate_template(&args.archive.name_template)?;
+    let targets = targets::local(targets::load(&args.targets)?)?;
+    let mut metadata = run_metadata::collect(&args.dir, args.label.clone()).await;
+    metadata.profile = args.preset.profile.map(|preset| preset.name().to_string());
+    metadata.fs_cache = args.fs_cache.map(|policy| policy.name().to_string());
+    metadata.target_env = targets::env(&targets);
+    metadata.node_version = versions::node().await;
+    metadata.target_versions = versions::packages(&args.dir, &targets).await;
+    metadata.framework_versions = versions::frameworks(&targets, &metadata.target_versions);
+    let title = format!("🔨 {} Build Time Comparison", targets::title(&targets));
+
+    println!("{}", title.cyan().bold());
+    println!("{}", metadata.describe().dimmed());
+    metadata.host_load = preflight::check(&args.preflight).await?;
+    stream::emit(
+        "run_started",
+        json!({ "benchmark": "build-times", "metadata": &metadata }),
     );
     println!(
         "{}",
@@ -246,23 +464,193 @@ async fn main() -> Result<()> {
     println!("{}", "Starting build comparison in 3 seconds...".dimmed());
     tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
 
-    let rari_dir = args.dir.join("apps/rari");
-    let nextjs_dir = args.dir.join("apps/nextjs");
-
-    let rari_result = run_build("rari", &rari_dir, "pnpm run build").await?;
-    let nextjs_result = run_build("Next.js", &nextjs_dir, "pnpm run build").await?;
+    let mut build_results = Vec::new();
+    let mut failures = Vec::new();
+    let measured = interrupt::or_interrupted(async {
+        'targets: for target in &targets {
+            let app_dir = args.dir.join(&target.dir);
+            let mut runs_ms = Vec::new();
+            let mut result = loop {
+                if args.runs > 1 {
+                    println!(
+                        "\n{}",
+                        format!("🔁 Run {}/{}", runs_ms.len() + 1, args.runs).dimmed()
+                    );
+                }
+                cache::prepare(args.fs_cache, &app_dir).await?;
+                args.hooks.before_build(target, &app_dir).await?;
+                // A build cut short measures nothing, so it is stopped and dropped.
+                let Some(result) = interrupt::until_cancelled(run_build(
+                    target,
+                    &app_dir,
+                    runs_ms.last().map(|ms: &units::Millis| ms.as_duration()),
+                ))
+                .await
+                else {
+                    break 'targets;
+                };
+                let result = result?;
+                // A failed build is kept as is; there is nothing to repeat.
+                if !result.success {
+                    break result;
+                }
+                runs_ms.push(result.duration_ms);
+                if runs_ms.len() as u64 == args.runs {
+                    break result;
+                }
+            };
+            if !result.success {
+                failures.push(
+                    errors::Error::new(
+                        errors::ErrorKind::BuildFailed,
+                        format!("{} build failed", target.label()),
+                    )
+                    .target(&target.name)
+                    .into(),
+                );
+            }
+            if result.success && runs_ms.len() > 1 {
+                let values: Vec<f64> = runs_ms.iter().map(|ms| ms.0).collect();
+                if let Some(median) = stats::median(&values) {
+                    result.duration_ms = units::Millis(median);
+                }
+                result.stddev_ms = stats::stddev(&values).map(units::Millis);
+                result.runs_ms = runs_ms;
+            }
+            stream::emit(
+                "build_finished",
+                json!({ "framework": &target.name, "result": &result }),
+            );
+            build_results.push(result);
+        }
+        Ok::<_, anyhow::Error>(())
+    })
+    .await;
+    let partial = match measured {
+        Some(Ok(())) if !interrupt::cancelled() => false,
+        Some(Err(e)) if !interrupt::cancelled() => {
+            tracing::error!("{:#}", e);
+            failures.push(errors::RunError::from_error(&e));
+            true
+        }
+        _ => {
+            interrupt::announce("Saving the finished builds");
+            true
+        }
+    };
 
-    display_comparison(&rari_result, &nextjs_result);
+    if !partial {
+        display_comparison(&targets, &build_results, args.tie_threshold);
+    }
 
     let results = BenchmarkResults {
+        version: schema::VERSION,
         timestamp: time_utils::format_timestamp(SystemTime::now()),
-        rari: rari_result,
-        nextjs: nextjs_result,
+        metadata,
+        targets: targets::names(&targets),
+        results: targets::names(&targets)
+            .into_iter()
+            .zip(build_results)
+            .collect(),
+        partial,
+        errors: failures.clone(),
     };
 
-    save_results(&results, &args.dir).await?;
+    let file_name = archive::file_name(
+        &args.archive.name_template,
+        metrics::ResultKind::BuildTimes,
+        &results.timestamp,
+        &results.metadata,
+    )?;
+    let results_dir = args
+        .results_dir
+        .clone()
+        .unwrap_or_else(|| args.dir.join("results"));
+    let saved_path = results::save(&results, &results_dir, &file_name).await?;
+    if partial {
+        // Partial results stay out of the archive, the store and the gate.
+        return Ok(results::Run {
+            results,
+            path: saved_path,
+            partial,
+            gate_passed: true,
+            errors: failures,
+        });
+    }
+    if let Err(e) =
+        archive::finalize(&results_dir, metrics::ResultKind::BuildTimes, &args.archive).await
+    {
+        tracing::warn!("Failed to update results archive: {:#}", e);
+    }
+
+    let document = serde_json::to_value(&results)?;
+    let store_path = (!args.no_store).then(|| {
+        args.store
+            .clone()
+            .unwrap_or_else(|| store::default_path(&results_dir))
+    });
+    store::record(
+        store_path.as_deref(),
+        metrics::ResultKind::BuildTimes,
+        &saved_path,
+        &document,
+    );
+    influx::export(&args.influx, metrics::ResultKind::BuildTimes, &document).await;
 
-    println!("\n{}", "🎉 Build comparison completed!".green().bold());
+    let result_metrics = metrics::flatten(&document);
+    let gate_passed = gate::check(&rules, &result_metrics)?;
 
-    Ok(())
+    let mut artifact = saved_path.display().to_string();
+    if let Some(destination) = &args.upload {
+        let date = time_utils::format_date(SystemTime::now());
+        match upload::upload(destination, &saved_path, &date).await {
+            Ok(uri) => artifact = uri,
+            Err(e) => tracing::warn!("{:#}", e),
+        }
+    }
+
+    let summary = notify::Summary {
+        title,
+        timestamp: results.timestamp.clone(),
+        targets: targets.clone(),
+        deltas: vec![notify::Delta {
+            label: "Build time".to_string(),
+            unit: units::Unit::Milliseconds,
+            values: targets
+                .iter()
+                .map(|target| results.results[&target.name].duration_ms.0)
+                .collect(),
+            lower_is_better: true,
+        }],
+        gate_passed: (!rules.is_empty()).then_some(gate_passed),
+        artifact: args.artifact_url.clone().unwrap_or(artifact),
+    };
+
+    notify::notify(args.notify_webhook.as_deref(), &summary).await;
+    if let Err(e) = publish::publish(&args.github, "benchmarks/build-times", &summary).await {
+        tracing::warn!("{:#}", e);
+    }
+
+    args.hooks.after_run(&saved_path).await?;
+    output::finish(&args.output, &summary.to_text(), &document)?;
+    stream::emit(
+        "run_finished",
+        json!({
+            "results": saved_path,
+            "artifact": summary.artifact,
+            "gatePassed": gate_passed,
+        }),
+    );
+
+    if gate_passed {
+        println!("\n{}", "🎉 Build comparison completed!".green().bold());
+    }
+
+    Ok(results::Run {
+        results,
+        path: saved_path,
+        partial,
+        gate_passed,
+        errors: failures,
+    })
 }
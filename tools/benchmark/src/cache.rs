@@ -0,0 +1,111 @@
+use crate::units;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use colored::Colorize;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsCache {
+    /// Read every file of the app so it is served from the page cache.
+    Warm,
+    /// Flush the OS page cache (needs root on Linux, sudo for `purge` on macOS).
+    Drop,
+}
+
+impl FsCache {
+    pub fn name(self) -> &'static str {
+        match self {
+            FsCache::Warm => "warm",
+            FsCache::Drop => "drop",
+        }
+    }
+}
+
+/// Reads every regular file under `dir` without following symlinks, so
+/// pnpm's store links are left alone. Returns the file count and bytes read.
+fn read_tree(dir: &Path) -> Result<(usize, u64)> {
+    let mut pending: Vec<PathBuf> = vec![dir.to_path_buf()];
+    let mut files = 0;
+    let mut bytes = 0;
+    let mut buffer = vec![0; 64 * 1024];
+
+    while let Some(dir) = pending.pop() {
+        let entries =
+            std::fs::read_dir(&dir).with_context(|| format!("Failed to read {}", dir.display()))?;
+        for entry in entries.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                if entry.file_name() != ".git" {
+                    pending.push(entry.path());
+                }
+            } else if file_type.is_file()
+                && let Ok(mut file) = std::fs::File::open(entry.path())
+            {
+                files += 1;
+                while let Ok(read) = file.read(&mut buffer) {
+                    if read == 0 {
+                        break;
+                    }
+                    bytes += read as u64;
+                }
+            }
+        }
+    }
+
+    Ok((files, bytes))
+}
+
+async fn warm(dir: &Path) -> Result<()> {
+    let tree = dir.to_path_buf();
+    let (files, bytes) = tokio::task::spawn_blocking(move || read_tree(&tree)).await??;
+    println!(
+        "{} Warmed {} files ({}) in {}",
+        "🔥".dimmed(),
+        files,
+        units::bytes(bytes as f64),
+        dir.display()
+    );
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+async fn drop_caches() -> Result<()> {
+    unsafe { libc::sync() };
+    tokio::fs::write("/proc/sys/vm/drop_caches", "3")
+        .await
+        .context("Failed to drop the page cache; --fs-cache drop needs root")?;
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+async fn drop_caches() -> Result<()> {
+    let status = tokio::process::Command::new("purge")
+        .status()
+        .await
+        .context("Failed to run purge")?;
+    if !status.success() {
+        anyhow::bail!("purge failed ({}); --fs-cache drop needs sudo", status);
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+async fn drop_caches() -> Result<()> {
+    anyhow::bail!("--fs-cache drop is only supported on Linux and macOS")
+}
+
+/// Applies the cache policy before a build or server start of the app in `dir`.
+pub async fn prepare(policy: Option<FsCache>, dir: &Path) -> Result<()> {
+    match policy {
+        None => Ok(()),
+        Some(FsCache::Warm) => warm(dir).await,
+        Some(FsCache::Drop) => {
+            drop_caches().await?;
+            println!("{} Dropped OS file caches", "🧊".dimmed());
+            Ok(())
+        }
+    }
+}
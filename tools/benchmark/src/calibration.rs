@@ -0,0 +1,133 @@
+use crate::stats;
+use crate::units::{self, Millis};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct CalibrationArgs {
+    #[arg(
+        long,
+        help = "First time requests to a local server that answers instantly, to measure the harness's own overhead, and record it with the results"
+    )]
+    pub calibrate: bool,
+}
+
+/// What the harness itself adds to every measured time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Overhead {
+    /// Requests to the no-op server, timed exactly as the targets are.
+    pub round_trip: stats::Latency,
+    /// Building the HTTP client.
+    #[serde(rename = "clientSetupMs")]
+    pub client_setup: Millis,
+    /// The smallest step the clock can measure.
+    #[serde(rename = "timerResolutionMs")]
+    pub timer_resolution: Millis,
+    /// Serializing one scenario's metrics.
+    #[serde(rename = "serializationMs")]
+    pub serialization: Millis,
+}
+
+impl Overhead {
+    /// How much round trips to a server that does nothing vary; differences
+    /// between targets below this are the harness's noise, not theirs.
+    pub fn noise_floor(&self) -> Millis {
+        Millis(self.round_trip.p95 - self.round_trip.min)
+    }
+
+    pub fn print(&self) {
+        println!("\n{}", "🧭 Harness overhead".bold());
+        println!(
+            "  Round trip:  avg {}, p50 {}, p95 {}",
+            units::duration_ms(self.round_trip.avg),
+            units::duration_ms(self.round_trip.p50),
+            units::duration_ms(self.round_trip.p95)
+        );
+        println!("  Client:      {} to build", self.client_setup);
+        // Well under a microsecond on most hosts, too fine for `Millis`'s display.
+        println!(
+            "  Timer:       {:.0}ns resolution",
+            self.timer_resolution.0 * 1_000_000.0
+        );
+        println!("  Serializing: {} per scenario", self.serialization);
+    }
+}
+
+/// A local HTTP server that answers every request at once with an empty
+/// body, keeping connections alive like a real server.
+pub struct NoopServer {
+    pub url: String,
+    task: JoinHandle<()>,
+}
+
+impl NoopServer {
+    pub async fn start() -> Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", 0))
+            .await
+            .context("Failed to start the calibration server")?;
+        let url = format!("http://{}/", listener.local_addr()?);
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(answer(stream));
+            }
+        });
+        Ok(NoopServer { url, task })
+    }
+}
+
+impl Drop for NoopServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Answers each request on `stream` as soon as its headers are in; the
+/// harness only sends bodiless GETs.
+async fn answer(mut stream: TcpStream) {
+    const RESPONSE: &[u8] = b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n";
+    let mut pending = Vec::new();
+    let mut buffer = [0; 4096];
+    loop {
+        match stream.read(&mut buffer).await {
+            Ok(0) | Err(_) => return,
+            Ok(read) => pending.extend_from_slice(&buffer[..read]),
+        }
+        while let Some(end) = pending.windows(4).position(|window| window == b"\r\n\r\n") {
+            pending.drain(..end + 4);
+            if stream.write_all(RESPONSE).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// The average time `f` takes over `calls` calls.
+pub fn per_call<T>(calls: u32, mut f: impl FnMut() -> T) -> Millis {
+    let start = Instant::now();
+    for _ in 0..calls {
+        std::hint::black_box(f());
+    }
+    Millis(Millis::since(start).0 / f64::from(calls.max(1)))
+}
+
+/// The smallest non-zero difference between two readings of the clock.
+pub fn timer_resolution() -> Millis {
+    (0..10_000)
+        .map(|_| {
+            let start = Instant::now();
+            loop {
+                let elapsed = start.elapsed();
+                if !elapsed.is_zero() {
+                    break Millis::from_duration(elapsed);
+                }
+            }
+        })
+        .min_by(Millis::total_cmp)
+        .unwrap_or_default()
+}
@@ -0,0 +1,155 @@
+use anyhow::{Result, anyhow};
+use plotters::prelude::*;
+use std::path::{Path, PathBuf};
+
+const SERIES_COLORS: [RGBColor; 4] = [
+    RGBColor(232, 93, 36),
+    RGBColor(34, 139, 34),
+    RGBColor(30, 110, 200),
+    RGBColor(150, 80, 190),
+];
+
+pub struct LatencySeries<'a> {
+    pub name: &'a str,
+    pub points: Vec<(f64, f64)>,
+}
+
+/// The suffix of every chart a benchmark saves with [`chart_path`].
+pub const SUFFIXES: [&str; 3] = ["latency", "average", "throughput"];
+
+pub fn chart_path(json_path: &Path, suffix: &str) -> PathBuf {
+    let stem = json_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("results");
+    json_path.with_file_name(format!("{}-{}.svg", stem, suffix))
+}
+
+pub fn latency_distribution_chart(
+    path: &Path,
+    title: &str,
+    series: &[LatencySeries],
+) -> Result<()> {
+    let max_latency = series
+        .iter()
+        .flat_map(|s| s.points.iter().map(|(_, ms)| *ms))
+        .fold(0.0, f64::max);
+    let y_max = if max_latency > 0.0 {
+        max_latency * 1.1
+    } else {
+        1.0
+    };
+
+    let x_min = series
+        .iter()
+        .flat_map(|s| s.points.iter().map(|(p, _)| *p))
+        .fold(100.0, f64::min);
+    let x_min = (x_min - 5.0).max(0.0);
+
+    let root = SVGBackend::new(path, (900, 540)).into_drawing_area();
+    root.fill(&WHITE).map_err(|e| anyhow!("{}", e))?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption(title, ("sans-serif", 24))
+        .margin(20)
+        .x_label_area_size(40)
+        .y_label_area_size(70)
+        .build_cartesian_2d(x_min..100.0, 0.0..y_max)
+        .map_err(|e| anyhow!("{}", e))?;
+
+    chart
+        .configure_mesh()
+        .x_desc("Percentile")
+        .y_desc("Latency (ms)")
+        .draw()
+        .map_err(|e| anyhow!("{}", e))?;
+
+    for (i, s) in series.iter().enumerate() {
+        let color = SERIES_COLORS[i % SERIES_COLORS.len()];
+        chart
+            .draw_series(LineSeries::new(
+                s.points.iter().copied(),
+                color.stroke_width(2),
+            ))
+            .map_err(|e| anyhow!("{}", e))?
+            .label(s.name)
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+        chart
+            .draw_series(
+                s.points
+                    .iter()
+                    .map(|&(p, ms)| Circle::new((p, ms), 3, color.filled())),
+            )
+            .map_err(|e| anyhow!("{}", e))?;
+    }
+
+    chart
+        .configure_series_labels()
+        .position(SeriesLabelPosition::UpperLeft)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()
+        .map_err(|e| anyhow!("{}", e))?;
+
+    root.present().map_err(|e| anyhow!("{}", e))?;
+    Ok(())
+}
+
+pub fn bar_chart(path: &Path, title: &str, y_desc: &str, bars: &[(&str, f64)]) -> Result<()> {
+    let max_value = bars.iter().map(|(_, v)| *v).fold(0.0, f64::max);
+    let y_max = if max_value > 0.0 {
+        max_value * 1.15
+    } else {
+        1.0
+    };
+
+    let root = SVGBackend::new(path, (900, 540)).into_drawing_area();
+    root.fill(&WHITE).map_err(|e| anyhow!("{}", e))?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption(title, ("sans-serif", 24))
+        .margin(20)
+        .x_label_area_size(40)
+        .y_label_area_size(90)
+        .build_cartesian_2d((0..bars.len()).into_segmented(), 0.0..y_max)
+        .map_err(|e| anyhow!("{}", e))?;
+
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .x_label_formatter(&|x| match x {
+            SegmentValue::CenterOf(i) => bars.get(*i).map_or(String::new(), |(n, _)| n.to_string()),
+            _ => String::new(),
+        })
+        .y_desc(y_desc)
+        .draw()
+        .map_err(|e| anyhow!("{}", e))?;
+
+    chart
+        .draw_series(
+            Histogram::vertical(&chart)
+                .margin(60)
+                .style_func(|x, _| {
+                    let i = match x {
+                        SegmentValue::Exact(i) | SegmentValue::CenterOf(i) => *i,
+                        SegmentValue::Last => 0,
+                    };
+                    SERIES_COLORS[i % SERIES_COLORS.len()].filled()
+                })
+                .data(bars.iter().enumerate().map(|(i, (_, value))| (i, *value))),
+        )
+        .map_err(|e| anyhow!("{}", e))?;
+
+    chart
+        .draw_series(bars.iter().enumerate().map(|(i, (_, value))| {
+            Text::new(
+                format!("{:.2}", value),
+                (SegmentValue::CenterOf(i), *value + y_max * 0.06),
+                ("sans-serif", 16),
+            )
+        }))
+        .map_err(|e| anyhow!("{}", e))?;
+
+    root.present().map_err(|e| anyhow!("{}", e))?;
+    Ok(())
+}
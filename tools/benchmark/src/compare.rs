@@ -0,0 +1,144 @@
+use anyhow::Result;
+use clap::Parser;
+use colored::Colorize;
+use rari_benchmark::{errors, logging, metrics, time_utils};
+use rari_benchmark_results::compare::{self, Change, MetricDelta};
+use std::path::PathBuf;
+
+#[derive(Parser, Debug)]
+#[command(name = "compare")]
+#[command(about = "Compare two benchmark result files of the same type")]
+struct Args {
+    baseline: PathBuf,
+    candidate: PathBuf,
+    #[arg(short, long, default_value = "1.0")]
+    threshold: f64,
+    #[arg(short, long)]
+    metric: Option<String>,
+    #[arg(long)]
+    all: bool,
+    #[command(flatten)]
+    log: logging::LogArgs,
+}
+
+fn display_deltas(deltas: &[MetricDelta]) {
+    let metric_width = deltas
+        .iter()
+        .map(|d| d.metric.chars().count())
+        .max()
+        .unwrap_or(6)
+        .max(6);
+
+    println!(
+        "\n  {:<metric_width$}  {:>12}  {:>12}  {:>9}",
+        "Metric".bold(),
+        "Baseline".bold(),
+        "Candidate".bold(),
+        "Change".bold(),
+        metric_width = metric_width
+    );
+
+    for delta in deltas {
+        let change = delta
+            .change_pct
+            .map_or("n/a".to_string(), |pct| format!("{:+.1}%", pct));
+        let change = format!("{:>9}", change);
+        let (change, marker) = match delta.change {
+            Change::Regression => (change.red(), "▲ regression".red()),
+            Change::Improvement => (change.green(), "▼ improvement".green()),
+            Change::Unchanged => (change.dimmed(), "".normal()),
+        };
+
+        println!(
+            "  {:<metric_width$}  {:>12}  {:>12}  {}  {}",
+            delta.metric,
+            compare::format_value(&delta.metric, delta.baseline),
+            compare::format_value(&delta.metric, delta.candidate),
+            change,
+            marker,
+            metric_width = metric_width
+        );
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run().await {
+        errors::exit(e);
+    }
+}
+
+async fn run() -> Result<()> {
+    let args = Args::parse();
+    logging::init(&args.log, 0)?;
+
+    println!("{}", "🔍 Benchmark Result Comparison".cyan().bold());
+
+    let baseline = metrics::read_result(&args.baseline).await?;
+    let candidate = metrics::read_result(&args.candidate).await?;
+
+    if baseline.kind != candidate.kind {
+        anyhow::bail!(
+            "Cannot compare {} results with {} results",
+            baseline.kind.title(),
+            candidate.kind.title()
+        );
+    }
+
+    println!(
+        "{}",
+        format!(
+            "{}: {} ({}) → {} ({})",
+            baseline.kind.title(),
+            baseline.path.display(),
+            time_utils::to_local(&baseline.timestamp),
+            candidate.path.display(),
+            time_utils::to_local(&candidate.timestamp)
+        )
+        .dimmed()
+    );
+
+    if baseline.mode != candidate.mode {
+        tracing::warn!(
+            "Baseline was recorded in {} mode but the candidate in {} mode",
+            baseline.mode.as_deref().unwrap_or("an unknown"),
+            candidate.mode.as_deref().unwrap_or("an unknown")
+        );
+    }
+
+    let deltas = compare::deltas(
+        &baseline.metrics,
+        &candidate.metrics,
+        &compare::Options {
+            threshold: args.threshold,
+            metric: args.metric.clone(),
+            all: args.all,
+        },
+    );
+    if deltas.is_empty() {
+        println!("\n{}", "No matching metrics to compare".dimmed());
+        return Ok(());
+    }
+
+    display_deltas(&deltas);
+
+    let regressions = deltas
+        .iter()
+        .filter(|d| matches!(d.change, Change::Regression))
+        .count();
+    let improvements = deltas
+        .iter()
+        .filter(|d| matches!(d.change, Change::Improvement))
+        .count();
+
+    println!(
+        "\n{} {} regressions, {} improvements, {} unchanged (threshold ±{}%)",
+        "📊".bold(),
+        regressions.to_string().red().bold(),
+        improvements.to_string().green().bold(),
+        deltas.len() - regressions - improvements,
+        args.threshold
+    );
+
+    Ok(())
+}
@@ -0,0 +1,260 @@
+use crate::containers::{ContainerStats, StatsSampler};
+use crate::errors::{self, Error, ErrorKind};
+use crate::interrupt;
+use crate::probe::{self, ProbeArgs};
+use crate::targets::Target;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::sync::watch;
+
+static RUNNING: Mutex<Option<(PathBuf, String)>> = Mutex::new(None);
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ComposeArgs {
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["manage_servers", "ssh"],
+        help = "Bring up every target with Docker Compose, benchmark them, and tear them down"
+    )]
+    pub compose: Option<PathBuf>,
+}
+
+pub struct ComposeEnv {
+    file: PathBuf,
+    project: String,
+    containers: Vec<(String, String)>,
+}
+
+fn compose_command(file: &Path, project: &str) -> Command {
+    let mut command = Command::new("docker");
+    command
+        .arg("compose")
+        .arg("-f")
+        .arg(file)
+        .arg("-p")
+        .arg(project);
+    command
+}
+
+async fn compose_output(file: &Path, project: &str, args: &[&str]) -> Result<String> {
+    let output = compose_command(file, project)
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| {
+            errors::spawn_error(e, "Failed to run docker compose. Is Docker installed?")
+        })?;
+    if !output.status.success() {
+        return Err(Error::new(
+            ErrorKind::ToolFailed,
+            format!(
+                "docker compose {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        )
+        .into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn down_blocking(file: &Path, project: &str) {
+    let _ = std::process::Command::new("docker")
+        .arg("compose")
+        .arg("-f")
+        .arg(file)
+        .arg("-p")
+        .arg(project)
+        .args(["down", "--volumes", "--remove-orphans"])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status();
+}
+
+async fn host_port(file: &Path, project: &str, service: &str, container_port: u16) -> Result<u16> {
+    let mapping = compose_output(
+        file,
+        project,
+        &["port", service, &container_port.to_string()],
+    )
+    .await?;
+    mapping
+        .lines()
+        .next()
+        .and_then(|line| line.rsplit(':').next())
+        .and_then(|port| port.parse().ok())
+        .with_context(|| {
+            format!(
+                "Service {} does not publish container port {} (got {:?})",
+                service, container_port, mapping
+            )
+        })
+}
+
+/// Starts the compose project, if `--compose` was given, resolves the
+/// published port of each target's service, and waits until they pass the
+/// readiness check. Each target's port is the one its app listens on inside
+/// the container and is replaced by the published host port.
+#[tracing::instrument(name = "compose_up", skip_all)]
+pub async fn up(
+    args: &ComposeArgs,
+    probe_args: &ProbeArgs,
+    targets: &mut [Target],
+) -> Result<Option<ComposeEnv>> {
+    let Some(file) = args.compose.clone() else {
+        return Ok(None);
+    };
+    let project = format!("rari-benchmark-{}", std::process::id());
+
+    interrupt::on_interrupt("stopping containers", || {
+        if let Some((file, project)) = RUNNING.lock().unwrap().take() {
+            down_blocking(&file, &project);
+        }
+    });
+
+    println!(
+        "{} Starting {} with Docker Compose (project {})",
+        "🐳".dimmed(),
+        file.display(),
+        project
+    );
+    *RUNNING.lock().unwrap() = Some((file.clone(), project.clone()));
+    let mut env = ComposeEnv {
+        file,
+        project,
+        containers: Vec::new(),
+    };
+
+    let status = compose_command(&env.file, &env.project)
+        .args(["up", "--detach", "--build"])
+        .status()
+        .await
+        .map_err(|e| {
+            errors::spawn_error(e, "Failed to run docker compose. Is Docker installed?")
+        })?;
+    if !status.success() {
+        return Err(Error::new(
+            ErrorKind::ToolFailed,
+            format!("docker compose up failed ({})", status),
+        )
+        .into());
+    }
+
+    for target in targets.iter_mut().filter(|target| !target.is_remote()) {
+        let service = target.compose_service();
+        let id = compose_output(&env.file, &env.project, &["ps", "--quiet", service]).await?;
+        let id = id
+            .lines()
+            .next()
+            .with_context(|| format!("No running container for service {}", service))?;
+        env.containers.push((target.name.clone(), id.to_string()));
+
+        let port = host_port(&env.file, &env.project, service, target.port).await?;
+        println!(
+            "{} {} published on port {}",
+            "🔌".dimmed(),
+            target.label(),
+            port
+        );
+        target.port = port;
+    }
+
+    for target in targets.iter() {
+        probe::wait_until_ready(target, &target.base_url(), probe_args, true, || Ok(())).await?;
+    }
+
+    Ok(Some(env))
+}
+
+fn parse_percent(value: &str) -> Option<f64> {
+    value.trim().trim_end_matches('%').parse().ok()
+}
+
+fn parse_size(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let multiplier = match unit {
+        "" | "B" => 1.0,
+        "kB" | "KB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        "KiB" => 1024.0,
+        "MiB" => 1024.0 * 1024.0,
+        "GiB" => 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some(number.trim().parse::<f64>().ok()? * multiplier)
+}
+
+async fn sample(containers: &[(String, String)], stats: &mut BTreeMap<String, ContainerStats>) {
+    let mut command = Command::new("docker");
+    command.args(["stats", "--no-stream", "--format", "{{json .}}"]);
+    for (_, id) in containers {
+        command.arg(id);
+    }
+    let Ok(output) = command.output().await else {
+        return;
+    };
+
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Ok(entry) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        let id = entry["ID"].as_str().unwrap_or_default();
+        let Some((framework, _)) = containers
+            .iter()
+            .find(|(_, container)| container.starts_with(id) || id.starts_with(container.as_str()))
+        else {
+            continue;
+        };
+        let cpu = entry["CPUPerc"].as_str().and_then(parse_percent);
+        let memory = entry["MemUsage"]
+            .as_str()
+            .and_then(|usage| usage.split('/').next())
+            .and_then(parse_size);
+        if let (Some(cpu), Some(memory)) = (cpu, memory) {
+            stats.entry(framework.clone()).or_default().add(cpu, memory);
+        }
+    }
+}
+
+impl ComposeEnv {
+    /// Samples `docker stats` for every target's container until `StatsSampler::finish`.
+    pub fn sample_stats(&self) -> StatsSampler {
+        let containers = self.containers.clone();
+        let (stop, mut stopped) = watch::channel(false);
+        let task = tokio::spawn(async move {
+            let mut stats = BTreeMap::new();
+            loop {
+                sample(&containers, &mut stats).await;
+                tokio::select! {
+                    _ = stopped.changed() => break,
+                    _ = tokio::time::sleep(Duration::from_secs(1)) => {}
+                }
+            }
+            stats
+        });
+        StatsSampler { stop, task }
+    }
+}
+
+impl Drop for ComposeEnv {
+    fn drop(&mut self) {
+        println!(
+            "{} Stopping Docker Compose project {}",
+            "🐳".dimmed(),
+            self.project
+        );
+        down_blocking(&self.file, &self.project);
+        RUNNING.lock().unwrap().take();
+    }
+}
@@ -0,0 +1,174 @@
+use crate::stats::Latency;
+use crate::targets::Target;
+use crate::units;
+use anyhow::Result;
+use colored::Colorize;
+use reqwest::header;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+use tabled::builder::Builder;
+
+/// What each payload is requested as; `identity` is the baseline.
+pub const ENCODINGS: [&str; 3] = ["identity", "gzip", "br"];
+
+/// One payload as served for one `Accept-Encoding`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Encoded {
+    /// The `Content-Encoding` the server answered with, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_encoding: Option<String>,
+    /// Mean bytes on the wire.
+    pub body_bytes: usize,
+    pub errors: usize,
+    pub latency: Latency,
+    /// Uncompressed over compressed size; not set for `identity`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ratio: Option<f64>,
+    /// Mean latency over `identity`'s; not set for `identity`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub added_ms: Option<f64>,
+}
+
+/// A payload's measurements keyed by encoding.
+pub type Encodings = BTreeMap<String, Encoded>;
+
+impl Encoded {
+    fn describe(&self) -> String {
+        let (Some(ratio), Some(added_ms)) = (self.ratio, self.added_ms) else {
+            return format!(
+                "{} · {}",
+                units::bytes(self.body_bytes as f64),
+                units::duration_ms(self.latency.avg)
+            );
+        };
+        if self.content_encoding.is_none() {
+            return "not compressed".yellow().to_string();
+        }
+        let added = if added_ms < 0.0 {
+            format!("-{}", units::duration_ms(-added_ms))
+        } else {
+            format!("+{}", units::duration_ms(added_ms))
+        };
+        format!("{:.1}× smaller · {}", ratio, added)
+    }
+}
+
+/// Requests `path` with each of [`ENCODINGS`] in turn, `requests` times each
+/// after one untimed round, and times every body to its last byte.
+pub async fn measure(target: &Target, path: &str, requests: usize) -> Result<Encodings> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()?;
+    let url = format!("{}{}", target.base_url(), path);
+
+    let mut times = vec![Vec::new(); ENCODINGS.len()];
+    let mut sizes = vec![Vec::new(); ENCODINGS.len()];
+    let mut errors = vec![0; ENCODINGS.len()];
+    let mut content_encodings = vec![None; ENCODINGS.len()];
+    // Alternating encodings keeps drift in the server from favouring one.
+    for round in 0..=requests {
+        for (index, encoding) in ENCODINGS.iter().enumerate() {
+            let start = Instant::now();
+            let answered = match client
+                .get(&url)
+                .header(header::ACCEPT_ENCODING, *encoding)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+            {
+                Ok(response) => {
+                    let content_encoding = response
+                        .headers()
+                        .get(header::CONTENT_ENCODING)
+                        .and_then(|value| value.to_str().ok())
+                        .filter(|value| *value != "identity")
+                        .map(str::to_string);
+                    response
+                        .bytes()
+                        .await
+                        .map(|body| (content_encoding, body.len()))
+                }
+                Err(e) => Err(e),
+            };
+            let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+            if round == 0 {
+                continue;
+            }
+            match answered {
+                Ok((content_encoding, size)) => {
+                    tracing::debug!(
+                        "{:<8} {:>4} {} in {}",
+                        encoding,
+                        round,
+                        units::bytes(size as f64),
+                        units::duration_ms(elapsed)
+                    );
+                    times[index].push(elapsed);
+                    sizes[index].push(size);
+                    content_encodings[index] = content_encoding;
+                }
+                Err(e) => {
+                    tracing::debug!("{:<8} {:>4} failed: {}", encoding, round, e);
+                    errors[index] += 1;
+                }
+            }
+        }
+    }
+
+    let mut encoded = BTreeMap::new();
+    for (index, encoding) in ENCODINGS.iter().enumerate() {
+        let Some(latency) = Latency::from_times(&times[index]) else {
+            anyhow::bail!("No successful {} requests", encoding);
+        };
+        encoded.insert(
+            encoding.to_string(),
+            Encoded {
+                content_encoding: content_encodings[index].take(),
+                body_bytes: sizes[index].iter().sum::<usize>() / sizes[index].len(),
+                errors: errors[index],
+                latency,
+                ratio: None,
+                added_ms: None,
+            },
+        );
+    }
+    let baseline = encoded["identity"].clone();
+    for (encoding, measured) in encoded.iter_mut() {
+        if encoding != "identity" {
+            measured.ratio = Some(baseline.body_bytes as f64 / measured.body_bytes.max(1) as f64);
+            measured.added_ms = Some(measured.latency.avg - baseline.latency.avg);
+        }
+    }
+    Ok(encoded)
+}
+
+/// One row per payload and encoding: each target's size and latency
+/// uncompressed, then how much smaller and how much slower each encoding is.
+/// There is no winner; a higher ratio bought with added latency is a trade-off.
+pub fn display(targets: &[Target], rows: &[(&str, Vec<Option<&Encodings>>)]) {
+    println!("\n{}", "🗜️ Compression".bold());
+
+    let mut header = vec!["Payload".to_string(), "Encoding".to_string()];
+    header.extend(targets.iter().map(|target| target.label().to_string()));
+
+    let mut builder = Builder::default();
+    builder.push_record(header);
+    for (name, measured) in rows {
+        for encoding in ENCODINGS {
+            let mut record = vec![name.to_string(), encoding.to_string()];
+            record.extend(measured.iter().map(|encodings| {
+                encodings
+                    .and_then(|encodings| encodings.get(encoding))
+                    .map_or("-".to_string(), Encoded::describe)
+            }));
+            builder.push_record(record);
+        }
+    }
+    println!("\n{}", builder.build());
+    println!(
+        "{}",
+        "Sizes are bytes on the wire; added latency is relative to identity".dimmed()
+    );
+}
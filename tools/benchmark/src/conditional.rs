@@ -0,0 +1,188 @@
+use crate::stats::Latency;
+use crate::targets::Target;
+use crate::tolerance;
+use crate::units;
+use anyhow::Result;
+use colored::Colorize;
+use reqwest::StatusCode;
+use reqwest::header::{self, HeaderMap};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tabled::builder::Builder;
+
+/// How one asset answered requests carrying the validators of its first
+/// response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Revalidation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_control: Option<String>,
+    pub requests: usize,
+    pub not_modified: usize,
+    pub errors: usize,
+    /// Mean body size of the conditional responses; 0 when all were 304s.
+    pub body_bytes: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency: Option<Latency>,
+}
+
+impl Revalidation {
+    pub fn has_validator(&self) -> bool {
+        self.etag.is_some() || self.last_modified.is_some()
+    }
+
+    /// Every conditional request came back as a 304.
+    pub fn revalidates(&self) -> bool {
+        self.has_validator()
+            && self.errors == 0
+            && self.requests > 0
+            && self.not_modified == self.requests
+    }
+
+    fn describe(&self) -> String {
+        if !self.has_validator() {
+            return "no ETag or Last-Modified".to_string();
+        }
+        let Some(latency) = &self.latency else {
+            return "all requests failed".to_string();
+        };
+        let answer = if self.revalidates() {
+            "304".to_string()
+        } else {
+            format!("{}/{} 304", self.not_modified, self.requests)
+        };
+        format!("{} · {}", answer, units::duration_ms(latency.avg))
+    }
+}
+
+fn header(headers: &HeaderMap, name: header::HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Fetches `path` once for its validators, then sends `requests` requests
+/// with `If-None-Match` and `If-Modified-Since` and times the answers.
+pub async fn check(target: &Target, path: &str, requests: usize) -> Result<Revalidation> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()?;
+    let url = format!("{}{}", target.base_url(), path);
+
+    let response = client.get(&url).send().await?.error_for_status()?;
+    let etag = header(response.headers(), header::ETAG);
+    let last_modified = header(response.headers(), header::LAST_MODIFIED);
+    let cache_control = header(response.headers(), header::CACHE_CONTROL);
+    response.bytes().await?;
+
+    let mut revalidation = Revalidation {
+        etag,
+        last_modified,
+        cache_control,
+        requests: 0,
+        not_modified: 0,
+        errors: 0,
+        body_bytes: 0,
+        latency: None,
+    };
+    if !revalidation.has_validator() {
+        return Ok(revalidation);
+    }
+
+    let mut times = Vec::new();
+    let mut sizes = Vec::new();
+    for i in 0..requests {
+        let mut request = client.get(&url);
+        if let Some(etag) = &revalidation.etag {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &revalidation.last_modified {
+            request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+        }
+
+        let start = Instant::now();
+        let answered = match request.send().await {
+            Ok(response) => {
+                let status = response.status();
+                response.bytes().await.map(|body| (status, body.len()))
+            }
+            Err(e) => Err(e),
+        };
+        let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+        match answered {
+            Ok((status, size)) if status == StatusCode::NOT_MODIFIED || status.is_success() => {
+                tracing::debug!(
+                    "conditional {:>4} {} in {}",
+                    i + 1,
+                    status,
+                    units::duration_ms(elapsed)
+                );
+                if status == StatusCode::NOT_MODIFIED {
+                    revalidation.not_modified += 1;
+                }
+                times.push(elapsed);
+                sizes.push(size);
+            }
+            answered => {
+                let outcome = match answered {
+                    Ok((status, _)) => status.to_string(),
+                    Err(e) => e.to_string(),
+                };
+                tracing::debug!("conditional {:>4} failed: {}", i + 1, outcome);
+                revalidation.errors += 1;
+            }
+        }
+    }
+
+    revalidation.requests = times.len();
+    if !times.is_empty() {
+        revalidation.body_bytes = sizes.iter().sum::<usize>() / sizes.len();
+    }
+    revalidation.latency = Latency::from_times(&times);
+    Ok(revalidation)
+}
+
+/// One row per asset: whether each target answered with 304s, and how fast.
+/// Only targets that revalidate every request compete for the win.
+pub fn display(targets: &[Target], rows: &[(&str, Vec<Option<&Revalidation>>)], tie_pct: f64) {
+    println!("\n{}", "🔁 Conditional Requests".bold());
+
+    let mut header = vec!["Asset".to_string()];
+    header.extend(targets.iter().map(|target| target.label().to_string()));
+    header.push("Winner".to_string());
+
+    let mut builder = Builder::default();
+    builder.push_record(header);
+    for (name, revalidations) in rows {
+        let mut record = vec![name.to_string()];
+        record.extend(revalidations.iter().map(|revalidation| match revalidation {
+            Some(revalidation) if revalidation.revalidates() => {
+                revalidation.describe().green().to_string()
+            }
+            Some(revalidation) => revalidation.describe().yellow().to_string(),
+            None => "-".to_string(),
+        }));
+
+        let competing: Vec<(usize, f64)> = revalidations
+            .iter()
+            .enumerate()
+            .filter_map(|(index, revalidation)| {
+                let revalidation = revalidation.filter(|r| r.revalidates())?;
+                Some((index, revalidation.latency.as_ref()?.avg))
+            })
+            .collect();
+        let averages: Vec<f64> = competing.iter().map(|(_, avg)| *avg).collect();
+        record.push(match tolerance::best(&averages, true, tie_pct) {
+            Some(best) => targets[competing[best].0].display(),
+            None if competing.is_empty() => "-".to_string(),
+            None => tolerance::TIE.to_string(),
+        });
+        builder.push_record(record);
+    }
+    println!("\n{}", builder.build());
+}
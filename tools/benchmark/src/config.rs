@@ -0,0 +1,390 @@
+use crate::errors::{self, ErrorKind};
+use crate::presets::Preset;
+use crate::{build_times, load_test, performance};
+use anyhow::{Context, Result};
+use clap::{ArgAction, CommandFactory, Parser, ValueEnum};
+use colored::Colorize;
+use serde::de::DeserializeOwned;
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+/// Read from the working directory when `--config` is not given.
+pub const DEFAULT_FILE: &str = "benchmark.toml";
+
+/// Benchmarks with a section of their own in the settings file.
+const SECTIONS: [&str; 6] = [
+    "browser",
+    "build-times",
+    "full",
+    "images",
+    "load-test",
+    "performance",
+];
+
+/// Flags of the benchmarks built as binaries of their own, whose commands
+/// the library can't see, where no library benchmark has them too. `parse`
+/// checks in debug builds that none is missing.
+const BINARY_FLAGS: [(&str, &[&str]); 3] = [
+    (
+        "browser",
+        &[
+            "chrome",
+            "lighthouse",
+            "lighthouse-preset",
+            "navigate",
+            "page",
+            "session-loads",
+            "sessions",
+        ],
+    ),
+    (
+        "full",
+        &[
+            "app-path",
+            "build-thresholds",
+            "install-command",
+            "load-thresholds",
+            "performance-thresholds",
+            "refs",
+            "repo",
+            "skip-build",
+        ],
+    ),
+    ("images", &["no-cache", "starts"]),
+];
+
+/// Keys that can be lists of tables, read from the settings file by the flag
+/// of the same name.
+const TABLE_KEYS: [&str; 2] = ["targets", "scenarios"];
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ConfigArgs {
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Settings file keyed by flag name; flags on the command line win [default: benchmark.toml, if present]"
+    )]
+    pub config: Option<PathBuf>,
+    #[arg(long, conflicts_with = "config", help = "Ignore benchmark.toml")]
+    pub no_config: bool,
+}
+
+impl ConfigArgs {
+    /// The flags that make another benchmark read the same settings.
+    pub fn forward(&self) -> Vec<OsString> {
+        match &self.config {
+            Some(file) => vec!["--config".into(), file.into()],
+            None if self.no_config => vec!["--no-config".into()],
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Parses the command line of `benchmark` with the settings file's flags in
+/// front of it, and the `--profile` preset's in front of those. Top-level keys
+/// apply to every benchmark that has the flag; keys in the benchmark's own
+/// section must all be its flags. A flag given on the command line replaces
+/// the file's value, lists included, and either replaces the profile's.
+pub fn parse<T: Parser>(benchmark: &str) -> Result<T> {
+    debug_assert!(
+        {
+            let known = all_flags();
+            T::command()
+                .get_arguments()
+                .filter_map(clap::Arg::get_long)
+                .all(|flag| known.contains(flag))
+        },
+        "a flag of {} is missing from BINARY_FLAGS",
+        benchmark
+    );
+    let argv: Vec<OsString> = std::env::args_os().collect();
+    let given = &argv[1..];
+    let settings = match path(given) {
+        Some(path) => {
+            let settings = (|| {
+                let contents = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                let file: Table = toml::from_str(&contents)
+                    .with_context(|| format!("Invalid {}", path.display()))?;
+                expand(&T::command(), &file, &path, benchmark, given)
+                    .with_context(|| format!("Invalid {}", path.display()))
+            })()
+            .map_err(|e| errors::classify(e, ErrorKind::InvalidConfig))?;
+            if !settings.is_empty() {
+                println!(
+                    "{}",
+                    format!("⚙️  Settings from {}", path.display()).dimmed()
+                );
+            }
+            settings
+        }
+        None => Vec::new(),
+    };
+
+    let mut args = vec![argv[0].clone()];
+    let explicit: Vec<OsString> = settings.into_iter().chain(given.iter().cloned()).collect();
+    if let Some((preset, flags)) = preset(&T::command(), benchmark, &explicit) {
+        println!(
+            "{}",
+            format!(
+                "🎚️  {} profile: {}",
+                preset.name(),
+                flags
+                    .iter()
+                    .map(|flag| flag.to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            )
+            .dimmed()
+        );
+        args.extend(flags);
+    }
+    args.extend(explicit);
+    Ok(parse_from(args))
+}
+
+/// The profile `args` name, the last one if several do, and the flags it
+/// sets for `benchmark` that `args` don't. An unknown profile is left for
+/// clap to report.
+fn preset(
+    command: &clap::Command,
+    benchmark: &str,
+    args: &[OsString],
+) -> Option<(Preset, Vec<OsString>)> {
+    let mut tokens = args
+        .iter()
+        .take_while(|arg| *arg != "--")
+        .filter_map(|arg| arg.to_str());
+    let mut named = None;
+    while let Some(token) = tokens.next() {
+        if token == "--profile" {
+            named = tokens.next();
+        } else if let Some(name) = token.strip_prefix("--profile=") {
+            named = Some(name);
+        }
+    }
+    let preset = Preset::from_str(named?, false).ok()?;
+    let flags = preset
+        .settings(benchmark)
+        .iter()
+        .filter_map(|(key, value)| {
+            let arg = command
+                .get_arguments()
+                .find(|arg| arg.get_long() == Some(key))?;
+            (!is_given(arg, args)).then(|| format!("--{}={}", key, value).into())
+        })
+        .collect::<Vec<OsString>>();
+    (!flags.is_empty()).then_some((preset, flags))
+}
+
+/// A flag's value that must be a finite number above zero.
+pub fn positive(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(number) if number.is_finite() && number > 0.0 => Ok(number),
+        Ok(_) => Err(format!("expected a number above 0, got {}", value)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// `T::parse_from`, exiting on invalid flags with the code of
+/// [`ErrorKind::InvalidConfig`] rather than clap's 2, which is the regression
+/// gate's.
+fn parse_from<T: Parser>(args: Vec<OsString>) -> T {
+    T::try_parse_from(args).unwrap_or_else(|e| {
+        if !e.use_stderr() {
+            e.exit();
+        }
+        let _ = e.print();
+        std::process::exit(ErrorKind::InvalidConfig.exit_code())
+    })
+}
+
+fn path(given: &[OsString]) -> Option<PathBuf> {
+    let mut args = given
+        .iter()
+        .take_while(|arg| *arg != "--")
+        .filter_map(|arg| arg.to_str());
+    while let Some(arg) = args.next() {
+        if arg == "--no-config" {
+            return None;
+        }
+        if arg == "--config" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(file) = arg.strip_prefix("--config=") {
+            return Some(PathBuf::from(file));
+        }
+    }
+    Path::new(DEFAULT_FILE)
+        .is_file()
+        .then(|| PathBuf::from(DEFAULT_FILE))
+}
+
+fn expand(
+    command: &clap::Command,
+    file: &Table,
+    path: &Path,
+    benchmark: &str,
+    given: &[OsString],
+) -> Result<Vec<OsString>> {
+    let section = file.get(benchmark).and_then(Value::as_table);
+    let known = all_flags();
+    let mut settings = Vec::new();
+    for (key, value) in file {
+        if value.is_table() {
+            if !SECTIONS.contains(&key.as_str()) {
+                anyhow::bail!(
+                    "[{}] is not a benchmark (expected one of: {})",
+                    key,
+                    SECTIONS.join(", ")
+                );
+            }
+            continue;
+        }
+        // The benchmark's own section wins over the shared keys.
+        if section.is_some_and(|section| section.contains_key(key)) {
+            continue;
+        }
+        match flag(command, key)? {
+            Some(arg) => settings.extend(setting(arg, key, value, path, given)?),
+            None if !known.contains(key) => anyhow::bail!("{}: no benchmark has this flag", key),
+            None => {}
+        }
+    }
+
+    for (key, value) in section.into_iter().flatten() {
+        let arg = flag(command, key)?
+            .with_context(|| format!("[{}] {}: no such flag", benchmark, key))?;
+        if is_table_list(value) {
+            anyhow::bail!("[{}] {}: list it at the top level", benchmark, key);
+        }
+        settings.extend(setting(arg, key, value, path, given)?);
+    }
+    Ok(settings)
+}
+
+fn flag<'a>(command: &'a clap::Command, key: &str) -> Result<Option<&'a clap::Arg>> {
+    if matches!(key, "config" | "no-config") {
+        anyhow::bail!("{}: only valid on the command line", key);
+    }
+    Ok(command
+        .get_arguments()
+        .find(|arg| arg.get_long() == Some(key)))
+}
+
+/// Every flag some benchmark has, so that a top-level key the running one
+/// lacks can be told apart from a typo.
+fn all_flags() -> HashSet<String> {
+    let commands = [
+        performance::Args::command(),
+        load_test::Args::command(),
+        build_times::Args::command(),
+    ];
+    let library = commands
+        .iter()
+        .flat_map(|command| command.get_arguments().filter_map(clap::Arg::get_long))
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    let binaries = BINARY_FLAGS
+        .iter()
+        .flat_map(|(_, flags)| flags.iter().map(|flag| flag.to_string()));
+    library.into_iter().chain(binaries).collect()
+}
+
+/// Whether `value` is a list of tables, such as `[[targets]]`.
+fn is_table_list(value: &Value) -> bool {
+    value
+        .as_array()
+        .and_then(|values| values.first())
+        .is_some_and(Value::is_table)
+}
+
+/// Whether the command line sets `arg` itself, by long or short name.
+fn is_given(arg: &clap::Arg, given: &[OsString]) -> bool {
+    given
+        .iter()
+        .take_while(|token| *token != "--")
+        .filter_map(|token| token.to_str())
+        .any(|token| match token.strip_prefix("--") {
+            Some(long) => arg.get_long() == long.split('=').next(),
+            None => {
+                token.starts_with('-')
+                    && arg.get_short().is_some()
+                    && token[1..].chars().next() == arg.get_short()
+            }
+        })
+}
+
+/// The command-line form of one key. A list of tables names the settings
+/// file itself, which the flag's reader deserializes.
+fn setting(
+    arg: &clap::Arg,
+    key: &str,
+    value: &Value,
+    path: &Path,
+    given: &[OsString],
+) -> Result<Vec<OsString>> {
+    if is_given(arg, given) {
+        return Ok(Vec::new());
+    }
+    let flag = format!("--{}", key);
+    if is_table_list(value) {
+        if !TABLE_KEYS.contains(&key) {
+            anyhow::bail!(
+                "{}: only {} can be lists of tables",
+                key,
+                TABLE_KEYS.join(" and ")
+            );
+        }
+        return Ok(vec![format!("{}={}", flag, path.display()).into()]);
+    }
+    if !arg.get_action().takes_values() {
+        return match (arg.get_action(), value) {
+            (ArgAction::Count, Value::Integer(count)) => {
+                let count = usize::try_from(*count)
+                    .with_context(|| format!("{}: expected a count", key))?;
+                Ok(vec![flag.into(); count])
+            }
+            (_, Value::Boolean(true)) => Ok(vec![flag.into()]),
+            (_, Value::Boolean(false)) => Ok(Vec::new()),
+            _ => anyhow::bail!("{}: expected true or false", key),
+        };
+    }
+
+    let values = match value {
+        Value::Array(values) => values.iter().collect(),
+        value => vec![value],
+    };
+    values
+        .into_iter()
+        .map(|value| {
+            let value = match value {
+                Value::String(value) => value.clone(),
+                Value::Integer(value) => value.to_string(),
+                Value::Float(value) if value.is_finite() => value.to_string(),
+                Value::Float(_) => anyhow::bail!("{}: expected a finite number", key),
+                Value::Boolean(value) => value.to_string(),
+                _ => anyhow::bail!("{}: expected a string, number, or list of them", key),
+            };
+            Ok(format!("{}={}", flag, value).into())
+        })
+        .collect()
+}
+
+/// Deserializes a file a flag names, as TOML if it ends in `.toml` and as
+/// JSON otherwise.
+pub fn read_file<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let parsed = if path
+        .extension()
+        .is_some_and(|extension| extension == "toml")
+    {
+        toml::from_str(&contents).map_err(anyhow::Error::from)
+    } else {
+        serde_json::from_str(&contents).map_err(anyhow::Error::from)
+    };
+    parsed.with_context(|| format!("Failed to parse {}", path.display()))
+}
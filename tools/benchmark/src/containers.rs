@@ -0,0 +1,68 @@
+use crate::targets::Target;
+use crate::units;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// CPU and memory of a server's container or process tree, as `docker stats`
+/// or a remote host reports them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContainerStats {
+    pub samples: usize,
+    pub avg_cpu_pct: f64,
+    pub peak_cpu_pct: f64,
+    pub avg_memory_bytes: f64,
+    pub peak_memory_bytes: f64,
+}
+
+impl ContainerStats {
+    pub fn add(&mut self, cpu_pct: f64, memory_bytes: f64) {
+        let n = self.samples as f64;
+        self.avg_cpu_pct = (self.avg_cpu_pct * n + cpu_pct) / (n + 1.0);
+        self.avg_memory_bytes = (self.avg_memory_bytes * n + memory_bytes) / (n + 1.0);
+        self.peak_cpu_pct = self.peak_cpu_pct.max(cpu_pct);
+        self.peak_memory_bytes = self.peak_memory_bytes.max(memory_bytes);
+        self.samples += 1;
+    }
+}
+
+pub struct StatsSampler {
+    pub stop: watch::Sender<bool>,
+    pub task: JoinHandle<BTreeMap<String, ContainerStats>>,
+}
+
+impl StatsSampler {
+    pub async fn finish(self) -> BTreeMap<String, ContainerStats> {
+        let _ = self.stop.send(true);
+        self.task.await.unwrap_or_default()
+    }
+}
+
+pub fn display(stats: &BTreeMap<String, ContainerStats>, targets: &[Target]) {
+    display_titled("🐳 Container Stats", stats, targets);
+}
+
+pub fn display_titled(title: &str, stats: &BTreeMap<String, ContainerStats>, targets: &[Target]) {
+    if stats.is_empty() {
+        return;
+    }
+
+    println!("\n{}", title.bold());
+    for target in targets {
+        let Some(stats) = stats.get(&target.name) else {
+            continue;
+        };
+        println!(
+            "  {}: CPU avg {:.1}% / peak {:.1}%, memory avg {} / peak {} ({} samples)",
+            target.label().bold(),
+            stats.avg_cpu_pct,
+            stats.peak_cpu_pct,
+            units::bytes(stats.avg_memory_bytes),
+            units::bytes(stats.peak_memory_bytes),
+            stats.samples
+        );
+    }
+}
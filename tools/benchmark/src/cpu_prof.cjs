@@ -0,0 +1,40 @@
+// Preloaded into managed Node.js servers by `load-test --cpu-prof`. The first
+// SIGUSR2 starts V8's CPU profiler and the next one stops it, writing the
+// profile to $RARI_BENCHMARK_CPU_PROF/<pid>.cpuprofile.
+const fs = require('node:fs')
+const inspector = require('node:inspector')
+const path = require('node:path')
+const process = require('node:process')
+
+const dir = process.env.RARI_BENCHMARK_CPU_PROF
+if (dir) {
+  const file = path.join(dir, `${process.pid}.cpuprofile`)
+  const session = new inspector.Session()
+  session.connect()
+
+  let profiling = false
+  process.on('SIGUSR2', () => {
+    if (!profiling) {
+      profiling = true
+      session.post('Profiler.enable', () => session.post('Profiler.start'))
+      return
+    }
+    profiling = false
+    session.post('Profiler.stop', (error, result) => {
+      if (error)
+        return
+      try {
+        // Renamed into place so a half-written profile is never read.
+        fs.writeFileSync(`${file}.tmp`, JSON.stringify(result.profile))
+        fs.renameSync(`${file}.tmp`, file)
+      }
+      catch {}
+    })
+  })
+
+  // Tells the benchmark which processes can be signalled safely.
+  try {
+    fs.writeFileSync(path.join(dir, `${process.pid}.ready`), '')
+  }
+  catch {}
+}
@@ -0,0 +1,170 @@
+use crate::errors::{Error, ErrorKind};
+use crate::profiles::{self, ProfileArtifact};
+use crate::targets::Target;
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const PRELOAD: &str = include_str!("cpu_prof.cjs");
+const DIR_ENV: &str = "RARI_BENCHMARK_CPU_PROF";
+/// How long a stopped profiler gets to serialize its profile.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Checks that the binary was built with the `profiling` feature.
+pub fn check() -> Result<()> {
+    if !cfg!(feature = "profiling") {
+        return Err(Error::new(
+            ErrorKind::InvalidConfig,
+            "--cpu-prof needs a build with the profiling feature: cargo build --features profiling",
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Preloads the profiler script into the named targets through `NODE_OPTIONS`
+/// and returns the directory each one writes its profiles to.
+pub fn inject(targets: &mut [Target], names: &[String]) -> Result<BTreeMap<String, PathBuf>> {
+    let root = std::env::temp_dir().join(format!("rari-benchmark-cpu-prof-{}", std::process::id()));
+    std::fs::create_dir_all(&root)
+        .with_context(|| format!("Failed to create {}", root.display()))?;
+    let preload = root.join("preload.cjs");
+    std::fs::write(&preload, PRELOAD)
+        .with_context(|| format!("Failed to write {}", preload.display()))?;
+
+    let mut dirs = BTreeMap::new();
+    for target in targets
+        .iter_mut()
+        .filter(|target| names.contains(&target.name))
+    {
+        let dir = root.join(&target.name);
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        let require = format!("--require \"{}\"", preload.display());
+        let options = match target.env.get("NODE_OPTIONS") {
+            Some(options) => format!("{} {}", options, require),
+            None => require,
+        };
+        target.env.insert("NODE_OPTIONS".to_string(), options);
+        target
+            .env
+            .insert(DIR_ENV.to_string(), dir.to_string_lossy().into_owned());
+        dirs.insert(target.name.clone(), dir);
+    }
+    Ok(dirs)
+}
+
+/// The Node processes in `dir` that loaded the script and are still running.
+fn ready_pids(dir: &Path) -> Vec<u32> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            name.to_str()?.strip_suffix(".ready")?.parse().ok()
+        })
+        .filter(|pid| alive(*pid))
+        .collect()
+}
+
+#[cfg(unix)]
+fn alive(pid: u32) -> bool {
+    unsafe { libc::kill(pid as libc::pid_t, 0) == 0 }
+}
+
+#[cfg(not(unix))]
+fn alive(_pid: u32) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn toggle(pids: &[u32]) {
+    for pid in pids {
+        unsafe {
+            libc::kill(*pid as libc::pid_t, libc::SIGUSR2);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn toggle(_pids: &[u32]) {}
+
+fn profile_path(dir: &Path, pid: u32) -> PathBuf {
+    dir.join(format!("{}.cpuprofile", pid))
+}
+
+/// V8's profiler running in every Node process of one server.
+pub struct Profiling {
+    target: String,
+    dir: PathBuf,
+    pids: Vec<u32>,
+}
+
+/// Starts the profiler in every Node process of `target` that loaded the script.
+pub fn start(target: &str, dir: &Path) -> Result<Profiling> {
+    let pids = ready_pids(dir);
+    if pids.is_empty() {
+        anyhow::bail!(
+            "No Node.js process of {} loaded the CPU profiler; is it a Node.js server?",
+            target
+        );
+    }
+    for pid in &pids {
+        let _ = std::fs::remove_file(profile_path(dir, *pid));
+    }
+    toggle(&pids);
+    Ok(Profiling {
+        target: target.to_string(),
+        dir: dir.to_path_buf(),
+        pids,
+    })
+}
+
+impl Profiling {
+    /// Stops the profilers and returns the largest profile. A server started
+    /// through pnpm also profiles pnpm itself, which sits idle.
+    pub async fn finish(self) -> Result<PathBuf> {
+        toggle(&self.pids);
+        let deadline = Instant::now() + WRITE_TIMEOUT;
+        loop {
+            let pending = self
+                .pids
+                .iter()
+                .any(|pid| alive(*pid) && !profile_path(&self.dir, *pid).exists());
+            if !pending || Instant::now() >= deadline {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        self.pids
+            .iter()
+            .map(|pid| profile_path(&self.dir, *pid))
+            .filter_map(|path| Some((std::fs::metadata(&path).ok()?.len(), path)))
+            .max()
+            .map(|(_, path)| path)
+            .with_context(|| format!("{} wrote no CPU profile", self.target))
+    }
+}
+
+/// `<stem>-<target>.cpuprofile` next to the results file at `json_path`.
+pub fn path(json_path: &Path, target: &str) -> PathBuf {
+    let stem = json_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("results");
+    json_path.with_file_name(format!("{}-{}.cpuprofile", stem, target))
+}
+
+/// Moves each target's profile next to the results file as
+/// `<stem>-<target>.cpuprofile`.
+pub async fn save(profiles: &BTreeMap<String, PathBuf>, json_path: &Path) -> Vec<ProfileArtifact> {
+    let mut artifacts = Vec::new();
+    for (target, recorded) in profiles {
+        artifacts.extend(profiles::keep(target, recorded, &path(json_path, target)).await);
+    }
+    artifacts
+}
@@ -0,0 +1,43 @@
+use crate::units::Bytes;
+use serde::{Deserialize, Serialize};
+
+/// `getrusage` counts storage I/O in 512-byte blocks.
+#[cfg(unix)]
+const BLOCK_BYTES: u64 = 512;
+
+/// Bytes read from and written to storage; reads served from the page cache
+/// are not counted.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct DiskIo {
+    pub read_bytes: Bytes,
+    pub written_bytes: Bytes,
+}
+
+impl DiskIo {
+    /// Storage I/O of every child process this process has waited for so far,
+    /// including the descendants they waited for.
+    #[cfg(unix)]
+    pub fn children() -> Option<Self> {
+        let mut usage = std::mem::MaybeUninit::<libc::rusage>::uninit();
+        if unsafe { libc::getrusage(libc::RUSAGE_CHILDREN, usage.as_mut_ptr()) } != 0 {
+            return None;
+        }
+        let usage = unsafe { usage.assume_init() };
+        Some(DiskIo {
+            read_bytes: Bytes(usage.ru_inblock as u64 * BLOCK_BYTES),
+            written_bytes: Bytes(usage.ru_oublock as u64 * BLOCK_BYTES),
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn children() -> Option<Self> {
+        None
+    }
+
+    pub fn since(self, earlier: DiskIo) -> DiskIo {
+        DiskIo {
+            read_bytes: self.read_bytes - earlier.read_bytes,
+            written_bytes: self.written_bytes - earlier.written_bytes,
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use crate::dry_run;
+use crate::errors::{Error, ErrorKind};
+use crate::targets::{self, Target, TargetArgs};
+use anyhow::Result;
+use colored::Colorize;
+use std::path::Path;
+use tokio::process::Command;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct DoctorArgs {
+    #[arg(
+        long,
+        value_name = "DIR",
+        default_value = ".",
+        help = "Directory that target directories are relative to"
+    )]
+    pub apps_dir: std::path::PathBuf,
+    #[arg(
+        long,
+        value_name = "MB",
+        default_value_t = 500,
+        help = "Free space the results directory needs"
+    )]
+    pub min_free_mb: u64,
+}
+
+/// What a check found: fine, worth knowing, or in the way of a run, with
+/// how to fix it.
+enum Finding {
+    Ok(String),
+    Warning(String, String),
+    Problem(ErrorKind, String, String),
+}
+
+struct Report {
+    problems: Vec<ErrorKind>,
+    warnings: usize,
+}
+
+impl Report {
+    fn print(&mut self, what: &str, finding: Finding) {
+        match finding {
+            Finding::Ok(found) => println!("  {} {}: {}", "✅".green(), what, found.dimmed()),
+            Finding::Warning(found, fix) => {
+                println!("  {} {}: {}", "⚠️".yellow(), what, found);
+                println!("     {} {}", "→".dimmed(), fix);
+                self.warnings += 1;
+            }
+            Finding::Problem(kind, found, fix) => {
+                println!("  {} {}: {}", "❌".red(), what, found);
+                println!("     {} {}", "→".dimmed(), fix);
+                self.problems.push(kind);
+            }
+        }
+    }
+}
+
+/// Checks everything the benchmarks need before a run, printing how to fix
+/// each problem, and fails with the kind of the first one.
+pub async fn run(target_args: &TargetArgs, results_dir: &Path, args: &DoctorArgs) -> Result<()> {
+    println!("{}\n", "🩺 Checking what the benchmarks need".bold());
+    let mut report = Report {
+        problems: Vec::new(),
+        warnings: 0,
+    };
+
+    report.print(
+        "oha",
+        tool("oha", "the load test", "cargo install oha").await,
+    );
+    report.print(
+        "node",
+        tool(
+            "node",
+            "the Node.js apps",
+            "install Node.js 20 or later from https://nodejs.org or with nvm",
+        )
+        .await,
+    );
+    report.print(
+        "pnpm",
+        tool(
+            "pnpm",
+            "installing and building the apps",
+            "corepack enable pnpm, or npm install -g pnpm",
+        )
+        .await,
+    );
+
+    match targets::load(target_args) {
+        Ok(targets) => {
+            for target in targets.iter().filter(|target| !target.is_remote()) {
+                report.print(
+                    &format!("{} app", target.label()),
+                    app_dir(&args.apps_dir, target),
+                );
+                report.print(&format!("{} port", target.label()), port(target).await);
+            }
+        }
+        Err(e) => report.print(
+            "Targets",
+            Finding::Problem(
+                ErrorKind::InvalidConfig,
+                format!("{:#}", e),
+                "fix the targets file, or leave out --targets for rari and Next.js".to_string(),
+            ),
+        ),
+    }
+
+    report.print("Results", results(results_dir, args.min_free_mb).await);
+
+    println!();
+    let Some(&kind) = report.problems.first() else {
+        println!(
+            "{} Ready to benchmark{}",
+            "✅".green(),
+            match report.warnings {
+                0 => String::new(),
+                n => format!(" ({} warning(s))", n),
+            }
+        );
+        return Ok(());
+    };
+    Err(Error::new(
+        kind,
+        format!(
+            "{} problem(s) found; fix them and run doctor again",
+            report.problems.len()
+        ),
+    )
+    .into())
+}
+
+async fn tool(program: &str, needed_for: &str, fix: &str) -> Finding {
+    let version = Command::new(program).arg("--version").output().await;
+    match version {
+        Ok(output) if output.status.success() => {
+            Finding::Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        }
+        Ok(output) => Finding::Problem(
+            ErrorKind::ToolFailed,
+            format!(
+                "{} --version failed: {}",
+                program,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            format!("reinstall it: {}", fix),
+        ),
+        Err(_) => Finding::Problem(
+            ErrorKind::ToolMissing,
+            format!("not installed, and needed for {}", needed_for),
+            fix.to_string(),
+        ),
+    }
+}
+
+fn app_dir(apps_dir: &Path, target: &Target) -> Finding {
+    let dir = apps_dir.join(&target.dir);
+    if !dir.is_dir() {
+        return Finding::Problem(
+            ErrorKind::InvalidConfig,
+            format!("{} not found", dir.display()),
+            "run from the repository root, or pass --apps-dir".to_string(),
+        );
+    }
+    if !dir.join("node_modules").is_dir() && dir.join("package.json").is_file() {
+        return Finding::Warning(
+            format!("{} has no node_modules", dir.display()),
+            "run pnpm install from the repository root".to_string(),
+        );
+    }
+    if dry_run::command(&target.start).is_err() {
+        return Finding::Problem(
+            ErrorKind::ToolMissing,
+            format!("{} is not installed", target.start),
+            "install the program the target's start command runs".to_string(),
+        );
+    }
+    Finding::Ok(dir.display().to_string())
+}
+
+async fn port(target: &Target) -> Finding {
+    match tokio::net::TcpListener::bind(("127.0.0.1", target.port)).await {
+        Ok(_) => Finding::Ok(format!("{} is free", target.port)),
+        Err(_) => Finding::Warning(
+            format!("{} is in use", target.port),
+            format!(
+                "fine if {} is already running there; otherwise stop what listens on it (lsof -i :{}), or use --port or --auto-ports",
+                target.label(),
+                target.port
+            ),
+        ),
+    }
+}
+
+async fn results(dir: &Path, min_free_mb: u64) -> Finding {
+    if let Err(e) = dry_run::output_dir(dir).await {
+        return Finding::Problem(
+            ErrorKind::InvalidConfig,
+            format!("{:#}", e),
+            "fix its permissions, or pass --results-dir".to_string(),
+        );
+    }
+    match free_mb(dir) {
+        Some(free) if free < min_free_mb => Finding::Problem(
+            ErrorKind::Other,
+            format!("{} has {} MB free", dir.display(), free),
+            format!(
+                "free up at least {} MB, or pass --results-dir on another disk",
+                min_free_mb
+            ),
+        ),
+        Some(free) => Finding::Ok(format!("{} is writable, {} MB free", dir.display(), free)),
+        None => Finding::Ok(format!("{} is writable", dir.display())),
+    }
+}
+
+#[cfg(unix)]
+fn free_mb(dir: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+    let path = std::ffi::CString::new(dir.as_os_str().as_bytes()).ok()?;
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    if unsafe { libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        return None;
+    }
+    let stat = unsafe { stat.assume_init() };
+    // The field types differ between platforms.
+    #[allow(clippy::unnecessary_cast)]
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64 / (1024 * 1024))
+}
+
+#[cfg(not(unix))]
+fn free_mb(_dir: &Path) -> Option<u64> {
+    None
+}
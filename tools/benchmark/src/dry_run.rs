@@ -0,0 +1,196 @@
+use crate::errors::{self, Error, ErrorKind};
+use crate::probe::{self, ProbeArgs};
+use crate::servers::{Mode, ServerArgs};
+use crate::ssh::SshArgs;
+use crate::targets::Target;
+use anyhow::Result;
+use colored::Colorize;
+use std::path::Path;
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct DryRunArgs {
+    #[arg(
+        long,
+        help = "Check the config, servers, tools, output directory, and scenarios, and print what would run, without sending any load"
+    )]
+    pub dry_run: bool,
+}
+
+/// The checks of a dry run, printed as they complete.
+pub struct Checks {
+    passed: usize,
+    failures: Vec<anyhow::Error>,
+}
+
+impl Checks {
+    pub fn new(benchmark: &str) -> Self {
+        println!(
+            "{} Dry run of the {}: nothing is started or measured\n",
+            "🧪".bold(),
+            benchmark
+        );
+        Checks {
+            passed: 0,
+            failures: Vec::new(),
+        }
+    }
+
+    /// Records the outcome of checking `what`; `Ok` holds what was found.
+    /// Returns whether it passed.
+    pub fn record(&mut self, what: &str, result: Result<String>) -> bool {
+        match result {
+            Ok(found) => {
+                println!("  {} {}: {}", "✅".green(), what, found.dimmed());
+                self.passed += 1;
+                true
+            }
+            Err(e) => {
+                println!("  {} {}: {:#}", "❌".red(), what, e);
+                self.failures.push(e);
+                false
+            }
+        }
+    }
+
+    /// Prints what a real run would do, one `(what, how)` per line.
+    pub fn plan(&self, steps: &[(&str, String)]) {
+        println!("\n{}", "📋 Plan".bold());
+        for (what, how) in steps {
+            println!("  {:<12} {}", what, how);
+        }
+    }
+
+    /// Fails with the kind of the first failed check, so a dry run exits
+    /// with the code the real run would have.
+    pub fn finish(self) -> Result<()> {
+        let Some(first) = self.failures.first() else {
+            println!("\n{} All {} checks passed", "✅".green(), self.passed);
+            return Ok(());
+        };
+        Err(Error::new(
+            errors::kind(first),
+            format!(
+                "{} of {} checks failed",
+                self.failures.len(),
+                self.failures.len() + self.passed
+            ),
+        )
+        .into())
+    }
+}
+
+/// Where `program` is installed, from `PATH`.
+pub fn program(program: &str) -> Result<String> {
+    let path = Path::new(program);
+    let found = if path.components().count() > 1 {
+        path.is_file().then(|| path.to_path_buf())
+    } else {
+        std::env::var_os("PATH").and_then(|paths| {
+            std::env::split_paths(&paths)
+                .map(|dir| dir.join(program))
+                .find(|candidate| candidate.is_file())
+        })
+    };
+    match found {
+        Some(path) => Ok(path.display().to_string()),
+        None => Err(Error::new(
+            ErrorKind::ToolMissing,
+            format!("{} is not installed", program),
+        )
+        .into()),
+    }
+}
+
+/// Checks that the program `command` starts with is installed.
+pub fn command(command: &str) -> Result<String> {
+    let program_name = command
+        .split_whitespace()
+        .next()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidConfig, "empty command"))?;
+    program(program_name).map(|_| command.to_string())
+}
+
+/// Checks that results can be written to `dir`, creating it as a run would.
+pub async fn output_dir(dir: &Path) -> Result<String> {
+    let probe_file = dir.join(format!(".rari-benchmark-dry-run-{}", std::process::id()));
+    let written = async {
+        tokio::fs::create_dir_all(dir).await?;
+        tokio::fs::write(&probe_file, b"").await?;
+        tokio::fs::remove_file(&probe_file).await
+    }
+    .await;
+    match written {
+        Ok(()) => Ok(format!("{} is writable", dir.display())),
+        Err(e) => Err(Error::new(
+            ErrorKind::InvalidConfig,
+            format!("{} is not writable: {}", dir.display(), e),
+        )
+        .into()),
+    }
+}
+
+/// Checks what the servers need, however the benchmark would reach them, and
+/// returns how, for the plan.
+pub async fn servers(
+    checks: &mut Checks,
+    targets: &[Target],
+    servers: &ServerArgs,
+    compose: Option<&Path>,
+    ssh: &SshArgs,
+    probe_args: &ProbeArgs,
+) -> String {
+    if let Some(file) = compose {
+        checks.record("docker", program("docker"));
+        checks.record("Compose file", existing(file));
+        return format!("started with docker compose -f {}", file.display());
+    }
+    if let Some(host) = &ssh.ssh {
+        checks.record("ssh", program("ssh"));
+        return format!("started on {} over SSH", host);
+    }
+    if servers.manage_servers {
+        for target in targets.iter().filter(|target| !target.is_remote()) {
+            let dir = servers.apps_dir.join(&target.dir);
+            let started = existing(&dir).and_then(|_| command(servers.mode.command(target)));
+            checks.record(&format!("{} server", target.label()), started);
+        }
+        return format!("started with {}", mode_commands(targets, servers.mode));
+    }
+
+    let client = reqwest::Client::new();
+    for target in targets {
+        let url = probe_args.url(target, &target.base_url());
+        let reached = probe::probe(&client, &url, probe_args)
+            .await
+            .map(|()| format!("{} is responding", url))
+            .map_err(|e| {
+                e.context(Error::new(
+                    ErrorKind::ServerUnreachable,
+                    format!("{} is not responding", url),
+                ))
+            });
+        checks.record(&format!("{} server", target.label()), reached);
+    }
+    "already running".to_string()
+}
+
+fn mode_commands(targets: &[Target], mode: Mode) -> String {
+    targets
+        .iter()
+        .filter(|target| !target.is_remote())
+        .map(|target| format!("{} ({})", mode.command(target), target.name))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn existing(path: &Path) -> Result<String> {
+    if path.exists() {
+        Ok(path.display().to_string())
+    } else {
+        Err(Error::new(
+            ErrorKind::InvalidConfig,
+            format!("{} not found", path.display()),
+        )
+        .into())
+    }
+}
@@ -0,0 +1,154 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+const POWERCAP_DIR: &str = "/sys/class/powercap";
+
+/// Package energy used while a target was under load.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Energy {
+    pub joules: f64,
+    pub avg_watts: f64,
+    pub joules_per_request: f64,
+}
+
+/// A RAPL package domain, e.g. `/sys/class/powercap/intel-rapl:0`.
+#[derive(Debug, Clone)]
+struct Domain {
+    energy_path: PathBuf,
+    /// The counter wraps to zero after this many microjoules.
+    max_uj: u64,
+}
+
+fn read_u64(path: &Path) -> Result<u64> {
+    std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?
+        .trim()
+        .parse()
+        .with_context(|| format!("{} is not a number", path.display()))
+}
+
+/// The package domains of every CPU socket. AMD CPUs expose theirs under the
+/// same `intel-rapl` names.
+#[derive(Debug, Clone)]
+pub struct Rapl {
+    domains: Vec<Domain>,
+}
+
+impl Rapl {
+    /// Finds the package counters and checks that they can be read; they are
+    /// only readable by root on most distributions.
+    pub fn detect() -> Result<Self> {
+        let entries = std::fs::read_dir(POWERCAP_DIR).with_context(|| {
+            format!(
+                "{} not found; RAPL needs Linux on Intel or AMD",
+                POWERCAP_DIR
+            )
+        })?;
+        let mut domains = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !is_package_zone(&entry.file_name().to_string_lossy()) {
+                continue;
+            }
+            let name = std::fs::read_to_string(path.join("name")).unwrap_or_default();
+            if !name.trim().starts_with("package") {
+                continue;
+            }
+            let domain = Domain {
+                energy_path: path.join("energy_uj"),
+                max_uj: read_u64(&path.join("max_energy_range_uj"))?,
+            };
+            read_u64(&domain.energy_path).context(
+                "RAPL energy counters are not readable; run as root or make energy_uj readable",
+            )?;
+            domains.push(domain);
+        }
+        if domains.is_empty() {
+            anyhow::bail!("No RAPL package domains found in {}", POWERCAP_DIR);
+        }
+        Ok(Rapl { domains })
+    }
+
+    fn read(&self) -> Result<Vec<u64>> {
+        self.domains
+            .iter()
+            .map(|domain| read_u64(&domain.energy_path))
+            .collect()
+    }
+
+    /// Starts counting the energy every package uses from now on.
+    pub fn start(&self) -> Result<EnergyMeter> {
+        Ok(EnergyMeter {
+            rapl: self.clone(),
+            started: Instant::now(),
+            start_uj: self.read()?,
+        })
+    }
+}
+
+/// Whether `zone` is a top-level zone such as `intel-rapl:0`. Its subzones
+/// (`intel-rapl:0:0`) are parts of the package, and on recent Intel CPUs
+/// `intel-rapl-mmio:0` is the same package again through another interface.
+fn is_package_zone(zone: &str) -> bool {
+    zone.strip_prefix("intel-rapl:")
+        .is_some_and(|socket| !socket.is_empty() && socket.bytes().all(|b| b.is_ascii_digit()))
+}
+
+pub struct EnergyMeter {
+    rapl: Rapl,
+    started: Instant,
+    start_uj: Vec<u64>,
+}
+
+impl EnergyMeter {
+    /// The energy used since `start`.
+    pub fn finish(self) -> Result<Reading> {
+        let seconds = self.started.elapsed().as_secs_f64();
+        let used_uj: u64 = self
+            .rapl
+            .domains
+            .iter()
+            .zip(self.start_uj)
+            .zip(self.rapl.read()?)
+            .map(|((domain, start), end)| {
+                if end >= start {
+                    end - start
+                } else {
+                    domain.max_uj - start + end
+                }
+            })
+            .sum();
+        Ok(Reading {
+            joules: used_uj as f64 / 1_000_000.0,
+            seconds,
+        })
+    }
+}
+
+/// What an [`EnergyMeter`] counted.
+pub struct Reading {
+    joules: f64,
+    seconds: f64,
+}
+
+impl Reading {
+    /// The energy spread over `requests`.
+    pub fn per_request(self, requests: f64) -> Energy {
+        Energy {
+            joules: self.joules,
+            avg_watts: if self.seconds > 0.0 {
+                self.joules / self.seconds
+            } else {
+                0.0
+            },
+            joules_per_request: if requests > 0.0 {
+                self.joules / requests
+            } else {
+                0.0
+            },
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// What went wrong, for wrappers that react to specific failures. Every kind
+/// exits with its own code; 2 is a failed regression gate and 130 an
+/// interrupt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorKind {
+    /// Anything not listed below.
+    Other,
+    /// A target did not answer, or never passed the readiness check.
+    ServerUnreachable,
+    /// A managed server exited or never reported its port.
+    ServerFailed,
+    /// A program the benchmark needs, such as oha, node, docker, or perf, is
+    /// not installed.
+    ToolMissing,
+    /// A program the benchmark ran, or a hook, exited with an error.
+    ToolFailed,
+    /// A production build failed.
+    BuildFailed,
+    /// A target answered, but not one request of a scenario succeeded.
+    RequestsFailed,
+    /// Tool output or a results file could not be read.
+    ParseError,
+    /// Flags, the config file, or the targets are invalid.
+    InvalidConfig,
+    /// The preflight check found the host busy.
+    HostBusy,
+}
+
+impl ErrorKind {
+    const ALL: [ErrorKind; 10] = [
+        ErrorKind::Other,
+        ErrorKind::ServerUnreachable,
+        ErrorKind::ServerFailed,
+        ErrorKind::ToolMissing,
+        ErrorKind::ToolFailed,
+        ErrorKind::BuildFailed,
+        ErrorKind::RequestsFailed,
+        ErrorKind::ParseError,
+        ErrorKind::InvalidConfig,
+        ErrorKind::HostBusy,
+    ];
+
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorKind::Other => 1,
+            ErrorKind::ServerUnreachable => 3,
+            ErrorKind::ServerFailed => 4,
+            ErrorKind::ToolMissing => 5,
+            ErrorKind::ToolFailed => 6,
+            ErrorKind::BuildFailed => 7,
+            ErrorKind::RequestsFailed => 8,
+            ErrorKind::ParseError => 9,
+            ErrorKind::InvalidConfig => 10,
+            ErrorKind::HostBusy => 11,
+        }
+    }
+
+    /// The kind a benchmark binary exited with, e.g. a step of `full`.
+    pub fn from_exit_code(code: i32) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.exit_code() == code)
+    }
+}
+
+/// An error of a known kind. Return it with `Err(Error::new(..).into())`, or
+/// attach it to another error with `.with_context(|| Error::new(..))`;
+/// [`kind`] finds it anywhere in the chain.
+#[derive(Debug)]
+pub struct Error {
+    pub kind: ErrorKind,
+    /// The target it happened to, if any.
+    pub target: Option<String>,
+    message: String,
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Error {
+            kind,
+            target: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn target(mut self, name: &str) -> Self {
+        self.target = Some(name.to_string());
+        self
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A failure to start `program`: [`ErrorKind::ToolMissing`] if it is not
+/// installed, [`ErrorKind::ToolFailed`] otherwise.
+pub fn spawn_error(error: std::io::Error, message: impl Into<String>) -> anyhow::Error {
+    let kind = if error.kind() == std::io::ErrorKind::NotFound {
+        ErrorKind::ToolMissing
+    } else {
+        ErrorKind::ToolFailed
+    };
+    anyhow::Error::new(error).context(Error::new(kind, message))
+}
+
+/// Marks an error as `kind`, with the same message, unless it already has a
+/// kind.
+pub fn classify(error: anyhow::Error, kind: ErrorKind) -> anyhow::Error {
+    if find(&error).is_some() {
+        return error;
+    }
+    Error::new(kind, format!("{:#}", error)).into()
+}
+
+/// The outermost [`Error`] in the chain.
+fn find(error: &anyhow::Error) -> Option<&Error> {
+    error.downcast_ref::<Error>()
+}
+
+pub fn kind(error: &anyhow::Error) -> ErrorKind {
+    find(error).map_or(ErrorKind::Other, |error| error.kind)
+}
+
+/// Ends a benchmark binary that failed: prints the error as returning it from
+/// `main` would, and exits with the code of its kind.
+pub fn exit(error: anyhow::Error) -> ! {
+    eprintln!("Error: {:?}", error);
+    std::process::exit(kind(&error).exit_code())
+}
+
+/// An error saved in the `errors` section of a results file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunError {
+    pub kind: ErrorKind,
+    pub exit_code: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    pub message: String,
+}
+
+impl RunError {
+    pub fn from_error(error: &anyhow::Error) -> Self {
+        let kind = kind(error);
+        RunError {
+            kind,
+            exit_code: kind.exit_code(),
+            target: find(error).and_then(|error| error.target.clone()),
+            message: format!("{:#}", error),
+        }
+    }
+
+    /// Records `target` unless the error named one itself.
+    pub fn for_target(mut self, target: &str) -> Self {
+        self.target.get_or_insert_with(|| target.to_string());
+        self
+    }
+}
+
+impl From<Error> for RunError {
+    fn from(error: Error) -> Self {
+        RunError::from_error(&error.into())
+    }
+}
+
+/// The exit code of a run that recorded `errors`: the first one's.
+pub fn exit_code(errors: &[RunError]) -> Option<i32> {
+    errors.first().map(|error| error.exit_code)
+}
@@ -0,0 +1,165 @@
+use crate::charts;
+use crate::errors::{self, Error, ErrorKind};
+use crate::monitor;
+use crate::profiles::{self, ProfileArtifact};
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::{Child, Command};
+
+/// Samples per second; an odd rate avoids lining up with timers.
+const FREQUENCY: &str = "99";
+
+/// Checks that flamegraphs can be made: the binary was built with the
+/// `profiling` feature and `perf` is installed.
+pub async fn check() -> Result<()> {
+    if !cfg!(feature = "profiling") {
+        return Err(Error::new(
+            ErrorKind::InvalidConfig,
+            "--flamegraph needs a build with the profiling feature: cargo build --features profiling",
+        )
+        .into());
+    }
+    let output = Command::new("perf")
+        .arg("--version")
+        .output()
+        .await
+        .map_err(|e| {
+            errors::spawn_error(
+                e,
+                "perf not found; install linux-perf (or linux-tools) to record flamegraphs",
+            )
+        })?;
+    if !output.status.success() {
+        return Err(Error::new(
+            ErrorKind::ToolMissing,
+            format!("`perf --version` failed ({})", output.status),
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// A `perf record` attached to one server's process tree.
+pub struct Recording {
+    target: String,
+    title: String,
+    data: PathBuf,
+    perf: Child,
+}
+
+/// Starts sampling the call stacks of `root` and every process it started.
+pub fn record(target: &str, title: &str, root: u32) -> Result<Recording> {
+    let pids = monitor::process_tree(root)
+        .iter()
+        .map(u32::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+    let data = std::env::temp_dir().join(format!(
+        "rari-benchmark-{}-{}.perf.data",
+        target,
+        std::process::id()
+    ));
+    let perf = Command::new("perf")
+        .args([
+            "record", "--quiet", "-F", FREQUENCY, "-g", "-p", &pids, "-o",
+        ])
+        .arg(&data)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("Failed to run perf record")?;
+    Ok(Recording {
+        target: target.to_string(),
+        title: title.to_string(),
+        data,
+        perf,
+    })
+}
+
+impl Recording {
+    /// Stops recording and renders the samples to an SVG in the temp directory.
+    pub async fn finish(self) -> Result<PathBuf> {
+        // perf writes its data file on SIGINT, not on SIGKILL.
+        #[cfg(unix)]
+        if let Some(pid) = self.perf.id() {
+            unsafe {
+                libc::kill(pid as libc::pid_t, libc::SIGINT);
+            }
+        }
+        let output = self.perf.wait_with_output().await?;
+        if !self.data.exists() {
+            anyhow::bail!(
+                "perf record failed for {} ({}): {}",
+                self.target,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        let script = Command::new("perf")
+            .args(["script", "-i"])
+            .arg(&self.data)
+            .stdin(Stdio::null())
+            .output()
+            .await
+            .context("Failed to run perf script")?;
+        let _ = std::fs::remove_file(&self.data);
+        if !script.status.success() {
+            anyhow::bail!(
+                "perf script failed for {} ({}): {}",
+                self.target,
+                script.status,
+                String::from_utf8_lossy(&script.stderr).trim()
+            );
+        }
+
+        let svg = self.data.with_extension("svg");
+        render(&script.stdout, &self.title, &svg)
+            .with_context(|| format!("Failed to render the flamegraph of {}", self.target))?;
+        Ok(svg)
+    }
+}
+
+#[cfg(feature = "profiling")]
+fn render(script: &[u8], title: &str, svg: &Path) -> Result<()> {
+    use inferno::collapse::Collapse;
+
+    let mut folded = Vec::new();
+    inferno::collapse::perf::Folder::default().collapse(script, &mut folded)?;
+    if folded.is_empty() {
+        anyhow::bail!("perf recorded no samples");
+    }
+    let mut options = inferno::flamegraph::Options::default();
+    options.title = title.to_string();
+    let file = std::fs::File::create(svg)
+        .with_context(|| format!("Failed to create {}", svg.display()))?;
+    inferno::flamegraph::from_reader(&mut options, folded.as_slice(), file)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "profiling"))]
+fn render(_script: &[u8], _title: &str, _svg: &Path) -> Result<()> {
+    anyhow::bail!("built without the profiling feature")
+}
+
+/// `<stem>-<target>-flamegraph.svg` next to the results file at `json_path`.
+pub fn path(json_path: &Path, target: &str) -> PathBuf {
+    charts::chart_path(json_path, &format!("{}-flamegraph", target))
+}
+
+/// Moves each target's flamegraph next to the results file as
+/// `<stem>-<target>-flamegraph.svg`, so it is archived and pruned with it.
+pub async fn save(
+    flamegraphs: &BTreeMap<String, PathBuf>,
+    json_path: &Path,
+) -> Vec<ProfileArtifact> {
+    let mut artifacts = Vec::new();
+    for (target, svg) in flamegraphs {
+        artifacts.extend(profiles::keep(target, svg, &path(json_path, target)).await);
+    }
+    artifacts
+}
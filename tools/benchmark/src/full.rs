@@ -0,0 +1,557 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use colored::Colorize;
+use rari_benchmark::{
+    apdex, cache, calibration, config, errors, gate, hooks, interrupt, limits, logging, network,
+    noise, order, preflight, presets, random, revisions, sample_size, seed, sibling, targets,
+    tolerance, units,
+};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tokio::process::Command;
+
+#[derive(Parser, Debug)]
+#[command(name = "full")]
+#[command(
+    about = "Build every target, start them, run the performance and load benchmarks, and combine the results"
+)]
+struct Args {
+    #[arg(short, long, default_value = ".")]
+    dir: PathBuf,
+    #[arg(long)]
+    label: Option<String>,
+    #[arg(
+        long,
+        help = "Use the existing production builds instead of rebuilding"
+    )]
+    skip_build: bool,
+    #[arg(
+        long,
+        help = "Start the apps on free ports instead of their configured ports"
+    )]
+    auto_ports: bool,
+    #[command(flatten)]
+    targets: targets::TargetArgs,
+    #[arg(
+        long,
+        num_args = 2,
+        value_names = ["BASE", "HEAD"],
+        conflicts_with = "targets_file",
+        help = "Compare two git revisions of the rari app instead of rari and Next.js"
+    )]
+    refs: Vec<String>,
+    #[arg(
+        long,
+        value_name = "DIR",
+        requires = "refs",
+        help = "Repository to check the revisions out of [default: --dir]"
+    )]
+    repo: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "PATH",
+        default_value = "apps/rari",
+        requires = "refs",
+        help = "Path of the app inside the repository"
+    )]
+    app_path: PathBuf,
+    #[arg(
+        long,
+        value_name = "COMMAND",
+        default_value = "pnpm install --frozen-lockfile",
+        requires = "refs",
+        help = "Command that installs dependencies in each checkout"
+    )]
+    install_command: String,
+    #[arg(
+        long,
+        value_enum,
+        value_name = "POLICY",
+        help = "Warm or drop the OS file cache before each build and server start"
+    )]
+    fs_cache: Option<cache::FsCache>,
+    #[arg(
+        long,
+        value_name = "MB",
+        help = "Cap each managed server's memory during the performance benchmark and load test"
+    )]
+    memory_limit: Option<u64>,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = limits::MemoryLimitMode::Cgroup,
+        requires = "memory_limit",
+        help = "Enforce --memory-limit with a cgroup on the whole process tree or with V8's heap limit"
+    )]
+    memory_limit_mode: limits::MemoryLimitMode,
+    #[arg(
+        long,
+        value_name = "CPUS",
+        help = "Cap each managed server at this many CPUs' worth of time during the performance benchmark and load test"
+    )]
+    cpu_limit: Option<f64>,
+    #[arg(long, default_value = "50")]
+    warmup: usize,
+    #[arg(long, default_value = "20")]
+    requests: usize,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value = "1",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Measure every performance scenario N times and combine the samples"
+    )]
+    repetitions: u64,
+    #[arg(long, default_value = "30")]
+    duration: u64,
+    #[arg(long, default_value = "50")]
+    connections: usize,
+    #[arg(
+        long,
+        value_name = "PCT",
+        default_value_t = tolerance::DEFAULT_TIE_PCT,
+        help = "Differences within ±PCT% are reported as a tie"
+    )]
+    tie_threshold: f64,
+    #[command(flatten)]
+    noise: noise::NoiseArgs,
+    #[command(flatten)]
+    calibration: calibration::CalibrationArgs,
+    #[command(flatten)]
+    sample_size: sample_size::SampleSizeArgs,
+    #[command(flatten)]
+    apdex: apdex::ApdexArgs,
+    #[command(flatten)]
+    order: order::OrderArgs,
+    #[command(flatten)]
+    random: random::RandomArgs,
+    #[command(flatten)]
+    hooks: hooks::HookArgs,
+    #[command(flatten)]
+    preflight: preflight::PreflightArgs,
+    #[command(flatten)]
+    preset: presets::PresetArgs,
+    #[command(flatten)]
+    seed: seed::SeedArgs,
+    #[command(flatten)]
+    network: network::NetworkArgs,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Regression thresholds for the production builds"
+    )]
+    build_thresholds: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Regression thresholds for the performance benchmark"
+    )]
+    performance_thresholds: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Regression thresholds for the load test"
+    )]
+    load_thresholds: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Dry-run every benchmark step instead of running it; with --refs, only check that the revisions exist"
+    )]
+    dry_run: bool,
+    #[command(flatten)]
+    config: config::ConfigArgs,
+    #[command(flatten)]
+    log: logging::LogArgs,
+}
+
+struct Step {
+    title: &'static str,
+    bin: &'static str,
+    args: Vec<OsString>,
+}
+
+fn plan(
+    args: &Args,
+    targets_file: Option<&Path>,
+    label: Option<&str>,
+    build: bool,
+    seed: u64,
+) -> Vec<Step> {
+    let results_dir = args.dir.join("results");
+    let mut common: Vec<OsString> = vec![
+        "--tie-threshold".into(),
+        args.tie_threshold.to_string().into(),
+    ];
+    if let Some(label) = label {
+        common.extend(["--label".into(), label.into()]);
+    }
+    if let Some(file) = targets_file {
+        common.extend(["--targets".into(), file.into()]);
+    }
+    common.extend(args.config.forward());
+    common.extend(args.log.forward());
+    common.extend(args.preset.forward());
+    if args.dry_run {
+        common.push("--dry-run".into());
+    }
+    if let Some(policy) = args.fs_cache {
+        common.extend(["--fs-cache".into(), policy.name().into()]);
+    }
+    common.extend([
+        "--host-busy".into(),
+        args.preflight.host_busy.name().into(),
+        "--max-host-cpu".into(),
+        args.preflight.max_host_cpu.to_string().into(),
+        "--max-host-memory".into(),
+        args.preflight.max_host_memory.to_string().into(),
+    ]);
+    // after_run runs once at the end of the pipeline, not after every step.
+    if let Some(command) = &args.hooks.before_build {
+        common.extend(["--before-build".into(), command.into()]);
+    }
+
+    let server_args = |extra: Vec<OsString>| -> Vec<OsString> {
+        let mut step_args: Vec<OsString> = vec![
+            "--manage-servers".into(),
+            "--mode".into(),
+            "prod".into(),
+            "--apps-dir".into(),
+            args.dir.clone().into(),
+            "--results-dir".into(),
+            results_dir.clone().into(),
+        ];
+        for port in &args.targets.ports {
+            step_args.extend(["--port".into(), port.to_string().into()]);
+        }
+        for url in &args.targets.urls {
+            step_args.extend(["--url".into(), url.to_string().into()]);
+        }
+        if args.auto_ports {
+            step_args.push("--auto-ports".into());
+        }
+        if let Some(limit_mb) = args.memory_limit {
+            step_args.extend([
+                "--memory-limit".into(),
+                limit_mb.to_string().into(),
+                "--memory-limit-mode".into(),
+                args.memory_limit_mode.name().into(),
+            ]);
+        }
+        if let Some(cpus) = args.cpu_limit {
+            step_args.extend(["--cpu-limit".into(), cpus.to_string().into()]);
+        }
+        if let Some(command) = &args.hooks.before_load {
+            step_args.extend(["--before-load".into(), command.into()]);
+        }
+        if let Some(command) = &args.seed.seed_command {
+            step_args.extend(["--seed-command".into(), command.into()]);
+        }
+        for endpoint in &args.seed.seed_endpoints {
+            step_args.extend(["--seed-endpoint".into(), endpoint.into()]);
+        }
+        if args.order.shuffle {
+            step_args.push("--shuffle".into());
+        }
+        // One seed for every step, so the whole pipeline can be reproduced.
+        step_args.extend(["--seed".into(), seed.to_string().into()]);
+        if let Some(preset) = args.network.network {
+            step_args.extend(["--network".into(), preset.name().into()]);
+        }
+        if let Some(rtt) = args.network.rtt {
+            step_args.extend(["--rtt".into(), rtt.to_string().into()]);
+        }
+        if args.network.jitter > 0 {
+            step_args.extend(["--jitter".into(), args.network.jitter.to_string().into()]);
+        }
+        if let Some(kbps) = args.network.bandwidth {
+            step_args.extend(["--bandwidth".into(), kbps.to_string().into()]);
+        }
+        if let Some(kbps) = args.network.upload_bandwidth {
+            step_args.extend(["--upload-bandwidth".into(), kbps.to_string().into()]);
+        }
+        if let Some(max_cv) = args.noise.max_cv {
+            step_args.extend([
+                "--max-cv".into(),
+                max_cv.to_string().into(),
+                "--max-retries".into(),
+                args.noise.max_retries.to_string().into(),
+            ]);
+        }
+        step_args.extend(extra);
+        step_args.extend(common.iter().cloned());
+        step_args
+    };
+
+    let thresholds = |file: &Option<PathBuf>| -> Vec<OsString> {
+        file.iter()
+            .flat_map(|file| ["--thresholds".into(), file.into()])
+            .collect()
+    };
+
+    let mut steps = Vec::new();
+    if build {
+        let mut build_args: Vec<OsString> = vec!["--dir".into(), args.dir.clone().into()];
+        build_args.extend(common.iter().cloned());
+        build_args.extend(thresholds(&args.build_thresholds));
+        steps.push(Step {
+            title: "Production builds",
+            bin: "build-times",
+            args: build_args,
+        });
+    }
+    steps.push(Step {
+        title: "Performance benchmark",
+        bin: "performance",
+        args: server_args(
+            [
+                vec![
+                    "--warmup".into(),
+                    args.warmup.to_string().into(),
+                    "--requests".into(),
+                    args.requests.to_string().into(),
+                    "--repetitions".into(),
+                    args.repetitions.to_string().into(),
+                ],
+                args.calibration
+                    .calibrate
+                    .then(|| "--calibrate".into())
+                    .into_iter()
+                    .collect(),
+                vec![
+                    "--detect".into(),
+                    args.sample_size.detect.to_string().into(),
+                ],
+                vec![
+                    "--apdex-target".into(),
+                    args.apdex.apdex_target.to_string().into(),
+                ],
+                args.apdex
+                    .apdex_tolerating
+                    .map(|tolerating| {
+                        vec!["--apdex-tolerating".into(), tolerating.to_string().into()]
+                    })
+                    .unwrap_or_default(),
+                if args.sample_size.extend_samples {
+                    vec![
+                        "--extend-samples".into(),
+                        "--max-requests".into(),
+                        args.sample_size.max_requests.to_string().into(),
+                    ]
+                } else {
+                    Vec::new()
+                },
+                thresholds(&args.performance_thresholds),
+            ]
+            .concat(),
+        ),
+    });
+    steps.push(Step {
+        title: "Load test",
+        bin: "load-test",
+        args: server_args(
+            [
+                vec![
+                    "--duration".into(),
+                    args.duration.to_string().into(),
+                    "--connections".into(),
+                    args.connections.to_string().into(),
+                ],
+                thresholds(&args.load_thresholds),
+            ]
+            .concat(),
+        ),
+    });
+    // A dry run saves no results to combine.
+    if args.dry_run {
+        return steps;
+    }
+    steps.push(Step {
+        title: "Combined report",
+        bin: "report",
+        args: vec![
+            "combine".into(),
+            "--results-dir".into(),
+            results_dir.into(),
+            "--tie-threshold".into(),
+            args.tie_threshold.to_string().into(),
+        ],
+    });
+    steps
+}
+
+/// Runs a step and returns how long it took and whether its regression gate
+/// failed, or `None` if the pipeline was interrupted. A failed gate doesn't
+/// stop the pipeline, so every result is saved.
+async fn run_step(step: &Step, index: usize, total: usize) -> Result<Option<(f64, bool)>> {
+    println!(
+        "\n{} {}",
+        format!("▶ Step {}/{}:", index + 1, total).cyan().bold(),
+        step.title.bold()
+    );
+
+    let binary = sibling::binary(step.bin)?;
+    let started = Instant::now();
+    let mut command = Command::new(&binary);
+    command.args(&step.args);
+    #[cfg(unix)]
+    command.process_group(0);
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("Failed to run {}", binary.display()))?;
+    let _forwarding = interrupt::hand_over(child.id());
+    let Some(status) = interrupt::or_interrupted(child.wait()).await else {
+        // The step got the Ctrl-C too and is saving its partial results.
+        interrupt::announce(&format!("Waiting for {} to finish", step.bin));
+        child.wait().await?;
+        return Ok(None);
+    };
+    let status = status?;
+    if interrupt::cancelled() {
+        // The step saved its partial results; the rest are skipped.
+        return Ok(None);
+    }
+
+    let regressed = status.code() == Some(gate::REGRESSION_EXIT_CODE);
+    if !status.success() && !regressed {
+        // Passed on, so `full` exits with the step's code.
+        let kind = status
+            .code()
+            .and_then(errors::ErrorKind::from_exit_code)
+            .unwrap_or(errors::ErrorKind::Other);
+        return Err(errors::Error::new(kind, format!("{} failed ({})", step.title, status)).into());
+    }
+    Ok(Some((started.elapsed().as_secs_f64(), regressed)))
+}
+
+fn check_dir(dir: &Path, targets: &[targets::Target]) -> Result<()> {
+    for target in targets.iter().filter(|target| !target.is_remote()) {
+        if !dir.join(&target.dir).is_dir() {
+            return Err(errors::Error::new(
+                errors::ErrorKind::InvalidConfig,
+                format!(
+                    "{} not found; run from the repository root or pass --dir",
+                    dir.join(&target.dir).display()
+                ),
+            )
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// The dry run of a comparison of revisions: the steps would run in
+/// worktrees that don't exist yet, so only the revisions are checked.
+async fn check_revisions(args: &Args) -> Result<()> {
+    let repo = args.repo.as_deref().unwrap_or(&args.dir);
+    let shas = revisions::resolve(repo, &args.refs).await?;
+    println!(
+        "{} Dry run: nothing is checked out, built, or measured\n",
+        "🧪".bold()
+    );
+    for (reference, sha) in args.refs.iter().zip(&shas) {
+        println!(
+            "  {} {} is {}",
+            "✅".green(),
+            reference,
+            &sha[..sha.len().min(8)]
+        );
+    }
+    println!(
+        "\n{} Would check out both into worktrees, install with `{}`, and compare {}",
+        "📋".bold(),
+        args.install_command,
+        args.app_path.display()
+    );
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run().await {
+        errors::exit(e);
+    }
+}
+
+async fn run() -> Result<()> {
+    let args = config::parse::<Args>("full")?;
+    logging::init(&args.log, 0)?;
+    args.apdex.validate()?;
+
+    let mut target_args = args.targets.clone();
+    let mut label = args.label.clone();
+    if args.dry_run && !args.refs.is_empty() {
+        return check_revisions(&args).await;
+    }
+    let worktrees = if args.refs.is_empty() {
+        None
+    } else {
+        let repo = args.repo.as_deref().unwrap_or(&args.dir);
+        let worktrees = revisions::checkout(repo, &args.refs, &args.install_command).await?;
+        target_args.targets_file = Some(worktrees.write_targets(&args.app_path)?);
+        label.get_or_insert_with(|| args.refs.join(".."));
+        Some(worktrees)
+    };
+
+    let targets = targets::load(&target_args)?;
+    check_dir(&args.dir, &targets)?;
+
+    println!(
+        "{}",
+        format!("🏎️  {} Full Comparison", targets::title(&targets))
+            .cyan()
+            .bold()
+    );
+
+    let local = targets.iter().filter(|target| !target.is_remote()).count();
+    let build = !args.skip_build && local >= 2;
+    if !args.skip_build && !build {
+        println!(
+            "{} Skipping production builds: fewer than two local targets",
+            "🌐".dimmed()
+        );
+    }
+    let steps = plan(
+        &args,
+        target_args.targets_file.as_deref(),
+        label.as_deref(),
+        build,
+        args.random.resolve(),
+    );
+    let mut timings = Vec::new();
+    for (index, step) in steps.iter().enumerate() {
+        let Some((seconds, regressed)) = run_step(step, index, steps.len()).await? else {
+            drop(worktrees);
+            std::process::exit(interrupt::EXIT_CODE);
+        };
+        timings.push((step.title, seconds, regressed));
+    }
+    drop(worktrees);
+    if args.dry_run {
+        println!("\n{}", "✅ Every step would run".green().bold());
+        return Ok(());
+    }
+    args.hooks.after_run(&args.dir.join("results")).await?;
+
+    println!("\n{}", "⏱️  Pipeline Summary".bold());
+    for (title, seconds, regressed) in &timings {
+        println!(
+            "  {} {:<24} {}",
+            if *regressed {
+                "🚨".red()
+            } else {
+                "✅".green()
+            },
+            title,
+            units::duration_ms(seconds * 1000.0).dimmed()
+        );
+    }
+
+    if timings.iter().any(|(_, _, regressed)| *regressed) {
+        println!("\n{}", "🚨 Regression gate failed".red().bold());
+        std::process::exit(gate::REGRESSION_EXIT_CODE);
+    }
+    println!("\n{}", "🎉 Full comparison completed!".green().bold());
+
+    Ok(())
+}
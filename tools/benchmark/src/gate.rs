@@ -0,0 +1,316 @@
+use crate::errors::{self, ErrorKind};
+use anyhow::{Context, Result, bail};
+use colored::Colorize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use tokio::fs;
+
+pub const REGRESSION_EXIT_CODE: i32 = 2;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Metric(String),
+    Op(char),
+    LParen,
+    RParen,
+    Cmp(CmpOp),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CmpOp {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+    Ne,
+}
+
+impl CmpOp {
+    fn apply(self, lhs: f64, rhs: f64) -> bool {
+        match self {
+            CmpOp::Gt => lhs > rhs,
+            CmpOp::Ge => lhs >= rhs,
+            CmpOp::Lt => lhs < rhs,
+            CmpOp::Le => lhs <= rhs,
+            CmpOp::Eq => lhs == rhs,
+            CmpOp::Ne => lhs != rhs,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Number(f64),
+    Metric(String),
+    Neg(Box<Expr>),
+    Binary(Box<Expr>, char, Box<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, metrics: &BTreeMap<String, f64>) -> Result<f64> {
+        Ok(match self {
+            Expr::Number(n) => *n,
+            Expr::Metric(name) => *metrics
+                .get(name)
+                .with_context(|| format!("Unknown metric '{}'", name))?,
+            Expr::Neg(inner) => -inner.eval(metrics)?,
+            Expr::Binary(lhs, op, rhs) => {
+                let (l, r) = (lhs.eval(metrics)?, rhs.eval(metrics)?);
+                match op {
+                    '+' => l + r,
+                    '-' => l - r,
+                    '*' => l * r,
+                    _ => l / r,
+                }
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub source: String,
+    lhs: Expr,
+    op: CmpOp,
+    rhs: Expr,
+}
+
+#[derive(Debug, Clone)]
+pub struct RuleOutcome {
+    pub rule: String,
+    pub lhs: f64,
+    pub rhs: f64,
+    pub failed: bool,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            ' ' | '\t' => i += 1,
+            '(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+            }
+            '+' | '-' | '*' | '/' => {
+                tokens.push(Token::Op(c));
+                i += 1;
+            }
+            '>' | '<' | '=' | '!' => {
+                let has_eq = chars.get(i + 1) == Some(&'=');
+                let op = match (c, has_eq) {
+                    ('>', true) => CmpOp::Ge,
+                    ('>', false) => CmpOp::Gt,
+                    ('<', true) => CmpOp::Le,
+                    ('<', false) => CmpOp::Lt,
+                    ('=', true) => CmpOp::Eq,
+                    ('!', true) => CmpOp::Ne,
+                    _ => bail!("Unexpected '{}' at position {}", c, i),
+                };
+                tokens.push(Token::Cmp(op));
+                i += if has_eq { 2 } else { 1 };
+            }
+            '\'' | '`' => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|&ch| ch == c)
+                    .with_context(|| format!("Unterminated {} quote", c))?;
+                tokens.push(Token::Metric(chars[i + 1..i + 1 + end].iter().collect()));
+                i += end + 2;
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                tokens.push(Token::Number(
+                    text.parse()
+                        .with_context(|| format!("Invalid number '{}'", text))?,
+                ));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '.'))
+                {
+                    i += 1;
+                }
+                tokens.push(Token::Metric(chars[start..i].iter().collect()));
+            }
+            _ => bail!("Unexpected '{}' at position {}", c, i),
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        let mut lhs = self.term()?;
+        while let Some(Token::Op(op @ ('+' | '-'))) = self.peek().cloned() {
+            self.pos += 1;
+            lhs = Expr::Binary(Box::new(lhs), op, Box::new(self.term()?));
+        }
+        Ok(lhs)
+    }
+
+    fn term(&mut self) -> Result<Expr> {
+        let mut lhs = self.factor()?;
+        while let Some(Token::Op(op @ ('*' | '/'))) = self.peek().cloned() {
+            self.pos += 1;
+            lhs = Expr::Binary(Box::new(lhs), op, Box::new(self.factor()?));
+        }
+        Ok(lhs)
+    }
+
+    fn factor(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Expr::Number(n)),
+            Some(Token::Metric(name)) => Ok(Expr::Metric(name)),
+            Some(Token::Op('-')) => Ok(Expr::Neg(Box::new(self.factor()?))),
+            Some(Token::LParen) => {
+                let inner = self.expr()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(inner),
+                    _ => bail!("Expected ')'"),
+                }
+            }
+            Some(token) => bail!("Unexpected token {:?}", token),
+            None => bail!("Unexpected end of expression"),
+        }
+    }
+}
+
+pub fn parse_rule(source: &str) -> Result<Rule> {
+    let tokens = tokenize(source).with_context(|| format!("Invalid threshold '{}'", source))?;
+    let mut parser = Parser { tokens, pos: 0 };
+
+    let parsed = (|| {
+        let lhs = parser.expr()?;
+        let op = match parser.next() {
+            Some(Token::Cmp(op)) => op,
+            _ => bail!("Expected a comparison operator (>, >=, <, <=, ==, !=)"),
+        };
+        let rhs = parser.expr()?;
+        if parser.peek().is_some() {
+            bail!("Unexpected trailing input");
+        }
+        Ok((lhs, op, rhs))
+    })()
+    .with_context(|| format!("Invalid threshold '{}'", source))?;
+
+    Ok(Rule {
+        source: source.to_string(),
+        lhs: parsed.0,
+        op: parsed.1,
+        rhs: parsed.2,
+    })
+}
+
+pub async fn load_rules(fail_if: &[String], thresholds_file: Option<&Path>) -> Result<Vec<Rule>> {
+    let mut rules = fail_if
+        .iter()
+        .map(|source| parse_rule(source))
+        .collect::<Result<Vec<_>>>()
+        .map_err(|e| errors::classify(e, ErrorKind::InvalidConfig))?;
+
+    if let Some(path) = thresholds_file {
+        let contents = fs::read_to_string(path)
+            .await
+            .with_context(|| format!("Failed to read thresholds file {}", path.display()))?;
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            rules
+                .push(parse_rule(line).map_err(|e| errors::classify(e, ErrorKind::InvalidConfig))?);
+        }
+    }
+
+    Ok(rules)
+}
+
+pub fn evaluate(rules: &[Rule], metrics: &BTreeMap<String, f64>) -> Result<Vec<RuleOutcome>> {
+    rules
+        .iter()
+        .map(|rule| {
+            let lhs = rule.lhs.eval(metrics)?;
+            let rhs = rule.rhs.eval(metrics)?;
+            Ok(RuleOutcome {
+                rule: rule.source.clone(),
+                lhs,
+                rhs,
+                failed: rule.op.apply(lhs, rhs),
+            })
+        })
+        .collect::<Result<Vec<_>>>()
+        .context("Failed to evaluate regression thresholds")
+}
+
+pub fn check(rules: &[Rule], metrics: &BTreeMap<String, f64>) -> Result<bool> {
+    if rules.is_empty() {
+        return Ok(true);
+    }
+
+    let outcomes = evaluate(rules, metrics)?;
+
+    println!("\n{}", "🚦 Regression Gate".bold());
+    for outcome in &outcomes {
+        if outcome.failed {
+            println!(
+                "  {} {} ({:.2} vs {:.2})",
+                "❌ FAIL".red().bold(),
+                outcome.rule,
+                outcome.lhs,
+                outcome.rhs
+            );
+        } else {
+            println!(
+                "  {} {} ({:.2} vs {:.2})",
+                "✅ pass".green(),
+                outcome.rule,
+                outcome.lhs,
+                outcome.rhs
+            );
+        }
+    }
+
+    let failures = outcomes.iter().filter(|o| o.failed).count();
+    if failures > 0 {
+        println!(
+            "\n{} {} of {} threshold(s) failed",
+            "🚨".red(),
+            failures,
+            outcomes.len()
+        );
+    }
+
+    Ok(failures == 0)
+}
@@ -0,0 +1,98 @@
+use crate::errors::{Error, ErrorKind};
+use crate::targets::Target;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::path::Path;
+use std::process::Stdio;
+use tokio::process::Command;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct HookArgs {
+    #[arg(
+        long,
+        value_name = "COMMAND",
+        help = "Shell command run before each target is built"
+    )]
+    pub before_build: Option<String>,
+    #[arg(
+        long,
+        value_name = "COMMAND",
+        help = "Shell command run before each target is measured, including re-runs"
+    )]
+    pub before_load: Option<String>,
+    #[arg(
+        long,
+        value_name = "COMMAND",
+        help = "Shell command run once the results are saved"
+    )]
+    pub after_run: Option<String>,
+}
+
+/// Runs `command` through the shell with the hook name and `env` exported as
+/// `BENCHMARK_*` variables. A failing hook aborts the benchmark.
+#[tracing::instrument(name = "hook", skip(command, env))]
+pub async fn run(hook: &str, command: &str, env: &[(&str, String)]) -> Result<()> {
+    println!("{} {}: {}", "🪝".dimmed(), hook, command.dimmed());
+
+    #[cfg(unix)]
+    let mut shell = {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        shell
+    };
+    #[cfg(not(unix))]
+    let mut shell = {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    };
+
+    let status = shell
+        .env("BENCHMARK_HOOK", hook)
+        .envs(env.iter().cloned())
+        .stdin(Stdio::null())
+        .status()
+        .await
+        .with_context(|| format!("Failed to run the {} hook", hook))?;
+    if !status.success() {
+        return Err(Error::new(
+            ErrorKind::ToolFailed,
+            format!("{} hook failed ({}): {}", hook, status, command),
+        )
+        .into());
+    }
+    Ok(())
+}
+
+fn target_env(target: &Target) -> Vec<(&'static str, String)> {
+    vec![
+        ("BENCHMARK_TARGET", target.name.clone()),
+        ("BENCHMARK_TARGET_URL", target.base_url()),
+    ]
+}
+
+impl HookArgs {
+    pub async fn before_build(&self, target: &Target, app_dir: &Path) -> Result<()> {
+        let Some(command) = &self.before_build else {
+            return Ok(());
+        };
+        let mut env = target_env(target);
+        env.push(("BENCHMARK_TARGET_DIR", app_dir.display().to_string()));
+        run("before_build", command, &env).await
+    }
+
+    pub async fn before_load(&self, target: &Target) -> Result<()> {
+        let Some(command) = &self.before_load else {
+            return Ok(());
+        };
+        run("before_load", command, &target_env(target)).await
+    }
+
+    pub async fn after_run(&self, results: &Path) -> Result<()> {
+        let Some(command) = &self.after_run else {
+            return Ok(());
+        };
+        let env = [("BENCHMARK_RESULTS", results.display().to_string())];
+        run("after_run", command, &env).await
+    }
+}
@@ -0,0 +1,615 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use colored::Colorize;
+use rari_benchmark::{
+    archive, config, errors, gate, hooks, influx, interrupt, metrics, notify, output, preflight,
+    presets, probe, publish, results, run_metadata, schema, stats, store, stream, targets,
+    time_utils, tolerance, units, upload, versions,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::process::Command;
+use tracing::Instrument;
+
+/// How often a starting container is polled for its first response.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Container started for the current cold start, removed on Ctrl-C.
+static RUNNING: Mutex<Option<String>> = Mutex::new(None);
+
+#[derive(Parser, Debug)]
+#[command(name = "images")]
+#[command(
+    about = "Compare production container images: build time, size, and cold start to first response"
+)]
+struct Args {
+    #[arg(short, long, default_value = ".")]
+    dir: PathBuf,
+    #[arg(long)]
+    label: Option<String>,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value = "5",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Cold starts measured per image"
+    )]
+    starts: u64,
+    #[arg(long, help = "Build the images without the Docker layer cache")]
+    no_cache: bool,
+    #[arg(
+        long,
+        value_name = "PCT",
+        default_value_t = tolerance::DEFAULT_TIE_PCT,
+        help = "Differences within ±PCT% are reported as a tie"
+    )]
+    tie_threshold: f64,
+    #[arg(long = "fail-if", value_name = "EXPR")]
+    fail_if: Vec<String>,
+    #[arg(long, value_name = "FILE")]
+    thresholds: Option<PathBuf>,
+    #[arg(long, value_name = "URL")]
+    notify_webhook: Option<String>,
+    #[arg(long, value_name = "URL")]
+    artifact_url: Option<String>,
+    #[arg(long, value_name = "URL")]
+    upload: Option<upload::Destination>,
+    #[arg(long, value_name = "FILE")]
+    store: Option<PathBuf>,
+    #[arg(long)]
+    no_store: bool,
+    #[command(flatten)]
+    targets: targets::TargetArgs,
+    #[command(flatten)]
+    probe: probe::ProbeArgs,
+    #[command(flatten)]
+    hooks: hooks::HookArgs,
+    #[command(flatten)]
+    preflight: preflight::PreflightArgs,
+    #[command(flatten)]
+    output: output::OutputArgs,
+    #[command(flatten)]
+    preset: presets::PresetArgs,
+    #[command(flatten)]
+    archive: archive::ArchiveArgs,
+    #[command(flatten)]
+    influx: influx::InfluxArgs,
+    #[command(flatten)]
+    github: publish::GitHubArgs,
+    #[command(flatten)]
+    config: config::ConfigArgs,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ColdStart {
+    runs: Vec<units::Millis>,
+    min: units::Millis,
+    avg: units::Millis,
+    p50: units::Millis,
+    max: units::Millis,
+}
+
+impl ColdStart {
+    fn from_runs(runs: Vec<units::Millis>) -> Self {
+        let values: Vec<f64> = runs.iter().map(|ms| ms.0).collect();
+        let stat =
+            |stat: fn(&[f64]) -> Option<f64>| units::Millis(stat(&values).unwrap_or_default());
+        ColdStart {
+            min: stat(stats::min),
+            avg: stat(stats::mean),
+            p50: stat(stats::median),
+            max: stat(stats::max),
+            runs,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ImageResult {
+    image: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    build_ms: Option<units::Millis>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pull_ms: Option<units::Millis>,
+    size_bytes: units::Bytes,
+    size: String,
+    layers: usize,
+    cold_start: ColdStart,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BenchmarkResults {
+    /// [`schema::VERSION`] when written; see [`schema::migrate`] for older files.
+    version: u32,
+    timestamp: String,
+    #[serde(default)]
+    metadata: run_metadata::RunMetadata,
+    targets: Vec<String>,
+    #[serde(flatten)]
+    results: BTreeMap<String, ImageResult>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    partial: bool,
+    /// What cut the run short, if anything.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<errors::RunError>,
+}
+
+async fn docker(args: &[&str]) -> Result<String> {
+    let output = Command::new("docker")
+        .args(args)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| errors::spawn_error(e, "Failed to run docker. Is Docker installed?"))?;
+    if !output.status.success() {
+        return Err(errors::Error::new(
+            errors::ErrorKind::ToolFailed,
+            format!(
+                "docker {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        )
+        .into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn remove_blocking(container: &str) {
+    let _ = std::process::Command::new("docker")
+        .args(["rm", "--force", container])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status();
+}
+
+/// Pulls the target's `image`, or builds its Dockerfile, and returns the
+/// image reference with how long that took.
+async fn prepare_image(
+    target: &targets::Target,
+    app_dir: &Path,
+    no_cache: bool,
+) -> Result<(String, Option<units::Millis>, Option<units::Millis>)> {
+    let start = Instant::now();
+
+    if let Some(image) = &target.image {
+        println!("\n{} Pulling {}...", "🐳".bold(), image.bold());
+        stream::with_progress(
+            docker(&["pull", "--quiet", image]),
+            |elapsed_secs| json!({ "framework": &target.name, "elapsedSecs": elapsed_secs }),
+        )
+        .await
+        .with_context(|| format!("Failed to pull the {} image", target.label()))?;
+        let pull_ms = units::Millis::since(start);
+        println!("  {} Pulled in {}", "✅".green(), pull_ms);
+        return Ok((image.clone(), None, Some(pull_ms)));
+    }
+
+    let dockerfile = app_dir.join(
+        target
+            .dockerfile
+            .as_deref()
+            .unwrap_or(Path::new("Dockerfile")),
+    );
+    let image = format!("rari-benchmark-{}:latest", target.name);
+    println!(
+        "\n{} Building the {} image...",
+        "🐳".bold(),
+        target.label().bold()
+    );
+    println!("  {} {}", "Dockerfile:".dimmed(), dockerfile.display());
+
+    let dockerfile = dockerfile.display().to_string();
+    let context = app_dir.display().to_string();
+    let mut build_args = vec!["build", "--tag", &image, "--file", &dockerfile];
+    if no_cache {
+        build_args.push("--no-cache");
+    }
+    build_args.push(&context);
+    stream::with_progress(
+        docker(&build_args),
+        |elapsed_secs| json!({ "framework": &target.name, "elapsedSecs": elapsed_secs }),
+    )
+    .await
+    .with_context(|| format!("Failed to build the {} image", target.label()))?;
+    let build_ms = units::Millis::since(start);
+    println!("  {} Built {} in {}", "✅".green(), image, build_ms);
+    Ok((image, Some(build_ms), None))
+}
+
+/// Starts a fresh container from `image` and returns the time from
+/// `docker run` to the first passing health check. The container is removed
+/// afterwards.
+async fn cold_start(
+    target: &targets::Target,
+    image: &str,
+    probe_args: &probe::ProbeArgs,
+) -> Result<units::Millis> {
+    let port = target.port.to_string();
+    let mut run_args = vec![
+        "run".to_string(),
+        "--detach".to_string(),
+        "--publish".to_string(),
+        format!("127.0.0.1::{}", port),
+        "--env".to_string(),
+        format!("PORT={}", port),
+    ];
+    for (key, value) in &target.env {
+        run_args.push("--env".to_string());
+        run_args.push(format!("{}={}", key, value));
+    }
+    run_args.push(image.to_string());
+    let run_args: Vec<&str> = run_args.iter().map(String::as_str).collect();
+
+    let start = Instant::now();
+    let container = docker(&run_args).await?;
+    *RUNNING.lock().unwrap() = Some(container.clone());
+
+    let result = first_response(target, &container, &port, start, probe_args).await;
+    remove_blocking(&container);
+    RUNNING.lock().unwrap().take();
+    result
+}
+
+async fn first_response(
+    target: &targets::Target,
+    container: &str,
+    port: &str,
+    start: Instant,
+    probe_args: &probe::ProbeArgs,
+) -> Result<units::Millis> {
+    let mapping = docker(&["port", container, &format!("{}/tcp", port)]).await?;
+    let host_port: u16 = mapping
+        .lines()
+        .next()
+        .and_then(|line| line.rsplit(':').next())
+        .and_then(|port| port.parse().ok())
+        .with_context(|| format!("Container port {} is not published", port))?;
+    let url = probe_args.url(target, &targets::local_url(host_port));
+
+    let client = reqwest::Client::new();
+    let timeout = probe_args.timeout(true);
+    loop {
+        let error = match probe::probe(&client, &url, probe_args).await {
+            Ok(()) => return Ok(units::Millis::since(start)),
+            Err(e) => e,
+        };
+        if start.elapsed() > timeout {
+            let logs = docker(&["logs", "--tail", "20", container])
+                .await
+                .unwrap_or_default();
+            return Err(errors::Error::new(
+                errors::ErrorKind::ServerUnreachable,
+                format!(
+                    "Container is not ready at {} after {:.0}s: {:#}\n{}",
+                    url,
+                    timeout.as_secs_f64(),
+                    error,
+                    logs
+                ),
+            )
+            .target(&target.name)
+            .into());
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+async fn image_size(image: &str) -> Result<(units::Bytes, usize)> {
+    let inspect = docker(&[
+        "image",
+        "inspect",
+        "--format",
+        "{{.Size}} {{len .RootFS.Layers}}",
+        image,
+    ])
+    .await?;
+    let mut fields = inspect.split_whitespace();
+    let size = fields
+        .next()
+        .and_then(|size| size.parse().ok())
+        .map(units::Bytes)
+        .with_context(|| format!("Unexpected docker image inspect output: {}", inspect))?;
+    let layers = fields
+        .next()
+        .and_then(|layers| layers.parse().ok())
+        .unwrap_or_default();
+    Ok((size, layers))
+}
+
+async fn measure_image(
+    target: &targets::Target,
+    app_dir: &Path,
+    args: &Args,
+) -> Result<ImageResult> {
+    let (image, build_ms, pull_ms) = prepare_image(target, app_dir, args.no_cache).await?;
+    let (size_bytes, layers) = image_size(&image).await?;
+    println!("  {} {} ({} layers)", "Size:".dimmed(), size_bytes, layers);
+
+    let mut runs = Vec::new();
+    for run in 1..=args.starts {
+        let elapsed_ms = cold_start(target, &image, &args.probe).await?;
+        println!(
+            "  {} Cold start {}/{}: {}",
+            "🚀".dimmed(),
+            run,
+            args.starts,
+            elapsed_ms
+        );
+        runs.push(elapsed_ms);
+    }
+
+    Ok(ImageResult {
+        image,
+        build_ms,
+        pull_ms,
+        size_bytes,
+        size: size_bytes.to_string(),
+        layers,
+        cold_start: ColdStart::from_runs(runs),
+    })
+}
+
+fn display_metric(
+    title: &str,
+    unit: units::Unit,
+    targets: &[targets::Target],
+    values: &[f64],
+    tie_pct: f64,
+) {
+    println!("\n{}:", title);
+    for (heading, value) in targets::headings(targets).iter().zip(values) {
+        println!("  {}{}", heading, unit.format(*value));
+    }
+
+    let subject = &targets[0];
+    for (other, value) in targets.iter().zip(values).skip(1) {
+        let diff = tolerance::difference_pct(values[0], *value);
+        match tolerance::classify(values[0], *value, true, tie_pct) {
+            tolerance::Outcome::SubjectBetter => println!(
+                "  {} {} is {:.1}% lower than {}",
+                "📈".green(),
+                subject.label(),
+                diff.abs(),
+                other.label()
+            ),
+            tolerance::Outcome::OtherBetter => println!(
+                "  {} {} is {:.1}% higher than {}",
+                "📉".red(),
+                subject.label(),
+                diff,
+                other.label()
+            ),
+            tolerance::Outcome::Tie => {
+                println!("{}", tolerance::tie_line(diff, tie_pct, other.label()))
+            }
+        }
+    }
+}
+
+fn display_comparison(targets: &[targets::Target], results: &[ImageResult], tie_pct: f64) {
+    println!("\n{}", "📊 Container Image Comparison".bold());
+
+    let sizes: Vec<f64> = results
+        .iter()
+        .map(|result| result.size_bytes.as_f64())
+        .collect();
+    display_metric(
+        "📦 Image Size",
+        units::Unit::Bytes,
+        targets,
+        &sizes,
+        tie_pct,
+    );
+
+    let cold_starts: Vec<f64> = results
+        .iter()
+        .map(|result| result.cold_start.p50.0)
+        .collect();
+    display_metric(
+        "🚀 Cold Start (median)",
+        units::Unit::Milliseconds,
+        targets,
+        &cold_starts,
+        tie_pct,
+    );
+}
+
+#[tokio::main]
+async fn main() {
+    let result = async {
+        let args = config::parse::<Args>("images")?;
+        output::init(&args.output)?;
+        run(args)
+            .instrument(tracing::info_span!("benchmark", kind = "images"))
+            .await
+    };
+    if let Err(e) = result.await {
+        errors::exit(e);
+    }
+}
+
+async fn run(args: Args) -> Result<()> {
+    let rules = gate::load_rules(&args.fail_if, args.thresholds.as_deref()).await?;
+    archive::validate_template(&args.archive.name_template)?;
+    let targets = targets::local(targets::load(&args.targets)?)?;
+    let mut metadata = run_metadata::collect(&args.dir, args.label.clone()).await;
+    metadata.profile = args.preset.profile.map(|preset| preset.name().to_string());
+    metadata.target_env = targets::env(&targets);
+    metadata.node_version = versions::node().await;
+    metadata.target_versions = versions::packages(&args.dir, &targets).await;
+    metadata.framework_versions = versions::frameworks(&targets, &metadata.target_versions);
+    let title = format!("🐳 {} Container Image Comparison", targets::title(&targets));
+
+    println!("{}", title.cyan().bold());
+    println!("{}", metadata.describe().dimmed());
+    metadata.host_load = preflight::check(&args.preflight).await?;
+    stream::emit(
+        "run_started",
+        json!({ "benchmark": "images", "metadata": &metadata }),
+    );
+
+    interrupt::on_interrupt("removing the container", || {
+        if let Some(container) = RUNNING.lock().unwrap().take() {
+            remove_blocking(&container);
+        }
+    });
+
+    let mut image_results = Vec::new();
+    let measured = interrupt::or_interrupted(async {
+        for target in &targets {
+            let app_dir = args.dir.join(&target.dir);
+            args.hooks.before_build(target, &app_dir).await?;
+            // An image cut short is dropped; the finished ones are saved.
+            let Some(result) =
+                interrupt::until_cancelled(measure_image(target, &app_dir, &args)).await
+            else {
+                break;
+            };
+            let result = result?;
+            stream::emit(
+                "image_finished",
+                json!({ "framework": &target.name, "result": &result }),
+            );
+            image_results.push(result);
+        }
+        Ok::<_, anyhow::Error>(())
+    })
+    .await;
+    let (partial, failures) = match measured {
+        Some(Ok(())) if !interrupt::cancelled() => (false, Vec::new()),
+        Some(Err(e)) if !interrupt::cancelled() => {
+            tracing::error!("{:#}", e);
+            (true, vec![errors::RunError::from_error(&e)])
+        }
+        _ => {
+            interrupt::announce("Saving the finished images");
+            if let Some(container) = RUNNING.lock().unwrap().take() {
+                remove_blocking(&container);
+            }
+            (true, Vec::new())
+        }
+    };
+
+    if !partial {
+        display_comparison(&targets, &image_results, args.tie_threshold);
+    }
+
+    let results = BenchmarkResults {
+        version: schema::VERSION,
+        timestamp: time_utils::format_timestamp(SystemTime::now()),
+        metadata,
+        targets: targets::names(&targets),
+        results: targets::names(&targets)
+            .into_iter()
+            .zip(image_results)
+            .collect(),
+        partial,
+        errors: failures,
+    };
+
+    let file_name = archive::file_name(
+        &args.archive.name_template,
+        metrics::ResultKind::Images,
+        &results.timestamp,
+        &results.metadata,
+    )?;
+    let saved_path = results::save(&results, &args.dir.join("results"), &file_name).await?;
+    if partial {
+        // Partial results stay out of the archive, the store and the gate.
+        std::process::exit(errors::exit_code(&results.errors).unwrap_or(interrupt::EXIT_CODE));
+    }
+    if let Err(e) = archive::finalize(
+        &args.dir.join("results"),
+        metrics::ResultKind::Images,
+        &args.archive,
+    )
+    .await
+    {
+        tracing::warn!("Failed to update results archive: {:#}", e);
+    }
+
+    let document = serde_json::to_value(&results)?;
+    let store_path = (!args.no_store).then(|| {
+        args.store
+            .clone()
+            .unwrap_or_else(|| store::default_path(&args.dir.join("results")))
+    });
+    store::record(
+        store_path.as_deref(),
+        metrics::ResultKind::Images,
+        &saved_path,
+        &document,
+    );
+    influx::export(&args.influx, metrics::ResultKind::Images, &document).await;
+
+    let result_metrics = metrics::flatten(&document);
+    let gate_passed = gate::check(&rules, &result_metrics)?;
+
+    let mut artifact = saved_path.display().to_string();
+    if let Some(destination) = &args.upload {
+        let date = time_utils::format_date(SystemTime::now());
+        match upload::upload(destination, &saved_path, &date).await {
+            Ok(uri) => artifact = uri,
+            Err(e) => tracing::warn!("{:#}", e),
+        }
+    }
+
+    let summary = notify::Summary {
+        title,
+        timestamp: results.timestamp.clone(),
+        targets: targets.clone(),
+        deltas: vec![
+            notify::Delta {
+                label: "Image size".to_string(),
+                unit: units::Unit::Bytes,
+                values: targets
+                    .iter()
+                    .map(|target| results.results[&target.name].size_bytes.as_f64())
+                    .collect(),
+                lower_is_better: true,
+            },
+            notify::Delta {
+                label: "Cold start".to_string(),
+                unit: units::Unit::Milliseconds,
+                values: targets
+                    .iter()
+                    .map(|target| results.results[&target.name].cold_start.p50.0)
+                    .collect(),
+                lower_is_better: true,
+            },
+        ],
+        gate_passed: (!rules.is_empty()).then_some(gate_passed),
+        artifact: args.artifact_url.clone().unwrap_or(artifact),
+    };
+
+    notify::notify(args.notify_webhook.as_deref(), &summary).await;
+    if let Err(e) = publish::publish(&args.github, "benchmarks/images", &summary).await {
+        tracing::warn!("{:#}", e);
+    }
+
+    args.hooks.after_run(&saved_path).await?;
+    output::finish(&args.output, &summary.to_text(), &document)?;
+    stream::emit(
+        "run_finished",
+        json!({
+            "results": saved_path,
+            "artifact": summary.artifact,
+            "gatePassed": gate_passed,
+        }),
+    );
+
+    if !gate_passed {
+        std::process::exit(gate::REGRESSION_EXIT_CODE);
+    }
+
+    println!("\n{}", "🎉 Image comparison completed!".green().bold());
+
+    Ok(())
+}
@@ -0,0 +1,180 @@
+use crate::metrics::{self, ResultKind};
+use crate::time_utils;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct InfluxArgs {
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Append results as InfluxDB line protocol to FILE"
+    )]
+    pub influx_file: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "URL",
+        env = "INFLUX_WRITE_URL",
+        help = "Post line protocol to an InfluxDB/VictoriaMetrics write endpoint"
+    )]
+    pub influx_url: Option<String>,
+    #[arg(long, env = "INFLUX_TOKEN", hide_env_values = true)]
+    pub influx_token: Option<String>,
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace('=', "\\=")
+        .replace(' ', "\\ ")
+}
+
+fn line(
+    measurement: &str,
+    tags: &[(&str, String)],
+    fields: &[(String, f64)],
+    timestamp_ns: u128,
+) -> Option<String> {
+    let fields: Vec<String> = fields
+        .iter()
+        .filter(|(_, value)| value.is_finite())
+        .map(|(key, value)| format!("{}={}", escape(key), value))
+        .collect();
+    if fields.is_empty() {
+        return None;
+    }
+
+    let tags: String = tags
+        .iter()
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(key, value)| format!(",{}={}", key, escape(value)))
+        .collect();
+
+    Some(format!(
+        "{}{} {} {}",
+        escape(measurement),
+        tags,
+        fields.join(","),
+        timestamp_ns
+    ))
+}
+
+pub fn to_line_protocol(kind: ResultKind, document: &Value) -> String {
+    let timestamp_ns = document["timestamp"]
+        .as_str()
+        .and_then(time_utils::parse_timestamp)
+        .and_then(|time| time.timestamp_nanos_opt())
+        .unwrap_or_default() as u128;
+    let measurement = format!("benchmark_{}", kind.prefix());
+
+    let metadata = &document["metadata"];
+    let metadata_tag = |key: &str| metadata[key].as_str().unwrap_or_default().to_string();
+    let common_tags = [
+        ("label", metadata_tag("label")),
+        ("git_sha", metadata_tag("gitSha")),
+        ("git_branch", metadata_tag("gitBranch")),
+        ("host", metadata_tag("hostname")),
+        ("mode", metadata_tag("mode")),
+    ];
+
+    let mut lines = Vec::new();
+    for framework in metrics::target_names(document) {
+        let framework = framework.as_str();
+        let mut tags = vec![("framework", framework.to_string())];
+        tags.extend(common_tags.iter().cloned());
+
+        if kind == ResultKind::Performance {
+            let Some(scenarios) = document[framework].as_object() else {
+                continue;
+            };
+            for (scenario, scenario_metrics) in scenarios {
+                let mut scenario_tags = tags.clone();
+                scenario_tags.insert(1, ("scenario", scenario.clone()));
+                let fields: Vec<(String, f64)> =
+                    metrics::flatten(scenario_metrics).into_iter().collect();
+                lines.extend(line(&measurement, &scenario_tags, &fields, timestamp_ns));
+            }
+        } else {
+            let fields: Vec<(String, f64)> =
+                metrics::flatten(&document[framework]).into_iter().collect();
+            lines.extend(line(&measurement, &tags, &fields, timestamp_ns));
+        }
+    }
+
+    lines.join("\n")
+}
+
+async fn append(path: &Path, lines: &str) -> Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    file.write_all(format!("{}\n", lines).as_bytes()).await?;
+    Ok(())
+}
+
+async fn post(url: &str, token: Option<&str>, lines: &str) -> Result<()> {
+    let mut request = reqwest::Client::new()
+        .post(url)
+        .header("Content-Type", "text/plain; charset=utf-8")
+        .body(format!("{}\n", lines));
+    if let Some(token) = token {
+        request = request.header("Authorization", format!("Token {}", token));
+    }
+
+    let response = request
+        .send()
+        .await
+        .context("Failed to reach InfluxDB write endpoint")?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("InfluxDB write returned {} {}", status, body.trim());
+    }
+
+    Ok(())
+}
+
+pub async fn write(args: &InfluxArgs, lines: &str) -> Result<()> {
+    if lines.is_empty() {
+        return Ok(());
+    }
+
+    if let Some(path) = &args.influx_file {
+        append(path, lines).await?;
+        println!(
+            "{} Line protocol appended to {}",
+            "📈".dimmed(),
+            path.display()
+        );
+    }
+
+    if let Some(url) = &args.influx_url {
+        post(url, args.influx_token.as_deref(), lines).await?;
+        println!("{} Results written to {}", "📈".dimmed(), url);
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument(name = "influx", skip_all)]
+pub async fn export(args: &InfluxArgs, kind: ResultKind, document: &Value) {
+    if args.influx_file.is_none() && args.influx_url.is_none() {
+        return;
+    }
+
+    if let Err(e) = write(args, &to_line_protocol(kind, document)).await {
+        tracing::warn!("{:#}", e);
+    }
+}
@@ -0,0 +1,160 @@
+use colored::Colorize;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Mutex, Once};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// Exit code after an interrupt, as a shell reports for SIGINT.
+pub const EXIT_CODE: i32 = 130;
+
+/// How long [`or_interrupted`] lets cancelled work wind down before dropping
+/// it.
+const GRACE_PERIOD: Duration = Duration::from_secs(10);
+
+/// What a cleanup does, for the message, and the cleanup itself.
+type Cleanup = (&'static str, fn());
+
+static CLEANUPS: Mutex<Vec<Cleanup>> = Mutex::new(Vec::new());
+/// Child processes that interrupts are passed on to, and whether each is a
+/// benchmark that tells the user about them itself.
+static FORWARDED: Mutex<Vec<(u32, bool)>> = Mutex::new(Vec::new());
+static DEFERRED: AtomicBool = AtomicBool::new(false);
+static LISTENER: Once = Once::new();
+static CANCEL: LazyLock<CancellationToken> = LazyLock::new(CancellationToken::new);
+
+/// Resolves on the next Ctrl-C, or SIGTERM on unix.
+async fn signalled() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+            return;
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Installs the process-wide signal listener. An interrupt runs every
+/// registered cleanup and exits, unless [`or_interrupted`] is waiting for
+/// work; then the first one cancels the run instead.
+fn listen() {
+    LISTENER.call_once(|| {
+        tokio::spawn(async {
+            loop {
+                signalled().await;
+                let reported = interrupt_children();
+                if DEFERRED.load(Ordering::SeqCst) && !CANCEL.is_cancelled() {
+                    if !reported {
+                        eprintln!(
+                            "\n{} Interrupted, stopping the measurement (press Ctrl-C again to quit now)",
+                            "⚠️".yellow()
+                        );
+                    }
+                    CANCEL.cancel();
+                    continue;
+                }
+                for (action, cleanup) in std::mem::take(&mut *CLEANUPS.lock().unwrap()) {
+                    eprintln!("\n{} Interrupted, {}", "⚠️".yellow(), action);
+                    cleanup();
+                }
+                std::process::exit(EXIT_CODE);
+            }
+        });
+    });
+}
+
+/// Runs `cleanup` if the process is interrupted, e.g. to stop spawned
+/// servers. `action` describes it and is only registered once.
+pub fn on_interrupt(action: &'static str, cleanup: fn()) {
+    listen();
+    let mut cleanups = CLEANUPS.lock().unwrap();
+    if !cleanups.iter().any(|(registered, _)| *registered == action) {
+        cleanups.push((action, cleanup));
+    }
+}
+
+/// Whether the first interrupt during [`or_interrupted`] cancelled the run.
+/// Long-running phases check it to stop sending requests and return what
+/// they measured so far, which is then partial.
+pub fn cancelled() -> bool {
+    CANCEL.is_cancelled()
+}
+
+/// Runs `work`, or returns `None` as soon as the run is interrupted, dropping
+/// it. For steps whose half-done result is worthless, e.g. a build.
+pub async fn until_cancelled<F: Future>(work: F) -> Option<F::Output> {
+    CANCEL.run_until_cancelled(work).await
+}
+
+/// Runs `work` to completion. The first interrupt cancels the run and gives
+/// `work` a grace period to return what it has; if it does not, it is dropped
+/// and `None` returned. Either way the caller saves the partial results,
+/// which [`cancelled`] tells apart. A second interrupt runs the cleanups and
+/// exits as usual.
+pub async fn or_interrupted<F: Future>(work: F) -> Option<F::Output> {
+    listen();
+    DEFERRED.store(true, Ordering::SeqCst);
+    tokio::pin!(work);
+    let output = tokio::select! {
+        output = &mut work => Some(output),
+        _ = CANCEL.cancelled() => tokio::time::timeout(GRACE_PERIOD, &mut work).await.ok(),
+    };
+    DEFERRED.store(false, Ordering::SeqCst);
+    output
+}
+
+/// Tells the user what an interrupted run is still doing before it exits.
+pub fn announce(action: &str) {
+    eprintln!("{} {}", "⚠️".yellow(), action);
+}
+
+/// Passes every interrupt on to child process `pid` as SIGINT while it lives.
+/// For children spawned in their own process group, which Ctrl-C does not
+/// reach, so that a tool such as oha hears it once and can still report what
+/// it measured.
+pub struct Forwarding(u32);
+
+pub fn forward_to(pid: Option<u32>) -> Option<Forwarding> {
+    forward(pid, false)
+}
+
+/// Like [`forward_to`], for a benchmark binary that reports the interrupt
+/// and saves its partial results itself, as `bench` and `full` run them.
+pub fn hand_over(pid: Option<u32>) -> Option<Forwarding> {
+    forward(pid, true)
+}
+
+fn forward(pid: Option<u32>, reports: bool) -> Option<Forwarding> {
+    let pid = pid?;
+    listen();
+    FORWARDED.lock().unwrap().push((pid, reports));
+    Some(Forwarding(pid))
+}
+
+impl Drop for Forwarding {
+    fn drop(&mut self) {
+        FORWARDED.lock().unwrap().retain(|(pid, _)| *pid != self.0);
+    }
+}
+
+/// Passes an interrupt on; true if a child reports it.
+#[cfg(unix)]
+fn interrupt_children() -> bool {
+    let children = FORWARDED.lock().unwrap();
+    for (pid, _) in children.iter() {
+        unsafe {
+            libc::kill(*pid as libc::pid_t, libc::SIGINT);
+        }
+    }
+    children.iter().any(|(_, reports)| *reports)
+}
+
+#[cfg(not(unix))]
+fn interrupt_children() -> bool {
+    false
+}
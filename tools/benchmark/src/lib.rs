@@ -0,0 +1,80 @@
+//! Shared by every benchmark binary: targets and servers, measurement and
+//! statistics, results I/O, and the reporting around them.
+//!
+//! The load test, performance benchmark, and build comparison can also be run
+//! from other Rust code with [`load_test::run_load_test`],
+//! [`performance::run_performance`], and [`build_times::run_build_comparison`].
+
+pub mod adapters;
+pub mod affinity;
+pub mod apdex;
+pub mod archive;
+pub mod assets;
+pub mod bootstrap;
+pub mod build_times;
+pub mod cache;
+pub mod calibration;
+pub mod charts;
+#[cfg(feature = "docker")]
+pub mod compose;
+pub mod compression;
+pub mod conditional;
+pub mod config;
+pub mod containers;
+pub mod cpu_prof;
+pub mod disk_io;
+pub mod doctor;
+pub mod dry_run;
+pub mod energy;
+pub mod errors;
+pub mod flamegraph;
+pub mod gate;
+pub mod hooks;
+pub mod influx;
+pub mod interrupt;
+pub mod lighthouse;
+pub mod limits;
+pub mod load_test;
+pub mod logging;
+pub mod metrics;
+pub mod monitor;
+pub mod mutation;
+pub mod network;
+pub mod node_stats;
+pub mod noise;
+pub mod notify;
+pub mod oha;
+pub mod order;
+pub mod outliers;
+pub mod output;
+pub mod performance;
+pub mod preflight;
+pub mod presets;
+pub mod probe;
+pub mod profiles;
+pub mod progress;
+pub mod publish;
+pub mod quality;
+pub mod random;
+pub mod redirects;
+pub mod results;
+pub mod revisions;
+pub mod run_metadata;
+pub mod sample_size;
+pub mod schema;
+pub mod script;
+pub mod seed;
+pub mod server_logs;
+pub mod servers;
+pub mod sibling;
+pub mod ssh;
+pub mod store;
+pub mod stream;
+pub mod targets;
+pub mod term_charts;
+pub mod time_utils;
+pub mod tolerance;
+pub mod upload;
+pub mod versions;
+
+pub use rari_benchmark_results::{distribution, stats, units};
@@ -0,0 +1,237 @@
+use crate::errors::{self, Error, ErrorKind};
+use crate::targets::Target;
+use crate::tolerance;
+use crate::units;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use std::process::Stdio;
+use tabled::builder::Builder;
+use tokio::process::Command;
+
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Preset {
+    /// Lighthouse's default: a mid-range phone on a throttled 4G connection.
+    #[default]
+    Mobile,
+    /// A desktop viewport without throttling.
+    Desktop,
+}
+
+impl Preset {
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::Mobile => "mobile",
+            Preset::Desktop => "desktop",
+        }
+    }
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct LighthouseArgs {
+    #[arg(
+        long,
+        help = "Also run Lighthouse on every page and store its performance score and key audits"
+    )]
+    pub lighthouse: bool,
+    #[arg(
+        long,
+        value_enum,
+        default_value = "mobile",
+        requires = "lighthouse",
+        help = "Form factor and throttling Lighthouse emulates"
+    )]
+    pub lighthouse_preset: Preset,
+}
+
+/// Lighthouse's performance score (0–100) and the audits it is made of.
+/// Timings are in milliseconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LighthouseReport {
+    pub score: f64,
+    pub first_contentful_paint: f64,
+    pub largest_contentful_paint: f64,
+    pub speed_index: f64,
+    pub total_blocking_time: f64,
+    pub cumulative_layout_shift: f64,
+    pub interactive: f64,
+}
+
+struct Row {
+    label: &'static str,
+    value: fn(&LighthouseReport) -> f64,
+    format: fn(f64) -> String,
+    lower_is_better: bool,
+}
+
+const ROWS: [Row; 7] = [
+    Row {
+        label: "Performance score",
+        value: |report| report.score,
+        format: |value| format!("{:.0}", value),
+        lower_is_better: false,
+    },
+    Row {
+        label: "First Contentful Paint",
+        value: |report| report.first_contentful_paint,
+        format: units::duration_ms,
+        lower_is_better: true,
+    },
+    Row {
+        label: "Largest Contentful Paint",
+        value: |report| report.largest_contentful_paint,
+        format: units::duration_ms,
+        lower_is_better: true,
+    },
+    Row {
+        label: "Speed Index",
+        value: |report| report.speed_index,
+        format: units::duration_ms,
+        lower_is_better: true,
+    },
+    Row {
+        label: "Total Blocking Time",
+        value: |report| report.total_blocking_time,
+        format: units::duration_ms,
+        lower_is_better: true,
+    },
+    Row {
+        label: "Cumulative Layout Shift",
+        value: |report| report.cumulative_layout_shift,
+        format: |value| format!("{:.3}", value),
+        lower_is_better: true,
+    },
+    Row {
+        label: "Time to Interactive",
+        value: |report| report.interactive,
+        format: units::duration_ms,
+        lower_is_better: true,
+    },
+];
+
+/// Runs the `lighthouse` CLI against `url` with only the performance category.
+/// Lighthouse starts its own Chrome; `chrome` picks the binary.
+pub async fn run(url: &str, preset: Preset, chrome: Option<&Path>) -> Result<LighthouseReport> {
+    let mut command = Command::new("lighthouse");
+    command.arg(url).args([
+        "--output=json",
+        "--output-path=stdout",
+        "--quiet",
+        "--only-categories=performance",
+        "--chrome-flags=--headless=new --no-sandbox",
+    ]);
+    if preset == Preset::Desktop {
+        command.arg("--preset=desktop");
+    }
+    if let Some(chrome) = chrome {
+        command.env("CHROME_PATH", chrome);
+    }
+    let output = command
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| {
+            errors::spawn_error(
+                e,
+                "lighthouse not found; install it with: npm install -g lighthouse",
+            )
+        })?;
+    if !output.status.success() {
+        return Err(Error::new(
+            ErrorKind::ToolFailed,
+            format!(
+                "Lighthouse failed on {} ({}): {}",
+                url,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        )
+        .into());
+    }
+
+    let report: Value = serde_json::from_slice(&output.stdout)
+        .with_context(|| Error::new(ErrorKind::ParseError, "Lighthouse printed no JSON report"))?;
+    if let Some(error) = report["runtimeError"]["message"].as_str() {
+        return Err(Error::new(
+            ErrorKind::RequestsFailed,
+            format!("Lighthouse could not load {}: {}", url, error),
+        )
+        .into());
+    }
+    let audit = |id: &str| {
+        report["audits"][id]["numericValue"]
+            .as_f64()
+            .with_context(|| format!("The Lighthouse report of {} has no {} audit", url, id))
+    };
+    Ok(LighthouseReport {
+        score: report["categories"]["performance"]["score"]
+            .as_f64()
+            .with_context(|| format!("Lighthouse could not score {}", url))?
+            * 100.0,
+        first_contentful_paint: audit("first-contentful-paint")?,
+        largest_contentful_paint: audit("largest-contentful-paint")?,
+        speed_index: audit("speed-index")?,
+        total_blocking_time: audit("total-blocking-time")?,
+        cumulative_layout_shift: audit("cumulative-layout-shift")?,
+        interactive: audit("interactive")?,
+    })
+}
+
+impl LighthouseReport {
+    pub fn describe(&self) -> String {
+        format!(
+            "score {:.0}, LCP {}, TBT {}, CLS {:.3}",
+            self.score,
+            units::duration_ms(self.largest_contentful_paint),
+            units::duration_ms(self.total_blocking_time),
+            self.cumulative_layout_shift
+        )
+    }
+}
+
+/// Compares one page's reports side by side, one row per audit.
+pub fn display(page: &str, targets: &[Target], reports: &[&LighthouseReport], tie_pct: f64) {
+    println!("\n{} {}", "🔦 Lighthouse".bold(), page.bold());
+
+    let mut header = vec!["Audit".to_string()];
+    header.extend(targets.iter().map(|target| target.label().to_string()));
+    if targets.len() == 2 {
+        header.push("Difference".to_string());
+    } else {
+        header.extend(
+            targets
+                .iter()
+                .skip(1)
+                .map(|target| format!("vs {}", target.label())),
+        );
+    }
+    header.push("Winner".to_string());
+
+    let mut builder = Builder::default();
+    builder.push_record(header);
+
+    for row in &ROWS {
+        let values: Vec<f64> = reports.iter().map(|report| (row.value)(report)).collect();
+        let mut record = vec![row.label.to_string()];
+        record.extend(values.iter().map(|value| (row.format)(*value)));
+        for other in &values[1..] {
+            let diff = tolerance::difference_pct(values[0], *other);
+            let outcome = tolerance::classify(values[0], *other, row.lower_is_better, tie_pct);
+            record.push(tolerance::paint(outcome, &format!("{:+.1}%", diff)).to_string());
+        }
+        record.push(
+            match tolerance::best(&values, row.lower_is_better, tie_pct) {
+                Some(index) => targets[index].display(),
+                None => tolerance::TIE.to_string(),
+            },
+        );
+        builder.push_record(record);
+    }
+
+    println!("\n{}", builder.build());
+}
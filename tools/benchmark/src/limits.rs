@@ -0,0 +1,374 @@
+use crate::targets::Target;
+use crate::units;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemoryLimitMode {
+    /// Kernel-enforced cap on the whole process tree (Linux cgroups).
+    #[default]
+    Cgroup,
+    /// V8's old-space limit through NODE_OPTIONS; only Node.js processes honour it.
+    Heap,
+}
+
+impl MemoryLimitMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            MemoryLimitMode::Cgroup => "cgroup",
+            MemoryLimitMode::Heap => "heap",
+        }
+    }
+}
+
+/// How a managed server fared under its memory limit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryPressure {
+    pub limit_mb: u64,
+    pub mode: MemoryLimitMode,
+    /// Processes the kernel killed for going over the limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oom_kills: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peak_bytes: Option<u64>,
+    /// The server exited before it was stopped.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub exited: bool,
+}
+
+/// Caps V8's old space through `NODE_OPTIONS`.
+pub fn limit_heap(target: &mut Target, limit_mb: u64) {
+    let flag = format!("--max-old-space-size={}", limit_mb);
+    let options = match target.env.get("NODE_OPTIONS") {
+        Some(options) => format!("{} {}", options, flag),
+        None => flag,
+    };
+    target.env.insert("NODE_OPTIONS".to_string(), options);
+}
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+/// Length of a CPU quota period, in microseconds.
+const CPU_PERIOD_US: u64 = 100_000;
+
+/// How often a server hit its CPU quota over its lifetime.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CpuThrottling {
+    pub limit_cpus: f64,
+    /// Quota periods in which the server ran at all.
+    pub periods: u64,
+    /// Periods in which it used up its quota and had to wait.
+    pub throttled_periods: u64,
+    pub throttled_seconds: f64,
+}
+
+/// The files that differ between the unified (v2) and legacy (v1) hierarchies.
+#[derive(Debug, Clone, Copy)]
+struct Layout {
+    unified: bool,
+    limit: &'static str,
+    swap_limit: &'static str,
+    /// v1 limits memory plus swap rather than swap alone.
+    swap_includes_memory: bool,
+    events: &'static str,
+    peak: &'static str,
+    /// The `cpu.stat` key holding the throttled time, and its unit in seconds.
+    throttled_time: (&'static str, f64),
+}
+
+const V2: Layout = Layout {
+    unified: true,
+    limit: "memory.max",
+    swap_limit: "memory.swap.max",
+    swap_includes_memory: false,
+    events: "memory.events",
+    peak: "memory.peak",
+    throttled_time: ("throttled_usec", 1e-6),
+};
+
+const V1: Layout = Layout {
+    unified: false,
+    limit: "memory.limit_in_bytes",
+    swap_limit: "memory.memsw.limit_in_bytes",
+    swap_includes_memory: true,
+    events: "memory.oom_control",
+    peak: "memory.max_usage_in_bytes",
+    throttled_time: ("throttled_time", 1e-9),
+};
+
+/// A cgroup directory the server joins, with its `cgroup.procs` held open.
+struct Group {
+    path: PathBuf,
+    procs: std::fs::File,
+}
+
+impl Group {
+    fn create(path: PathBuf) -> Result<Self> {
+        std::fs::create_dir(&path).with_context(|| {
+            format!(
+                "Failed to create {}; cgroup limits need root",
+                path.display()
+            )
+        })?;
+        let procs = std::fs::OpenOptions::new()
+            .write(true)
+            .open(path.join("cgroup.procs"));
+        match procs {
+            Ok(procs) => Ok(Group { path, procs }),
+            Err(e) => {
+                let _ = std::fs::remove_dir(&path);
+                Err(e).with_context(|| format!("Failed to open {}/cgroup.procs", path.display()))
+            }
+        }
+    }
+}
+
+/// The cgroup(s) holding one managed server; removed again on drop.
+pub struct Cgroup {
+    layout: Layout,
+    memory: Option<PathBuf>,
+    cpu: Option<PathBuf>,
+    /// A single directory on v2; one per controller hierarchy on v1.
+    groups: Vec<Group>,
+    cpus: Option<f64>,
+}
+
+fn write(path: &Path, value: String) -> Result<()> {
+    std::fs::write(path, value).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+impl Cgroup {
+    /// Creates a cgroup capped at `memory_mb` (with swap disabled, so going
+    /// over the limit reclaims or OOM-kills instead of swapping) and at `cpus`
+    /// CPUs' worth of time per period.
+    #[cfg(target_os = "linux")]
+    pub fn create(name: &str, memory_mb: Option<u64>, cpus: Option<f64>) -> Result<Self> {
+        let root = PathBuf::from(CGROUP_ROOT);
+        let group = format!("rari-benchmark-{}-{}", std::process::id(), name);
+        let controllers: Vec<&str> = [memory_mb.map(|_| "memory"), cpus.map(|_| "cpu")]
+            .into_iter()
+            .flatten()
+            .collect();
+        let mut cgroup = Cgroup {
+            layout: V1,
+            memory: None,
+            cpu: None,
+            groups: Vec::new(),
+            cpus,
+        };
+        if root.join("cgroup.controllers").exists() {
+            let enabled =
+                std::fs::read_to_string(root.join("cgroup.subtree_control")).unwrap_or_default();
+            for controller in &controllers {
+                if !enabled.split_whitespace().any(|name| name == *controller) {
+                    anyhow::bail!(
+                        "The {} controller is not enabled in {}/cgroup.subtree_control",
+                        controller,
+                        CGROUP_ROOT
+                    );
+                }
+            }
+            let path = root.join(group);
+            cgroup.layout = V2;
+            cgroup.memory = memory_mb.map(|_| path.clone());
+            cgroup.cpu = cpus.map(|_| path.clone());
+            cgroup.groups.push(Group::create(path)?);
+        } else {
+            // v1 mounts each controller as its own hierarchy.
+            for controller in &controllers {
+                let hierarchy = root.join(controller);
+                if !hierarchy.is_dir() {
+                    anyhow::bail!(
+                        "No {} cgroup hierarchy found under {}",
+                        controller,
+                        CGROUP_ROOT
+                    );
+                }
+                let path = hierarchy.join(&group);
+                cgroup.groups.push(Group::create(path.clone())?);
+                match *controller {
+                    "memory" => cgroup.memory = Some(path),
+                    _ => cgroup.cpu = Some(path),
+                }
+            }
+        }
+
+        if let (Some(dir), Some(memory_mb)) = (&cgroup.memory, memory_mb) {
+            let limit_bytes = memory_mb * 1024 * 1024;
+            write(&dir.join(cgroup.layout.limit), limit_bytes.to_string())?;
+            // Only present when swap accounting is enabled.
+            let swap = if cgroup.layout.swap_includes_memory {
+                limit_bytes
+            } else {
+                0
+            };
+            let _ = std::fs::write(dir.join(cgroup.layout.swap_limit), swap.to_string());
+        }
+        if let (Some(dir), Some(cpus)) = (&cgroup.cpu, cpus) {
+            let quota_us = ((cpus * CPU_PERIOD_US as f64) as u64).max(1000);
+            if cgroup.layout.unified {
+                write(
+                    &dir.join("cpu.max"),
+                    format!("{} {}", quota_us, CPU_PERIOD_US),
+                )?;
+            } else {
+                write(&dir.join("cpu.cfs_period_us"), CPU_PERIOD_US.to_string())?;
+                write(&dir.join("cpu.cfs_quota_us"), quota_us.to_string())?;
+            }
+        }
+        Ok(cgroup)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn create(_name: &str, _memory_mb: Option<u64>, _cpus: Option<f64>) -> Result<Self> {
+        anyhow::bail!("cgroup limits need Linux")
+    }
+
+    /// Makes `command` join the cgroup before it runs, so everything it starts
+    /// is limited too.
+    #[cfg(unix)]
+    pub fn apply(&self, command: &mut std::process::Command) {
+        use std::os::fd::AsRawFd;
+        use std::os::unix::process::CommandExt;
+
+        let procs: Vec<i32> = self
+            .groups
+            .iter()
+            .map(|group| group.procs.as_raw_fd())
+            .collect();
+        // Writing 0 moves the writing process. Only async-signal-safe calls
+        // between fork and exec.
+        unsafe {
+            command.pre_exec(move || {
+                for procs in &procs {
+                    if libc::write(*procs, b"0".as_ptr().cast(), 1) != 1 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+                Ok(())
+            });
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub fn apply(&self, _command: &mut std::process::Command) {}
+
+    fn read(dir: Option<&PathBuf>, file: &str) -> Option<String> {
+        std::fs::read_to_string(dir?.join(file)).ok()
+    }
+
+    /// The value of `key` in a file of `key value` lines.
+    fn stat(contents: &str, key: &str) -> Option<u64> {
+        contents.lines().find_map(|line| {
+            let (name, value) = line.split_once(' ')?;
+            (name == key).then(|| value.trim().parse().ok())?
+        })
+    }
+
+    pub fn oom_kills(&self) -> Option<u64> {
+        Self::stat(
+            &Self::read(self.memory.as_ref(), self.layout.events)?,
+            "oom_kill",
+        )
+    }
+
+    pub fn peak_bytes(&self) -> Option<u64> {
+        Self::read(self.memory.as_ref(), self.layout.peak)?
+            .trim()
+            .parse()
+            .ok()
+    }
+
+    pub fn throttling(&self) -> Option<CpuThrottling> {
+        let stat = Self::read(self.cpu.as_ref(), "cpu.stat")?;
+        let (key, unit) = self.layout.throttled_time;
+        Some(CpuThrottling {
+            limit_cpus: self.cpus?,
+            periods: Self::stat(&stat, "nr_periods")?,
+            throttled_periods: Self::stat(&stat, "nr_throttled")?,
+            throttled_seconds: Self::stat(&stat, key)? as f64 * unit,
+        })
+    }
+}
+
+impl Drop for Cgroup {
+    fn drop(&mut self) {
+        for group in &self.groups {
+            // Killed processes take a moment to leave the group.
+            for _ in 0..20 {
+                if std::fs::remove_dir(&group.path).is_ok() {
+                    break;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(100));
+            }
+        }
+    }
+}
+
+pub fn display_memory(pressure: &BTreeMap<String, MemoryPressure>, targets: &[Target]) {
+    let Some(first) = pressure.values().next() else {
+        return;
+    };
+
+    println!(
+        "\n{} {}",
+        "🧱 Memory Pressure".bold(),
+        format!("(limit {} MB, {})", first.limit_mb, first.mode.name()).dimmed()
+    );
+    for target in targets {
+        let Some(pressure) = pressure.get(&target.name) else {
+            continue;
+        };
+        let mut parts = Vec::new();
+        if let Some(oom_kills) = pressure.oom_kills {
+            parts.push(format!("{} OOM kills", oom_kills));
+        }
+        if let Some(peak) = pressure.peak_bytes {
+            parts.push(format!("peak {}", units::bytes(peak as f64)));
+        }
+        if parts.is_empty() && !pressure.exited {
+            parts.push("kept running".to_string());
+        }
+        println!("  {}: {}", target.label().bold(), parts.join(", "));
+        if pressure.exited {
+            println!(
+                "     {} The server exited before the benchmark finished",
+                "⚠️".yellow()
+            );
+        }
+    }
+}
+
+pub fn display_cpu(throttling: &BTreeMap<String, CpuThrottling>, targets: &[Target]) {
+    let Some(first) = throttling.values().next() else {
+        return;
+    };
+
+    println!(
+        "\n{} {}",
+        "🐢 CPU Throttling".bold(),
+        format!("(limit {} CPUs)", first.limit_cpus).dimmed()
+    );
+    for target in targets {
+        let Some(throttling) = throttling.get(&target.name) else {
+            continue;
+        };
+        let throttled_pct = if throttling.periods > 0 {
+            throttling.throttled_periods as f64 / throttling.periods as f64 * 100.0
+        } else {
+            0.0
+        };
+        println!(
+            "  {}: hit the quota in {:.1}% of periods, {} spent waiting",
+            target.label().bold(),
+            throttled_pct,
+            units::duration_ms(throttling.throttled_seconds * 1000.0)
+        );
+    }
+}
@@ -1,135 +1,427 @@
-mod time_utils;
-
+#[cfg(feature = "docker")]
+use crate::compose;
+use crate::units::Millis;
+use crate::{
+    affinity, archive, charts, config, containers, cpu_prof, dry_run, energy, errors, flamegraph,
+    gate, hooks, influx, interrupt, limits, metrics, monitor, network, node_stats, noise, notify,
+    oha, order, output, preflight, presets, probe, profiles, progress, publish, quality, random,
+    results, run_metadata, schema, seed, server_logs, servers, ssh, stats, store, stream, targets,
+    term_charts, time_utils, tolerance, units, upload, versions,
+};
 use anyhow::{Context, Result};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use colored::Colorize;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use std::path::PathBuf;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
 use std::time::{Duration, SystemTime};
 use tokio::fs;
 use tokio::process::Command;
+use tracing::Instrument;
 
 #[derive(Parser, Debug)]
 #[command(name = "load-test")]
-#[command(about = "Run load tests comparing rari and Next.js using oha")]
-struct Args {
+#[command(
+    about = "Run load tests comparing rari and Next.js (or any configured targets) using oha"
+)]
+pub struct Args {
     #[arg(short, long, default_value = "30")]
-    duration: u64,
+    pub duration: u64,
     #[arg(short, long, default_value = "50")]
-    connections: usize,
-    #[arg(long, default_value = "3000")]
-    rari_port: u16,
-    #[arg(long, default_value = "3001")]
-    nextjs_port: u16,
+    pub connections: usize,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = Execution::Sequential,
+        help = "Test the targets one after another with all connections each, or all at once with the connections split between them"
+    )]
+    pub execution: Execution,
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Send N requests/sec to each target instead of as many as it can serve"
+    )]
+    pub rps: Option<u64>,
+    #[arg(
+        long,
+        conflicts_with = "rps",
+        help = "Search for each target's maximum sustainable request rate, then measure it at that rate"
+    )]
+    pub capacity: bool,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 100,
+        requires = "capacity",
+        help = "Request rate the capacity search starts from; it doubles until a step fails"
+    )]
+    pub capacity_start: u64,
+    #[arg(
+        long,
+        value_name = "SECS",
+        default_value_t = 10,
+        requires = "capacity",
+        help = "Length of each capacity search step"
+    )]
+    pub step_duration: u64,
+    #[arg(
+        long,
+        value_name = "PCT",
+        default_value_t = 1.0,
+        requires = "capacity",
+        help = "A capacity step fails when more than PCT% of requests fail or time out"
+    )]
+    pub max_error_pct: f64,
+    #[arg(
+        long,
+        requires = "rps",
+        help = "Measure the CPU package energy used during each target's load window (Linux RAPL)"
+    )]
+    pub energy: bool,
+    #[arg(
+        long,
+        help = "Send the load through a local proxy that counts the bytes sent and received, headers included"
+    )]
+    pub count_bytes: bool,
+    #[arg(
+        long,
+        value_name = "MB",
+        default_value_t = 1.0,
+        help = "Flag a managed server whose memory grows steadily by more than MB per minute during a load window of at least a minute"
+    )]
+    pub leak_threshold: f64,
+    #[arg(
+        long,
+        value_name = "TARGET",
+        requires = "manage_servers",
+        help = "Record the named managed server with `perf` during its load window and save a flamegraph next to the results (needs the flamegraph feature)"
+    )]
+    pub flamegraph: Vec<String>,
+    #[arg(
+        long,
+        value_name = "TARGET",
+        requires = "manage_servers",
+        help = "Profile the named Node.js server with V8's CPU profiler during its load window and save the .cpuprofile next to the results"
+    )]
+    pub cpu_prof: Vec<String>,
     #[arg(long, default_value = "results")]
-    results_dir: PathBuf,
+    pub results_dir: PathBuf,
+    #[arg(long)]
+    pub label: Option<String>,
+    #[arg(long = "attach-profile", value_name = "FRAMEWORK=PATH")]
+    pub attach_profile: Vec<profiles::ProfileSpec>,
+    #[arg(
+        long,
+        value_name = "PCT",
+        default_value_t = tolerance::DEFAULT_TIE_PCT,
+        help = "Differences within ±PCT% are reported as a tie"
+    )]
+    pub tie_threshold: f64,
+    #[arg(long = "fail-if", value_name = "EXPR")]
+    pub fail_if: Vec<String>,
+    #[arg(long, value_name = "FILE")]
+    pub thresholds: Option<PathBuf>,
+    #[arg(long, value_name = "URL")]
+    pub notify_webhook: Option<String>,
+    #[arg(long, value_name = "URL")]
+    pub artifact_url: Option<String>,
+    #[arg(long, value_name = "URL")]
+    pub upload: Option<upload::Destination>,
+    #[arg(long, value_name = "FILE")]
+    pub store: Option<PathBuf>,
+    #[arg(long)]
+    pub no_store: bool,
+    #[arg(
+        long,
+        value_name = "CPUS",
+        help = "Pin the load generator to these CPUs, e.g. 4-7 (Linux only)"
+    )]
+    pub client_cpus: Option<String>,
+    #[arg(
+        long,
+        requires = "manage_servers",
+        help = "Preload a script into the Node.js servers that reports event-loop delay and GC pauses"
+    )]
+    pub node_stats: bool,
+    #[command(flatten)]
+    pub targets: targets::TargetArgs,
+    #[command(flatten)]
+    pub hooks: hooks::HookArgs,
+    #[command(flatten)]
+    pub preflight: preflight::PreflightArgs,
+    #[command(flatten)]
+    pub seed: seed::SeedArgs,
+    #[command(flatten)]
+    pub servers: servers::ServerArgs,
+    #[command(flatten)]
+    pub probe: probe::ProbeArgs,
+    #[command(flatten)]
+    pub noise: noise::NoiseArgs,
+    #[command(flatten)]
+    pub order: order::OrderArgs,
+    #[command(flatten)]
+    pub random: random::RandomArgs,
+    #[cfg(feature = "docker")]
+    #[command(flatten)]
+    pub compose: compose::ComposeArgs,
+    #[command(flatten)]
+    pub ssh: ssh::SshArgs,
+    #[command(flatten)]
+    pub network: network::NetworkArgs,
+    #[command(flatten)]
+    pub output: output::OutputArgs,
+    #[command(flatten)]
+    pub preset: presets::PresetArgs,
+    #[command(flatten)]
+    pub dry_run: dry_run::DryRunArgs,
+    #[command(flatten)]
+    pub archive: archive::ArchiveArgs,
+    #[command(flatten)]
+    pub influx: influx::InfluxArgs,
+    #[command(flatten)]
+    pub github: publish::GitHubArgs,
+    #[command(flatten)]
+    pub config: config::ConfigArgs,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct RequestStats {
-    #[serde(serialize_with = "time_utils::serialize_float_as_int_if_whole")]
-    total: f64,
-    average: f64,
-    mean: f64,
-    stddev: f64,
-    #[serde(serialize_with = "time_utils::serialize_float_as_int_if_whole")]
-    min: f64,
-    #[serde(serialize_with = "time_utils::serialize_float_as_int_if_whole")]
-    max: f64,
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Execution {
+    /// Isolated latency: each target gets the machine to itself.
+    #[default]
+    Sequential,
+    /// Capacity under contention: every target is loaded at the same time.
+    Simultaneous,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct LatencyStats {
-    average: f64,
-    mean: f64,
-    stddev: f64,
-    #[serde(serialize_with = "time_utils::serialize_float_as_int_if_whole")]
-    min: f64,
+pub struct RequestStats {
     #[serde(serialize_with = "time_utils::serialize_float_as_int_if_whole")]
-    max: f64,
-    #[serde(serialize_with = "time_utils::serialize_float_as_int_if_whole")]
-    p50: f64,
-    #[serde(serialize_with = "time_utils::serialize_float_as_int_if_whole")]
-    p90: f64,
-    #[serde(serialize_with = "time_utils::serialize_float_as_int_if_whole")]
-    p95: f64,
-    #[serde(serialize_with = "time_utils::serialize_float_as_int_if_whole")]
-    p99: f64,
+    pub total: f64,
+    pub average: f64,
+    pub mean: f64,
+    /// Spread of the requests completed in each second of the run; `None`
+    /// when oha's per-request records could not be read.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stddev: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct ThroughputStats {
-    average: f64,
-    mean: f64,
-    stddev: f64,
-    #[serde(serialize_with = "time_utils::serialize_float_as_int_if_whole")]
-    min: f64,
-    #[serde(serialize_with = "time_utils::serialize_float_as_int_if_whole")]
-    max: f64,
+pub struct LatencyStats {
+    pub average: Millis,
+    pub mean: Millis,
+    /// Over every request; `None` when oha's per-request records could not
+    /// be read.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stddev: Option<Millis>,
+    pub min: Millis,
+    pub max: Millis,
+    pub p50: Millis,
+    pub p90: Millis,
+    pub p95: Millis,
+    pub p99: Millis,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct LoadTestResult {
-    requests: RequestStats,
-    latency: LatencyStats,
-    throughput: ThroughputStats,
-    errors: usize,
-    timeouts: usize,
-    duration: f64,
-    start: String,
-    finish: String,
+pub struct ThroughputStats {
+    pub average: f64,
+    pub mean: f64,
+    /// Spread of the bytes received in each second of the run, like
+    /// [`RequestStats`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stddev: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct BenchmarkResults {
-    timestamp: String,
-    config: TestConfig,
-    rari: LoadTestResult,
-    nextjs: LoadTestResult,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistogramBucket {
+    pub latency: Millis,
+    pub count: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct TestConfig {
-    duration: u64,
-    connections: usize,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadTestResult {
+    pub requests: RequestStats,
+    pub latency: LatencyStats,
+    pub throughput: ThroughputStats,
+    pub errors: usize,
+    pub timeouts: usize,
+    pub duration: f64,
+    pub start: String,
+    pub finish: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub latency_histogram: Vec<HistogramBucket>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rps_timeline: Vec<f64>,
+    /// CPU and memory of the managed server's process tree during the load.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resources: Option<monitor::ResourceUsage>,
+    /// Requests served per second of server CPU time.
+    #[serde(
+        rename = "requestsPerCpuSecond",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub requests_per_cpu_second: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub energy: Option<energy::Energy>,
+    /// Bytes on the wire between the load generator and the server.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wire: Option<network::WireBytes>,
+    #[serde(
+        rename = "wireBytesPerRequest",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub wire_bytes_per_request: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capacity: Option<Capacity>,
+    /// Share of requests that succeeded, as reported by oha.
+    #[serde(skip)]
+    pub success_rate: f64,
+    /// The flamegraph recorded during the load, until it is saved.
+    #[serde(skip)]
+    pub flamegraph: Option<PathBuf>,
+    /// The V8 CPU profile recorded during the load, until it is saved.
+    #[serde(skip)]
+    pub cpu_profile: Option<PathBuf>,
+    #[serde(flatten)]
+    pub noise: noise::Noise,
 }
 
-async fn check_oha_installed() -> Result<()> {
-    let output = Command::new("oha").arg("--version").output().await;
-
-    match output {
-        Ok(output) if output.status.success() => {
-            let version = String::from_utf8_lossy(&output.stdout);
-            println!("{} oha {}", "✅".green(), version.trim());
-            Ok(())
-        }
-        _ => {
-            anyhow::bail!(
-                "oha is not installed. Install it with: cargo install oha\n\
-                 Or visit: https://github.com/hatoo/oha"
-            )
+impl LoadTestResult {
+    fn attach_wire(&mut self, wire: network::WireBytes) {
+        if self.requests.total > 0.0 {
+            self.wire_bytes_per_request =
+                Some((wire.sent_bytes + wire.received_bytes).as_f64() / self.requests.total);
         }
+        self.wire = Some(wire);
+    }
+
+    fn attach_resources(&mut self, mut usage: monitor::ResourceUsage, leak_threshold_mb: f64) {
+        usage.fit_memory_growth(leak_threshold_mb);
+        self.requests_per_cpu_second = usage.per_cpu_second(self.requests.total);
+        self.resources = Some(usage);
     }
 }
 
-async fn check_server(name: &str, port: u16) -> Result<()> {
-    let url = format!("http://localhost:{}", port);
-    reqwest::get(&url)
-        .await
-        .context(format!("{} server is not responding at {}", name, url))?;
-    println!("{} {} server is responding", "✅".green(), name);
-    Ok(())
+/// The outcome of a capacity search: the highest request rate a target
+/// sustained, the lowest it failed at, and every step tried.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Capacity {
+    pub max_sustainable_rps: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub breaking_rps: Option<u64>,
+    pub max_error_pct: f64,
+    pub steps: Vec<CapacityStep>,
 }
 
-async fn run_load_test(
-    name: &str,
-    port: u16,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapacityStep {
+    pub offered_rps: u64,
+    pub achieved_rps: f64,
+    pub error_pct: f64,
+    pub p99_ms: Millis,
+    pub sustainable: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BenchmarkResults {
+    /// [`schema::VERSION`] when written; see [`schema::migrate`] for older files.
+    pub version: u32,
+    pub timestamp: String,
+    #[serde(default)]
+    pub metadata: run_metadata::RunMetadata,
+    pub config: TestConfig,
+    pub targets: Vec<String>,
+    #[serde(flatten)]
+    pub results: BTreeMap<String, LoadTestResult>,
+    /// How far the numbers can be trusted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quality: Option<quality::Quality>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<profiles::ProfileArtifact>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub containers: BTreeMap<String, containers::ContainerStats>,
+    #[serde(
+        rename = "serverStats",
+        default,
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub server_stats: BTreeMap<String, containers::ContainerStats>,
+    #[serde(
+        rename = "serverLogs",
+        default,
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub server_logs: BTreeMap<String, server_logs::ServerLog>,
+    /// Each managed server's memory before any load.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub footprint: BTreeMap<String, monitor::Footprint>,
+    /// How each managed server fared under `--memory-limit`.
+    #[serde(
+        rename = "memoryPressure",
+        default,
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub memory_pressure: BTreeMap<String, limits::MemoryPressure>,
+    /// How often each managed server hit its `--cpu-limit`.
+    #[serde(
+        rename = "cpuThrottling",
+        default,
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub cpu_throttling: BTreeMap<String, limits::CpuThrottling>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub partial: bool,
+    /// What cut the run short, if anything.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<errors::RunError>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TestConfig {
+    pub duration: u64,
+    pub connections: usize,
+    #[serde(default)]
+    pub execution: Execution,
+    #[serde(
+        rename = "shuffleSeed",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub shuffle_seed: Option<u64>,
+    #[serde(rename = "maxCvPct", default, skip_serializing_if = "Option::is_none")]
+    pub max_cv_pct: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rps: Option<u64>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub capacity: bool,
+}
+
+#[tracing::instrument(name = "load", skip_all, fields(target = %target.name))]
+async fn run_target(
+    target: &targets::Target,
     duration: u64,
     connections: usize,
+    rps: Option<u64>,
+    timeout_secs: Option<u64>,
+    client_cpus: &[usize],
+    rapl: Option<&energy::Rapl>,
 ) -> Result<LoadTestResult> {
-    println!("\n{} Load Testing {}", "🔥".bold(), name.bold());
-    let url = format!("http://localhost:{}", port);
+    println!("\n{} Load Testing {}", "🔥".bold(), target.label().bold());
+    let url = target.base_url();
     println!("  {} {}", "URL:".dimmed(), url);
     println!(
         "  {} {}s, Connections: {}",
@@ -137,12 +429,19 @@ async fn run_load_test(
         duration,
         connections
     );
+    if let Some(rps) = rps {
+        println!("  {} {} req/s", "Rate:".dimmed(), rps);
+    }
+
+    let framework = target.name.as_str();
+    let db_path = std::env::temp_dir().join(format!("oha-{}-{}.db", framework, std::process::id()));
+    let _ = fs::remove_file(&db_path).await;
 
     let start_time = SystemTime::now();
     let start_str = time_utils::format_timestamp(start_time);
 
-    let output = Command::new("oha")
-        .arg(&url)
+    let mut oha = Command::new("oha");
+    oha.arg(&url)
         .arg("-z")
         .arg(format!("{}s", duration))
         .arg("-c")
@@ -150,62 +449,143 @@ async fn run_load_test(
         .arg("-H")
         .arg("Accept-Encoding: zstd, br, gzip")
         .arg("--no-tui")
-        .arg("--output-format")
-        .arg("json")
-        .output()
-        .await
-        .context("Failed to execute oha")?;
+        .args(oha::json_args())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    // In its own process group, oha hears Ctrl-C only once, as forwarded below.
+    #[cfg(unix)]
+    oha.process_group(0);
+    if oha::records_requests() {
+        oha.arg("--db-url").arg(&db_path);
+    }
+    if let Some(rps) = rps {
+        oha.arg("-q").arg(rps.to_string());
+    }
+    if let Some(timeout_secs) = timeout_secs {
+        oha.arg("-t").arg(format!("{}s", timeout_secs));
+    }
+    affinity::apply(oha.as_std_mut(), client_cpus, None)?;
+    let command = oha.as_std();
+    tracing::debug!(
+        "Command: {} {}",
+        command.get_program().to_string_lossy(),
+        command
+            .get_args()
+            .map(|arg| arg.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
+
+    // Only oha's run is metered, not reading its results afterwards.
+    let meter = rapl.map(energy::Rapl::start);
+    let child = oha
+        .spawn()
+        .map_err(|e| errors::spawn_error(e, "Failed to execute oha"))?;
+    // On Ctrl-C, oha stops and reports the requests it has sent so far.
+    let _forwarding = interrupt::forward_to(child.id());
+    let finished = progress::timed(
+        child.wait_with_output(),
+        "Loading",
+        Some(Duration::from_secs(duration)),
+    );
+    let output = stream::with_progress(finished, |elapsed_secs| {
+        json!({
+            "framework": framework,
+            "elapsedSecs": elapsed_secs,
+            "durationSecs": duration,
+        })
+    })
+    .await
+    .context("Failed to wait for oha")?;
+    let reading = meter.map(|meter| meter.and_then(energy::EnergyMeter::finish));
 
     let finish_time = SystemTime::now();
     let finish_str = time_utils::format_timestamp(finish_time);
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("oha failed: {}", stderr);
+        return Err(errors::Error::new(
+            errors::ErrorKind::ToolFailed,
+            format!("oha failed: {}", stderr),
+        )
+        .target(framework)
+        .into());
     }
 
     let stdout = String::from_utf8_lossy(&output.stdout);
-    let json: Value = serde_json::from_str(&stdout).context("Failed to parse oha JSON output")?;
+    tracing::trace!("{}", stdout.trim());
+    let report = oha::parse(&stdout).map_err(|e| {
+        let kind = errors::kind(&e);
+        e.context(errors::Error::new(kind, "Failed to read oha's results").target(framework))
+    })?;
 
-    let summary = &json["summary"];
-    let percentiles = &json["latencyPercentiles"];
+    // Without --db-url support, check_installed has already warned.
+    let samples = if !oha::records_requests() {
+        Samples::default()
+    } else {
+        match read_samples(&db_path, duration) {
+            Ok(samples) => samples,
+            Err(e) => {
+                tracing::warn!(
+                    "Per-request records unavailable, so no per-second RPS or spread: {}",
+                    e
+                );
+                Samples::default()
+            }
+        }
+    };
+    let _ = fs::remove_file(&db_path).await;
 
-    let total_requests = (summary["successRate"].as_f64().unwrap_or(1.0)
-        * summary["requestsPerSec"].as_f64().unwrap_or(0.0)
-        * summary["total"].as_f64().unwrap_or(0.0)) as f64;
+    let total_requests = report.success_rate * report.requests_per_sec * report.total_secs;
+    let energy = match reading {
+        Some(Ok(reading)) => Some(reading.per_request(total_requests)),
+        Some(Err(e)) => {
+            tracing::warn!("Energy reading unavailable for {}: {:#}", framework, e);
+            None
+        }
+        None => None,
+    };
 
     let requests = RequestStats {
         total: total_requests,
-        average: summary["requestsPerSec"].as_f64().unwrap_or(0.0),
-        mean: summary["requestsPerSec"].as_f64().unwrap_or(0.0),
-        stddev: 0.0,
-        min: 0.0,
-        max: 0.0,
+        average: report.requests_per_sec,
+        mean: report.requests_per_sec,
+        stddev: stats::stddev(&samples.rps_timeline),
+        min: stats::min(&samples.rps_timeline),
+        max: stats::max(&samples.rps_timeline),
     };
 
     let latency = LatencyStats {
-        average: summary["average"].as_f64().unwrap_or(0.0) * 1000.0,
-        mean: summary["average"].as_f64().unwrap_or(0.0) * 1000.0,
-        stddev: 0.0,
-        min: summary["fastest"].as_f64().unwrap_or(0.0) * 1000.0,
-        max: summary["slowest"].as_f64().unwrap_or(0.0) * 1000.0,
-        p50: percentiles["p50"].as_f64().unwrap_or(0.0) * 1000.0,
-        p90: percentiles["p90"].as_f64().unwrap_or(0.0) * 1000.0,
-        p95: percentiles["p95"].as_f64().unwrap_or(0.0) * 1000.0,
-        p99: percentiles["p99"].as_f64().unwrap_or(0.0) * 1000.0,
+        average: report.average,
+        mean: report.average,
+        stddev: stats::stddev(&samples.latencies).map(Millis),
+        min: report.fastest,
+        max: report.slowest,
+        p50: report.p50,
+        p90: report.p90,
+        p95: report.p95,
+        p99: report.p99,
     };
 
-    let duration_secs = summary["total"].as_f64().unwrap_or(duration as f64);
+    let duration_secs = report.total_secs;
 
     let throughput = ThroughputStats {
-        average: summary["sizePerSec"].as_f64().unwrap_or(0.0),
-        mean: summary["sizePerSec"].as_f64().unwrap_or(0.0),
-        stddev: 0.0,
-        min: 0.0,
-        max: 0.0,
+        average: report.size_per_sec,
+        mean: report.size_per_sec,
+        stddev: stats::stddev(&samples.bytes_timeline),
+        min: stats::min(&samples.bytes_timeline),
+        max: stats::max(&samples.bytes_timeline),
     };
 
-    let success_rate = summary["successRate"].as_f64().unwrap_or(1.0);
+    let mut latency_histogram: Vec<HistogramBucket> = report
+        .histogram
+        .iter()
+        .map(|&(latency, count)| HistogramBucket { latency, count })
+        .collect();
+    latency_histogram.sort_by(|a, b| a.latency.total_cmp(&b.latency));
+
+    let success_rate = report.success_rate;
     let total = total_requests as usize;
     let errors = ((1.0 - success_rate) * total as f64) as usize;
 
@@ -226,145 +606,1274 @@ async fn run_load_test(
         duration: duration_secs,
         start: start_str,
         finish: finish_str,
+        latency_histogram,
+        rps_timeline: samples.rps_timeline,
+        resources: None,
+        requests_per_cpu_second: None,
+        energy,
+        wire: None,
+        wire_bytes_per_request: None,
+        capacity: None,
+        success_rate,
+        flamegraph: None,
+        cpu_profile: None,
+        noise: noise::Noise::default(),
     })
 }
 
-fn display_comparison(rari: &LoadTestResult, nextjs: &LoadTestResult) {
-    println!("\n{}", "📊 Load Test Comparison".bold());
+/// A failed request rate is narrowed down until the bounds are this close.
+const CAPACITY_RESOLUTION_PCT: f64 = 5.0;
+const MAX_CAPACITY_STEPS: usize = 20;
+/// Requests slower than this count as failed during a capacity search.
+const CAPACITY_TIMEOUT_SECS: u64 = 5;
 
-    println!("\n📈 Throughput (req/sec):");
-    println!("  🦀 rari:     {:.2}", rari.requests.average);
-    println!("  🟢 Next.js:  {:.2}", nextjs.requests.average);
-
-    let throughput_diff =
-        ((rari.requests.average - nextjs.requests.average) / nextjs.requests.average) * 100.0;
-    if throughput_diff > 0.0 {
-        println!(
-            "  {} rari handles {:.1}% more requests/sec",
-            "📈".green(),
-            throughput_diff
-        );
-    } else {
+/// Doubles the offered rate from `--capacity-start` until a step fails, then
+/// bisects between the last rate that held and the first that did not. A step
+/// fails when too many requests error or time out, or when the server serves
+/// less than 90% of the offered rate.
+#[tracing::instrument(name = "capacity", skip_all, fields(target = %target.name))]
+async fn find_capacity(
+    target: &targets::Target,
+    args: &Args,
+    client_cpus: &[usize],
+) -> Result<Capacity> {
+    println!(
+        "\n{} Searching for the capacity of {}",
+        "🧗".bold(),
+        target.label().bold()
+    );
+    let mut capacity = Capacity {
+        max_error_pct: args.max_error_pct,
+        ..Capacity::default()
+    };
+    let mut rate = args.capacity_start.max(1);
+    while capacity.steps.len() < MAX_CAPACITY_STEPS {
+        let result = run_target(
+            target,
+            args.step_duration,
+            args.connections,
+            Some(rate),
+            Some(CAPACITY_TIMEOUT_SECS),
+            client_cpus,
+            None,
+        )
+        .await?;
+        // A step cut short by an interrupt says nothing about the rate.
+        if interrupt::cancelled() {
+            break;
+        }
+        let error_pct = (1.0 - result.success_rate) * 100.0;
+        let sustainable =
+            error_pct <= args.max_error_pct && result.requests.average >= rate as f64 * 0.9;
         println!(
-            "  {} rari handles {:.1}% fewer requests/sec",
-            "📉".red(),
-            throughput_diff.abs()
+            "  {} {} req/s offered: {:.0} served, {:.1}% failed, P99 {}",
+            if sustainable { "✅" } else { "❌" },
+            rate,
+            result.requests.average,
+            error_pct,
+            result.latency.p99
         );
+        capacity.steps.push(CapacityStep {
+            offered_rps: rate,
+            achieved_rps: result.requests.average,
+            error_pct,
+            p99_ms: result.latency.p99,
+            sustainable,
+        });
+
+        if sustainable {
+            capacity.max_sustainable_rps = rate;
+        } else {
+            capacity.breaking_rps = Some(rate);
+        }
+        let Some(breaking) = capacity.breaking_rps else {
+            rate = rate.saturating_mul(2);
+            continue;
+        };
+        let low = capacity.max_sustainable_rps;
+        if (breaking - low) as f64 <= (low as f64 * CAPACITY_RESOLUTION_PCT / 100.0).max(1.0) {
+            break;
+        }
+        rate = low + (breaking - low) / 2;
     }
 
-    println!("\n⏱️  Latency (ms):");
-    println!(
-        "  🦀 rari:     {:.2}ms (P95: {:.2}ms)",
-        rari.latency.mean, rari.latency.p95
-    );
+    match capacity.breaking_rps {
+        Some(breaking) => println!(
+            "  {} {} sustains {} req/s and breaks at {} req/s",
+            "🧗".dimmed(),
+            target.label(),
+            capacity.max_sustainable_rps,
+            breaking
+        ),
+        None => println!(
+            "  {} {} sustained every rate up to {} req/s",
+            "🧗".dimmed(),
+            target.label(),
+            capacity.max_sustainable_rps
+        ),
+    }
+    Ok(capacity)
+}
+
+/// Loads every target at once, splitting `connections` evenly between them.
+async fn run_simultaneous(
+    targets: &[targets::Target],
+    duration: u64,
+    connections: usize,
+    rps: Option<u64>,
+    client_cpus: &[usize],
+) -> Result<Vec<LoadTestResult>> {
+    let per_target = (connections / targets.len()).max(1);
     println!(
-        "  🟢 Next.js:  {:.2}ms (P95: {:.2}ms)",
-        nextjs.latency.mean, nextjs.latency.p95
+        "\n{} Loading {} targets at once with {} connections each",
+        "🔥".bold(),
+        targets.len(),
+        per_target
     );
 
-    let latency_diff = ((rari.latency.mean - nextjs.latency.mean) / nextjs.latency.mean) * 100.0;
-    if latency_diff < 0.0 {
+    let mut tasks = tokio::task::JoinSet::new();
+    for (index, target) in targets.iter().cloned().enumerate() {
+        let client_cpus = client_cpus.to_vec();
+        tasks.spawn(async move {
+            let result =
+                run_target(&target, duration, per_target, rps, None, &client_cpus, None).await;
+            (index, result)
+        });
+    }
+
+    let mut results = vec![None; targets.len()];
+    while let Some(joined) = tasks.join_next().await {
+        let (index, result) = joined?;
+        results[index] = Some(result?);
+    }
+    Ok(results.into_iter().flatten().collect())
+}
+
+/// What oha recorded about every request it sent.
+#[derive(Debug, Default)]
+struct Samples {
+    /// Milliseconds, per request.
+    latencies: Vec<f64>,
+    /// Requests completed in each second of the run.
+    rps_timeline: Vec<f64>,
+    /// Bytes received in each second of the run.
+    bytes_timeline: Vec<f64>,
+}
+
+fn read_samples(db_path: &Path, duration: u64) -> Result<Samples> {
+    let conn = rusqlite::Connection::open(db_path).context("Failed to open oha database")?;
+    let mut stmt = conn
+        .prepare("SELECT end - (SELECT MIN(start) FROM oha), end - start, len_bytes FROM oha")
+        .context("Failed to query oha database")?;
+
+    let mut samples = Samples {
+        latencies: Vec::new(),
+        rps_timeline: vec![0.0; duration as usize],
+        bytes_timeline: vec![0.0; duration as usize],
+    };
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, f64>(0)?,
+            row.get::<_, f64>(1)?,
+            row.get::<_, i64>(2)?,
+        ))
+    })?;
+    for row in rows {
+        let (end, latency, bytes) = row?;
+        samples.latencies.push(Millis::from_secs(latency).0);
+        let second = end.floor();
+        if second >= 0.0 && (second as usize) < samples.rps_timeline.len() {
+            samples.rps_timeline[second as usize] += 1.0;
+            samples.bytes_timeline[second as usize] += bytes as f64;
+        }
+    }
+
+    Ok(samples)
+}
+
+fn display_comparison(targets: &[targets::Target], results: &[LoadTestResult], tie_pct: f64) {
+    println!("\n{}", "📊 Load Test Comparison".bold());
+    let headings = targets::headings(targets);
+    let subject = &targets[0];
+
+    println!("\n📈 Throughput:");
+    for (heading, result) in headings.iter().zip(results) {
+        let range = match (result.requests.min, result.requests.max) {
+            (Some(min), Some(max)) => format!(
+                " (per second: {} to {})",
+                units::Unit::RequestsPerSec.format(min),
+                units::Unit::RequestsPerSec.format(max)
+            ),
+            _ => String::new(),
+        };
         println!(
-            "  {} rari is {:.1}% faster response time",
-            "📈".green(),
-            latency_diff.abs()
+            "  {}{}{}",
+            heading,
+            units::Unit::RequestsPerSec.format(result.requests.average),
+            range.dimmed()
         );
-    } else {
+    }
+
+    for (other, result) in targets.iter().zip(results).skip(1) {
+        let subject_rps = results[0].requests.average;
+        let throughput_diff = tolerance::difference_pct(subject_rps, result.requests.average);
+        match tolerance::classify(subject_rps, result.requests.average, false, tie_pct) {
+            tolerance::Outcome::SubjectBetter => println!(
+                "  {} {} handles {:.1}% more requests/sec than {}",
+                "📈".green(),
+                subject.label(),
+                throughput_diff,
+                other.label()
+            ),
+            tolerance::Outcome::OtherBetter => println!(
+                "  {} {} handles {:.1}% fewer requests/sec than {}",
+                "📉".red(),
+                subject.label(),
+                throughput_diff.abs(),
+                other.label()
+            ),
+            tolerance::Outcome::Tie => println!(
+                "{}",
+                tolerance::tie_line(throughput_diff, tie_pct, other.label())
+            ),
+        }
+    }
+
+    println!("\n⏱️  Latency:");
+    for (heading, result) in headings.iter().zip(results) {
+        let spread = result
+            .latency
+            .stddev
+            .map(|stddev| format!(", ±{}", stddev))
+            .unwrap_or_default();
+        println!(
+            "  {}{} (P95: {}{})",
+            heading, result.latency.mean, result.latency.p95, spread
+        );
+    }
+
+    for (other, result) in targets.iter().zip(results).skip(1) {
+        let subject_latency = results[0].latency.mean.0;
+        let latency_diff = tolerance::difference_pct(subject_latency, result.latency.mean.0);
+        match tolerance::classify(subject_latency, result.latency.mean.0, true, tie_pct) {
+            tolerance::Outcome::SubjectBetter => println!(
+                "  {} {} is {:.1}% faster response time than {}",
+                "📈".green(),
+                subject.label(),
+                latency_diff.abs(),
+                other.label()
+            ),
+            tolerance::Outcome::OtherBetter => println!(
+                "  {} {} is {:.1}% slower response time than {}",
+                "📉".red(),
+                subject.label(),
+                latency_diff,
+                other.label()
+            ),
+            tolerance::Outcome::Tie => println!(
+                "{}",
+                tolerance::tie_line(latency_diff, tie_pct, other.label())
+            ),
+        }
+    }
+
+    println!("\n📊 Latency Distribution:");
+    for (target, result) in targets.iter().zip(results) {
+        let label = target.display();
+        let buckets: Vec<(String, u64)> = result
+            .latency_histogram
+            .iter()
+            .map(|b| (b.latency.to_string(), b.count))
+            .collect();
+        let lines = term_charts::histogram(&buckets, 40);
+        if lines.is_empty() {
+            println!("  {}: {}", label, "no histogram data".dimmed());
+            continue;
+        }
+        println!("  {}:", label);
+        for line in lines {
+            println!("  {}", line);
+        }
+    }
+
+    println!("\n📈 Requests/sec over time:");
+    for (heading, result) in headings.iter().zip(results) {
+        if result.rps_timeline.is_empty() {
+            println!("  {}{}", heading, "no timeline data".dimmed());
+            continue;
+        }
+        let values: Vec<Option<f64>> = result.rps_timeline.iter().map(|v| Some(*v)).collect();
+        let min = result
+            .rps_timeline
+            .iter()
+            .copied()
+            .fold(f64::INFINITY, f64::min);
+        let max = result.rps_timeline.iter().copied().fold(0.0, f64::max);
         println!(
-            "  {} rari is {:.1}% slower response time",
-            "📉".red(),
-            latency_diff
+            "  {}{} (min {:.0}, max {:.0})",
+            heading,
+            term_charts::sparkline(&values),
+            min,
+            max
         );
     }
 
+    if results
+        .iter()
+        .any(|result| result.requests_per_cpu_second.is_some())
+    {
+        println!("\n💰 Efficiency:");
+        for (heading, result) in headings.iter().zip(results) {
+            match result.requests_per_cpu_second {
+                Some(efficiency) => {
+                    println!("  {}{:.0} requests per CPU-second", heading, efficiency)
+                }
+                None => println!("  {}{}", heading, "no CPU samples".dimmed()),
+            }
+        }
+        if let Some(subject_efficiency) = results[0].requests_per_cpu_second {
+            for (other, result) in targets.iter().zip(results).skip(1) {
+                let Some(efficiency) = result.requests_per_cpu_second else {
+                    continue;
+                };
+                let diff = tolerance::difference_pct(subject_efficiency, efficiency);
+                match tolerance::classify(subject_efficiency, efficiency, false, tie_pct) {
+                    tolerance::Outcome::SubjectBetter => println!(
+                        "  {} {} serves {:.1}% more requests per CPU-second than {}",
+                        "📈".green(),
+                        subject.label(),
+                        diff,
+                        other.label()
+                    ),
+                    tolerance::Outcome::OtherBetter => println!(
+                        "  {} {} serves {:.1}% fewer requests per CPU-second than {}",
+                        "📉".red(),
+                        subject.label(),
+                        diff.abs(),
+                        other.label()
+                    ),
+                    tolerance::Outcome::Tie => {
+                        println!("{}", tolerance::tie_line(diff, tie_pct, other.label()))
+                    }
+                }
+            }
+        }
+    }
+
+    if results.iter().any(|result| result.energy.is_some()) {
+        println!("\n⚡ Energy:");
+        for (heading, result) in headings.iter().zip(results) {
+            match &result.energy {
+                Some(energy) => println!(
+                    "  {}{:.2} J ({:.1} W avg), {:.2} mJ per request",
+                    heading,
+                    energy.joules,
+                    energy.avg_watts,
+                    energy.joules_per_request * 1000.0
+                ),
+                None => println!("  {}{}", heading, "not measured".dimmed()),
+            }
+        }
+        if let Some(subject_energy) = &results[0].energy {
+            for (other, result) in targets.iter().zip(results).skip(1) {
+                let Some(energy) = &result.energy else {
+                    continue;
+                };
+                let (subject_jpr, other_jpr) =
+                    (subject_energy.joules_per_request, energy.joules_per_request);
+                let diff = tolerance::difference_pct(subject_jpr, other_jpr);
+                match tolerance::classify(subject_jpr, other_jpr, true, tie_pct) {
+                    tolerance::Outcome::SubjectBetter => println!(
+                        "  {} {} uses {:.1}% less energy per request than {}",
+                        "📈".green(),
+                        subject.label(),
+                        diff.abs(),
+                        other.label()
+                    ),
+                    tolerance::Outcome::OtherBetter => println!(
+                        "  {} {} uses {:.1}% more energy per request than {}",
+                        "📉".red(),
+                        subject.label(),
+                        diff,
+                        other.label()
+                    ),
+                    tolerance::Outcome::Tie => {
+                        println!("{}", tolerance::tie_line(diff, tie_pct, other.label()))
+                    }
+                }
+            }
+        }
+    }
+
+    if results.iter().any(|result| result.wire.is_some()) {
+        println!("\n📡 Bytes on the wire:");
+        for (heading, result) in headings.iter().zip(results) {
+            match (result.wire, result.wire_bytes_per_request) {
+                (Some(wire), per_request) => println!(
+                    "  {}{} sent, {} received{}",
+                    heading,
+                    wire.sent_bytes,
+                    wire.received_bytes,
+                    per_request
+                        .map(|bytes| format!(", {} per request", units::bytes(bytes)))
+                        .unwrap_or_default()
+                ),
+                (None, _) => println!("  {}{}", heading, "not measured".dimmed()),
+            }
+        }
+    }
+
+    if results.iter().any(|result| result.capacity.is_some()) {
+        println!("\n🧗 Capacity:");
+        for (heading, result) in headings.iter().zip(results) {
+            let Some(capacity) = &result.capacity else {
+                println!("  {}{}", heading, "not searched".dimmed());
+                continue;
+            };
+            println!(
+                "  {}{} sustained{}",
+                heading,
+                units::Unit::RequestsPerSec.format(capacity.max_sustainable_rps as f64),
+                capacity
+                    .breaking_rps
+                    .map(|rps| format!(", breaks at {} req/s", rps))
+                    .unwrap_or_default()
+            );
+        }
+        if let Some(subject_capacity) = &results[0].capacity {
+            let subject_rps = subject_capacity.max_sustainable_rps as f64;
+            for (other, result) in targets.iter().zip(results).skip(1) {
+                let Some(capacity) = &result.capacity else {
+                    continue;
+                };
+                let other_rps = capacity.max_sustainable_rps as f64;
+                let diff = tolerance::difference_pct(subject_rps, other_rps);
+                match tolerance::classify(subject_rps, other_rps, false, tie_pct) {
+                    tolerance::Outcome::SubjectBetter => println!(
+                        "  {} {} sustains {:.1}% more requests/sec than {}",
+                        "📈".green(),
+                        subject.label(),
+                        diff,
+                        other.label()
+                    ),
+                    tolerance::Outcome::OtherBetter => println!(
+                        "  {} {} sustains {:.1}% fewer requests/sec than {}",
+                        "📉".red(),
+                        subject.label(),
+                        diff.abs(),
+                        other.label()
+                    ),
+                    tolerance::Outcome::Tie => {
+                        println!("{}", tolerance::tie_line(diff, tie_pct, other.label()))
+                    }
+                }
+            }
+        }
+    }
+
     println!("\n🚨 Errors:");
-    println!(
-        "  🦀 rari:     {} errors, {} timeouts",
-        rari.errors, rari.timeouts
-    );
-    println!(
-        "  🟢 Next.js:  {} errors, {} timeouts",
-        nextjs.errors, nextjs.timeouts
-    );
+    for (heading, result) in headings.iter().zip(results) {
+        println!(
+            "  {}{} errors, {} timeouts",
+            heading, result.errors, result.timeouts
+        );
+    }
 }
 
-async fn save_results(results: &BenchmarkResults, results_dir: &PathBuf) -> Result<()> {
-    fs::create_dir_all(results_dir).await?;
+async fn save_results(
+    results: &BenchmarkResults,
+    targets: &[targets::Target],
+    results_dir: &Path,
+    file_name: &str,
+) -> Result<PathBuf> {
+    let filename = results::save(results, results_dir, file_name).await?;
+    save_charts(results, targets, &filename)?;
 
-    let now = SystemTime::now();
-    let date = time_utils::format_date(now);
-    let filename = results_dir.join(format!("loadtest-{}.json", date));
+    Ok(filename)
+}
+
+fn save_charts(
+    results: &BenchmarkResults,
+    targets: &[targets::Target],
+    json_path: &Path,
+) -> Result<()> {
+    let latency_points = |latency: &LatencyStats| {
+        vec![
+            (50.0, latency.p50.0),
+            (90.0, latency.p90.0),
+            (95.0, latency.p95.0),
+            (99.0, latency.p99.0),
+        ]
+    };
+    let measured = || {
+        targets
+            .iter()
+            .filter_map(|target| Some((target, results.results.get(&target.name)?)))
+    };
 
-    let json = format!("{}\n", serde_json::to_string_pretty(results)?);
-    fs::write(&filename, json).await?;
+    let latency_path = charts::chart_path(json_path, "latency");
+    let series: Vec<charts::LatencySeries> = measured()
+        .map(|(target, result)| charts::LatencySeries {
+            name: target.label(),
+            points: latency_points(&result.latency),
+        })
+        .collect();
+    charts::latency_distribution_chart(&latency_path, "Load Test Latency Distribution", &series)?;
+
+    let throughput_path = charts::chart_path(json_path, "throughput");
+    let bars: Vec<(&str, f64)> = measured()
+        .map(|(target, result)| (target.label(), result.requests.average))
+        .collect();
+    charts::bar_chart(
+        &throughput_path,
+        "Load Test Throughput",
+        "Requests / sec",
+        &bars,
+    )?;
 
     println!(
-        "\n{} Results saved to {}",
-        "💾".dimmed(),
-        filename.display()
+        "{} Charts saved to {} and {}",
+        "📊".dimmed(),
+        latency_path.display(),
+        throughput_path.display()
     );
 
     Ok(())
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let args = Args::parse();
+/// Starts `perf` on `target` when it was picked with `--flamegraph`.
+fn start_flamegraph(
+    args: &Args,
+    target: &targets::Target,
+    server_pids: &BTreeMap<String, u32>,
+) -> Result<Option<flamegraph::Recording>> {
+    if !args.flamegraph.contains(&target.name) {
+        return Ok(None);
+    }
+    let Some(pid) = server_pids.get(&target.name) else {
+        return Ok(None);
+    };
+    let title = format!("{} under load", target.label());
+    flamegraph::record(&target.name, &title, *pid).map(Some)
+}
+
+/// A failed recording is reported but does not fail the load test.
+async fn finish_flamegraph(recording: Option<flamegraph::Recording>) -> Option<PathBuf> {
+    match recording?.finish().await {
+        Ok(svg) => Some(svg),
+        Err(e) => {
+            tracing::warn!("{:#}", e);
+            None
+        }
+    }
+}
+
+/// Starts V8's profiler in `target` when it was picked with `--cpu-prof`.
+fn start_cpu_profile(
+    target: &targets::Target,
+    cpu_prof_dirs: &BTreeMap<String, PathBuf>,
+) -> Result<Option<cpu_prof::Profiling>> {
+    cpu_prof_dirs
+        .get(&target.name)
+        .map(|dir| cpu_prof::start(&target.name, dir))
+        .transpose()
+}
 
-    println!("{}", "🔥 rari vs Next.js Load Test".cyan().bold());
+async fn finish_cpu_profile(profiling: Option<cpu_prof::Profiling>) -> Option<PathBuf> {
+    match profiling?.finish().await {
+        Ok(profile) => Some(profile),
+        Err(e) => {
+            tracing::warn!("{:#}", e);
+            None
+        }
+    }
+}
+
+/// Runs the load test as its binary does, from `Args::parse_from` or
+/// [`config::parse`], and returns the saved results. It exits neither on a
+/// failed gate nor on Ctrl-C, and a failure while measuring still saves the
+/// finished targets with the error; see [`results::Run::exit_code`].
+pub async fn run_load_test(args: &Args) -> Result<results::Run<BenchmarkResults>> {
+    output::init(&args.output)?;
+    run(args)
+        .instrument(tracing::info_span!("benchmark", kind = "load-test"))
+        .await
+}
+
+/// Checks the flags that only make sense together, and the targets they name.
+fn validate(args: &Args, targets: &[targets::Target]) -> Result<()> {
+    if args.capacity && args.execution == Execution::Simultaneous {
+        return Err(errors::Error::new(
+            errors::ErrorKind::InvalidConfig,
+            "--capacity needs sequential execution, so each search has the machine to itself",
+        )
+        .into());
+    }
+    if args.energy && args.execution == Execution::Simultaneous {
+        return Err(errors::Error::new(
+            errors::ErrorKind::InvalidConfig,
+            "--energy needs sequential execution: RAPL measures the whole CPU package, not one server",
+        )
+        .into());
+    }
+    for (flag, names) in [
+        ("--flamegraph", &args.flamegraph),
+        ("--cpu-prof", &args.cpu_prof),
+    ] {
+        if let Some(name) = names
+            .iter()
+            .find(|name| !targets.iter().any(|target| &target.name == *name))
+        {
+            return Err(errors::Error::new(
+                errors::ErrorKind::InvalidConfig,
+                format!("{} {}: no such target", flag, name),
+            )
+            .into());
+        }
+    }
+    if !args.cpu_prof.is_empty() {
+        cpu_prof::check()?;
+    }
+    if args.node_stats {
+        node_stats::check()?;
+    }
+    Ok(())
+}
+
+/// Checks what a load test with `args` needs, oha and the servers among
+/// them, and prints what it would do, without starting anything or sending
+/// load. Fails with the kind of the first failed check.
+pub async fn dry_run(args: &Args) -> Result<()> {
+    output::init(&args.output)?;
+    let mut checks = dry_run::Checks::new("load test");
+    checks.record(
+        "Regression gate",
+        gate::load_rules(&args.fail_if, args.thresholds.as_deref())
+            .await
+            .map(|rules| format!("{} rule(s)", rules.len())),
+    );
+    checks.record(
+        "Name template",
+        archive::validate_template(&args.archive.name_template)
+            .map(|()| args.archive.name_template.clone()),
+    );
+    let targets = match targets::load(&args.targets) {
+        Ok(targets) => targets,
+        Err(e) => {
+            checks.record("Targets", Err(e));
+            return checks.finish();
+        }
+    };
+    checks.record(
+        "Targets",
+        validate(args, &targets).map(|()| {
+            targets
+                .iter()
+                .map(targets::Target::display)
+                .collect::<Vec<_>>()
+                .join(", ")
+        }),
+    );
+    if args.client_cpus.is_some() {
+        checks.record(
+            "Client CPUs",
+            affinity::client_cpus(args.client_cpus.as_deref(), &targets)
+                .map(|cpus| format!("{:?}", cpus)),
+        );
+    }
+    checks.record("oha", dry_run::program("oha"));
+    if args.energy {
+        checks.record(
+            "RAPL",
+            energy::Rapl::detect().map(|_| "energy counters found".to_string()),
+        );
+    }
+    if !args.flamegraph.is_empty() {
+        checks.record(
+            "perf",
+            flamegraph::check()
+                .await
+                .map(|()| "can record flamegraphs".to_string()),
+        );
+    }
+
+    #[cfg(feature = "docker")]
+    let compose = args.compose.compose.as_deref();
+    #[cfg(not(feature = "docker"))]
+    let compose = None;
+    let servers = dry_run::servers(
+        &mut checks,
+        &targets,
+        &args.servers,
+        compose,
+        &args.ssh,
+        &args.probe,
+    )
+    .await;
+    checks.record("Results", dry_run::output_dir(&args.results_dir).await);
+
+    let load = if args.capacity {
+        format!(
+            "capacity search from {} req/s in {}s steps, then {}s at the capacity found",
+            args.capacity_start, args.step_duration, args.duration
+        )
+    } else {
+        match args.rps {
+            Some(rps) => format!("{}s at {} req/s", args.duration, rps),
+            None => format!("{}s at full speed", args.duration),
+        }
+    };
+    let (windows, execution) = match args.execution {
+        Execution::Sequential => (targets.len() as u64, "one target at a time"),
+        Execution::Simultaneous => (1, "all targets at once"),
+    };
+    checks.plan(&[
+        ("Targets", targets::title(&targets)),
+        ("Servers", servers),
+        (
+            "Load",
+            format!(
+                "{} with {} connection(s), {}",
+                load, args.connections, execution
+            ),
+        ),
+        (
+            "Takes",
+            if args.capacity {
+                "depends on the capacity search".to_string()
+            } else {
+                format!("at least {}s", windows * args.duration)
+            },
+        ),
+        ("Results", args.results_dir.display().to_string()),
+    ]);
+    checks.finish()
+}
+
+async fn run(args: &Args) -> Result<results::Run<BenchmarkResults>> {
+    let rules = gate::load_rules(&args.fail_if, args.thresholds.as_deref()).await?;
+    archive::validate_template(&args.archive.name_template)?;
+    let mut targets = targets::load(&args.targets)?;
+    validate(args, &targets)?;
+    let rapl = if args.energy {
+        Some(energy::Rapl::detect()?)
+    } else {
+        None
+    };
+    if !args.flamegraph.is_empty() {
+        flamegraph::check().await?;
+    }
+    let mut metadata = run_metadata::collect(Path::new("."), args.label.clone()).await;
+    metadata.profile = args.preset.profile.map(|preset| preset.name().to_string());
+    metadata.mode = Some(args.servers.mode.name().to_string());
+    metadata.fs_cache = args
+        .servers
+        .fs_cache
+        .map(|policy| policy.name().to_string());
+    metadata.target_env = targets::env(&targets);
+    metadata.node_version = versions::node().await;
+    metadata.target_versions = versions::packages(&args.servers.apps_dir, &targets).await;
+    metadata.framework_versions = versions::frameworks(&targets, &metadata.target_versions);
+    metadata.target_scheduling = targets::scheduling(&targets);
+    metadata.client_cpus = args.client_cpus.clone();
+    metadata.target_urls = targets::remote_urls(&targets);
+    metadata.seed = args.seed.describe();
+    metadata.ssh_host = args.ssh.ssh.clone();
+    metadata.network = args.network.profile();
+    let seed = args.random.resolve();
+    metadata.random_seed = Some(seed);
+    let title = format!("🔥 {} Load Test", targets::title(&targets));
+
+    println!("{}", title.cyan().bold());
+    println!("{}", metadata.describe().dimmed());
+    metadata.host_load = preflight::check(&args.preflight).await?;
+    stream::emit(
+        "run_started",
+        json!({ "benchmark": "load-test", "metadata": &metadata, "config": { "duration": args.duration, "connections": args.connections, "execution": args.execution } }),
+    );
     println!(
         "{}",
         "This test measures concurrent request handling performance\n".dimmed()
     );
 
-    if let Err(e) = check_oha_installed().await {
-        eprintln!("{} {}", "❌".red(), e);
-        std::process::exit(1);
-    }
+    let client_cpus = affinity::client_cpus(args.client_cpus.as_deref(), &targets)?;
 
-    if let Err(e) = check_server("rari", args.rari_port).await {
-        eprintln!("{} {}", "❌".red(), e);
-        eprintln!("Please start the rari server with: cd apps/rari && pnpm dev");
-        std::process::exit(1);
-    }
+    oha::check_installed().await?;
 
-    if let Err(e) = check_server("Next.js", args.nextjs_port).await {
-        eprintln!("{} {}", "❌".red(), e);
-        eprintln!("Please start the Next.js server with: cd apps/nextjs && pnpm dev");
-        std::process::exit(1);
-    }
+    #[cfg(feature = "docker")]
+    let compose_env = compose::up(&args.compose, &args.probe, &mut targets).await?;
+    #[cfg(feature = "docker")]
+    let composed = compose_env.is_some();
+    #[cfg(not(feature = "docker"))]
+    let composed = false;
+    let mut ssh_env = None;
+    let mut node_stats_dirs = BTreeMap::new();
+    let mut cpu_prof_dirs = BTreeMap::new();
+    let mut managed_servers = if composed {
+        None
+    } else if args.ssh.ssh.is_some() {
+        ssh_env = Some(ssh::up(&args.ssh, args.servers.mode, &args.probe, &mut targets).await?);
+        None
+    } else if args.servers.manage_servers {
+        if args.node_stats {
+            node_stats_dirs = node_stats::inject(&mut targets)?;
+        }
+        if !args.cpu_prof.is_empty() {
+            cpu_prof_dirs = cpu_prof::inject(&mut targets, &args.cpu_prof)?;
+        }
+        Some(servers::start(&args.servers, &args.probe, &args.hooks, &mut targets).await?)
+    } else {
+        servers::require_running(&targets, &args.probe, args.servers.mode).await?;
+        None
+    };
+
+    let footprint = match &managed_servers {
+        Some(servers) => monitor::footprints(&targets, &servers.pids()).await,
+        None => BTreeMap::new(),
+    };
+    args.seed.run(&targets).await?;
+    let shaper = network::shape(&args.network, args.count_bytes, seed, &mut targets).await?;
 
     println!(
         "\n{}",
-        "⚠️  This test will generate significant load on both servers".yellow()
+        "⚠️  This test will generate significant load on every server".yellow()
     );
     println!("{}", "Starting load test in 3 seconds...".dimmed());
     tokio::time::sleep(Duration::from_secs(3)).await;
+    let steal = quality::StealMeter::start();
+
+    #[cfg(feature = "docker")]
+    let stats_sampler = compose_env.as_ref().map(compose::ComposeEnv::sample_stats);
+    #[cfg(not(feature = "docker"))]
+    let stats_sampler: Option<containers::StatsSampler> = None;
+    let server_sampler = ssh_env.as_ref().map(ssh::SshEnv::sample_stats);
+    let log_watcher = managed_servers
+        .as_ref()
+        .map(|servers| server_logs::LogWatcher::start(servers.logs()));
+    let wire_bytes = |name: &str| shaper.as_ref().and_then(|shaper| shaper.wire_bytes(name));
+    let server_pids = managed_servers
+        .as_ref()
+        .map(servers::ManagedServers::pids)
+        .unwrap_or_default();
+
+    // Simultaneous runs load every target at once, so there is nothing to shuffle.
+    let mut shuffler = match args.execution {
+        Execution::Sequential => args.order.shuffler(seed),
+        Execution::Simultaneous => None,
+    };
+    let shuffle_seed = shuffler.is_some().then_some(seed);
+    let mut load_results: Vec<Option<LoadTestResult>> = vec![None; targets.len()];
+    let measured = interrupt::or_interrupted(async {
+        match args.execution {
+            Execution::Sequential => {
+                let mut order: Vec<usize> = (0..targets.len()).collect();
+                if let Some(shuffler) = &mut shuffler {
+                    shuffler.shuffle(&mut order);
+                }
+                for (position, &index) in order.iter().enumerate() {
+                    if interrupt::cancelled() {
+                        break;
+                    }
+                    let target = &targets[index];
+                    if position > 0 {
+                        println!("\n{}", "Pausing between tests...".dimmed());
+                        tokio::time::sleep(Duration::from_secs(2)).await;
+                    }
+                    if interrupt::cancelled() {
+                        break;
+                    }
+
+                    let capacity = if args.capacity {
+                        Some(find_capacity(target, args, &client_cpus).await?)
+                    } else {
+                        None
+                    };
+                    if interrupt::cancelled() {
+                        break;
+                    }
+                    let (mut result, noise) = args
+                        .noise
+                        .measure(
+                            target.label(),
+                            || async {
+                                args.hooks.before_load(target).await?;
+                                let monitor = server_pids.get(&target.name).and_then(|pid| {
+                                    monitor::start(*pid, node_stats_dirs.get(&target.name).cloned())
+                                });
+                                let wire_before = wire_bytes(&target.name);
+                                let recording = start_flamegraph(args, target, &server_pids)?;
+                                let profiling = start_cpu_profile(target, &cpu_prof_dirs)?;
+                                let mut result = run_target(
+                                    target,
+                                    args.duration,
+                                    args.connections,
+                                    capacity.as_ref().map_or(args.rps, |capacity| {
+                                        Some(capacity.max_sustainable_rps.max(1))
+                                    }),
+                                    None,
+                                    &client_cpus,
+                                    rapl.as_ref(),
+                                )
+                                .await?;
+                                result.flamegraph = finish_flamegraph(recording).await;
+                                result.cpu_profile = finish_cpu_profile(profiling).await;
+                                result.capacity = capacity.clone();
+                                if let Some((after, before)) =
+                                    wire_bytes(&target.name).zip(wire_before)
+                                {
+                                    result.attach_wire(after.since(before));
+                                }
+                                if let Some(monitor) = monitor {
+                                    result.attach_resources(
+                                        monitor.finish().await,
+                                        args.leak_threshold,
+                                    );
+                                }
+                                Ok(result)
+                            },
+                            |result| noise::cv_pct(&result.rps_timeline),
+                        )
+                        .await?;
+                    result.noise = noise;
+                    stream::emit(
+                        "test_finished",
+                        json!({ "framework": &target.name, "result": &result }),
+                    );
+                    load_results[index] = Some(result);
+                }
+            }
+            Execution::Simultaneous => {
+                // Re-running a single target would measure it without the others'
+                // load, so a noisy round is repeated as a whole.
+                let (mut results, noise) = args
+                    .noise
+                    .measure(
+                        "Simultaneous run",
+                        || async {
+                            for target in &targets {
+                                args.hooks.before_load(target).await?;
+                            }
+                            let monitors: Vec<_> = targets
+                                .iter()
+                                .map(|target| {
+                                    server_pids.get(&target.name).and_then(|pid| {
+                                        monitor::start(
+                                            *pid,
+                                            node_stats_dirs.get(&target.name).cloned(),
+                                        )
+                                    })
+                                })
+                                .collect();
+                            let wire_before: Vec<_> = targets
+                                .iter()
+                                .map(|target| wire_bytes(&target.name))
+                                .collect();
+                            let recordings = targets
+                                .iter()
+                                .map(|target| start_flamegraph(args, target, &server_pids))
+                                .collect::<Result<Vec<_>>>()?;
+                            let profilings = targets
+                                .iter()
+                                .map(|target| start_cpu_profile(target, &cpu_prof_dirs))
+                                .collect::<Result<Vec<_>>>()?;
+                            let mut results = run_simultaneous(
+                                &targets,
+                                args.duration,
+                                args.connections,
+                                args.rps,
+                                &client_cpus,
+                            )
+                            .await?;
+                            for (((((target, result), monitor), before), recording), profiling) in
+                                targets
+                                    .iter()
+                                    .zip(results.iter_mut())
+                                    .zip(monitors)
+                                    .zip(wire_before)
+                                    .zip(recordings)
+                                    .zip(profilings)
+                            {
+                                result.flamegraph = finish_flamegraph(recording).await;
+                                result.cpu_profile = finish_cpu_profile(profiling).await;
+                                if let Some(monitor) = monitor {
+                                    result.attach_resources(
+                                        monitor.finish().await,
+                                        args.leak_threshold,
+                                    );
+                                }
+                                if let Some((after, before)) = wire_bytes(&target.name).zip(before)
+                                {
+                                    result.attach_wire(after.since(before));
+                                }
+                            }
+                            Ok(results)
+                        },
+                        |results| {
+                            results
+                                .iter()
+                                .filter_map(|result| noise::cv_pct(&result.rps_timeline))
+                                .max_by(f64::total_cmp)
+                        },
+                    )
+                    .await?;
+                for (target, result) in targets.iter().zip(&mut results) {
+                    let cv_pct = noise::cv_pct(&result.rps_timeline);
+                    result.noise = noise::Noise {
+                        cv_pct,
+                        retries: noise.retries,
+                        noisy: noise.noisy
+                            && args
+                                .noise
+                                .max_cv
+                                .zip(cv_pct)
+                                .is_some_and(|(max, cv)| cv > max),
+                    };
+                    stream::emit(
+                        "test_finished",
+                        json!({ "framework": &target.name, "result": &result }),
+                    );
+                }
+                load_results = results.into_iter().map(Some).collect();
+            }
+        }
+        Ok::<_, anyhow::Error>(())
+    })
+    .await;
+    let steal_pct = steal.finish();
+    let (partial, failures) = match measured {
+        Some(Ok(())) if !interrupt::cancelled() => (false, Vec::new()),
+        Some(Err(e)) if !interrupt::cancelled() => {
+            tracing::error!("{:#}", e);
+            (true, vec![errors::RunError::from_error(&e)])
+        }
+        _ => {
+            interrupt::announce("Saving what was measured");
+            (true, Vec::new())
+        }
+    };
 
-    let rari_result =
-        run_load_test("rari", args.rari_port, args.duration, args.connections).await?;
+    let containers = match stats_sampler {
+        Some(sampler) => sampler.finish().await,
+        None => BTreeMap::new(),
+    };
+    let server_stats = match server_sampler {
+        Some(sampler) => sampler.finish().await,
+        None => BTreeMap::new(),
+    };
+    let logs = match log_watcher {
+        Some(watcher) => watcher.finish().await,
+        None => BTreeMap::new(),
+    };
+    let memory_pressure = managed_servers
+        .as_mut()
+        .map(servers::ManagedServers::memory_pressure)
+        .unwrap_or_default();
+    let cpu_throttling = managed_servers
+        .as_ref()
+        .map(servers::ManagedServers::cpu_throttling)
+        .unwrap_or_default();
+    drop(shaper);
+    drop(managed_servers);
+    #[cfg(feature = "docker")]
+    drop(compose_env);
+    drop(ssh_env);
 
-    println!("\n{}", "Pausing between tests...".dimmed());
-    tokio::time::sleep(Duration::from_secs(2)).await;
+    if !partial {
+        let load_results: Vec<LoadTestResult> = load_results.iter().flatten().cloned().collect();
+        display_comparison(&targets, &load_results, args.tie_threshold);
+    }
 
-    let nextjs_result =
-        run_load_test("Next.js", args.nextjs_port, args.duration, args.connections).await?;
+    let timestamp = time_utils::format_timestamp(SystemTime::now());
+    let file_name = archive::file_name(
+        &args.archive.name_template,
+        metrics::ResultKind::LoadTest,
+        &timestamp,
+        &metadata,
+    )?;
+    let recorded = |profile: fn(&LoadTestResult) -> Option<PathBuf>| -> BTreeMap<String, PathBuf> {
+        targets
+            .iter()
+            .zip(&load_results)
+            .filter_map(|(target, result)| Some((target.name.clone(), profile(result.as_ref()?)?)))
+            .collect()
+    };
+    let json_path = args.results_dir.join(&file_name);
+    let mut profile_artifacts = profiles::collect(&args.attach_profile).await;
+    profile_artifacts
+        .extend(flamegraph::save(&recorded(|result| result.flamegraph.clone()), &json_path).await);
+    profile_artifacts
+        .extend(cpu_prof::save(&recorded(|result| result.cpu_profile.clone()), &json_path).await);
+    profiles::display(&profile_artifacts);
+    containers::display(&containers, &targets);
+    containers::display_titled("🔐 Remote Server Stats", &server_stats, &targets);
+    let resources: BTreeMap<String, monitor::ResourceUsage> = targets
+        .iter()
+        .zip(&load_results)
+        .filter_map(|(target, result)| {
+            let usage = result.as_ref()?.resources.clone()?;
+            Some((target.name.clone(), usage))
+        })
+        .collect();
+    monitor::display(&resources, &footprint, &targets);
+    limits::display_memory(&memory_pressure, &targets);
+    limits::display_cpu(&cpu_throttling, &targets);
+    server_logs::display(&logs, &targets);
 
-    display_comparison(&rari_result, &nextjs_result);
+    let measurements: Vec<quality::Measurement> = load_results
+        .iter()
+        .flatten()
+        .map(|result| quality::Measurement {
+            cv_pct: result.noise.cv_pct,
+            samples: result.requests.total as usize,
+            requests: result.requests.total as usize,
+            errors: result.errors + result.timeouts,
+        })
+        .collect();
+    let quality = quality::Quality::grade(
+        &measurements,
+        metadata.host_load.as_ref(),
+        steal_pct,
+        partial,
+    );
+    quality.print();
 
     let results = BenchmarkResults {
-        timestamp: time_utils::format_timestamp(SystemTime::now()),
+        version: schema::VERSION,
+        timestamp,
+        metadata,
         config: TestConfig {
             duration: args.duration,
             connections: args.connections,
+            execution: args.execution,
+            shuffle_seed,
+            max_cv_pct: args.noise.max_cv,
+            rps: args.rps,
+            capacity: args.capacity,
         },
-        rari: rari_result,
-        nextjs: nextjs_result,
+        targets: targets::names(&targets),
+        results: targets::names(&targets)
+            .into_iter()
+            .zip(load_results)
+            .filter_map(|(name, result)| Some((name, result?)))
+            .collect(),
+        quality: Some(quality),
+        profiles: profile_artifacts,
+        containers,
+        server_stats,
+        server_logs: logs,
+        footprint,
+        memory_pressure,
+        cpu_throttling,
+        partial,
+        errors: failures.clone(),
     };
 
-    save_results(&results, &args.results_dir).await?;
+    let saved_path = save_results(&results, &targets, &args.results_dir, &file_name).await?;
+    if partial {
+        // Partial results stay out of the archive, the store and the gate.
+        return Ok(results::Run {
+            results,
+            path: saved_path,
+            partial,
+            gate_passed: true,
+            errors: failures,
+        });
+    }
+    if let Err(e) = archive::finalize(
+        &args.results_dir,
+        metrics::ResultKind::LoadTest,
+        &args.archive,
+    )
+    .await
+    {
+        tracing::warn!("Failed to update results archive: {:#}", e);
+    }
 
-    println!("\n{}", "🎉 Load test completed!".green().bold());
+    let document = serde_json::to_value(&results)?;
+    let store_path = (!args.no_store).then(|| {
+        args.store
+            .clone()
+            .unwrap_or_else(|| store::default_path(&args.results_dir))
+    });
+    store::record(
+        store_path.as_deref(),
+        metrics::ResultKind::LoadTest,
+        &saved_path,
+        &document,
+    );
+    influx::export(&args.influx, metrics::ResultKind::LoadTest, &document).await;
 
-    Ok(())
+    let result_metrics = metrics::flatten(&document);
+    let gate_passed = gate::check(&rules, &result_metrics)?;
+
+    let mut artifact = saved_path.display().to_string();
+    if let Some(destination) = &args.upload {
+        let date = time_utils::format_date(SystemTime::now());
+        match upload::upload(destination, &saved_path, &date).await {
+            Ok(uri) => artifact = uri,
+            Err(e) => tracing::warn!("{:#}", e),
+        }
+    }
+
+    let values = |metric: fn(&LoadTestResult) -> f64| -> Vec<f64> {
+        targets
+            .iter()
+            .map(|target| metric(&results.results[&target.name]))
+            .collect()
+    };
+    let summary = notify::Summary {
+        title,
+        timestamp: results.timestamp.clone(),
+        targets: targets.clone(),
+        deltas: vec![
+            notify::Delta {
+                label: "Throughput".to_string(),
+                unit: units::Unit::RequestsPerSec,
+                values: values(|result| result.requests.average),
+                lower_is_better: false,
+            },
+            notify::Delta {
+                label: "Mean latency".to_string(),
+                unit: units::Unit::Milliseconds,
+                values: values(|result| result.latency.mean.0),
+                lower_is_better: true,
+            },
+            notify::Delta {
+                label: "P95 latency".to_string(),
+                unit: units::Unit::Milliseconds,
+                values: values(|result| result.latency.p95.0),
+                lower_is_better: true,
+            },
+        ],
+        gate_passed: (!rules.is_empty()).then_some(gate_passed),
+        artifact: args.artifact_url.clone().unwrap_or(artifact),
+    };
+
+    notify::notify(args.notify_webhook.as_deref(), &summary).await;
+    if let Err(e) = publish::publish(&args.github, "benchmarks/load-test", &summary).await {
+        tracing::warn!("{:#}", e);
+    }
+
+    args.hooks.after_run(&saved_path).await?;
+    output::finish(&args.output, &summary.to_text(), &document)?;
+    stream::emit(
+        "run_finished",
+        json!({
+            "results": saved_path,
+            "artifact": summary.artifact,
+            "gatePassed": gate_passed,
+        }),
+    );
+
+    if gate_passed {
+        println!("\n{}", "🎉 Load test completed!".green().bold());
+    }
+
+    Ok(results::Run {
+        results,
+        path: saved_path,
+        partial,
+        gate_passed,
+        errors: failures,
+    })
 }
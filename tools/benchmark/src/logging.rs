@@ -0,0 +1,169 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs::File;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::fmt::format::{FmtSpan, Writer};
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+/// Libraries that log every connection at debug level; only their warnings are
+/// shown unless `--log-level` asks for more.
+const QUIET_DEPENDENCIES: &str = "hyper=warn,hyper_util=warn,h2=warn,reqwest=warn,rustls=warn,chromiumoxide=warn,tungstenite=warn";
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Messages as the terminal shows them
+    #[default]
+    Text,
+    /// One JSON object per event, with the benchmark phase spans it ran in
+    Json,
+}
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct LogArgs {
+    #[arg(
+        long,
+        value_name = "LEVEL",
+        help = "error, warn, info, debug, or trace, or a filter such as rari_benchmark::servers=debug [default: info; debug with -v, trace with -vv]"
+    )]
+    pub log_level: Option<String>,
+    #[arg(long, value_enum, value_name = "FORMAT", default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Append logs to FILE [default: stdout for text, stderr for json]"
+    )]
+    pub log_file: Option<PathBuf>,
+}
+
+impl LogArgs {
+    /// The flags that make another benchmark log the same way.
+    pub fn forward(&self) -> Vec<OsString> {
+        let mut args: Vec<OsString> = Vec::new();
+        if let Some(level) = &self.log_level {
+            args.extend(["--log-level".into(), level.into()]);
+        }
+        if self.log_format == LogFormat::Json {
+            args.extend(["--log-format".into(), "json".into()]);
+        }
+        if let Some(file) = &self.log_file {
+            args.extend(["--log-file".into(), file.into()]);
+        }
+        args
+    }
+}
+
+/// Installs the logger for this process. `verbosity` is the `-v` count, which
+/// picks the level when `--log-level` is not given. Only the first call in a
+/// process takes effect.
+pub fn init(args: &LogArgs, verbosity: u8) -> Result<()> {
+    let filter = match &args.log_level {
+        Some(level) => {
+            EnvFilter::try_new(level).with_context(|| format!("Invalid --log-level {:?}", level))?
+        }
+        None => {
+            let level = ["info", "debug", "trace"][verbosity.min(2) as usize];
+            EnvFilter::new(format!("{},{}", level, QUIET_DEPENDENCIES))
+        }
+    };
+
+    let writer = match &args.log_file {
+        Some(path) => {
+            let file = File::options()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("Failed to open {}", path.display()))?;
+            BoxMakeWriter::new(Mutex::new(file))
+        }
+        None if args.log_format == LogFormat::Json => BoxMakeWriter::new(std::io::stderr),
+        // Where println! goes, so --quiet, --json, and --stream move both.
+        None => BoxMakeWriter::new(std::io::stdout),
+    };
+    let layer = match args.log_format {
+        LogFormat::Text => tracing_subscriber::fmt::layer()
+            .event_format(Console)
+            .with_ansi(args.log_file.is_none())
+            .with_writer(writer)
+            .boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .with_span_events(FmtSpan::CLOSE)
+            .with_writer(writer)
+            .boxed(),
+    };
+
+    let _ = tracing_subscriber::registry()
+        .with(filter)
+        .with(layer)
+        .try_init();
+    Ok(())
+}
+
+/// Renders events like the rest of the terminal output: warnings and errors
+/// with their icon, details dimmed and indented, and fields left to JSON.
+struct Console;
+
+impl<S, N> FormatEvent<S, N> for Console
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        _ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let mut message = Message(String::new());
+        event.record(&mut message);
+        let message = message.0;
+        let ansi = writer.has_ansi_escapes();
+        let paint = |text: colored::ColoredString| {
+            if ansi {
+                text.to_string()
+            } else {
+                text.clear().to_string()
+            }
+        };
+        match *event.metadata().level() {
+            Level::ERROR => writeln!(writer, "{} {}", paint("❌".red()), message),
+            Level::WARN => writeln!(writer, "{} {}", paint("⚠️".yellow()), message),
+            Level::INFO => writeln!(writer, "{}", message),
+            _ => writeln!(
+                writer,
+                "    {} {}",
+                paint("·".dimmed()),
+                paint(message.dimmed())
+            ),
+        }
+    }
+}
+
+struct Message(String);
+
+impl Visit for Message {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.0 = value.to_string();
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{:?}", value);
+        }
+    }
+}
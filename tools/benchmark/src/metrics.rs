@@ -0,0 +1,79 @@
+use crate::schema;
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+pub use rari_benchmark_results::metrics::{
+    NON_TARGET_KEYS, ResultKind, flatten, is_config_metric, lower_is_better, target_names,
+};
+
+#[derive(Debug, Clone)]
+pub struct ResultFile {
+    pub kind: ResultKind,
+    pub path: PathBuf,
+    pub date: String,
+    pub timestamp: String,
+    pub mode: Option<String>,
+    pub git_sha: Option<String>,
+    pub metrics: BTreeMap<String, f64>,
+}
+
+pub async fn read_result(path: &Path) -> Result<ResultFile> {
+    let json = schema::read_json(path).await?;
+
+    let kind = ResultKind::from_path(path)
+        .or_else(|| ResultKind::from_json(&json))
+        .with_context(|| format!("{} is not a benchmark result file", path.display()))?;
+
+    let date = json["timestamp"]
+        .as_str()
+        .and_then(|timestamp| timestamp.get(..10))
+        .map(str::to_string)
+        .or_else(|| {
+            let stem = path.file_stem()?.to_str()?;
+            stem.strip_prefix(&format!("{}-", kind.prefix()))
+                .map(str::to_string)
+        })
+        .unwrap_or_default();
+    let timestamp = json["timestamp"]
+        .as_str()
+        .map_or_else(|| date.clone(), str::to_string);
+
+    Ok(ResultFile {
+        kind,
+        path: path.to_path_buf(),
+        date,
+        timestamp,
+        mode: json["metadata"]["mode"].as_str().map(str::to_string),
+        git_sha: json["metadata"]["gitSha"].as_str().map(str::to_string),
+        metrics: flatten(&json),
+    })
+}
+
+fn is_untyped_result_candidate(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with(".json") && name != "latest.json")
+}
+
+pub async fn scan_results(results_dir: &Path) -> Result<Vec<ResultFile>> {
+    let mut entries = fs::read_dir(results_dir)
+        .await
+        .with_context(|| format!("Failed to read {}", results_dir.display()))?;
+
+    let mut results = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if ResultKind::from_path(&path).is_some() {
+            results.push(read_result(&path).await?);
+        } else if is_untyped_result_candidate(&path)
+            && let Ok(result) = read_result(&path).await
+        {
+            results.push(result);
+        }
+    }
+
+    results.sort_by(|a, b| (&a.timestamp, a.kind).cmp(&(&b.timestamp, b.kind)));
+    Ok(results)
+}
@@ -0,0 +1,635 @@
+use crate::containers::{self, ContainerStats};
+#[cfg(feature = "monitoring")]
+use crate::node_stats;
+use crate::node_stats::NodeStats;
+use crate::targets::Target;
+use crate::units;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+#[cfg(feature = "monitoring")]
+use std::collections::HashSet;
+use std::path::PathBuf;
+#[cfg(feature = "monitoring")]
+use std::time::{Duration, Instant};
+#[cfg(feature = "monitoring")]
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+#[cfg(feature = "monitoring")]
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+/// Memory growth is only fitted over windows of at least a minute.
+const MIN_GROWTH_SAMPLES: usize = 60;
+/// A fit this poor means memory went up and down rather than steadily up.
+const MIN_LEAK_R_SQUARED: f64 = 0.5;
+/// How long descriptors get to return to their baseline once the load stops.
+#[cfg(feature = "monitoring")]
+const FD_SETTLE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// CPU and memory of a server's process tree over a load window.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceUsage {
+    #[serde(flatten)]
+    pub stats: ContainerStats,
+    /// CPU time the tree used over the whole window, in seconds.
+    #[serde(default)]
+    pub cpu_seconds: f64,
+    /// CPU% of the whole tree once per second; 100% is one full core.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cpu_timeline: Vec<f64>,
+    /// Resident memory of the whole tree once per second, in bytes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub memory_timeline: Vec<f64>,
+    /// Open file descriptors of the tree; only sampled on Linux.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fds: Option<FdUsage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_growth: Option<MemoryGrowth>,
+    /// Event-loop delay and GC pauses, with `--node-stats`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node: Option<NodeStats>,
+    /// The most processes and threads the tree ran at once.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peak_tasks: Option<TaskCount>,
+}
+
+/// Resident memory of a server's process tree before any load.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Footprint {
+    /// Right after the server passed its readiness check.
+    pub idle_bytes: f64,
+    /// After one request to the target's base URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warm_bytes: Option<f64>,
+    /// Processes and threads right after the readiness check.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tasks: Option<TaskCount>,
+}
+
+/// Processes in a server's tree and the OS threads they run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskCount {
+    pub processes: usize,
+    pub threads: usize,
+}
+
+impl TaskCount {
+    #[cfg(feature = "monitoring")]
+    fn max(self, other: TaskCount) -> TaskCount {
+        TaskCount {
+            processes: self.processes.max(other.processes),
+            threads: self.threads.max(other.threads),
+        }
+    }
+
+    fn describe(self) -> String {
+        format!(
+            "{} process{}, {} thread{}",
+            self.processes,
+            if self.processes == 1 { "" } else { "es" },
+            self.threads,
+            if self.threads == 1 { "" } else { "s" }
+        )
+    }
+}
+
+/// A least-squares line through the memory timeline.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryGrowth {
+    pub bytes_per_min: f64,
+    pub r_squared: f64,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub likely_leak: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FdCount {
+    pub fds: usize,
+    pub sockets: usize,
+}
+
+/// Descriptors open before the load, at its peak, and once it has stopped.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FdUsage {
+    pub baseline: FdCount,
+    pub peak: FdCount,
+    #[serde(rename = "final")]
+    pub after: FdCount,
+    /// Descriptors still open above the baseline after the settle time.
+    pub leaked: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fd_timeline: Vec<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub socket_timeline: Vec<usize>,
+}
+
+impl FdUsage {
+    #[cfg(feature = "monitoring")]
+    fn add(&mut self, count: FdCount) {
+        self.peak.fds = self.peak.fds.max(count.fds);
+        self.peak.sockets = self.peak.sockets.max(count.sockets);
+        self.fd_timeline.push(count.fds);
+        self.socket_timeline.push(count.sockets);
+    }
+}
+
+pub struct ProcessMonitor {
+    stop: watch::Sender<bool>,
+    task: JoinHandle<ResourceUsage>,
+}
+
+#[cfg(feature = "monitoring")]
+/// `root` and every process descended from it. Threads are left out: on Linux
+/// they are listed as well, each reporting its whole process's memory.
+fn tree(system: &System, root: Pid) -> HashSet<Pid> {
+    let mut members = HashSet::from([root]);
+    loop {
+        let before = members.len();
+        for (pid, process) in system.processes() {
+            if process.thread_kind().is_none()
+                && process
+                    .parent()
+                    .is_some_and(|parent| members.contains(&parent))
+            {
+                members.insert(*pid);
+            }
+        }
+        if members.len() == before {
+            return members;
+        }
+    }
+}
+
+/// The pids of `root` and every process it started, without their threads.
+#[cfg(feature = "monitoring")]
+pub fn process_tree(root: u32) -> Vec<u32> {
+    let mut system = System::new();
+    refresh(&mut system);
+    let mut pids: Vec<u32> = tree(&system, Pid::from_u32(root))
+        .into_iter()
+        .map(Pid::as_u32)
+        .collect();
+    pids.sort_unstable();
+    pids
+}
+
+#[cfg(not(feature = "monitoring"))]
+pub fn process_tree(root: u32) -> Vec<u32> {
+    vec![root]
+}
+
+/// Open descriptors and sockets across `members`, or `None` when none of
+/// their descriptor tables can be read.
+#[cfg(all(feature = "monitoring", target_os = "linux"))]
+fn count_fds(members: &HashSet<Pid>) -> Option<FdCount> {
+    let mut count = None;
+    for pid in members {
+        let Ok(entries) = std::fs::read_dir(format!("/proc/{}/fd", pid)) else {
+            continue;
+        };
+        let count = count.get_or_insert_with(FdCount::default);
+        for entry in entries.flatten() {
+            count.fds += 1;
+            if std::fs::read_link(entry.path())
+                .is_ok_and(|target| target.to_string_lossy().starts_with("socket:"))
+            {
+                count.sockets += 1;
+            }
+        }
+    }
+    count
+}
+
+#[cfg(all(feature = "monitoring", not(target_os = "linux")))]
+fn count_fds(_members: &HashSet<Pid>) -> Option<FdCount> {
+    None
+}
+
+/// Processes and threads across `members`, or `None` where sysinfo cannot list
+/// a process's threads.
+#[cfg(feature = "monitoring")]
+fn count_tasks(system: &System, members: &HashSet<Pid>) -> Option<TaskCount> {
+    let mut count = TaskCount::default();
+    for process in members.iter().filter_map(|pid| system.process(*pid)) {
+        count.processes += 1;
+        // `tasks` lists every thread except the main one.
+        count.threads += 1 + process.tasks()?.len();
+    }
+    (count.processes > 0).then_some(count)
+}
+
+#[cfg(feature = "monitoring")]
+fn tree_memory(system: &mut System, root: Pid) -> Option<f64> {
+    refresh(system);
+    let memory: u64 = tree(system, root)
+        .iter()
+        .filter_map(|pid| system.process(*pid))
+        .map(|process| process.memory())
+        .sum();
+    (memory > 0).then_some(memory as f64)
+}
+
+/// Measures each managed server's memory once it is ready, then again after a
+/// single request to its base URL, and prints both.
+#[cfg(feature = "monitoring")]
+pub async fn footprints(
+    targets: &[Target],
+    pids: &BTreeMap<String, u32>,
+) -> BTreeMap<String, Footprint> {
+    if pids.is_empty() {
+        return BTreeMap::new();
+    }
+    let client = reqwest::Client::new();
+    let mut system = System::new();
+    let mut footprints = BTreeMap::new();
+    println!("\n{}", "🪶 Idle Footprint".bold());
+    for target in targets {
+        let Some(root) = pids.get(&target.name).map(|pid| Pid::from_u32(*pid)) else {
+            continue;
+        };
+        let Some(idle_bytes) = tree_memory(&mut system, root) else {
+            continue;
+        };
+        let tasks = count_tasks(&system, &tree(&system, root));
+        let warmed = match client
+            .get(target.base_url())
+            .timeout(Duration::from_secs(30))
+            .send()
+            .await
+        {
+            Ok(response) => response.bytes().await.is_ok(),
+            Err(_) => false,
+        };
+        let warm_bytes = if warmed {
+            tree_memory(&mut system, root)
+        } else {
+            None
+        };
+        println!(
+            "  {}: {} idle, {}{}",
+            target.label().bold(),
+            units::bytes(idle_bytes),
+            warm_bytes.map_or("warm-up request failed".to_string(), |bytes| {
+                format!("{} after one request", units::bytes(bytes))
+            }),
+            tasks.map_or(String::new(), |tasks| format!(" ({})", tasks.describe()))
+        );
+        footprints.insert(
+            target.name.clone(),
+            Footprint {
+                idle_bytes,
+                warm_bytes,
+                tasks,
+            },
+        );
+    }
+    footprints
+}
+
+#[cfg(not(feature = "monitoring"))]
+pub async fn footprints(
+    _targets: &[Target],
+    pids: &BTreeMap<String, u32>,
+) -> BTreeMap<String, Footprint> {
+    if !pids.is_empty() {
+        println!(
+            "\n{}",
+            "🪶 Server CPU and memory are not sampled: cargo build --features monitoring".dimmed()
+        );
+    }
+    BTreeMap::new()
+}
+
+#[cfg(feature = "monitoring")]
+fn refresh(system: &mut System) {
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing().with_cpu().with_memory(),
+    );
+}
+
+/// Samples the CPU, resident memory and open descriptors of `root`'s process
+/// tree (e.g. pnpm and the node server it starts) every second until `finish`,
+/// then waits for the descriptors to settle. When `node_stats` is given, the
+/// Node.js stats the server reported there during the window are added too.
+/// `None` in a build without the `monitoring` feature.
+#[cfg(feature = "monitoring")]
+pub fn start(root: u32, node_stats: Option<PathBuf>) -> Option<ProcessMonitor> {
+    let (stop, mut stopped) = watch::channel(false);
+    let task = tokio::spawn(async move {
+        let started_ms = node_stats::now_ms();
+        let root = Pid::from_u32(root);
+        let mut system = System::new();
+        // CPU usage is measured between two refreshes.
+        refresh(&mut system);
+        let mut last = Instant::now();
+        let mut usage = ResourceUsage {
+            fds: count_fds(&tree(&system, root)).map(|baseline| FdUsage {
+                baseline,
+                peak: baseline,
+                ..FdUsage::default()
+            }),
+            ..ResourceUsage::default()
+        };
+        loop {
+            let stopping = tokio::select! {
+                _ = stopped.changed() => true,
+                _ = tokio::time::sleep(SAMPLE_INTERVAL) => false,
+            };
+            refresh(&mut system);
+            let elapsed = last.elapsed().as_secs_f64();
+            last = Instant::now();
+            let members = tree(&system, root);
+            let (cpu_pct, memory_bytes) = members
+                .iter()
+                .filter_map(|pid| system.process(*pid))
+                .fold((0.0, 0.0), |(cpu, memory), process| {
+                    (
+                        cpu + process.cpu_usage() as f64,
+                        memory + process.memory() as f64,
+                    )
+                });
+            if memory_bytes > 0.0 {
+                usage.cpu_seconds += cpu_pct / 100.0 * elapsed;
+                // The last, partial interval only counts towards the CPU time.
+                if !stopping {
+                    usage.stats.add(cpu_pct, memory_bytes);
+                    usage.cpu_timeline.push(cpu_pct);
+                    usage.memory_timeline.push(memory_bytes);
+                    if let (Some(fds), Some(count)) = (&mut usage.fds, count_fds(&members)) {
+                        fds.add(count);
+                    }
+                    if let Some(tasks) = count_tasks(&system, &members) {
+                        usage.peak_tasks =
+                            Some(usage.peak_tasks.map_or(tasks, |peak| peak.max(tasks)));
+                    }
+                }
+            }
+            if stopping {
+                break;
+            }
+        }
+        let stopped_ms = node_stats::now_ms();
+
+        // Keep-alive connections take a moment to close once the load stops.
+        if let Some(fds) = &mut usage.fds {
+            let settled_by = Instant::now() + FD_SETTLE_TIMEOUT;
+            loop {
+                refresh(&mut system);
+                fds.after = count_fds(&tree(&system, root)).unwrap_or_default();
+                if fds.after.fds <= fds.baseline.fds || Instant::now() >= settled_by {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(500)).await;
+            }
+            fds.leaked = fds.after.fds.saturating_sub(fds.baseline.fds);
+        }
+        if let Some(dir) = node_stats {
+            // The server writes its stats once a second, so the line covering
+            // the end of the load lands up to a second later.
+            let until = stopped_ms + SAMPLE_INTERVAL.as_millis() as u64;
+            let wait_ms = (until + 100).saturating_sub(node_stats::now_ms());
+            tokio::time::sleep(Duration::from_millis(wait_ms)).await;
+            usage.node = node_stats::read(&dir, started_ms, until);
+        }
+        usage
+    });
+    Some(ProcessMonitor { stop, task })
+}
+
+#[cfg(not(feature = "monitoring"))]
+pub fn start(_root: u32, _node_stats: Option<PathBuf>) -> Option<ProcessMonitor> {
+    None
+}
+
+impl ResourceUsage {
+    /// Fits a line through the per-second memory samples of a long enough
+    /// window, flagging steady growth above `leak_threshold_mb` per minute. The
+    /// first quarter is skipped, since heaps and caches grow while warming up.
+    pub fn fit_memory_growth(&mut self, leak_threshold_mb: f64) {
+        if self.memory_timeline.len() < MIN_GROWTH_SAMPLES {
+            return;
+        }
+        let samples = &self.memory_timeline[self.memory_timeline.len() / 4..];
+        let n = samples.len() as f64;
+        let mean_x = (n - 1.0) / 2.0;
+        let mean_y = samples.iter().sum::<f64>() / n;
+        let (mut sxy, mut sxx, mut syy) = (0.0, 0.0, 0.0);
+        for (x, y) in samples.iter().enumerate() {
+            let (dx, dy) = (x as f64 - mean_x, y - mean_y);
+            sxy += dx * dy;
+            sxx += dx * dx;
+            syy += dy * dy;
+        }
+        let slope_per_sec = sxy / sxx;
+        let r_squared = if syy > 0.0 {
+            sxy * sxy / (sxx * syy)
+        } else {
+            0.0
+        };
+        let bytes_per_min = slope_per_sec * 60.0;
+        self.memory_growth = Some(MemoryGrowth {
+            bytes_per_min,
+            r_squared,
+            likely_leak: bytes_per_min > leak_threshold_mb * 1_000_000.0
+                && r_squared >= MIN_LEAK_R_SQUARED,
+        });
+    }
+
+    /// `count` divided by the CPU time used, or `None` without CPU samples.
+    pub fn per_cpu_second(&self, count: f64) -> Option<f64> {
+        (self.cpu_seconds > 0.0).then(|| count / self.cpu_seconds)
+    }
+}
+
+impl ProcessMonitor {
+    pub async fn finish(self) -> ResourceUsage {
+        let _ = self.stop.send(true);
+        self.task.await.unwrap_or_default()
+    }
+}
+
+/// Prints average/peak CPU and memory per target, how the subject's peak
+/// memory compares with the others', and descriptor and thread usage.
+pub fn display(
+    all_usage: &BTreeMap<String, ResourceUsage>,
+    footprints: &BTreeMap<String, Footprint>,
+    targets: &[Target],
+) {
+    let usage: BTreeMap<String, ContainerStats> = all_usage
+        .iter()
+        .map(|(name, usage)| (name.clone(), usage.stats.clone()))
+        .collect();
+    containers::display_titled("🖥️ Server Resources", &usage, targets);
+    display_ratios(&usage, targets);
+    display_growth(all_usage, targets);
+    display_fds(all_usage, targets);
+    display_tasks(all_usage, footprints, targets);
+    display_node(all_usage, targets);
+}
+
+fn display_ratios(usage: &BTreeMap<String, ContainerStats>, targets: &[Target]) {
+    let Some(subject) = targets.first() else {
+        return;
+    };
+    let Some(subject_usage) = usage.get(&subject.name) else {
+        return;
+    };
+    for other in &targets[1..] {
+        let Some(other_usage) = usage.get(&other.name) else {
+            continue;
+        };
+        if subject_usage.peak_memory_bytes == 0.0 || other_usage.peak_memory_bytes == 0.0 {
+            continue;
+        }
+        let ratio = subject_usage.peak_memory_bytes / other_usage.peak_memory_bytes;
+        let line = if (1.0 / 1.05..1.05).contains(&ratio) {
+            format!(
+                "{} peaks at about the same memory as {}",
+                subject.label(),
+                other.label()
+            )
+        } else if ratio > 1.0 {
+            format!(
+                "{} peaks at {:.1}x the memory of {}",
+                subject.label(),
+                ratio,
+                other.label()
+            )
+        } else {
+            format!(
+                "{} peaks at {:.1}x less memory than {}",
+                subject.label(),
+                1.0 / ratio,
+                other.label()
+            )
+        };
+        println!("  {} {}", "🧠".dimmed(), line);
+    }
+}
+
+fn display_fds(usage: &BTreeMap<String, ResourceUsage>, targets: &[Target]) {
+    if usage.values().all(|usage| usage.fds.is_none()) {
+        return;
+    }
+
+    println!("\n{}", "🔌 File Descriptors".bold());
+    for target in targets {
+        let Some(fds) = usage.get(&target.name).and_then(|usage| usage.fds.as_ref()) else {
+            continue;
+        };
+        println!(
+            "  {}: {} before, peak {}, {} after ({} / {} / {} sockets)",
+            target.label().bold(),
+            fds.baseline.fds,
+            fds.peak.fds,
+            fds.after.fds,
+            fds.baseline.sockets,
+            fds.peak.sockets,
+            fds.after.sockets
+        );
+        if fds.leaked > 0 {
+            println!(
+                "     {} {} descriptors did not return to the baseline after the run",
+                "⚠️".yellow(),
+                fds.leaked
+            );
+        }
+    }
+}
+
+/// Idle and peak parallelism, the context a throughput difference needs.
+fn display_tasks(
+    usage: &BTreeMap<String, ResourceUsage>,
+    footprints: &BTreeMap<String, Footprint>,
+    targets: &[Target],
+) {
+    if usage.values().all(|usage| usage.peak_tasks.is_none()) {
+        return;
+    }
+
+    println!("\n{}", "🧵 Processes and Threads".bold());
+    for target in targets {
+        let Some(peak) = usage.get(&target.name).and_then(|usage| usage.peak_tasks) else {
+            continue;
+        };
+        let idle = footprints
+            .get(&target.name)
+            .and_then(|footprint| footprint.tasks)
+            .map_or(String::new(), |idle| {
+                format!(" (idle: {})", idle.describe())
+            });
+        println!(
+            "  {}: {} under load{}",
+            target.label().bold(),
+            peak.describe(),
+            idle
+        );
+    }
+}
+
+fn display_node(usage: &BTreeMap<String, ResourceUsage>, targets: &[Target]) {
+    if usage.values().all(|usage| usage.node.is_none()) {
+        return;
+    }
+
+    println!("\n{}", "⏳ Node.js Runtime".bold());
+    for target in targets {
+        let Some(node) = usage
+            .get(&target.name)
+            .and_then(|usage| usage.node.as_ref())
+        else {
+            continue;
+        };
+        let delay = match &node.event_loop_delay {
+            Some(delay) => format!(
+                "event-loop delay mean {} / P99 {} / max {}",
+                units::duration_ms(delay.mean_ms),
+                units::duration_ms(delay.p99_ms),
+                units::duration_ms(delay.max_ms)
+            ),
+            None => "no event-loop delay samples".to_string(),
+        };
+        println!(
+            "  {}: {}, GC {} pauses totalling {} (longest {})",
+            target.label().bold(),
+            delay,
+            node.gc.count,
+            units::duration_ms(node.gc.total_pause_ms),
+            units::duration_ms(node.gc.max_pause_ms)
+        );
+    }
+}
+
+fn display_growth(usage: &BTreeMap<String, ResourceUsage>, targets: &[Target]) {
+    for target in targets {
+        let Some(growth) = usage
+            .get(&target.name)
+            .and_then(|usage| usage.memory_growth.as_ref())
+        else {
+            continue;
+        };
+        let line = format!(
+            "{} memory {} {}/min under load (R² {:.2})",
+            target.label(),
+            if growth.bytes_per_min >= 0.0 {
+                "grew"
+            } else {
+                "shrank"
+            },
+            units::bytes(growth.bytes_per_min.abs()),
+            growth.r_squared
+        );
+        if growth.likely_leak {
+            println!("  {} {}, a likely leak", "⚠️".yellow(), line.yellow());
+        } else {
+            println!("  {} {}", "📈".dimmed(), line);
+        }
+    }
+}
@@ -0,0 +1,298 @@
+use crate::assets;
+use crate::stats::Latency;
+use crate::targets::Target;
+use crate::tolerance;
+use crate::units;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use reqwest::header;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tabled::builder::Builder;
+
+/// The page both apps render the guestbook form on.
+pub const DEFAULT_PAGE: &str = "/guestbook";
+
+/// The first form of a page, as a browser without JavaScript would submit it.
+struct Form {
+    action: String,
+    multipart: bool,
+    /// Hidden inputs, sent unchanged (a server action's id, for one).
+    fields: Vec<(String, String)>,
+    /// The text input the benchmark fills in.
+    input: String,
+}
+
+fn parse_form(html: &str, page: &str) -> Option<Form> {
+    let mut tags = assets::start_tags(html).into_iter();
+    let (_, form) = tags.by_ref().find(|(name, _)| name == "form")?;
+    let method = form.get("method").map(String::as_str).unwrap_or("get");
+    if !method.eq_ignore_ascii_case("post") {
+        return None;
+    }
+
+    let mut fields = Vec::new();
+    let mut input = None;
+    for (name, attributes) in tags {
+        if name == "form" {
+            break;
+        }
+        if !matches!(name.as_str(), "input" | "textarea") {
+            continue;
+        }
+        let Some(field) = attributes.get("name") else {
+            continue;
+        };
+        let kind = attributes.get("type").map(String::as_str).unwrap_or("text");
+        if kind.eq_ignore_ascii_case("hidden") {
+            let value = attributes.get("value").cloned().unwrap_or_default();
+            fields.push((field.clone(), value));
+        } else if input.is_none() && matches!(kind, "text" | "search" | "textarea") {
+            input = Some(field.clone());
+        }
+    }
+
+    let action = match form
+        .get("action")
+        .map(|action| action.replace("&amp;", "&"))
+    {
+        Some(action) if action.starts_with('/') => action,
+        _ => page.to_string(),
+    };
+    Some(Form {
+        action,
+        multipart: form
+            .get("enctype")
+            .is_some_and(|enctype| enctype.eq_ignore_ascii_case("multipart/form-data")),
+        fields,
+        input: input?,
+    })
+}
+
+fn url_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'*' => {
+                (byte as char).to_string()
+            }
+            b' ' => "+".to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+impl Form {
+    /// The content type and body of a submission with `text` in the input.
+    fn encode(&self, text: &str) -> (String, Vec<u8>) {
+        let fields = self
+            .fields
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .chain([(self.input.as_str(), text)]);
+        if self.multipart {
+            let boundary = format!("rari-benchmark-{}", std::process::id());
+            let mut body = String::new();
+            for (name, value) in fields {
+                body.push_str(&format!(
+                    "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                    boundary, name, value
+                ));
+            }
+            body.push_str(&format!("--{}--\r\n", boundary));
+            (
+                format!("multipart/form-data; boundary={}", boundary),
+                body.into_bytes(),
+            )
+        } else {
+            let body = fields
+                .map(|(name, value)| format!("{}={}", url_encode(name), url_encode(value)))
+                .collect::<Vec<_>>()
+                .join("&");
+            (
+                "application/x-www-form-urlencoded".to_string(),
+                body.into_bytes(),
+            )
+        }
+    }
+}
+
+/// The full write path of one target: the POST and its redirect, then the
+/// page it redirects to, which has to show the new entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Mutation {
+    pub page: String,
+    pub requests: usize,
+    pub errors: usize,
+    /// The POST, to its redirect.
+    pub submit: Latency,
+    /// The page after the redirect, to its last byte.
+    pub render: Latency,
+    pub round_trip: Latency,
+}
+
+/// Submits the form on `page` `warmup + requests` times, each with a new
+/// entry, follows every redirect by hand, and times both halves.
+pub async fn measure(
+    target: &Target,
+    page: &str,
+    warmup: usize,
+    requests: usize,
+) -> Result<Mutation> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .redirect(reqwest::redirect::Policy::none())
+        .build()?;
+    let base_url = target.base_url();
+
+    let html = client
+        .get(format!("{}{}", base_url, page))
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    let form = parse_form(&html, page)
+        .with_context(|| format!("No POST form with a text input on {}", page))?;
+
+    let mut submits = Vec::new();
+    let mut renders = Vec::new();
+    let mut round_trips = Vec::new();
+    let mut errors = 0;
+    for round in 0..warmup + requests {
+        let text = format!("benchmark entry {} {}", std::process::id(), round);
+        let (content_type, body) = form.encode(&text);
+        let measured = submit(&client, &base_url, &form.action, &content_type, body, &text).await;
+        if round < warmup {
+            continue;
+        }
+        match measured {
+            Ok((submit_ms, render_ms)) => {
+                tracing::debug!(
+                    "submit {:>4} in {}, then rendered in {}",
+                    round - warmup + 1,
+                    units::duration_ms(submit_ms),
+                    units::duration_ms(render_ms)
+                );
+                submits.push(submit_ms);
+                renders.push(render_ms);
+                round_trips.push(submit_ms + render_ms);
+            }
+            Err(e) => {
+                tracing::debug!("submit {:>4} failed: {:#}", round - warmup + 1, e);
+                errors += 1;
+            }
+        }
+    }
+
+    let (Some(submit), Some(render), Some(round_trip)) = (
+        Latency::from_times(&submits),
+        Latency::from_times(&renders),
+        Latency::from_times(&round_trips),
+    ) else {
+        anyhow::bail!("No successful submissions");
+    };
+    Ok(Mutation {
+        page: page.to_string(),
+        requests,
+        errors,
+        submit,
+        render,
+        round_trip,
+    })
+}
+
+/// One submission: milliseconds to the redirect, then to the last byte of
+/// the page it points at.
+async fn submit(
+    client: &reqwest::Client,
+    base_url: &str,
+    action: &str,
+    content_type: &str,
+    body: Vec<u8>,
+    text: &str,
+) -> Result<(f64, f64)> {
+    let start = Instant::now();
+    let response = client
+        .post(format!("{}{}", base_url, action))
+        .header(header::CONTENT_TYPE, content_type)
+        .body(body)
+        .send()
+        .await?;
+    let status = response.status();
+    let location = response
+        .headers()
+        .get(header::LOCATION)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    response.bytes().await?;
+    let submit_ms = start.elapsed().as_secs_f64() * 1000.0;
+    let Some(location) = location.filter(|_| status.is_redirection()) else {
+        anyhow::bail!("expected a redirect, got {}", status);
+    };
+
+    let url = if location.starts_with('/') {
+        format!("{}{}", base_url, location)
+    } else {
+        location
+    };
+    let start = Instant::now();
+    let page = client
+        .get(&url)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    let render_ms = start.elapsed().as_secs_f64() * 1000.0;
+    if !page.contains(text) {
+        anyhow::bail!("{} does not show the new entry", url);
+    }
+    Ok((submit_ms, render_ms))
+}
+
+type Row = (&'static str, fn(&Mutation) -> f64, units::Unit);
+
+const ROWS: [Row; 5] = [
+    ("Submit (avg)", |m| m.submit.avg, units::Unit::Milliseconds),
+    (
+        "Render after redirect (avg)",
+        |m| m.render.avg,
+        units::Unit::Milliseconds,
+    ),
+    (
+        "Round trip (avg)",
+        |m| m.round_trip.avg,
+        units::Unit::Milliseconds,
+    ),
+    (
+        "Round trip (p95)",
+        |m| m.round_trip.p95,
+        units::Unit::Milliseconds,
+    ),
+    ("Errors", |m| m.errors as f64, units::Unit::Count),
+];
+
+/// Compares each target's write path, one row per timing.
+pub fn display(targets: &[Target], mutations: &[&Mutation], tie_pct: f64) {
+    println!("\n{}", "✍️ Form Submission".bold());
+
+    let mut header = vec!["Metric".to_string()];
+    header.extend(targets.iter().map(|target| target.label().to_string()));
+    header.push("Winner".to_string());
+
+    let mut builder = Builder::default();
+    builder.push_record(header);
+    for (label, value, unit) in ROWS {
+        let values: Vec<f64> = mutations.iter().map(|mutation| value(mutation)).collect();
+        let mut record = vec![label.to_string()];
+        record.extend(values.iter().map(|value| unit.format(*value)));
+        record.push(match tolerance::best(&values, true, tie_pct) {
+            Some(index) => targets[index].display(),
+            None => tolerance::TIE.to_string(),
+        });
+        builder.push_record(record);
+    }
+    println!("\n{}", builder.build());
+}
@@ -0,0 +1,351 @@
+use crate::random::Rng;
+use crate::run_metadata::NetworkProfile;
+use crate::targets::Target;
+use crate::units::Bytes;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+const CHUNK_BYTES: usize = 16 * 1024;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct NetworkArgs {
+    #[arg(
+        long,
+        value_enum,
+        value_name = "PRESET",
+        help = "Send the load through a proxy that simulates a mobile network"
+    )]
+    pub network: Option<NetworkPreset>,
+    #[arg(
+        long,
+        value_name = "MS",
+        help = "Round-trip time added by the network proxy [default: the preset's]"
+    )]
+    pub rtt: Option<u64>,
+    #[arg(
+        long,
+        value_name = "MS",
+        default_value = "0",
+        help = "Random extra delay of up to MS per direction, on top of --rtt"
+    )]
+    pub jitter: u64,
+    #[arg(
+        long,
+        value_name = "KBPS",
+        help = "Download bandwidth per connection in kbit/s [default: the preset's, or unlimited]"
+    )]
+    pub bandwidth: Option<u64>,
+    #[arg(
+        long,
+        value_name = "KBPS",
+        help = "Upload bandwidth per connection in kbit/s [default: the preset's, or unlimited]"
+    )]
+    pub upload_bandwidth: Option<u64>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkPreset {
+    /// 150ms RTT, 1.6 Mbit/s down, 750 kbit/s up.
+    #[value(name = "4g")]
+    FourG,
+    /// 563ms RTT, 1.44 Mbit/s down, 675 kbit/s up.
+    #[value(name = "3g")]
+    ThreeG,
+    /// 2s RTT, 400 kbit/s down and up.
+    #[value(name = "slow-3g")]
+    SlowThreeG,
+}
+
+impl NetworkPreset {
+    pub fn name(self) -> &'static str {
+        match self {
+            NetworkPreset::FourG => "4g",
+            NetworkPreset::ThreeG => "3g",
+            NetworkPreset::SlowThreeG => "slow-3g",
+        }
+    }
+
+    /// RTT in milliseconds and download/upload bandwidth in kbit/s.
+    fn conditions(self) -> (u64, u64, u64) {
+        match self {
+            NetworkPreset::FourG => (150, 1600, 750),
+            NetworkPreset::ThreeG => (563, 1440, 675),
+            NetworkPreset::SlowThreeG => (2000, 400, 400),
+        }
+    }
+}
+
+impl NetworkArgs {
+    /// The simulated conditions, or `None` when no shaping was asked for.
+    pub fn profile(&self) -> Option<NetworkProfile> {
+        if self.network.is_none()
+            && self.rtt.is_none()
+            && self.jitter == 0
+            && self.bandwidth.is_none()
+            && self.upload_bandwidth.is_none()
+        {
+            return None;
+        }
+        let (rtt, download, upload) = self
+            .network
+            .map(NetworkPreset::conditions)
+            .map_or((0, None, None), |(rtt, down, up)| {
+                (rtt, Some(down), Some(up))
+            });
+        Some(NetworkProfile {
+            preset: self.network.map(|preset| preset.name().to_string()),
+            rtt_ms: self.rtt.unwrap_or(rtt),
+            jitter_ms: self.jitter,
+            download_kbps: self.bandwidth.or(download),
+            upload_kbps: self.upload_bandwidth.or(upload),
+        })
+    }
+}
+
+/// Bytes the load generator sent to and received from a target on the wire,
+/// headers included.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WireBytes {
+    pub sent_bytes: Bytes,
+    pub received_bytes: Bytes,
+}
+
+impl WireBytes {
+    pub fn since(self, earlier: WireBytes) -> WireBytes {
+        WireBytes {
+            sent_bytes: self.sent_bytes - earlier.sent_bytes,
+            received_bytes: self.received_bytes - earlier.received_bytes,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    sent: AtomicU64,
+    received: AtomicU64,
+}
+
+/// Proxies started by `shape`, stopped when dropped.
+pub struct Shaper {
+    tasks: Vec<JoinHandle<()>>,
+    counters: BTreeMap<String, Arc<Counters>>,
+}
+
+impl Shaper {
+    /// Bytes sent to and received from `target` through its proxy so far.
+    pub fn wire_bytes(&self, target: &str) -> Option<WireBytes> {
+        let counters = self.counters.get(target)?;
+        Some(WireBytes {
+            sent_bytes: Bytes(counters.sent.load(Ordering::Relaxed)),
+            received_bytes: Bytes(counters.received.load(Ordering::Relaxed)),
+        })
+    }
+}
+
+impl Drop for Shaper {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// One direction of a proxied connection: how long data takes to arrive and
+/// how fast it can be sent.
+#[derive(Debug, Clone, Copy)]
+struct Link {
+    delay: Duration,
+    jitter_ms: u64,
+    bytes_per_sec: Option<f64>,
+}
+
+fn jitter(rng: &mut Rng, max_ms: u64) -> Duration {
+    if max_ms == 0 {
+        return Duration::ZERO;
+    }
+    Duration::from_micros(rng.up_to(max_ms * 1000))
+}
+
+/// Forwards `reader` to `writer`, delivering each chunk `link.delay` (plus
+/// jitter drawn from `rng`) after it was read, in order, at no more than the
+/// link's bandwidth.
+async fn pipe(
+    mut reader: impl AsyncRead + Unpin + Send + 'static,
+    mut writer: impl AsyncWrite + Unpin,
+    link: Link,
+    mut rng: Rng,
+    counter: Arc<Counters>,
+    count: fn(&Counters) -> &AtomicU64,
+) {
+    let (sender, mut receiver) = mpsc::unbounded_channel::<(Instant, Vec<u8>)>();
+    let read = tokio::spawn(async move {
+        let mut last = Instant::now();
+        let mut buffer = vec![0; CHUNK_BYTES];
+        loop {
+            let n = match reader.read(&mut buffer).await {
+                Ok(0) | Err(_) => break,
+                Ok(n) => n,
+            };
+            count(&counter).fetch_add(n as u64, Ordering::Relaxed);
+            // Chunks never overtake each other, however the jitter falls.
+            last = last.max(Instant::now() + link.delay + jitter(&mut rng, link.jitter_ms));
+            if sender.send((last, buffer[..n].to_vec())).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut free_at = Instant::now();
+    while let Some((arrives_at, chunk)) = receiver.recv().await {
+        let mut deliver_at = arrives_at;
+        if let Some(bytes_per_sec) = link.bytes_per_sec {
+            free_at = free_at.max(arrives_at)
+                + Duration::from_secs_f64(chunk.len() as f64 / bytes_per_sec);
+            deliver_at = free_at;
+        }
+        tokio::time::sleep_until(deliver_at).await;
+        if writer.write_all(&chunk).await.is_err() {
+            break;
+        }
+    }
+    let _ = writer.shutdown().await;
+    read.abort();
+}
+
+async fn proxy(
+    client: TcpStream,
+    upstream: String,
+    up: Link,
+    down: Link,
+    mut rng: Rng,
+    counters: Arc<Counters>,
+) {
+    let Ok(server) = TcpStream::connect(&upstream).await else {
+        return;
+    };
+    let _ = client.set_nodelay(true);
+    let _ = server.set_nodelay(true);
+    let (client_read, client_write) = client.into_split();
+    let (server_read, server_write) = server.into_split();
+    tokio::join!(
+        pipe(
+            client_read,
+            server_write,
+            up,
+            rng.fork(),
+            counters.clone(),
+            |c| &c.sent
+        ),
+        pipe(server_read, client_write, down, rng.fork(), counters, |c| {
+            &c.received
+        })
+    );
+}
+
+fn describe(profile: &NetworkProfile) -> String {
+    let mut parts = vec![format!("{}ms RTT", profile.rtt_ms)];
+    if profile.jitter_ms > 0 {
+        parts.push(format!("up to {}ms jitter", profile.jitter_ms));
+    }
+    if let Some(kbps) = profile.download_kbps {
+        parts.push(format!("{} kbit/s down", kbps));
+    }
+    if let Some(kbps) = profile.upload_kbps {
+        parts.push(format!("{} kbit/s up", kbps));
+    }
+    match &profile.preset {
+        Some(preset) => format!("{} ({})", preset, parts.join(", ")),
+        None => parts.join(", "),
+    }
+}
+
+fn link(profile: &NetworkProfile, kbps: Option<u64>) -> Link {
+    Link {
+        delay: Duration::from_micros(profile.rtt_ms * 500),
+        jitter_ms: profile.jitter_ms,
+        bytes_per_sec: kbps.map(|kbps| kbps as f64 * 1000.0 / 8.0),
+    }
+}
+
+/// Starts a proxy for every target and points the target at it. The proxies
+/// shape the traffic when network conditions were asked for, and always count
+/// the bytes passing through. The jitter of each target's n-th connection is
+/// the same for the same `seed`. Returns `None` when neither shaping nor
+/// `count_bytes` was asked for.
+pub async fn shape(
+    args: &NetworkArgs,
+    count_bytes: bool,
+    seed: u64,
+    targets: &mut [Target],
+) -> Result<Option<Shaper>> {
+    let profile = match args.profile() {
+        Some(profile) => {
+            println!("\n{} Simulating {}", "📶".dimmed(), describe(&profile));
+            profile
+        }
+        None if count_bytes => {
+            println!("\n{} Counting bytes on the wire", "📶".dimmed());
+            NetworkProfile::default()
+        }
+        None => return Ok(None),
+    };
+    let up = link(&profile, profile.upload_kbps);
+    let down = link(&profile, profile.download_kbps);
+
+    let mut shaper = Shaper {
+        tasks: Vec::new(),
+        counters: BTreeMap::new(),
+    };
+    for (index, target) in targets.iter_mut().enumerate() {
+        let base_url = target.base_url();
+        let upstream = base_url
+            .strip_prefix("http://")
+            .with_context(|| {
+                format!(
+                    "{}: the network proxy only supports http:// targets, not {}",
+                    target.name, base_url
+                )
+            })?
+            .split('/')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        let listener = TcpListener::bind(("127.0.0.1", 0))
+            .await
+            .context("Failed to start the network proxy")?;
+        let port = listener.local_addr()?.port();
+        println!("   {} {} → 127.0.0.1:{}", target.label(), upstream, port);
+
+        let counters = Arc::new(Counters::default());
+        shaper
+            .counters
+            .insert(target.name.clone(), counters.clone());
+        let mut connections = Rng::stream(seed, index as u64);
+        shaper.tasks.push(tokio::spawn(async move {
+            while let Ok((client, _)) = listener.accept().await {
+                tokio::spawn(proxy(
+                    client,
+                    upstream.clone(),
+                    up,
+                    down,
+                    connections.fork(),
+                    counters.clone(),
+                ));
+            }
+        }));
+        target.url = Some(format!("http://127.0.0.1:{}", port));
+    }
+    Ok(Some(shaper))
+}
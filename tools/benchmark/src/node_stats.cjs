@@ -0,0 +1,48 @@
+// Preloaded into managed Node.js servers by `load-test --node-stats`. Once a
+// second, appends the event-loop delay and GC pauses since the previous line to
+// $RARI_BENCHMARK_NODE_STATS/<pid>.jsonl.
+const { monitorEventLoopDelay, PerformanceObserver } = require('node:perf_hooks')
+const fs = require('node:fs')
+const path = require('node:path')
+const process = require('node:process')
+
+// The histogram records whole timer intervals, so the resolution itself is
+// subtracted to leave the delay.
+const RESOLUTION_MS = 10
+
+const dir = process.env.RARI_BENCHMARK_NODE_STATS
+if (dir) {
+  const file = path.join(dir, `${process.pid}.jsonl`)
+  const delay = monitorEventLoopDelay({ resolution: RESOLUTION_MS })
+  delay.enable()
+
+  let gc = { count: 0, totalMs: 0, maxMs: 0 }
+  try {
+    new PerformanceObserver((list) => {
+      for (const entry of list.getEntries()) {
+        gc.count += 1
+        gc.totalMs += entry.duration
+        gc.maxMs = Math.max(gc.maxMs, entry.duration)
+      }
+    }).observe({ entryTypes: ['gc'] })
+  }
+  catch {}
+
+  const ms = nanoseconds => Math.max(0, nanoseconds / 1e6 - RESOLUTION_MS)
+  setInterval(() => {
+    const line = { at: Date.now(), gc }
+    if (delay.max > 0) {
+      line.delay = {
+        meanMs: ms(delay.mean),
+        p99Ms: ms(delay.percentile(99)),
+        maxMs: ms(delay.max),
+      }
+    }
+    delay.reset()
+    gc = { count: 0, totalMs: 0, maxMs: 0 }
+    try {
+      fs.appendFileSync(file, `${JSON.stringify(line)}\n`)
+    }
+    catch {}
+  }, 1000).unref()
+}
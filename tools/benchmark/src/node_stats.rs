@@ -0,0 +1,170 @@
+use crate::errors::{Error, ErrorKind};
+use crate::targets::Target;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const PRELOAD: &str = include_str!("node_stats.cjs");
+const DIR_ENV: &str = "RARI_BENCHMARK_NODE_STATS";
+
+/// Event-loop delay and GC pauses reported by a Node.js server over a window.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeStats {
+    pub samples: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_loop_delay: Option<DelayStats>,
+    pub gc: GcStats,
+    /// Worst P99 event-loop delay of each second, in milliseconds.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub delay_p99_timeline: Vec<f64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DelayStats {
+    pub mean_ms: f64,
+    /// The worst per-second P99.
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GcStats {
+    pub count: u64,
+    pub total_pause_ms: f64,
+    pub max_pause_ms: f64,
+}
+
+/// One line of the preload script's output.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Line {
+    at: u64,
+    #[serde(default)]
+    delay: Option<LineDelay>,
+    gc: LineGc,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LineDelay {
+    mean_ms: f64,
+    p99_ms: f64,
+    max_ms: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LineGc {
+    count: u64,
+    total_ms: f64,
+    max_ms: f64,
+}
+
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Checks that the binary was built with the `monitoring` feature, which
+/// samples the servers the stats are reported alongside.
+pub fn check() -> Result<()> {
+    if !cfg!(feature = "monitoring") {
+        return Err(Error::new(
+            ErrorKind::InvalidConfig,
+            "--node-stats needs a build with the monitoring feature: cargo build --features monitoring",
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Preloads the stats script into every local target through `NODE_OPTIONS`
+/// and returns the directory each one reports to.
+pub fn inject(targets: &mut [Target]) -> Result<BTreeMap<String, PathBuf>> {
+    let root =
+        std::env::temp_dir().join(format!("rari-benchmark-node-stats-{}", std::process::id()));
+    std::fs::create_dir_all(&root)
+        .with_context(|| format!("Failed to create {}", root.display()))?;
+    let preload = root.join("preload.cjs");
+    std::fs::write(&preload, PRELOAD)
+        .with_context(|| format!("Failed to write {}", preload.display()))?;
+
+    let mut dirs = BTreeMap::new();
+    for target in targets.iter_mut().filter(|target| !target.is_remote()) {
+        let dir = root.join(&target.name);
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        let require = format!("--require \"{}\"", preload.display());
+        let options = match target.env.get("NODE_OPTIONS") {
+            Some(options) => format!("{} {}", options, require),
+            None => require,
+        };
+        target.env.insert("NODE_OPTIONS".to_string(), options);
+        target
+            .env
+            .insert(DIR_ENV.to_string(), dir.to_string_lossy().into_owned());
+        dirs.insert(target.name.clone(), dir);
+    }
+    Ok(dirs)
+}
+
+/// Combines the lines every process in `dir` wrote between `from` and `to`
+/// (milliseconds since the epoch). A server started through pnpm runs two Node
+/// processes, so each second keeps the worst delay and adds up the GC pauses.
+pub fn read(dir: &Path, from: u64, to: u64) -> Option<NodeStats> {
+    let mut seconds: BTreeMap<u64, (Option<DelayStats>, GcStats)> = BTreeMap::new();
+    for entry in std::fs::read_dir(dir).ok()?.flatten() {
+        let Ok(contents) = std::fs::read_to_string(entry.path()) else {
+            continue;
+        };
+        let lines = contents
+            .lines()
+            .filter_map(|line| serde_json::from_str::<Line>(line).ok())
+            .filter(|line| (from..=to).contains(&line.at));
+        for line in lines {
+            let (delay, gc) = seconds.entry((line.at - from) / 1000).or_default();
+            if let Some(reported) = line.delay {
+                let delay = delay.get_or_insert_with(DelayStats::default);
+                delay.mean_ms = delay.mean_ms.max(reported.mean_ms);
+                delay.p99_ms = delay.p99_ms.max(reported.p99_ms);
+                delay.max_ms = delay.max_ms.max(reported.max_ms);
+            }
+            gc.count += line.gc.count;
+            gc.total_pause_ms += line.gc.total_ms;
+            gc.max_pause_ms = gc.max_pause_ms.max(line.gc.max_ms);
+        }
+    }
+    if seconds.is_empty() {
+        return None;
+    }
+
+    let mut stats = NodeStats {
+        samples: seconds.len(),
+        ..NodeStats::default()
+    };
+    let delays: Vec<&DelayStats> = seconds
+        .values()
+        .filter_map(|(delay, _)| delay.as_ref())
+        .collect();
+    if !delays.is_empty() {
+        stats.event_loop_delay = Some(DelayStats {
+            mean_ms: delays.iter().map(|delay| delay.mean_ms).sum::<f64>() / delays.len() as f64,
+            p99_ms: delays.iter().map(|delay| delay.p99_ms).fold(0.0, f64::max),
+            max_ms: delays.iter().map(|delay| delay.max_ms).fold(0.0, f64::max),
+        });
+        stats.delay_p99_timeline = delays.iter().map(|delay| delay.p99_ms).collect();
+    }
+    for (_, gc) in seconds.values() {
+        stats.gc.count += gc.count;
+        stats.gc.total_pause_ms += gc.total_pause_ms;
+        stats.gc.max_pause_ms = stats.gc.max_pause_ms.max(gc.max_pause_ms);
+    }
+    Some(stats)
+}
@@ -0,0 +1,109 @@
+use crate::{interrupt, stats};
+use anyhow::Result;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct NoiseArgs {
+    #[arg(
+        long,
+        value_name = "PCT",
+        help = "Re-run a measurement whose coefficient of variation exceeds PCT%"
+    )]
+    pub max_cv: Option<f64>,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 2,
+        requires = "max_cv",
+        help = "How often a noisy measurement is re-run before it is kept and flagged"
+    )]
+    pub max_retries: usize,
+}
+
+/// How stable a measurement was, stored next to its results.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Noise {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cv_pct: Option<f64>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub retries: usize,
+    /// Still above `--max-cv` after the last re-run.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub noisy: bool,
+}
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
+
+/// Coefficient of variation (standard deviation over mean) of `samples` in
+/// percent, or `None` with fewer than two samples or a zero mean.
+pub fn cv_pct(samples: &[f64]) -> Option<f64> {
+    let mean = stats::mean(samples)?;
+    if mean == 0.0 {
+        return None;
+    }
+    Some(stats::stddev(samples)? / mean.abs() * 100.0)
+}
+
+impl NoiseArgs {
+    /// Runs `measure` until the coefficient of variation reported by `cv` is
+    /// within `--max-cv` or the retries are used up. The last run is kept.
+    pub async fn measure<T, F, Fut>(
+        &self,
+        label: &str,
+        mut measure: F,
+        cv: impl Fn(&T) -> Option<f64>,
+    ) -> Result<(T, Noise)>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut retries = 0;
+        loop {
+            let result = measure().await?;
+            let cv_pct = cv(&result);
+            let noise = Noise {
+                cv_pct,
+                retries,
+                noisy: false,
+            };
+            let (Some(max), Some(cv_pct)) = (self.max_cv, cv_pct) else {
+                return Ok((result, noise));
+            };
+            // An interrupted run saves what it has rather than re-running.
+            if cv_pct <= max || interrupt::cancelled() {
+                return Ok((result, noise));
+            }
+            if retries == self.max_retries {
+                tracing::warn!(
+                    "{} is still noisy after {} re-run(s) (CV {:.1}% > {}%)",
+                    label,
+                    retries,
+                    cv_pct,
+                    max
+                );
+                return Ok((
+                    result,
+                    Noise {
+                        noisy: true,
+                        ..noise
+                    },
+                ));
+            }
+            retries += 1;
+            println!(
+                "  {} {}: CV {:.1}% exceeds {}%, re-running ({}/{})",
+                "🔁".yellow(),
+                label,
+                cv_pct,
+                max,
+                retries,
+                self.max_retries
+            );
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use crate::targets::Target;
+use crate::tolerance;
+use crate::units::Unit;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde_json::json;
+
+pub struct Summary {
+    pub title: String,
+    pub timestamp: String,
+    pub targets: Vec<Target>,
+    pub deltas: Vec<Delta>,
+    pub gate_passed: Option<bool>,
+    pub artifact: String,
+}
+
+/// One metric across all targets; `values` is in the same order as `Summary::targets`.
+pub struct Delta {
+    pub label: String,
+    pub unit: Unit,
+    pub values: Vec<f64>,
+    pub lower_is_better: bool,
+}
+
+impl Delta {
+    fn differences(&self, targets: &[Target]) -> String {
+        let Some((subject, others)) = self.values.split_first() else {
+            return String::new();
+        };
+        let differences: Vec<String> = others
+            .iter()
+            .zip(targets.iter().skip(1))
+            .map(|(other, target)| {
+                let diff = tolerance::difference_pct(*subject, *other);
+                if others.len() == 1 {
+                    format!("{:+.1}%", diff)
+                } else {
+                    format!("{:+.1}% vs {}", diff, target.label())
+                }
+            })
+            .collect();
+        differences.join(", ")
+    }
+}
+
+impl Summary {
+    pub fn winner(&self) -> String {
+        let mut wins = vec![0; self.targets.len()];
+        for delta in &self.deltas {
+            if let Some(best) = tolerance::best(&delta.values, delta.lower_is_better, 0.0) {
+                wins[best] += 1;
+            }
+        }
+
+        let most = wins.iter().copied().max().unwrap_or_default();
+        let leaders: Vec<usize> = (0..wins.len()).filter(|&i| wins[i] == most).collect();
+        match leaders.as_slice() {
+            [leader] if most > 0 => self.targets[*leader].display(),
+            _ => tolerance::TIE.to_string(),
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![
+            format!("{} ({})", self.title, self.timestamp),
+            format!("Winner: {}", self.winner()),
+        ];
+
+        for delta in &self.deltas {
+            let values: Vec<String> = self
+                .targets
+                .iter()
+                .zip(&delta.values)
+                .map(|(target, value)| format!("{} {}", target.label(), delta.unit.format(*value)))
+                .collect();
+            lines.push(format!(
+                "• {}: {} ({})",
+                delta.label,
+                values.join(" vs "),
+                delta.differences(&self.targets)
+            ));
+        }
+
+        match self.gate_passed {
+            Some(true) => lines.push("Regression gate: ✅ passed".to_string()),
+            Some(false) => lines.push("Regression gate: ❌ failed".to_string()),
+            None => {}
+        }
+
+        lines.push(format!("Results: {}", self.artifact));
+        lines.join("\n")
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let headers: Vec<&str> = self.targets.iter().map(Target::label).collect();
+        let mut md = format!(
+            "### {}\n\n**Winner:** {} · {}\n\n| Metric | {} | Difference |\n|---|{}---|\n",
+            self.title,
+            self.winner(),
+            self.timestamp,
+            headers.join(" | "),
+            "---|".repeat(headers.len())
+        );
+
+        for delta in &self.deltas {
+            let values: Vec<String> = delta.values.iter().map(|v| delta.unit.format(*v)).collect();
+            md.push_str(&format!(
+                "| {} | {} | {} |\n",
+                delta.label,
+                values.join(" | "),
+                delta.differences(&self.targets)
+            ));
+        }
+
+        match self.gate_passed {
+            Some(true) => md.push_str("\n**Regression gate:** ✅ passed\n"),
+            Some(false) => md.push_str("\n**Regression gate:** ❌ failed\n"),
+            None => {}
+        }
+
+        if self.artifact.starts_with("http://") || self.artifact.starts_with("https://") {
+            md.push_str(&format!("\n[Full results]({})\n", self.artifact));
+        } else {
+            md.push_str(&format!("\nFull results: `{}`\n", self.artifact));
+        }
+
+        md
+    }
+}
+
+fn payload_for(url: &str, text: &str) -> serde_json::Value {
+    let is_discord = reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_string))
+        .is_some_and(|host| host.ends_with("discord.com") || host.ends_with("discordapp.com"));
+
+    if is_discord {
+        json!({ "content": text })
+    } else {
+        json!({ "text": text })
+    }
+}
+
+async fn send(url: &str, summary: &Summary) -> Result<()> {
+    let text = summary.to_text();
+
+    let response = reqwest::Client::new()
+        .post(url)
+        .json(&payload_for(url, &text))
+        .send()
+        .await
+        .context("Failed to reach notification webhook")?;
+
+    if !response.status().is_success() {
+        anyhow::bail!("Notification webhook returned {}", response.status());
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument(name = "notify", skip_all)]
+pub async fn notify(url: Option<&str>, summary: &Summary) {
+    let Some(url) = url else {
+        return;
+    };
+
+    match send(url, summary).await {
+        Ok(()) => println!("{} Summary posted to webhook", "📣".dimmed()),
+        Err(e) => tracing::warn!("Failed to post summary: {:#}", e),
+    }
+}
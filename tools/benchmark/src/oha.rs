@@ -0,0 +1,212 @@
+use crate::errors::{Error, ErrorKind};
+use crate::units::Millis;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde_json::Value;
+use std::sync::OnceLock;
+use tokio::process::Command;
+
+static FEATURES: OnceLock<Features> = OnceLock::new();
+
+/// Flags of the installed oha that not every release has, read from its
+/// `--help`.
+#[derive(Debug, Clone, Copy)]
+struct Features {
+    /// `--output-format json`; older releases only have `--json`.
+    output_format: bool,
+    /// `--db-url`, which records every request in SQLite.
+    db_url: bool,
+}
+
+impl Default for Features {
+    fn default() -> Self {
+        Features {
+            output_format: true,
+            db_url: true,
+        }
+    }
+}
+
+fn features() -> Features {
+    FEATURES.get().copied().unwrap_or_default()
+}
+
+/// Checks that oha is installed, prints its version, and notes which of the
+/// flags the load test uses it supports.
+pub async fn check_installed() -> Result<()> {
+    let version = match Command::new("oha").arg("--version").output().await {
+        Ok(output) if output.status.success() => output.stdout,
+        _ => {
+            return Err(Error::new(
+                ErrorKind::ToolMissing,
+                "oha is not installed. Install it with: cargo install oha\n\
+                 Or visit: https://github.com/hatoo/oha",
+            )
+            .into());
+        }
+    };
+    println!(
+        "{} {}",
+        "✅".green(),
+        String::from_utf8_lossy(&version).trim()
+    );
+
+    let help = Command::new("oha")
+        .arg("--help")
+        .output()
+        .await
+        .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+        .unwrap_or_default();
+    let features = Features {
+        output_format: help.contains("--output-format") || !help.contains("--json"),
+        db_url: help.contains("--db-url"),
+    };
+    tracing::debug!("oha supports {:?}", features);
+    if !features.db_url {
+        tracing::warn!(
+            "This oha cannot record requests (no --db-url), so there is no per-second RPS or spread; upgrade it with: cargo install oha"
+        );
+    }
+    let _ = FEATURES.set(features);
+    Ok(())
+}
+
+/// The flags that make oha print its results as JSON.
+pub fn json_args() -> &'static [&'static str] {
+    if features().output_format {
+        &["--output-format", "json"]
+    } else {
+        &["--json"]
+    }
+}
+
+/// Whether oha can record every request with `--db-url`.
+pub fn records_requests() -> bool {
+    features().db_url
+}
+
+/// The layouts of oha's JSON output this build reads. oha has
+/// renamed fields between releases; a layout is told apart by its latency
+/// percentiles, and anything else is rejected rather than read as zeros.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// Current releases: percentiles under `latencyPercentiles`.
+    Percentiles,
+    /// Older releases: the same numbers under `latencyDistribution`.
+    Distribution,
+}
+
+impl Layout {
+    const ALL: [Layout; 2] = [Layout::Percentiles, Layout::Distribution];
+
+    fn percentiles_key(self) -> &'static str {
+        match self {
+            Layout::Percentiles => "latencyPercentiles",
+            Layout::Distribution => "latencyDistribution",
+        }
+    }
+
+    fn detect(json: &Value) -> Option<Self> {
+        if !json["summary"].is_object() {
+            return None;
+        }
+        Self::ALL
+            .into_iter()
+            .find(|layout| json[layout.percentiles_key()].is_object())
+    }
+}
+
+/// What oha reported about a run, with its latencies, which it writes in
+/// seconds, in [`Millis`].
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub layout: Layout,
+    /// Successful requests, 0–1.
+    pub success_rate: f64,
+    /// How long the run took.
+    pub total_secs: f64,
+    pub slowest: Millis,
+    pub fastest: Millis,
+    pub average: Millis,
+    pub requests_per_sec: f64,
+    /// Response bytes per second.
+    pub size_per_sec: f64,
+    pub p50: Millis,
+    pub p90: Millis,
+    pub p95: Millis,
+    pub p99: Millis,
+    /// Latency bucket and its request count, unsorted; empty if oha did not
+    /// write one.
+    pub histogram: Vec<(Millis, u64)>,
+}
+
+/// Reads oha's JSON output, or fails with a [`ErrorKind::ParseError`] naming
+/// what is missing if the layout is not one of [`Layout`].
+pub fn parse(output: &str) -> Result<Report> {
+    let json: Value = serde_json::from_str(output)
+        .with_context(|| Error::new(ErrorKind::ParseError, "Failed to parse oha JSON output"))?;
+    let layout = Layout::detect(&json).ok_or_else(|| {
+        let found: Vec<&str> = json
+            .as_object()
+            .map(|object| object.keys().map(String::as_str).collect())
+            .unwrap_or_default();
+        Error::new(
+            ErrorKind::ParseError,
+            format!(
+                "Unrecognized oha JSON layout (top-level fields: {}); expected summary and {}. Is oha too old or too new?",
+                if found.is_empty() { "none".to_string() } else { found.join(", ") },
+                Layout::ALL.map(Layout::percentiles_key).join(" or ")
+            ),
+        )
+    })?;
+    tracing::debug!("oha JSON layout: {:?}", layout);
+
+    let mut missing = Vec::new();
+    let summary = &json["summary"];
+    let percentiles = &json[layout.percentiles_key()];
+    let mut field = |section: &Value, name: &str, key: &str| match &section[key] {
+        // oha writes null for statistics of a run without successful requests.
+        Value::Null if section.get(key).is_some() => 0.0,
+        value => value.as_f64().unwrap_or_else(|| {
+            missing.push(format!("{}.{}", name, key));
+            0.0
+        }),
+    };
+    let report = Report {
+        layout,
+        success_rate: field(summary, "summary", "successRate"),
+        total_secs: field(summary, "summary", "total"),
+        slowest: Millis::from_secs(field(summary, "summary", "slowest")),
+        fastest: Millis::from_secs(field(summary, "summary", "fastest")),
+        average: Millis::from_secs(field(summary, "summary", "average")),
+        requests_per_sec: field(summary, "summary", "requestsPerSec"),
+        size_per_sec: field(summary, "summary", "sizePerSec"),
+        p50: Millis::from_secs(field(percentiles, layout.percentiles_key(), "p50")),
+        p90: Millis::from_secs(field(percentiles, layout.percentiles_key(), "p90")),
+        p95: Millis::from_secs(field(percentiles, layout.percentiles_key(), "p95")),
+        p99: Millis::from_secs(field(percentiles, layout.percentiles_key(), "p99")),
+        histogram: json["responseTimeHistogram"]
+            .as_object()
+            .map(|buckets| {
+                buckets
+                    .iter()
+                    .filter_map(|(latency, count)| {
+                        Some((Millis::from_secs(latency.parse().ok()?), count.as_u64()?))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+    };
+    if !missing.is_empty() {
+        return Err(Error::new(
+            ErrorKind::ParseError,
+            format!(
+                "oha JSON output is missing {} ({:?} layout); this oha release is not supported",
+                missing.join(", "),
+                report.layout
+            ),
+        )
+        .into());
+    }
+    Ok(report)
+}
@@ -0,0 +1,27 @@
+use crate::random::Rng;
+use colored::Colorize;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct OrderArgs {
+    #[arg(
+        long,
+        help = "Measure the targets (and scenarios) in a random order, reshuffled every repetition; --seed reproduces it"
+    )]
+    pub shuffle: bool,
+}
+
+impl OrderArgs {
+    /// Returns the generator that shuffles the order for the run's `seed`, or
+    /// `None` to keep the configured order.
+    pub fn shuffler(&self, seed: u64) -> Option<Rng> {
+        if !self.shuffle {
+            return None;
+        }
+        println!(
+            "{} Shuffling the measurement order with --seed {}",
+            "🔀".dimmed(),
+            seed
+        );
+        Some(Rng::new(seed))
+    }
+}
@@ -0,0 +1,113 @@
+use crate::stats;
+use crate::units::{Bytes, Millis};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// Fewest samples the quartiles mean anything for.
+const MIN_SAMPLES: usize = 4;
+
+/// One measured request, with what it takes to find it again in the
+/// server's logs.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sample {
+    /// Position among the measured requests of its target and scenario,
+    /// from 1, across repetitions.
+    pub index: usize,
+    /// When the request was sent.
+    pub timestamp: String,
+    pub status: u16,
+    pub size: Bytes,
+    pub ms: Millis,
+}
+
+/// A request much slower than the rest of its scenario.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Outlier {
+    pub target: String,
+    pub scenario: String,
+    #[serde(flatten)]
+    pub sample: Sample,
+    /// The time above which a request is an outlier.
+    pub fence_ms: Millis,
+    pub median_ms: Millis,
+}
+
+/// Tukey's upper fence, Q3 + 1.5 × IQR, of `times`.
+fn fence(times: &[f64]) -> Option<f64> {
+    if times.len() < MIN_SAMPLES {
+        return None;
+    }
+    let mut sorted = times.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let q1 = stats::percentile(&sorted, 0.25);
+    let q3 = stats::percentile(&sorted, 0.75);
+    Some(q3 + 1.5 * (q3 - q1))
+}
+
+/// The samples above the upper fence of all of them.
+pub fn find(target: &str, scenario: &str, samples: &[Sample]) -> Vec<Outlier> {
+    let times: Vec<f64> = samples.iter().map(|sample| sample.ms.0).collect();
+    let (Some(fence), Some(median)) = (fence(&times), stats::median(&times)) else {
+        return Vec::new();
+    };
+    samples
+        .iter()
+        .filter(|sample| sample.ms.0 > fence)
+        .map(|sample| Outlier {
+            target: target.to_string(),
+            scenario: scenario.to_string(),
+            sample: sample.clone(),
+            fence_ms: Millis(fence),
+            median_ms: Millis(median),
+        })
+        .collect()
+}
+
+/// `#14 801.20ms (200, 12.00 kB)` for up to three outliers.
+pub fn describe(outliers: &[Outlier]) -> String {
+    let mut described: Vec<String> = outliers
+        .iter()
+        .take(3)
+        .map(|outlier| {
+            format!(
+                "#{} {} ({}, {})",
+                outlier.sample.index, outlier.sample.ms, outlier.sample.status, outlier.sample.size
+            )
+        })
+        .collect();
+    if outliers.len() > 3 {
+        described.push(format!("{} more", outliers.len() - 3));
+    }
+    described.join(", ")
+}
+
+/// The outlier log next to a results file.
+pub fn path(json_path: &Path) -> PathBuf {
+    let stem = json_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("results");
+    json_path.with_file_name(format!("{}-outliers.jsonl", stem))
+}
+
+/// Writes one JSON line per outlier next to the results file, or nothing
+/// without outliers.
+pub async fn save(outliers: &[Outlier], json_path: &Path) -> Result<Option<PathBuf>> {
+    if outliers.is_empty() {
+        return Ok(None);
+    }
+    let mut lines = String::new();
+    for outlier in outliers {
+        lines.push_str(&serde_json::to_string(outlier)?);
+        lines.push('\n');
+    }
+    let path = path(json_path);
+    fs::write(&path, lines)
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(Some(path))
+}
@@ -0,0 +1,106 @@
+use crate::{logging, progress, stream};
+use anyhow::Result;
+use clap::ArgAction;
+use std::fs::File;
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+
+static STDOUT: OnceLock<Mutex<File>> = OnceLock::new();
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct OutputArgs {
+    #[arg(
+        short,
+        long,
+        conflicts_with_all = ["verbose", "stream"],
+        help = "Only print the final summary"
+    )]
+    pub quiet: bool,
+    #[arg(short, long, action = ArgAction::Count, help = "Log per-request details (-vv adds raw tool output)")]
+    pub verbose: u8,
+    #[arg(long, help = "Print only the final results JSON to stdout")]
+    pub json: bool,
+    #[arg(
+        long,
+        conflicts_with = "json",
+        help = "Stream progress events as NDJSON on stdout (human output moves to stderr)"
+    )]
+    pub stream: bool,
+    #[command(flatten)]
+    pub log: logging::LogArgs,
+}
+
+enum Redirect {
+    Stderr,
+    Null,
+}
+
+#[cfg(unix)]
+fn detach_stdout(redirect: Redirect) -> Result<File> {
+    use std::os::fd::{AsFd, AsRawFd};
+
+    // Keep a handle on the real stdout for machine-readable output and point
+    // fd 1 elsewhere, so the human-readable println! calls stay out of it.
+    std::io::stdout().flush()?;
+    let stdout = std::io::stdout().as_fd().try_clone_to_owned()?;
+    let target = match redirect {
+        Redirect::Stderr => std::io::stderr().as_fd().try_clone_to_owned()?,
+        Redirect::Null => File::options().write(true).open("/dev/null")?.into(),
+    };
+    if unsafe { libc::dup2(target.as_raw_fd(), libc::STDOUT_FILENO) } < 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(File::from(stdout))
+}
+
+#[cfg(not(unix))]
+fn detach_stdout(_redirect: Redirect) -> Result<File> {
+    anyhow::bail!("--quiet, --json, and --stream are only supported on Unix platforms")
+}
+
+pub fn init(args: &OutputArgs) -> Result<()> {
+    if args.json {
+        colored::control::set_override(false);
+    }
+
+    let redirect = if args.quiet || args.json {
+        Some(Redirect::Null)
+    } else if args.stream {
+        Some(Redirect::Stderr)
+    } else {
+        None
+    };
+    if let Some(redirect) = redirect {
+        let _ = STDOUT.set(Mutex::new(detach_stdout(redirect)?));
+    }
+
+    if args.stream {
+        stream::enable();
+    }
+    // Per-request logging and NDJSON events would break up the bars.
+    if args.stream || args.verbose > 0 || args.log.log_level.is_some() {
+        progress::hide();
+    }
+
+    logging::init(&args.log, args.verbose)
+}
+
+pub fn write_line(line: &str) {
+    match STDOUT.get() {
+        Some(stdout) => {
+            if let Ok(mut stdout) = stdout.lock() {
+                let _ = writeln!(stdout, "{}", line);
+            }
+        }
+        None => println!("{}", line),
+    }
+}
+
+pub fn finish(args: &OutputArgs, summary: &str, results: &serde_json::Value) -> Result<()> {
+    if args.json {
+        write_line(&serde_json::to_string_pretty(results)?);
+    } else if args.quiet {
+        write_line(summary);
+    }
+    Ok(())
+}
@@ -1,252 +1,1460 @@
-mod time_utils;
-
+#[cfg(feature = "docker")]
+use crate::compose;
+use crate::{
+    affinity, apdex, archive, assets, bootstrap, calibration, charts, compression, conditional,
+    config, containers, distribution, dry_run, errors, gate, hooks, influx, interrupt, limits,
+    metrics, monitor, mutation, network, noise, notify, order, outliers, output, preflight,
+    presets, probe, profiles, progress, publish, quality, random, redirects, results, run_metadata,
+    sample_size, schema, script, seed, server_logs, servers, ssh, stats, store, stream, targets,
+    time_utils, tolerance, units, upload, versions,
+};
 use anyhow::{Context, Result};
 use clap::Parser;
 use colored::Colorize;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant, SystemTime};
-use tabled::{Table, Tabled};
+use tabled::builder::Builder;
 use tokio::fs;
+use tokio::sync::Mutex;
+use tracing::Instrument;
 
 #[derive(Parser, Debug)]
 #[command(name = "performance")]
-#[command(about = "Run performance benchmarks comparing rari and Next.js")]
-struct Args {
+#[command(
+    about = "Run performance benchmarks comparing rari and Next.js (or any configured targets)"
+)]
+pub struct Args {
     #[arg(short, long, default_value = "50")]
-    warmup: usize,
+    pub warmup: usize,
     #[arg(short, long, default_value = "20")]
-    requests: usize,
-    #[arg(long, default_value = "3000")]
-    rari_port: u16,
-    #[arg(long, default_value = "3001")]
-    nextjs_port: u16,
+    pub requests: usize,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value = "1",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Measure every target and scenario N times and combine the samples"
+    )]
+    pub repetitions: u64,
     #[arg(long, default_value = "results")]
-    results_dir: PathBuf,
+    pub results_dir: PathBuf,
+    #[arg(long)]
+    pub label: Option<String>,
+    #[arg(long = "attach-profile", value_name = "FRAMEWORK=PATH")]
+    pub attach_profile: Vec<profiles::ProfileSpec>,
+    #[arg(
+        long,
+        value_name = "PCT",
+        default_value_t = tolerance::DEFAULT_TIE_PCT,
+        help = "Differences within ±PCT% are reported as a tie"
+    )]
+    pub tie_threshold: f64,
+    #[arg(long = "fail-if", value_name = "EXPR")]
+    pub fail_if: Vec<String>,
+    #[arg(long, value_name = "FILE")]
+    pub thresholds: Option<PathBuf>,
+    #[arg(long, value_name = "URL")]
+    pub notify_webhook: Option<String>,
+    #[arg(long, value_name = "URL")]
+    pub artifact_url: Option<String>,
+    #[arg(long, value_name = "URL")]
+    pub upload: Option<upload::Destination>,
+    #[arg(long, value_name = "FILE")]
+    pub store: Option<PathBuf>,
+    #[arg(long)]
+    pub no_store: bool,
+    #[arg(
+        long,
+        value_name = "CPUS",
+        help = "Pin the load generator to these CPUs, e.g. 4-7 (Linux only)"
+    )]
+    pub client_cpus: Option<String>,
+    #[arg(
+        long,
+        help = "Also request a large JS chunk, a stylesheet, and an image from every target"
+    )]
+    pub static_assets: bool,
+    #[arg(
+        long,
+        help = "Also check that those static files answer conditional requests with a 304, and time the answers"
+    )]
+    pub conditional: bool,
+    #[arg(
+        long,
+        help = "Also compare gzip and brotli responses with uncompressed ones for the pages and any static files"
+    )]
+    pub compression: bool,
+    #[arg(
+        long,
+        help = "Also stream a multi-megabyte page (/large) from every target and measure TTFB, total time, and throughput"
+    )]
+    pub large_payload: bool,
+    #[arg(
+        long,
+        help = "Also submit the form on --mutation-page over and over, following each redirect, and time the round trip"
+    )]
+    pub mutation: bool,
+    #[arg(
+        long,
+        value_name = "PATH",
+        default_value = mutation::DEFAULT_PAGE,
+        requires = "mutation"
+    )]
+    pub mutation_page: String,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "JavaScript module whose scenarios pick each request's path and headers and check each response; run with Node.js"
+    )]
+    pub script: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "JSON or TOML file of pages to measure after the homepage, as `scenarios` with a name and a path each"
+    )]
+    pub scenarios: Option<PathBuf>,
+    #[command(flatten)]
+    pub targets: targets::TargetArgs,
+    #[command(flatten)]
+    pub hooks: hooks::HookArgs,
+    #[command(flatten)]
+    pub preflight: preflight::PreflightArgs,
+    #[command(flatten)]
+    pub seed: seed::SeedArgs,
+    #[command(flatten)]
+    pub servers: servers::ServerArgs,
+    #[command(flatten)]
+    pub probe: probe::ProbeArgs,
+    #[command(flatten)]
+    pub noise: noise::NoiseArgs,
+    #[command(flatten)]
+    pub calibration: calibration::CalibrationArgs,
+    #[command(flatten)]
+    pub sample_size: sample_size::SampleSizeArgs,
+    #[command(flatten)]
+    pub apdex: apdex::ApdexArgs,
+    #[command(flatten)]
+    pub order: order::OrderArgs,
+    #[command(flatten)]
+    pub random: random::RandomArgs,
+    #[command(flatten)]
+    pub redirects: redirects::RedirectArgs,
+    #[cfg(feature = "docker")]
+    #[command(flatten)]
+    pub compose: compose::ComposeArgs,
+    #[command(flatten)]
+    pub ssh: ssh::SshArgs,
+    #[command(flatten)]
+    pub network: network::NetworkArgs,
+    #[command(flatten)]
+    pub output: output::OutputArgs,
+    #[command(flatten)]
+    pub preset: presets::PresetArgs,
+    #[command(flatten)]
+    pub dry_run: dry_run::DryRunArgs,
+    #[command(flatten)]
+    pub archive: archive::ArchiveArgs,
+    #[command(flatten)]
+    pub influx: influx::InfluxArgs,
+    #[command(flatten)]
+    pub github: publish::GitHubArgs,
+    #[command(flatten)]
+    pub config: config::ConfigArgs,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct PerformanceMetrics {
-    min: f64,
-    max: f64,
-    avg: f64,
-    p50: f64,
-    p95: f64,
-    p99: f64,
+pub struct PerformanceMetrics {
+    pub min: f64,
+    pub max: f64,
+    pub avg: f64,
+    /// Population standard deviation of the response times.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stddev: Option<f64>,
+    pub p50: f64,
+    #[serde(default)]
+    pub p90: f64,
+    pub p95: f64,
+    pub p99: f64,
+    #[serde(default)]
+    pub p999: f64,
     #[serde(rename = "avgSize")]
-    avg_size: usize,
-    errors: usize,
+    pub avg_size: usize,
+    pub errors: usize,
     #[serde(
         rename = "successRate",
         serialize_with = "time_utils::serialize_float_as_int_if_whole"
     )]
-    success_rate: f64,
+    pub success_rate: f64,
+    /// Static assets and large payloads only, whose times run to the last byte.
+    #[serde(
+        rename = "bytesPerSec",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub bytes_per_sec: Option<f64>,
+    /// Time to the first body chunk, where times run to the last byte.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttfb: Option<stats::Latency>,
+    /// The redirects the requests followed on their way to the page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redirects: Option<redirects::Redirects>,
+    /// 95% bootstrap intervals of `p95` and `p99`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ci: Option<bootstrap::Tails>,
+    /// 95% bootstrap intervals of how much the first target's `p95` and
+    /// `p99` differ from this target's, in percent.
+    #[serde(rename = "deltaCi", default, skip_serializing_if = "Option::is_none")]
+    pub delta_ci: Option<bootstrap::Tails>,
+    /// How the first target's times are distributed compared with this
+    /// target's, tails included.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distribution: Option<distribution::Distribution>,
+    /// Scored against `--apdex-target`, failed requests included.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub apdex: Option<apdex::Apdex>,
+    /// Requests slower than Q3 + 1.5 × IQR, listed in the `-outliers.jsonl`
+    /// file next to the results.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub outliers: usize,
+    /// Successful requests the times come from.
+    #[serde(default)]
+    pub samples: usize,
+    /// Samples needed to detect a `--detect` difference from the other
+    /// targets, given how much the times varied.
+    #[serde(
+        rename = "samplesNeeded",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub samples_needed: Option<usize>,
+    #[serde(flatten)]
+    pub noise: noise::Noise,
+}
+
+/// The percentiles the comparison puts side by side, from the median to the
+/// far tail.
+const PERCENTILE_LABELS: [&str; 5] = ["p50", "p90", "p95", "p99", "p99.9"];
+
+impl PerformanceMetrics {
+    /// In the order of [`PERCENTILE_LABELS`].
+    fn percentiles(&self) -> [f64; 5] {
+        [self.p50, self.p90, self.p95, self.p99, self.p999]
+    }
+}
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct BenchmarkResults {
-    timestamp: String,
-    rari: HashMap<String, PerformanceMetrics>,
-    nextjs: HashMap<String, PerformanceMetrics>,
-    summary: TestSummary,
+pub struct BenchmarkResults {
+    /// [`schema::VERSION`] when written; see [`schema::migrate`] for older files.
+    pub version: u32,
+    pub timestamp: String,
+    #[serde(default)]
+    pub metadata: run_metadata::RunMetadata,
+    pub targets: Vec<String>,
+    #[serde(flatten)]
+    pub results: BTreeMap<String, HashMap<String, PerformanceMetrics>>,
+    /// `--static-assets` scenarios per target, kept apart from the pages.
+    #[serde(
+        rename = "staticAssets",
+        default,
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub static_assets: BTreeMap<String, HashMap<String, PerformanceMetrics>>,
+    /// `--large-payload` scenarios per target, kept apart from the pages.
+    #[serde(
+        rename = "largePayload",
+        default,
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub large_payload: BTreeMap<String, HashMap<String, PerformanceMetrics>>,
+    /// `--conditional` checks per target and static asset.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub conditional: BTreeMap<String, BTreeMap<String, conditional::Revalidation>>,
+    /// `--compression` measurements per target, payload, and encoding.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub compression: BTreeMap<String, BTreeMap<String, compression::Encodings>>,
+    /// `--mutation` round trips per target.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub mutation: BTreeMap<String, mutation::Mutation>,
+    /// `--calibrate`: what the harness itself adds to the times.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overhead: Option<calibration::Overhead>,
+    /// How far the numbers can be trusted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quality: Option<quality::Quality>,
+    pub summary: TestSummary,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<profiles::ProfileArtifact>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub containers: BTreeMap<String, containers::ContainerStats>,
+    #[serde(
+        rename = "serverStats",
+        default,
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub server_stats: BTreeMap<String, containers::ContainerStats>,
+    #[serde(
+        rename = "serverLogs",
+        default,
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub server_logs: BTreeMap<String, server_logs::ServerLog>,
+    /// Each managed server's memory before any load.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub footprint: BTreeMap<String, monitor::Footprint>,
+    /// How each managed server fared under `--memory-limit`.
+    #[serde(
+        rename = "memoryPressure",
+        default,
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub memory_pressure: BTreeMap<String, limits::MemoryPressure>,
+    /// How often each managed server hit its `--cpu-limit`.
+    #[serde(
+        rename = "cpuThrottling",
+        default,
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub cpu_throttling: BTreeMap<String, limits::CpuThrottling>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub partial: bool,
+    /// Scenarios that failed, and what cut the run short, if anything.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<errors::RunError>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct TestSummary {
+pub struct TestSummary {
     #[serde(rename = "testRequests")]
-    test_requests: usize,
+    pub test_requests: usize,
     #[serde(rename = "warmupRequests")]
-    warmup_requests: usize,
-    scenarios: usize,
+    pub warmup_requests: usize,
+    pub scenarios: usize,
+    #[serde(default)]
+    pub repetitions: u64,
+    #[serde(
+        rename = "shuffleSeed",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub shuffle_seed: Option<u64>,
+    #[serde(rename = "maxCvPct", default, skip_serializing_if = "Option::is_none")]
+    pub max_cv_pct: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub script: Option<PathBuf>,
+    /// The `--redirects` policy; `--script` scenarios may set their own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redirects: Option<String>,
+    /// The `--detect` difference behind each scenario's `samplesNeeded`.
+    #[serde(rename = "detectPct", default, skip_serializing_if = "Option::is_none")]
+    pub detect_pct: Option<f64>,
+    /// The `--apdex-target` and tolerated time behind each scenario's `apdex`.
+    #[serde(
+        rename = "apdexTargetMs",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub apdex_target_ms: Option<f64>,
+    #[serde(
+        rename = "apdexToleratingMs",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub apdex_tolerating_ms: Option<f64>,
 }
 
 #[derive(Debug, Clone)]
 struct Scenario {
     path: String,
     name: String,
+    /// Static asset scenarios request a different path on every target.
+    asset: Option<assets::AssetKind>,
+    /// The multi-megabyte `--large-payload` page.
+    large: bool,
+    /// The index of a `--script` scenario, which picks its own requests.
+    script: Option<usize>,
+    /// The scenario's own redirect policy, over `--redirects`.
+    redirects: Option<redirects::RedirectPolicy>,
+}
+
+impl Scenario {
+    fn page(path: &str, name: &str) -> Self {
+        Scenario {
+            path: path.to_string(),
+            name: name.to_string(),
+            asset: None,
+            large: false,
+            script: None,
+            redirects: None,
+        }
+    }
+
+    fn is_page(&self) -> bool {
+        self.asset.is_none() && !self.large
+    }
+
+    /// Static files and large payloads are about the transfer, not the headers.
+    fn to_last_byte(&self) -> bool {
+        !self.is_page()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ScenariosFile {
+    scenarios: Vec<PageScenario>,
+}
+
+/// A page the `--scenarios` file adds.
+#[derive(Debug, Deserialize)]
+struct PageScenario {
+    name: String,
+    path: String,
+}
+
+/// The pages of the `--scenarios` file.
+fn read_scenarios(path: &Path) -> Result<Vec<Scenario>> {
+    let file: ScenariosFile = config::read_file(path)
+        .map_err(|e| errors::classify(e, errors::ErrorKind::InvalidConfig))?;
+    file.scenarios
+        .into_iter()
+        .map(|page| {
+            if !page.path.starts_with('/') {
+                return Err(errors::Error::new(
+                    errors::ErrorKind::InvalidConfig,
+                    format!(
+                        "{}: scenario {} has path '{}', which does not start with /",
+                        path.display(),
+                        page.name,
+                        page.path
+                    ),
+                )
+                .into());
+            }
+            Ok(Scenario::page(&page.path, &page.name))
+        })
+        .collect()
+}
+
+#[derive(Debug, Default)]
+struct Samples {
+    times: Vec<f64>,
+    /// Times to the first body chunk, only kept when `times` run to the last byte.
+    first_bytes: Vec<f64>,
+    sizes: Vec<usize>,
+    errors: usize,
+    requests: usize,
+    /// The redirects of the first redirected request.
+    chain: Vec<redirects::Hop>,
+    /// Time spent on redirects, per redirected request.
+    redirect_times: Vec<f64>,
+    /// Every timed request, for finding outliers.
+    measured: Vec<outliers::Sample>,
+}
+
+impl Samples {
+    fn extend(&mut self, other: Samples) {
+        self.times.extend(other.times);
+        self.first_bytes.extend(other.first_bytes);
+        if self.chain.is_empty() {
+            self.chain = other.chain;
+        }
+        self.redirect_times.extend(other.redirect_times);
+        let offset = self.requests;
+        self.measured
+            .extend(other.measured.into_iter().map(|sample| outliers::Sample {
+                index: sample.index + offset,
+                ..sample
+            }));
+        self.sizes.extend(other.sizes);
+        self.errors += other.errors;
+        self.requests += other.requests;
+    }
+
+    /// Needs at least one successful request.
+    fn metrics(&self) -> PerformanceMetrics {
+        let mut sorted_times = self.times.clone();
+        sorted_times.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let avg_size = self.sizes.iter().sum::<usize>() / self.sizes.len();
+        let success_rate = ((self.requests - self.errors) as f64 / self.requests as f64) * 100.0;
+
+        PerformanceMetrics {
+            min: sorted_times[0],
+            max: sorted_times[sorted_times.len() - 1],
+            avg: stats::mean(&self.times).unwrap_or_default(),
+            stddev: stats::stddev(&self.times),
+            p50: stats::percentile(&sorted_times, 0.50),
+            p90: stats::percentile(&sorted_times, 0.90),
+            p95: stats::percentile(&sorted_times, 0.95),
+            p99: stats::percentile(&sorted_times, 0.99),
+            p999: stats::percentile(&sorted_times, 0.999),
+            avg_size,
+            errors: self.errors,
+            success_rate,
+            bytes_per_sec: None,
+            ttfb: stats::Latency::from_times(&self.first_bytes),
+            redirects: stats::mean(&self.redirect_times).map(|added| redirects::Redirects {
+                chain: self.chain.clone(),
+                redirected: self.redirect_times.len(),
+                added_ms: units::Millis(added),
+            }),
+            ci: None,
+            delta_ci: None,
+            distribution: None,
+            apdex: None,
+            outliers: 0,
+            samples: self.times.len(),
+            samples_needed: None,
+            noise: noise::Noise {
+                cv_pct: noise::cv_pct(&self.times),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// One target and scenario, combined over every repetition.
+#[derive(Debug, Default)]
+struct Measured {
+    samples: Samples,
+    retries: usize,
+    noisy: bool,
+}
+
+impl Measured {
+    fn add(&mut self, samples: Samples, noise: noise::Noise) {
+        self.samples.extend(samples);
+        self.retries += noise.retries;
+        self.noisy |= noise.noisy;
+    }
+
+    fn metrics(&self, sample_size: &sample_size::SampleSizeArgs) -> PerformanceMetrics {
+        let metrics = self.samples.metrics();
+        PerformanceMetrics {
+            samples_needed: metrics.noise.cv_pct.map(|cv| sample_size.needed(cv)),
+            noise: noise::Noise {
+                retries: self.retries,
+                noisy: self.noisy,
+                ..metrics.noise
+            },
+            ..metrics
+        }
+    }
 }
 
-#[derive(Tabled)]
-struct ComparisonRow {
-    #[tabled(rename = "Scenario")]
-    scenario: String,
-    #[tabled(rename = "rari (ms)")]
-    rari_ms: String,
-    #[tabled(rename = "Next.js (ms)")]
-    nextjs_ms: String,
-    #[tabled(rename = "Difference")]
-    difference: String,
-    #[tabled(rename = "Winner")]
-    winner: String,
+/// A `--script` scenario being measured against one target.
+struct Scripted<'a> {
+    script: &'a Mutex<script::Script>,
+    scenario: usize,
+    validates: bool,
+    target: &'a str,
+    base_url: String,
 }
 
-async fn check_server(name: &str, port: u16) -> Result<()> {
-    let url = format!("http://localhost:{}", port);
-    reqwest::get(&url)
+/// The next request: a GET of `url`, or whatever the script asks for.
+async fn next_request(
+    client: &reqwest::Client,
+    url: &str,
+    scripted: Option<&Scripted<'_>>,
+    index: usize,
+) -> Result<reqwest::RequestBuilder> {
+    let Some(scripted) = scripted else {
+        return Ok(client.get(url));
+    };
+    let request = scripted
+        .script
+        .lock()
         .await
-        .context(format!("{} server is not responding at {}", name, url))?;
-    println!("{} {} server is responding", "✅".green(), name);
-    Ok(())
+        .request(
+            scripted.scenario,
+            scripted.target,
+            &scripted.base_url,
+            index,
+        )
+        .await?;
+    let method = match &request.method {
+        Some(method) => reqwest::Method::from_bytes(method.to_uppercase().as_bytes())
+            .with_context(|| format!("Invalid method {:?} from the script", method))?,
+        None => reqwest::Method::GET,
+    };
+    let mut builder = client.request(method, format!("{}{}", scripted.base_url, request.path));
+    for (name, value) in &request.headers {
+        builder = builder.header(name, value);
+    }
+    if let Some(body) = request.body {
+        builder = builder.body(body);
+    }
+    Ok(builder)
 }
 
-async fn measure_request(url: &str, warmup: usize, requests: usize) -> Result<PerformanceMetrics> {
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(10))
+/// A client that leaves redirects to [`redirects::send`].
+fn client(to_last_byte: bool) -> reqwest::Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(Duration::from_secs(if to_last_byte { 60 } else { 10 }))
         .default_headers({
             let mut headers = reqwest::header::HeaderMap::new();
             headers.insert("accept-encoding", "zstd, br, gzip".parse().unwrap());
             headers
         })
-        .build()?;
+        .build()
+}
+
+/// Times each request to its response headers, or to the last byte of the
+/// body with `to_last_byte`, through any redirects `policy` follows.
+async fn sample_requests(
+    url: &str,
+    warmup: usize,
+    requests: usize,
+    to_last_byte: bool,
+    policy: redirects::RedirectPolicy,
+    scripted: Option<&Scripted<'_>>,
+) -> Result<Samples> {
+    let client = client(to_last_byte)?;
 
-    println!("  Testing {}...", url);
+    match scripted {
+        Some(_) => println!("  Testing {} with paths from the script...", url),
+        None => println!("  Testing {}...", url),
+    }
 
-    for _ in 0..warmup {
-        let _ = client.get(url).send().await;
+    let bar = progress::requests("Warmup", warmup as u64);
+    for i in 0..warmup {
+        let request = next_request(&client, url, scripted, i).await?;
+        let start = Instant::now();
+        let Some(sent) =
+            interrupt::until_cancelled(redirects::send(&client, request, policy)).await
+        else {
+            break;
+        };
+        bar.inc(1);
+        tracing::trace!(
+            "warmup {:>4} {} in {}",
+            i + 1,
+            sent.as_ref().map_or("error".to_string(), |sent| sent
+                .response
+                .status()
+                .to_string()),
+            units::duration_ms(start.elapsed().as_secs_f64() * 1000.0)
+        );
     }
 
     let mut times = Vec::new();
+    let mut first_bytes = Vec::new();
     let mut sizes = Vec::new();
     let mut errors = 0;
+    let mut chain = Vec::new();
+    let mut redirect_times = Vec::new();
+    let mut measured = Vec::new();
 
-    for _ in 0..requests {
+    bar.finish_and_clear();
+    let bar = progress::requests("Measured", requests as u64);
+    for i in 0..requests {
+        let request = next_request(&client, url, scripted, warmup + i).await?;
+        let sent_at = SystemTime::now();
         let start = Instant::now();
 
-        match client.get(url).send().await {
-            Ok(response) if response.status().is_success() => {
-                let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+        // An interrupt keeps the samples so far; the run saves them as partial.
+        let Some(sent) =
+            interrupt::until_cancelled(redirects::send(&client, request, policy)).await
+        else {
+            break;
+        };
+        bar.inc(1);
+        match sent {
+            Ok(redirects::Sent {
+                response,
+                hops,
+                added,
+            }) if policy.accepts(response.status()) => {
+                let mut elapsed = start.elapsed().as_secs_f64() * 1000.0;
+                let status = response.status();
+                let headers = scripted.filter(|scripted| scripted.validates).map(|_| {
+                    response
+                        .headers()
+                        .iter()
+                        .filter_map(|(name, value)| {
+                            Some((name.to_string(), value.to_str().ok()?.to_string()))
+                        })
+                        .collect::<BTreeMap<_, _>>()
+                });
 
-                match response.text().await {
-                    Ok(text) => {
+                let mut first_byte = None;
+                let body = if to_last_byte {
+                    read_streamed(response, start)
+                        .await
+                        .map(|(size, first_chunk)| {
+                            first_byte = first_chunk;
+                            (size, String::new())
+                        })
+                } else {
+                    response.text().await.map(|text| (text.len(), text))
+                };
+                if to_last_byte {
+                    elapsed = start.elapsed().as_secs_f64() * 1000.0;
+                }
+                match body {
+                    Ok((size, text)) => {
+                        if let (Some(scripted), Some(headers)) = (scripted, headers)
+                            && let Some(invalid) = scripted
+                                .script
+                                .lock()
+                                .await
+                                .validate(
+                                    scripted.scenario,
+                                    scripted.target,
+                                    status.as_u16(),
+                                    headers,
+                                    &text,
+                                )
+                                .await?
+                        {
+                            tracing::debug!("request {:>4} invalid: {}", i + 1, invalid);
+                            errors += 1;
+                            continue;
+                        }
+                        tracing::debug!(
+                            "request {:>4} {} in {} ({})",
+                            i + 1,
+                            status,
+                            units::duration_ms(elapsed),
+                            units::bytes(size as f64)
+                        );
                         times.push(elapsed);
-                        sizes.push(text.len());
+                        measured.push(outliers::Sample {
+                            index: i + 1,
+                            timestamp: time_utils::format_timestamp(sent_at),
+                            status: status.as_u16(),
+                            size: units::Bytes(size as u64),
+                            ms: units::Millis(elapsed),
+                        });
+                        first_bytes.extend(first_byte);
+                        sizes.push(size);
+                        if !hops.is_empty() {
+                            if chain.is_empty() {
+                                chain = hops;
+                            }
+                            redirect_times.push(added.0);
+                        }
+                    }
+                    Err(e) => {
+                        tracing::debug!("request {:>4} body error: {}", i + 1, e);
+                        errors += 1
                     }
-                    Err(_) => errors += 1,
                 }
             }
-            _ => errors += 1,
+            sent => {
+                let outcome = match sent {
+                    Ok(sent) => sent.response.status().to_string(),
+                    Err(e) => e.to_string(),
+                };
+                tracing::debug!("request {:>4} failed: {}", i + 1, outcome);
+                errors += 1
+            }
         }
     }
 
+    bar.finish_and_clear();
+
     if times.is_empty() {
-        anyhow::bail!("No successful requests");
+        return Err(errors::Error::new(
+            errors::ErrorKind::RequestsFailed,
+            "No successful requests",
+        )
+        .into());
     }
 
-    let mut sorted_times = times.clone();
-    sorted_times.sort_by(|a, b| a.partial_cmp(b).unwrap());
-
-    let sum: f64 = times.iter().sum();
-    let avg = sum / times.len() as f64;
-    let avg_size = sizes.iter().sum::<usize>() / sizes.len();
-    let success_rate = ((requests - errors) as f64 / requests as f64) * 100.0;
-
-    Ok(PerformanceMetrics {
-        min: sorted_times[0],
-        max: sorted_times[sorted_times.len() - 1],
-        avg,
-        p50: percentile(&sorted_times, 0.50),
-        p95: percentile(&sorted_times, 0.95),
-        p99: percentile(&sorted_times, 0.99),
-        avg_size,
+    Ok(Samples {
+        times,
+        first_bytes,
+        sizes,
         errors,
-        success_rate,
+        requests,
+        chain,
+        redirect_times,
+        measured,
     })
 }
 
-fn percentile(sorted_data: &[f64], p: f64) -> f64 {
-    let index = (p * (sorted_data.len() - 1) as f64) as usize;
-    sorted_data[index]
+/// Reads the body chunk by chunk, returning its size and the milliseconds
+/// from `start` to its first chunk.
+async fn read_streamed(
+    mut response: reqwest::Response,
+    start: Instant,
+) -> reqwest::Result<(usize, Option<f64>)> {
+    let mut size = 0;
+    let mut first_chunk = None;
+    while let Some(chunk) = response.chunk().await? {
+        first_chunk.get_or_insert_with(|| start.elapsed().as_secs_f64() * 1000.0);
+        size += chunk.len();
+    }
+    Ok((size, first_chunk))
 }
 
-async fn benchmark_framework(
-    name: &str,
-    port: u16,
-    scenarios: &[Scenario],
-    warmup: usize,
-    requests: usize,
-) -> Result<HashMap<String, PerformanceMetrics>> {
-    println!(
-        "\n{} Benchmarking {} (port {})",
-        "🚀".bold(),
-        name.bold(),
-        port
-    );
+/// Measures the harness against a local server that answers instantly, with
+/// the same client, timing, and request counts as the targets get.
+async fn calibrate(args: &Args) -> Result<calibration::Overhead> {
+    println!("\n{}", "🧭 Calibrating against a no-op server".bold());
+    let server = calibration::NoopServer::start().await?;
+    let samples = sample_requests(
+        &server.url,
+        args.warmup,
+        args.requests,
+        false,
+        redirects::RedirectPolicy::Follow,
+        None,
+    )
+    .await?;
+    let metrics = samples.metrics();
+    let overhead = calibration::Overhead {
+        round_trip: stats::Latency::from_times(&samples.times)
+            .context("No calibration requests succeeded")?,
+        client_setup: calibration::per_call(20, || client(false)),
+        timer_resolution: calibration::timer_resolution(),
+        serialization: calibration::per_call(100, || serde_json::to_vec(&metrics)),
+    };
+    overhead.print();
+    Ok(overhead)
+}
 
-    let mut results = HashMap::new();
+/// Measures one scenario of one target, re-running it while it is noisy.
+/// The outer error aborts the run (a failing hook); the inner one is a failed
+/// scenario, which is only reported.
+#[tracing::instrument(name = "scenario", skip_all, fields(target = %target.name, scenario = %scenario.name))]
+async fn benchmark_scenario(
+    target: &targets::Target,
+    scenario: &Scenario,
+    path: &str,
+    args: &Args,
+    scripted: Option<&Scripted<'_>>,
+) -> Result<Result<(Samples, noise::Noise)>> {
+    let url = format!("{}{}", target.base_url(), path);
+    println!("\n📊 {} · {}", target.label().bold(), scenario.name);
 
-    for scenario in scenarios {
-        let url = format!("http://localhost:{}{}", port, scenario.path);
-        println!("\n📊 {}", scenario.name);
+    let (mut sampled, noise) = args
+        .noise
+        .measure(
+            &format!("{} {}", target.label(), scenario.name),
+            || async {
+                args.hooks.before_load(target).await?;
+                Ok(sample_requests(
+                    &url,
+                    args.warmup,
+                    args.requests,
+                    scenario.to_last_byte(),
+                    scenario.redirects.unwrap_or(args.redirects.redirects),
+                    scripted,
+                )
+                .await)
+            },
+            |sampled| {
+                sampled
+                    .as_ref()
+                    .ok()
+                    .and_then(|samples| noise::cv_pct(&samples.times))
+            },
+        )
+        .await?;
+
+    if let Ok(samples) = &mut sampled {
+        extend_samples(samples, &url, scenario, args, scripted).await;
+    }
 
-        match measure_request(&url, warmup, requests).await {
-            Ok(metrics) => {
+    match &sampled {
+        Ok(samples) => {
+            let metrics = samples.metrics();
+            println!(
+                "  {} Avg: {}, P95: {}, Size: {}",
+                "✅".green(),
+                units::duration_ms(metrics.avg),
+                units::duration_ms(metrics.p95),
+                units::bytes(metrics.avg_size as f64)
+            );
+            if let Some(redirects) = &metrics.redirects {
                 println!(
-                    "  {} Avg: {:.2}ms, P95: {:.2}ms, Size: {}b",
-                    "✅".green(),
-                    metrics.avg,
-                    metrics.p95,
-                    metrics.avg_size
+                    "  {} {} (+{} avg)",
+                    "↪".yellow(),
+                    redirects.describe(),
+                    redirects.added_ms
                 );
-                results.insert(scenario.name.clone(), metrics);
             }
-            Err(e) => {
-                println!("  {} Failed: {}", "❌".red(), e);
+            let slow = outliers::find(&target.name, &scenario.name, &samples.measured);
+            if !slow.is_empty() {
+                println!(
+                    "  {} {} outlier(s): {}",
+                    "🔎".yellow(),
+                    slow.len(),
+                    outliers::describe(&slow)
+                );
+            }
+            if let Some(cv) = metrics.noise.cv_pct
+                && args.sample_size.needed(cv) > metrics.samples
+            {
+                println!(
+                    "{}",
+                    sample_size::warning(
+                        cv,
+                        metrics.samples,
+                        args.sample_size.needed(cv),
+                        args.sample_size.detect
+                    )
+                );
             }
         }
+        Err(e) => {
+            println!("  {} Failed: {}", "❌".red(), e);
+        }
     }
+    Ok(sampled.map(|samples| (samples, noise)))
+}
 
-    Ok(results)
+/// With `--extend-samples`, measures more requests until the samples are
+/// enough to detect a `--detect` difference or `--max-requests` is reached.
+/// A failing extension keeps what was measured before it.
+async fn extend_samples(
+    samples: &mut Samples,
+    url: &str,
+    scenario: &Scenario,
+    args: &Args,
+    scripted: Option<&Scripted<'_>>,
+) {
+    while !interrupt::cancelled() {
+        let more = args.sample_size.more(
+            noise::cv_pct(&samples.times),
+            samples.times.len(),
+            samples.requests,
+        );
+        if more == 0 {
+            return;
+        }
+        println!(
+            "  {} {} more request(s) to detect a {}% difference",
+            "➕".cyan(),
+            more,
+            args.sample_size.detect
+        );
+        match sample_requests(
+            url,
+            0,
+            more,
+            scenario.to_last_byte(),
+            scenario.redirects.unwrap_or(args.redirects.redirects),
+            scripted,
+        )
+        .await
+        {
+            Ok(extra) => samples.extend(extra),
+            Err(e) => {
+                tracing::warn!("Stopped extending the samples: {:#}", e);
+                return;
+            }
+        }
+    }
 }
 
 fn display_comparison(
     scenarios: &[Scenario],
-    rari_results: &HashMap<String, PerformanceMetrics>,
-    nextjs_results: &HashMap<String, PerformanceMetrics>,
+    targets: &[targets::Target],
+    results: &[HashMap<String, PerformanceMetrics>],
+    tie_pct: f64,
+    overhead: Option<&calibration::Overhead>,
 ) {
     println!("\n{}", "📈 Performance Comparison".bold());
 
-    let mut rows = Vec::new();
+    let mut header = vec!["Scenario".to_string()];
+    header.extend(targets.iter().map(|target| target.label().to_string()));
+    if targets.len() == 2 {
+        header.push("Difference".to_string());
+    } else {
+        header.extend(
+            targets
+                .iter()
+                .skip(1)
+                .map(|target| format!("vs {}", target.label())),
+        );
+    }
+    header.push("Winner".to_string());
+
+    // Each target's value, the first target's difference from each other
+    // target, and the winner.
+    let compared = |values: &[f64]| -> (Vec<String>, Option<usize>) {
+        let mut cells: Vec<String> = values
+            .iter()
+            .map(|value| units::duration_ms(*value))
+            .collect();
+        for other in &values[1..] {
+            let diff = tolerance::difference_pct(values[0], *other);
+            let outcome = tolerance::classify(values[0], *other, true, tie_pct);
+            cells.push(tolerance::paint(outcome, &format!("{:+.1}%", diff)).to_string());
+        }
+        let best = tolerance::best(values, true, tie_pct);
+        cells.push(match best {
+            Some(index) => targets[index].display(),
+            None => tolerance::TIE.to_string(),
+        });
+        (cells, best)
+    };
+
+    let mut builder = Builder::default();
+    builder.push_record(header.clone());
+    let mut percentile_header = header;
+    percentile_header.insert(1, "Percentile".to_string());
+    let mut percentile_builder = Builder::default();
+    percentile_builder.push_record(percentile_header);
+    let mut trading_places = Vec::new();
 
     for scenario in scenarios {
-        if let (Some(rari), Some(nextjs)) = (
-            rari_results.get(&scenario.name),
-            nextjs_results.get(&scenario.name),
-        ) {
-            let diff = ((rari.avg - nextjs.avg) / nextjs.avg) * 100.0;
-            let winner = if rari.avg < nextjs.avg {
-                "🦀 rari"
-            } else {
-                "🟢 Next.js"
+        let Some(measured) = results
+            .iter()
+            .map(|result| result.get(&scenario.name))
+            .collect::<Option<Vec<&PerformanceMetrics>>>()
+        else {
+            continue;
+        };
+
+        let averages: Vec<f64> = measured.iter().map(|metrics| metrics.avg).collect();
+        let mut row = vec![scenario.name.clone()];
+        row.extend(compared(&averages).0);
+        builder.push_record(row);
+
+        let mut winners = Vec::new();
+        for (index, label) in PERCENTILE_LABELS.into_iter().enumerate() {
+            let values: Vec<f64> = measured
+                .iter()
+                .map(|metrics| metrics.percentiles()[index])
+                .collect();
+            let (cells, best) = compared(&values);
+            let mut row = vec![scenario.name.clone(), label.to_string()];
+            row.extend(cells);
+            percentile_builder.push_record(row);
+            winners.extend(best);
+        }
+        winners.dedup();
+        if winners.len() > 1 {
+            trading_places.push(scenario.name.as_str());
+        }
+    }
+
+    let table = builder.build().to_string();
+    println!("\n{}", table);
+    println!("\n{}", "Latency percentiles:".bold());
+    println!("{}", percentile_builder.build());
+    if !trading_places.is_empty() {
+        println!(
+            "  {} The winner changes between p50 and p99.9 on {}",
+            "🔀".yellow(),
+            trading_places.join(", ")
+        );
+    }
+    println!(
+        "{}",
+        format!("Differences within ±{}% count as a tie", tie_pct).dimmed()
+    );
+    if let Some(overhead) = overhead {
+        println!(
+            "{}",
+            format!(
+                "Differences under {} are within the harness's own noise",
+                overhead.noise_floor()
+            )
+            .dimmed()
+        );
+    }
+}
+
+/// Each target's Apdex per scenario, one number for how many requests were
+/// fast enough for users.
+fn display_apdex(
+    scenarios: &[Scenario],
+    targets: &[targets::Target],
+    results: &[HashMap<String, PerformanceMetrics>],
+    args: &Args,
+) {
+    let mut header = vec!["Scenario".to_string()];
+    header.extend(targets.iter().map(|target| target.label().to_string()));
+    header.push("Winner".to_string());
+    let mut builder = Builder::default();
+    builder.push_record(header);
+    let mut any = false;
+    for scenario in scenarios {
+        let Some(scores) = results
+            .iter()
+            .map(|result| result.get(&scenario.name)?.apdex.as_ref())
+            .collect::<Option<Vec<&apdex::Apdex>>>()
+        else {
+            continue;
+        };
+        any = true;
+        let mut row = vec![scenario.name.clone()];
+        row.extend(
+            scores
+                .iter()
+                .map(|apdex| format!("{:.2} ({})", apdex.score, apdex.rating())),
+        );
+        let values: Vec<f64> = scores.iter().map(|apdex| apdex.score).collect();
+        row.push(match tolerance::best(&values, false, args.tie_threshold) {
+            Some(index) => targets[index].display(),
+            None => tolerance::TIE.to_string(),
+        });
+        builder.push_record(row);
+    }
+    if !any {
+        return;
+    }
+    println!("\n{}", "😊 Apdex".bold());
+    println!("{}", builder.build());
+    println!("{}", args.apdex.describe().dimmed());
+}
+
+/// p95 and p99 per target with their bootstrap intervals, and how sure the
+/// first target's difference from each other target is.
+fn display_tails(
+    scenarios: &[Scenario],
+    targets: &[targets::Target],
+    results: &[HashMap<String, PerformanceMetrics>],
+) {
+    let Some(subject) = targets.first() else {
+        return;
+    };
+    let with_interval = |value: f64, interval: Option<bootstrap::Interval>| match interval {
+        Some(interval) => format!(
+            "{} [{}–{}]",
+            units::duration_ms(value),
+            units::duration_ms(interval.low),
+            units::duration_ms(interval.high)
+        ),
+        None => units::duration_ms(value),
+    };
+    let delta = |subject: f64, other: f64, interval: Option<bootstrap::Interval>| {
+        let diff = tolerance::difference_pct(subject, other);
+        match interval {
+            Some(interval) => {
+                let text = format!(
+                    "{:+.1}% [{:+.1}, {:+.1}]",
+                    diff, interval.low, interval.high
+                );
+                if interval.contains(0.0) {
+                    text.dimmed().to_string()
+                } else if interval.high < 0.0 {
+                    text.green().to_string()
+                } else {
+                    text.red().to_string()
+                }
+            }
+            None => format!("{:+.1}%", diff),
+        }
+    };
+
+    let mut builder = Builder::default();
+    builder.push_record([
+        "Scenario".to_string(),
+        "Target".to_string(),
+        "P95 (95% CI)".to_string(),
+        "P99 (95% CI)".to_string(),
+        format!("{} Δ P95", subject.label()),
+        format!("{} Δ P99", subject.label()),
+    ]);
+    let mut any = false;
+    for scenario in scenarios {
+        let Some(first) = results
+            .first()
+            .and_then(|result| result.get(&scenario.name))
+        else {
+            continue;
+        };
+        for (index, (target, result)) in targets.iter().zip(results).enumerate() {
+            let Some(metrics) = result.get(&scenario.name) else {
+                continue;
             };
-            let diff_str = if diff > 0.0 {
-                format!("+{:.1}%", diff)
-            } else {
-                format!("{:.1}%", diff)
+            any = true;
+            let ci = metrics.ci.as_ref();
+            let delta_ci = metrics.delta_ci.as_ref();
+            let is_subject = index == 0;
+            builder.push_record([
+                scenario.name.clone(),
+                target.display(),
+                with_interval(metrics.p95, ci.map(|ci| ci.p95)),
+                with_interval(metrics.p99, ci.map(|ci| ci.p99)),
+                if is_subject {
+                    "—".to_string()
+                } else {
+                    delta(first.p95, metrics.p95, delta_ci.map(|ci| ci.p95))
+                },
+                if is_subject {
+                    "—".to_string()
+                } else {
+                    delta(first.p99, metrics.p99, delta_ci.map(|ci| ci.p99))
+                },
+            ]);
+        }
+    }
+    if !any {
+        return;
+    }
+    println!("\n{}", "📐 Tail Latency".bold());
+    println!("{}", builder.build());
+    println!(
+        "{}",
+        format!(
+            "95% bootstrap intervals over {} resamples; a difference whose interval spans 0 may be sampling noise",
+            bootstrap::RESAMPLES
+        )
+        .dimmed()
+    );
+}
+
+/// How the first target's latency distribution compares with each other
+/// target's, since equal means can hide very different tails.
+fn display_distributions(
+    scenarios: &[Scenario],
+    targets: &[targets::Target],
+    results: &[HashMap<String, PerformanceMetrics>],
+) {
+    let Some(subject) = targets.first() else {
+        return;
+    };
+    let mut builder = Builder::default();
+    builder.push_record([
+        "Scenario".to_string(),
+        "Target".to_string(),
+        "KS D".to_string(),
+        "KS p".to_string(),
+        format!("Cliff's δ ({})", subject.label()),
+        "Effect".to_string(),
+    ]);
+    let mut any = false;
+    for scenario in scenarios {
+        for (target, result) in targets.iter().zip(results).skip(1) {
+            let Some(compared) = result
+                .get(&scenario.name)
+                .and_then(|metrics| metrics.distribution.as_ref())
+            else {
+                continue;
+            };
+            any = true;
+            let p_value = format!("{:.3}", compared.ks_p_value);
+            builder.push_record([
+                scenario.name.clone(),
+                target.display(),
+                format!("{:.2}", compared.ks),
+                if compared.differs() {
+                    p_value
+                } else {
+                    p_value.dimmed().to_string()
+                },
+                effect(compared.cliffs_delta),
+                compared.magnitude.as_str().to_string(),
+            ]);
+        }
+    }
+    if !any {
+        return;
+    }
+    println!("\n{}", "📏 Distributions".bold());
+    println!("{}", builder.build());
+    println!(
+        "{}",
+        "KS p under 0.05: the distributions differ; a negative δ means the first target's requests are usually faster"
+            .dimmed()
+    );
+}
+
+/// A Cliff's delta, green when the first target is usually faster and red
+/// when it is usually slower, unless the effect is negligible.
+fn effect(cliffs_delta: f64) -> String {
+    let text = format!("{:+.2}", cliffs_delta);
+    match distribution::Magnitude::of(cliffs_delta) {
+        distribution::Magnitude::Negligible => text.dimmed().to_string(),
+        _ if cliffs_delta < 0.0 => text.green().to_string(),
+        _ => text.red().to_string(),
+    }
+}
+
+/// The redirects behind any scenario's times, per target, since a target
+/// that redirects (a trailing slash, a locale) is timed over more requests.
+fn display_redirects(
+    scenarios: &[Scenario],
+    targets: &[targets::Target],
+    results: &[HashMap<String, PerformanceMetrics>],
+) {
+    let mut builder = Builder::default();
+    builder.push_record(["Scenario", "Target", "Chain", "Redirected", "Added (avg)"]);
+    let mut any = false;
+    for scenario in scenarios {
+        for (target, result) in targets.iter().zip(results) {
+            let Some(redirects) = result
+                .get(&scenario.name)
+                .and_then(|metrics| metrics.redirects.as_ref())
+            else {
+                continue;
             };
+            any = true;
+            builder.push_record([
+                scenario.name.clone(),
+                target.display(),
+                redirects.describe(),
+                redirects.redirected.to_string(),
+                redirects.added_ms.to_string(),
+            ]);
+        }
+    }
+    if any {
+        println!("\n{}", "↪ Redirects".bold());
+        println!("{}", builder.build());
+        println!(
+            "{}",
+            "These times include the redirects; use --redirects none to time the redirect alone"
+                .dimmed()
+        );
+    }
+}
 
-            rows.push(ComparisonRow {
-                scenario: scenario.name.clone(),
-                rari_ms: format!("{:.2}", rari.avg),
-                nextjs_ms: format!("{:.2}", nextjs.avg),
-                difference: diff_str,
-                winner: winner.to_string(),
-            });
+/// The scenarios whose times varied too much for their sample count to
+/// detect a `--detect` difference, and what would.
+fn display_sample_sizes(
+    scenarios: &[Scenario],
+    targets: &[targets::Target],
+    results: &[HashMap<String, PerformanceMetrics>],
+    args: &Args,
+) {
+    let sample_size = &args.sample_size;
+    let mut builder = Builder::default();
+    builder.push_record(["Scenario", "Target", "CV", "Samples", "Needed"]);
+    let mut most = 0;
+    for scenario in scenarios {
+        for (target, result) in targets.iter().zip(results) {
+            let Some(metrics) = result.get(&scenario.name) else {
+                continue;
+            };
+            let (Some(cv), Some(needed)) = (metrics.noise.cv_pct, metrics.samples_needed) else {
+                continue;
+            };
+            if needed <= metrics.samples {
+                continue;
+            }
+            most = most.max(needed);
+            builder.push_record([
+                scenario.name.clone(),
+                target.display(),
+                format!("{:.1}%", cv),
+                metrics.samples.to_string(),
+                needed.to_string(),
+            ]);
         }
     }
+    println!("\n{}", "🎯 Sample Size".bold());
+    if most == 0 {
+        println!(
+            "  {} Enough samples everywhere to detect a {}",
+            "✅".green(),
+            sample_size.describe()
+        );
+        return;
+    }
+    // The samples of every repetition are combined.
+    let requests = most.div_ceil(args.repetitions as usize);
+    println!("{}", builder.build());
+    println!(
+        "{}",
+        if sample_size.extend_samples {
+            format!(
+                "Too few samples to detect a {}; raise --max-requests to {}",
+                sample_size.describe(),
+                requests
+            )
+        } else {
+            format!(
+                "Too few samples to detect a {}; use --requests {} or --extend-samples",
+                sample_size.describe(),
+                requests
+            )
+        }
+        .dimmed()
+    );
+}
 
-    let table = Table::new(rows).to_string();
-    println!("\n{}", table);
+/// TTFB, total time, and throughput of the `--large-payload` page per target.
+fn display_large_payload(
+    targets: &[targets::Target],
+    results: &[HashMap<String, PerformanceMetrics>],
+    tie_pct: f64,
+) {
+    let Some(measured) = results
+        .iter()
+        .map(|result| result.values().next())
+        .collect::<Option<Vec<&PerformanceMetrics>>>()
+    else {
+        return;
+    };
+    println!("\n{}", "🚚 Large Payload".bold());
+
+    let mut header = vec!["Metric".to_string()];
+    header.extend(targets.iter().map(|target| target.label().to_string()));
+    header.push("Winner".to_string());
+
+    type Row = (
+        &'static str,
+        fn(&PerformanceMetrics) -> f64,
+        units::Unit,
+        bool,
+    );
+    let rows: [Row; 5] = [
+        (
+            "TTFB (p50)",
+            |m| m.ttfb.as_ref().map_or(f64::NAN, |ttfb| ttfb.p50),
+            units::Unit::Milliseconds,
+            true,
+        ),
+        (
+            "Total time (avg)",
+            |m| m.avg,
+            units::Unit::Milliseconds,
+            true,
+        ),
+        (
+            "Total time (p95)",
+            |m| m.p95,
+            units::Unit::Milliseconds,
+            true,
+        ),
+        (
+            "Throughput",
+            |m| m.bytes_per_sec.unwrap_or(f64::NAN),
+            units::Unit::BytesPerSec,
+            false,
+        ),
+        ("Size", |m| m.avg_size as f64, units::Unit::Bytes, true),
+    ];
+
+    let mut builder = Builder::default();
+    builder.push_record(header);
+    for (label, value, unit, lower_is_better) in rows {
+        let values: Vec<f64> = measured.iter().map(|metrics| value(metrics)).collect();
+        let mut record = vec![label.to_string()];
+        record.extend(values.iter().map(|value| unit.format(*value)));
+        record.push(match tolerance::best(&values, lower_is_better, tie_pct) {
+            Some(index) => targets[index].display(),
+            None => tolerance::TIE.to_string(),
+        });
+        builder.push_record(record);
+    }
+    println!("\n{}", builder.build());
 }
 
 fn calculate_summary(
     scenarios: &[Scenario],
-    rari_results: &HashMap<String, PerformanceMetrics>,
-    nextjs_results: &HashMap<String, PerformanceMetrics>,
+    targets: &[targets::Target],
+    results: &[HashMap<String, PerformanceMetrics>],
+    tie_pct: f64,
 ) {
     let valid_scenarios: Vec<_> = scenarios
         .iter()
-        .filter(|s| rari_results.contains_key(&s.name) && nextjs_results.contains_key(&s.name))
+        .filter(|s| results.iter().all(|result| result.contains_key(&s.name)))
         .collect();
 
     if valid_scenarios.is_empty() {
@@ -254,121 +1462,997 @@ fn calculate_summary(
         return;
     }
 
-    let rari_avg: f64 = valid_scenarios
-        .iter()
-        .map(|s| rari_results[&s.name].avg)
-        .sum::<f64>()
-        / valid_scenarios.len() as f64;
-
-    let nextjs_avg: f64 = valid_scenarios
+    let averages: Vec<f64> = results
         .iter()
-        .map(|s| nextjs_results[&s.name].avg)
-        .sum::<f64>()
-        / valid_scenarios.len() as f64;
-
-    let improvement = ((nextjs_avg - rari_avg) / nextjs_avg) * 100.0;
+        .map(|result| {
+            let averages: Vec<f64> = valid_scenarios
+                .iter()
+                .map(|s| result[&s.name].avg)
+                .collect();
+            across_scenarios(&averages)
+        })
+        .collect();
 
     println!("\n{}", "📊 Summary".bold());
-    println!("Average Response Time:");
-    println!("  🦀 rari:     {:.2}ms", rari_avg);
-    println!("  🟢 Next.js:  {:.2}ms", nextjs_avg);
+    println!("Average Response Time (geometric mean over scenarios):");
+    for (heading, avg) in targets::headings(targets).iter().zip(&averages) {
+        println!("  {}{}", heading, units::duration_ms(*avg));
+    }
 
-    if improvement > 0.0 {
-        println!("  {} rari is {:.1}% faster", "📈".green(), improvement);
-    } else {
-        println!("  {} rari is {:.1}% slower", "📉".red(), improvement.abs());
+    let subject = &targets[0];
+    for (other, other_avg) in targets.iter().zip(&averages).skip(1) {
+        let diff = tolerance::difference_pct(averages[0], *other_avg);
+        match tolerance::classify(averages[0], *other_avg, true, tie_pct) {
+            tolerance::Outcome::SubjectBetter => println!(
+                "  {} {} is {:.1}% faster than {}",
+                "📈".green(),
+                subject.label(),
+                diff.abs(),
+                other.label()
+            ),
+            tolerance::Outcome::OtherBetter => println!(
+                "  {} {} is {:.1}% slower than {}",
+                "📉".red(),
+                subject.label(),
+                diff,
+                other.label()
+            ),
+            tolerance::Outcome::Tie => {
+                println!("{}", tolerance::tie_line(diff, tie_pct, other.label()))
+            }
+        }
+    }
+
+    for (other, result) in targets.iter().zip(results).skip(1) {
+        let compared: Vec<(&str, &distribution::Distribution)> = valid_scenarios
+            .iter()
+            .filter_map(|s| Some((s.name.as_str(), result[&s.name].distribution.as_ref()?)))
+            .collect();
+        let Some((scenario, largest)) = compared
+            .iter()
+            .max_by(|a, b| a.1.cliffs_delta.abs().total_cmp(&b.1.cliffs_delta.abs()))
+        else {
+            continue;
+        };
+        println!(
+            "  {} Distributions vs {} differ in {} of {} scenario(s); largest effect {} ({}, {})",
+            "📏".dimmed(),
+            other.label(),
+            compared.iter().filter(|(_, d)| d.differs()).count(),
+            compared.len(),
+            largest.magnitude.as_str(),
+            effect(largest.cliffs_delta),
+            scenario
+        );
     }
 }
 
-async fn save_results(results: &BenchmarkResults, results_dir: &PathBuf) -> Result<()> {
-    fs::create_dir_all(results_dir).await?;
+/// Scenarios differ in scale, so a 10% gain on a fast page counts as much as
+/// one on a slow page. Falls back to the arithmetic mean for a zero value.
+fn across_scenarios(values: &[f64]) -> f64 {
+    stats::geometric_mean(values)
+        .or_else(|| stats::mean(values))
+        .unwrap_or_default()
+}
+
+fn scenario_average(
+    results: &HashMap<String, PerformanceMetrics>,
+    metric: impl Fn(&PerformanceMetrics) -> f64,
+) -> f64 {
+    across_scenarios(&results.values().map(metric).collect::<Vec<_>>())
+}
+
+async fn save_results(
+    results: &BenchmarkResults,
+    targets: &[targets::Target],
+    results_dir: &Path,
+    file_name: &str,
+) -> Result<PathBuf> {
+    let filename = results::save(results, results_dir, file_name).await?;
+    fs::copy(&filename, results_dir.join("latest.json")).await?;
+    save_charts(results, targets, &filename)?;
+
+    Ok(filename)
+}
+
+fn save_charts(
+    results: &BenchmarkResults,
+    targets: &[targets::Target],
+    json_path: &Path,
+) -> Result<()> {
+    let measured: Vec<(&targets::Target, &HashMap<String, PerformanceMetrics>)> = targets
+        .iter()
+        .filter_map(|target| Some((target, results.results.get(&target.name)?)))
+        .collect();
+
+    let mut scenario_names: Vec<&String> = measured
+        .first()
+        .map(|(_, scenarios)| scenarios.keys().collect())
+        .unwrap_or_default();
+    scenario_names.sort();
+
+    let mut series_names = Vec::new();
+    let mut series_points = Vec::new();
+    for (target, framework_results) in &measured {
+        for scenario in &scenario_names {
+            if let Some(metrics) = framework_results.get(*scenario) {
+                series_names.push(format!("{} · {}", target.label(), scenario));
+                series_points.push(vec![
+                    (50.0, metrics.p50),
+                    (90.0, metrics.p90),
+                    (95.0, metrics.p95),
+                    (99.0, metrics.p99),
+                ]);
+            }
+        }
+    }
 
-    let now = SystemTime::now();
-    let date = time_utils::format_date(now);
-    let filename = results_dir.join(format!("performance-{}.json", date));
+    let series: Vec<charts::LatencySeries> = series_names
+        .iter()
+        .zip(series_points)
+        .map(|(name, points)| charts::LatencySeries { name, points })
+        .collect();
 
-    let json = format!("{}\n", serde_json::to_string_pretty(results)?);
-    fs::write(&filename, &json).await?;
+    let latency_path = charts::chart_path(json_path, "latency");
+    charts::latency_distribution_chart(&latency_path, "Response Time Distribution", &series)?;
 
-    let latest = results_dir.join("latest.json");
-    fs::write(&latest, &json).await?;
+    let average_path = charts::chart_path(json_path, "average");
+    charts::bar_chart(
+        &average_path,
+        "Average Response Time",
+        "Response time (ms)",
+        &measured
+            .iter()
+            .map(|(target, results)| (target.label(), scenario_average(results, |m| m.avg)))
+            .collect::<Vec<_>>(),
+    )?;
 
     println!(
-        "\n{} Results saved to {}",
-        "💾".dimmed(),
-        filename.display()
+        "{} Charts saved to {} and {}",
+        "📊".dimmed(),
+        latency_path.display(),
+        average_path.display()
     );
 
     Ok(())
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let args = Args::parse();
+/// Runs the performance benchmark as its binary does, from `Args::parse_from` or
+/// [`config::parse`], and returns the saved results. It exits neither on a
+/// failed gate nor on Ctrl-C, and failed scenarios, or a failure that ends the
+/// run early, are saved with the results; see [`results::Run::exit_code`].
+pub async fn run_performance(args: &Args) -> Result<results::Run<BenchmarkResults>> {
+    output::init(&args.output)?;
+    run(args)
+        .instrument(tracing::info_span!("benchmark", kind = "performance"))
+        .await
+}
 
-    println!(
-        "{}",
-        "🏁 rari vs Next.js Performance Benchmark".cyan().bold()
+/// Checks what a run with `args` needs, from the config to the servers and
+/// the results directory, and prints what it would do, without starting
+/// anything or sending requests. Fails with the kind of the first failed check.
+pub async fn dry_run(args: &Args) -> Result<()> {
+    output::init(&args.output)?;
+    let mut checks = dry_run::Checks::new("performance benchmark");
+    checks.record(
+        "Regression gate",
+        gate::load_rules(&args.fail_if, args.thresholds.as_deref())
+            .await
+            .map(|rules| format!("{} rule(s)", rules.len())),
+    );
+    checks.record(
+        "Name template",
+        archive::validate_template(&args.archive.name_template)
+            .map(|()| args.archive.name_template.clone()),
+    );
+    checks.record(
+        "Apdex",
+        args.apdex.validate().map(|()| args.apdex.describe()),
+    );
+    let targets = match targets::load(&args.targets) {
+        Ok(targets) => targets,
+        Err(e) => {
+            checks.record("Targets", Err(e));
+            return checks.finish();
+        }
+    };
+    checks.record(
+        "Targets",
+        Ok(targets
+            .iter()
+            .map(targets::Target::display)
+            .collect::<Vec<_>>()
+            .join(", ")),
+    );
+    if args.client_cpus.is_some() {
+        checks.record(
+            "Client CPUs",
+            affinity::client_cpus(args.client_cpus.as_deref(), &targets)
+                .map(|cpus| format!("{:?}", cpus)),
+        );
+    }
+
+    let mut scenarios = vec!["Homepage (All Components)".to_string()];
+    if let Some(path) = &args.scenarios {
+        let pages = read_scenarios(path);
+        if let Ok(pages) = &pages {
+            scenarios.extend(pages.iter().map(|page| page.name.clone()));
+        }
+        checks.record(
+            "Scenarios",
+            pages.map(|pages| format!("{} page(s) from {}", pages.len(), path.display())),
+        );
+    }
+    if let Some(path) = &args.script {
+        let loaded = script::Script::start(path).await.map(|script| {
+            script
+                .scenarios
+                .iter()
+                .map(|scenario| scenario.name.clone())
+                .collect::<Vec<_>>()
+        });
+        if let Ok(names) = &loaded {
+            scenarios.extend(names.iter().cloned());
+        }
+        checks.record(
+            "Script",
+            loaded.map(|names| format!("{} scenario(s) from {}", names.len(), path.display())),
+        );
+    }
+    if args.static_assets {
+        scenarios.extend(assets::AssetKind::ALL.map(|kind| kind.scenario().to_string()));
+    }
+    if args.large_payload {
+        scenarios.push("Large payload (/large)".to_string());
+    }
+
+    #[cfg(feature = "docker")]
+    let compose = args.compose.compose.as_deref();
+    #[cfg(not(feature = "docker"))]
+    let compose = None;
+    let servers = dry_run::servers(
+        &mut checks,
+        &targets,
+        &args.servers,
+        compose,
+        &args.ssh,
+        &args.probe,
+    )
+    .await;
+    checks.record("Results", dry_run::output_dir(&args.results_dir).await);
+
+    let per_scenario = (args.warmup + args.requests) as u64 * args.repetitions;
+    let extras: Vec<&str> = [
+        (args.conditional, "conditional requests"),
+        (args.compression, "compression"),
+        (args.mutation, "form submission"),
+    ]
+    .into_iter()
+    .filter_map(|(enabled, name)| enabled.then_some(name))
+    .collect();
+    checks.plan(&[
+        ("Targets", targets::title(&targets)),
+        ("Servers", servers),
+        ("Redirects", args.redirects.redirects.to_string()),
+        (
+            "Sample size",
+            if args.sample_size.extend_samples {
+                format!(
+                    "extended to detect a {}, up to {} requests per scenario",
+                    args.sample_size.describe(),
+                    args.sample_size.max_requests
+                )
+            } else {
+                format!("checked against a {}", args.sample_size.describe())
+            },
+        ),
+        (
+            "Calibration",
+            if args.calibration.calibrate {
+                format!(
+                    "{} warmup + {} measured requests to a local no-op server first",
+                    args.warmup, args.requests
+                )
+            } else {
+                "none".to_string()
+            },
+        ),
+        ("Scenarios", scenarios.join(", ")),
+        (
+            "Requests",
+            format!(
+                "{} warmup + {} measured per scenario, {} repetition(s): {} in total",
+                args.warmup,
+                args.requests,
+                args.repetitions,
+                per_scenario * (scenarios.len() * targets.len()) as u64
+            ),
+        ),
+        (
+            "Afterwards",
+            if extras.is_empty() {
+                "nothing else".to_string()
+            } else {
+                extras.join(", ")
+            },
+        ),
+        ("Results", args.results_dir.display().to_string()),
+    ]);
+    checks.finish()
+}
+
+async fn run(args: &Args) -> Result<results::Run<BenchmarkResults>> {
+    let rules = gate::load_rules(&args.fail_if, args.thresholds.as_deref()).await?;
+    archive::validate_template(&args.archive.name_template)?;
+    args.apdex.validate()?;
+    let mut targets = targets::load(&args.targets)?;
+    let mut metadata = run_metadata::collect(Path::new("."), args.label.clone()).await;
+    metadata.profile = args.preset.profile.map(|preset| preset.name().to_string());
+    metadata.mode = Some(args.servers.mode.name().to_string());
+    metadata.fs_cache = args
+        .servers
+        .fs_cache
+        .map(|policy| policy.name().to_string());
+    metadata.target_env = targets::env(&targets);
+    metadata.node_version = versions::node().await;
+    metadata.target_versions = versions::packages(&args.servers.apps_dir, &targets).await;
+    metadata.framework_versions = versions::frameworks(&targets, &metadata.target_versions);
+    metadata.target_scheduling = targets::scheduling(&targets);
+    metadata.client_cpus = args.client_cpus.clone();
+    metadata.target_urls = targets::remote_urls(&targets);
+    metadata.seed = args.seed.describe();
+    metadata.ssh_host = args.ssh.ssh.clone();
+    metadata.network = args.network.profile();
+    let seed = args.random.resolve();
+    metadata.random_seed = Some(seed);
+    let title = format!("🏁 {} Performance Benchmark", targets::title(&targets));
+
+    println!("{}", title.cyan().bold());
+    println!("{}", metadata.describe().dimmed());
+    metadata.host_load = preflight::check(&args.preflight).await?;
+    stream::emit(
+        "run_started",
+        json!({ "benchmark": "performance", "metadata": &metadata, "config": { "requests": args.requests, "warmup": args.warmup, "repetitions": args.repetitions } }),
     );
     println!(
         "{}",
         "This benchmark compares server-side rendering performance\n".dimmed()
     );
 
-    let scenarios = vec![Scenario {
-        path: "/".to_string(),
-        name: "Homepage (All Components)".to_string(),
-    }];
-
-    if let Err(e) = check_server("rari", args.rari_port).await {
-        eprintln!("{} {}", "❌".red(), e);
-        eprintln!("Please start the rari server with: cd apps/rari && pnpm dev");
-        std::process::exit(1);
+    let mut scenarios = vec![Scenario::page("/", "Homepage (All Components)")];
+    if let Some(path) = &args.scenarios {
+        scenarios.extend(read_scenarios(path)?);
+    }
+    let script = match &args.script {
+        Some(path) => Some(Mutex::new(script::Script::start(path).await?)),
+        None => None,
+    };
+    if let Some(script) = &script {
+        scenarios.extend(script.lock().await.scenarios.iter().enumerate().map(
+            |(index, scripted)| Scenario {
+                script: Some(index),
+                redirects: scripted.redirects,
+                ..Scenario::page("", &scripted.name)
+            },
+        ));
     }
 
-    if let Err(e) = check_server("Next.js", args.nextjs_port).await {
-        eprintln!("{} {}", "❌".red(), e);
-        eprintln!("Please start the Next.js server with: cd apps/nextjs && pnpm dev");
-        std::process::exit(1);
+    let client_cpus = affinity::client_cpus(args.client_cpus.as_deref(), &targets)?;
+
+    #[cfg(feature = "docker")]
+    let compose_env = compose::up(&args.compose, &args.probe, &mut targets).await?;
+    #[cfg(feature = "docker")]
+    let composed = compose_env.is_some();
+    #[cfg(not(feature = "docker"))]
+    let composed = false;
+    let mut ssh_env = None;
+    let mut managed_servers = if composed {
+        None
+    } else if args.ssh.ssh.is_some() {
+        ssh_env = Some(ssh::up(&args.ssh, args.servers.mode, &args.probe, &mut targets).await?);
+        None
+    } else if args.servers.manage_servers {
+        Some(servers::start(&args.servers, &args.probe, &args.hooks, &mut targets).await?)
+    } else {
+        servers::require_running(&targets, &args.probe, args.servers.mode).await?;
+        None
+    };
+
+    // Pinned only now so the servers started above don't inherit the mask.
+    if !client_cpus.is_empty() {
+        affinity::pin_current_process(&client_cpus)?;
     }
 
+    let footprint = match &managed_servers {
+        Some(servers) => monitor::footprints(&targets, &servers.pids()).await,
+        None => BTreeMap::new(),
+    };
+    args.seed.run(&targets).await?;
+    let shaper = network::shape(&args.network, false, seed, &mut targets).await?;
+
+    let overhead = if args.calibration.calibrate {
+        Some(calibrate(args).await?)
+    } else {
+        None
+    };
+
     println!("\n{}", "Starting benchmark in 3 seconds...".dimmed());
     tokio::time::sleep(Duration::from_secs(3)).await;
+    let steal = quality::StealMeter::start();
 
-    let rari_results = benchmark_framework(
-        "rari",
-        args.rari_port,
-        &scenarios,
-        args.warmup,
-        args.requests,
-    )
-    .await?;
-    let nextjs_results = benchmark_framework(
-        "Next.js",
-        args.nextjs_port,
-        &scenarios,
-        args.warmup,
-        args.requests,
-    )
-    .await?;
+    #[cfg(feature = "docker")]
+    let stats_sampler = compose_env.as_ref().map(compose::ComposeEnv::sample_stats);
+    #[cfg(not(feature = "docker"))]
+    let stats_sampler: Option<containers::StatsSampler> = None;
+    let server_sampler = ssh_env.as_ref().map(ssh::SshEnv::sample_stats);
+    let log_watcher = managed_servers
+        .as_ref()
+        .map(|servers| server_logs::LogWatcher::start(servers.logs()));
+
+    let mut asset_paths = Vec::new();
+    if args.static_assets || args.conditional {
+        let client = reqwest::Client::new();
+        println!("\n{}", "📦 Static assets".bold());
+        for target in &targets {
+            let paths = assets::discover(&client, target).await;
+            let listed: Vec<&str> = paths.values().map(String::as_str).collect();
+            println!("  {}{}", target.display(), listed.join(", ").dimmed());
+            asset_paths.push(paths);
+        }
+    }
+    if args.static_assets {
+        scenarios.extend(assets::AssetKind::ALL.map(|kind| Scenario {
+            path: String::new(),
+            name: kind.scenario().to_string(),
+            asset: Some(kind),
+            large: false,
+            script: None,
+            redirects: None,
+        }));
+    }
+    if args.large_payload {
+        scenarios.push(Scenario {
+            large: true,
+            ..Scenario::page("/large", "Large payload (/large)")
+        });
+    }
+    let path_for = |target_index: usize, scenario: &Scenario| -> Option<String> {
+        match scenario.asset {
+            Some(kind) => asset_paths[target_index].get(&kind).cloned(),
+            None => Some(scenario.path.clone()),
+        }
+    };
+
+    let mut shuffler = args.order.shuffler(seed);
+    let shuffle_seed = shuffler.is_some().then_some(seed);
+    let mut order: Vec<(usize, usize)> = (0..targets.len())
+        .flat_map(|target| (0..scenarios.len()).map(move |scenario| (target, scenario)))
+        .filter(|&(target, scenario)| path_for(target, &scenarios[scenario]).is_some())
+        .collect();
+    let mut measured: HashMap<(usize, usize), Measured> = HashMap::new();
+    let mut failures = Vec::new();
+    let run = interrupt::or_interrupted(async {
+        for repetition in 1..=args.repetitions {
+            if interrupt::cancelled() {
+                break;
+            }
+            if let Some(shuffler) = &mut shuffler {
+                shuffler.shuffle(&mut order);
+            }
+            if args.repetitions > 1 {
+                println!(
+                    "\n{}",
+                    format!("🔁 Repetition {}/{}", repetition, args.repetitions).bold()
+                );
+            }
+            for (index, &(target_index, scenario_index)) in order.iter().enumerate() {
+                if interrupt::cancelled() {
+                    break;
+                }
+                let target = &targets[target_index];
+                let scenario = &scenarios[scenario_index];
+                let path = path_for(target_index, scenario).unwrap_or_default();
+                let scripted = match (scenario.script, &script) {
+                    (Some(index), Some(script)) => Some(Scripted {
+                        script,
+                        scenario: index,
+                        validates: script.lock().await.scenarios[index].validates,
+                        target: &target.name,
+                        base_url: target.base_url(),
+                    }),
+                    _ => None,
+                };
+                let sampled =
+                    benchmark_scenario(target, scenario, &path, args, scripted.as_ref()).await?;
+                stream::emit(
+                    "scenario_finished",
+                    json!({
+                        "framework": &target.name,
+                        "scenario": scenario.name,
+                        "repetition": repetition,
+                        "completed": index + 1,
+                        "total": order.len(),
+                        "metrics": sampled.as_ref().ok().map(|(samples, _)| samples.metrics()),
+                        "error": sampled.as_ref().err().map(|e| e.to_string()),
+                    }),
+                );
+                match sampled {
+                    Ok((samples, noise)) => measured
+                        .entry((target_index, scenario_index))
+                        .or_default()
+                        .add(samples, noise),
+                    // A scenario stopped by an interrupt did not fail.
+                    Err(_) if interrupt::cancelled() => {}
+                    Err(e) => failures.push(
+                        errors::RunError::from_error(&e.context(scenario.name.clone()))
+                            .for_target(&target.name),
+                    ),
+                }
+            }
+        }
+        Ok::<_, anyhow::Error>(())
+    })
+    .await;
+    let mut partial = match run {
+        Some(Ok(())) if !interrupt::cancelled() => false,
+        Some(Err(e)) if !interrupt::cancelled() => {
+            tracing::error!("{:#}", e);
+            failures.push(errors::RunError::from_error(&e));
+            true
+        }
+        _ => {
+            interrupt::announce("Saving what was measured");
+            true
+        }
+    };
+    let results_where =
+        |include: fn(&Scenario) -> bool| -> Vec<HashMap<String, PerformanceMetrics>> {
+            (0..targets.len())
+                .map(|target_index| {
+                    scenarios
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, scenario)| include(scenario))
+                        .filter_map(|(scenario_index, scenario)| {
+                            let samples = &measured.get(&(target_index, scenario_index))?.samples;
+                            let times = &samples.times;
+                            let mut metrics = measured
+                                .get(&(target_index, scenario_index))?
+                                .metrics(&args.sample_size);
+                            let mut rng = bootstrap::rng(seed, target_index, scenario_index);
+                            metrics.ci = bootstrap::tails(times, &mut rng);
+                            metrics.apdex = args.apdex.score(times, samples.errors);
+                            metrics.outliers = outliers::find(
+                                &targets[target_index].name,
+                                &scenario.name,
+                                &samples.measured,
+                            )
+                            .len();
+                            if target_index > 0
+                                && let Some(subject) = measured.get(&(0, scenario_index))
+                            {
+                                metrics.delta_ci =
+                                    bootstrap::tail_deltas(&subject.samples.times, times, &mut rng);
+                                metrics.distribution = distribution::Distribution::compare(
+                                    &subject.samples.times,
+                                    times,
+                                );
+                            }
+                            if scenario.to_last_byte() {
+                                metrics.bytes_per_sec =
+                                    Some(metrics.avg_size as f64 / (metrics.avg / 1000.0));
+                            }
+                            Some((scenario.name.clone(), metrics))
+                        })
+                        .collect()
+                })
+                .collect()
+        };
 
-    display_comparison(&scenarios, &rari_results, &nextjs_results);
-    calculate_summary(&scenarios, &rari_results, &nextjs_results);
+    let mut payloads: Vec<String> = scenarios
+        .iter()
+        .filter(|scenario| scenario.is_page() && scenario.script.is_none())
+        .map(|scenario| scenario.name.clone())
+        .collect();
+    if !asset_paths.is_empty() {
+        payloads.extend(assets::AssetKind::ALL.map(|kind| kind.scenario().to_string()));
+    }
+    let mut revalidations: BTreeMap<String, BTreeMap<String, conditional::Revalidation>> =
+        BTreeMap::new();
+    let mut compressed: BTreeMap<String, BTreeMap<String, compression::Encodings>> =
+        BTreeMap::new();
+    let mut mutations = BTreeMap::new();
+    if !partial {
+        let extras = interrupt::or_interrupted(async {
+            if args.conditional {
+                println!("\n{}", "🔁 Conditional requests".bold());
+                for (target, paths) in targets.iter().zip(&asset_paths) {
+                    for (kind, path) in paths {
+                        if interrupt::cancelled() {
+                            break;
+                        }
+                        println!("  {} {}", target.display(), path.dimmed());
+                        match conditional::check(target, path, args.requests).await {
+                            Ok(revalidation) => {
+                                revalidations
+                                    .entry(target.name.clone())
+                                    .or_default()
+                                    .insert(kind.scenario().to_string(), revalidation);
+                            }
+                            Err(e) => println!("  {} Failed: {}", "❌".red(), e),
+                        }
+                    }
+                }
+            }
+
+            if args.compression {
+                println!("\n{}", "🗜️ Compression".bold());
+                for (target_index, target) in targets.iter().enumerate() {
+                    let mut paths: Vec<(&str, &str)> = scenarios
+                        .iter()
+                        .filter(|scenario| scenario.is_page() && scenario.script.is_none())
+                        .map(|scenario| (scenario.name.as_str(), scenario.path.as_str()))
+                        .collect();
+                    if let Some(found) = asset_paths.get(target_index) {
+                        paths.extend(
+                            found
+                                .iter()
+                                .map(|(kind, path)| (kind.scenario(), path.as_str())),
+                        );
+                    }
+                    for (name, path) in paths {
+                        if interrupt::cancelled() {
+                            break;
+                        }
+                        println!("  {} {}", target.display(), path.dimmed());
+                        match compression::measure(target, path, args.requests).await {
+                            Ok(encoded) => {
+                                compressed
+                                    .entry(target.name.clone())
+                                    .or_default()
+                                    .insert(name.to_string(), encoded);
+                            }
+                            Err(e) => println!("  {} Failed: {}", "❌".red(), e),
+                        }
+                    }
+                }
+            }
+
+            if args.mutation {
+                println!("\n{}", "✍️ Form submission".bold());
+                for target in &targets {
+                    if interrupt::cancelled() {
+                        break;
+                    }
+                    println!("  {} {}", target.display(), args.mutation_page.dimmed());
+                    match mutation::measure(target, &args.mutation_page, args.warmup, args.requests)
+                        .await
+                    {
+                        Ok(measured) => {
+                            println!(
+                                "  {} Round trip avg: {}, P95: {}",
+                                "✅".green(),
+                                units::duration_ms(measured.round_trip.avg),
+                                units::duration_ms(measured.round_trip.p95)
+                            );
+                            mutations.insert(target.name.clone(), measured);
+                        }
+                        Err(e) => println!("  {} Failed: {:#}", "❌".red(), e),
+                    }
+                }
+            }
+        })
+        .await;
+        if extras.is_none() || interrupt::cancelled() {
+            interrupt::announce("Saving what was measured");
+            partial = true;
+        }
+    }
+
+    let steal_pct = steal.finish();
+    let mut slow: Vec<outliers::Outlier> = measured
+        .iter()
+        .flat_map(|(&(target_index, scenario_index), measured)| {
+            outliers::find(
+                &targets[target_index].name,
+                &scenarios[scenario_index].name,
+                &measured.samples.measured,
+            )
+        })
+        .collect();
+    slow.sort_by(|a, b| {
+        (&a.target, &a.scenario, a.sample.index).cmp(&(&b.target, &b.scenario, b.sample.index))
+    });
+    let framework_results = results_where(Scenario::is_page);
+    let asset_results = results_where(|scenario| scenario.asset.is_some());
+    let large_results = results_where(|scenario| scenario.large);
+    scenarios.retain(Scenario::is_page);
+
+    let containers = match stats_sampler {
+        Some(sampler) => sampler.finish().await,
+        None => BTreeMap::new(),
+    };
+    let server_stats = match server_sampler {
+        Some(sampler) => sampler.finish().await,
+        None => BTreeMap::new(),
+    };
+    let logs = match log_watcher {
+        Some(watcher) => watcher.finish().await,
+        None => BTreeMap::new(),
+    };
+    let memory_pressure = managed_servers
+        .as_mut()
+        .map(servers::ManagedServers::memory_pressure)
+        .unwrap_or_default();
+    let cpu_throttling = managed_servers
+        .as_ref()
+        .map(servers::ManagedServers::cpu_throttling)
+        .unwrap_or_default();
+    drop(shaper);
+    drop(managed_servers);
+    #[cfg(feature = "docker")]
+    drop(compose_env);
+    drop(ssh_env);
+
+    if !partial {
+        display_comparison(
+            &scenarios,
+            &targets,
+            &framework_results,
+            args.tie_threshold,
+            overhead.as_ref(),
+        );
+        display_apdex(&scenarios, &targets, &framework_results, args);
+        display_tails(&scenarios, &targets, &framework_results);
+        display_distributions(&scenarios, &targets, &framework_results);
+        display_redirects(&scenarios, &targets, &framework_results);
+        display_sample_sizes(&scenarios, &targets, &framework_results, args);
+        calculate_summary(&scenarios, &targets, &framework_results, args.tie_threshold);
+        if args.static_assets {
+            let rows: Vec<(&str, Vec<(f64, f64)>)> = assets::AssetKind::ALL
+                .iter()
+                .filter_map(|kind| {
+                    let measured = asset_results
+                        .iter()
+                        .map(|result| {
+                            let metrics = result.get(kind.scenario())?;
+                            Some((metrics.avg, metrics.bytes_per_sec?))
+                        })
+                        .collect::<Option<Vec<_>>>()?;
+                    Some((kind.scenario(), measured))
+                })
+                .collect();
+            assets::display(&targets, &rows, args.tie_threshold);
+        }
+        if args.conditional {
+            let rows: Vec<(&str, Vec<Option<&conditional::Revalidation>>)> = assets::AssetKind::ALL
+                .iter()
+                .map(|kind| {
+                    let checked = targets
+                        .iter()
+                        .map(|target| revalidations.get(&target.name)?.get(kind.scenario()))
+                        .collect();
+                    (kind.scenario(), checked)
+                })
+                .collect();
+            conditional::display(&targets, &rows, args.tie_threshold);
+        }
+        if args.large_payload {
+            display_large_payload(&targets, &large_results, args.tie_threshold);
+        }
+        if let Some(measured) = targets
+            .iter()
+            .map(|target| mutations.get(&target.name))
+            .collect::<Option<Vec<_>>>()
+            .filter(|_| args.mutation)
+        {
+            mutation::display(&targets, &measured, args.tie_threshold);
+        }
+        if args.compression {
+            let rows: Vec<(&str, Vec<Option<&compression::Encodings>>)> = payloads
+                .iter()
+                .map(|name| {
+                    let measured = targets
+                        .iter()
+                        .map(|target| compressed.get(&target.name)?.get(name))
+                        .collect();
+                    (name.as_str(), measured)
+                })
+                .collect();
+            compression::display(&targets, &rows);
+        }
+    }
+
+    let profile_artifacts = profiles::collect(&args.attach_profile).await;
+    profiles::display(&profile_artifacts);
+    containers::display(&containers, &targets);
+    containers::display_titled("🔐 Remote Server Stats", &server_stats, &targets);
+    limits::display_memory(&memory_pressure, &targets);
+    limits::display_cpu(&cpu_throttling, &targets);
+    server_logs::display(&logs, &targets);
+
+    let measurements: Vec<quality::Measurement> =
+        [&framework_results, &asset_results, &large_results]
+            .into_iter()
+            .flatten()
+            .flat_map(HashMap::values)
+            .map(|metrics| quality::Measurement {
+                cv_pct: metrics.noise.cv_pct,
+                samples: metrics.samples,
+                requests: metrics.samples + metrics.errors,
+                errors: metrics.errors,
+            })
+            .collect();
+    let quality = quality::Quality::grade(
+        &measurements,
+        metadata.host_load.as_ref(),
+        steal_pct,
+        partial,
+    );
+    quality.print();
 
     let results = BenchmarkResults {
+        version: schema::VERSION,
         timestamp: time_utils::format_timestamp(SystemTime::now()),
+        metadata,
         summary: TestSummary {
             test_requests: args.requests,
             warmup_requests: args.warmup,
             scenarios: scenarios.len(),
+            repetitions: args.repetitions,
+            shuffle_seed,
+            max_cv_pct: args.noise.max_cv,
+            script: args.script.clone(),
+            redirects: Some(args.redirects.redirects.to_string()),
+            detect_pct: Some(args.sample_size.detect),
+            apdex_target_ms: Some(args.apdex.apdex_target),
+            apdex_tolerating_ms: Some(args.apdex.tolerating()),
         },
-        rari: rari_results,
-        nextjs: nextjs_results,
+        targets: targets::names(&targets),
+        results: targets::names(&targets)
+            .into_iter()
+            .zip(framework_results)
+            .collect(),
+        static_assets: targets::names(&targets)
+            .into_iter()
+            .zip(asset_results)
+            .filter(|(_, result)| !result.is_empty())
+            .collect(),
+        large_payload: targets::names(&targets)
+            .into_iter()
+            .zip(large_results.clone())
+            .filter(|(_, result)| !result.is_empty())
+            .collect(),
+        conditional: revalidations,
+        compression: compressed,
+        mutation: mutations,
+        overhead,
+        quality: Some(quality),
+        profiles: profile_artifacts,
+        containers,
+        server_stats,
+        server_logs: logs,
+        footprint,
+        memory_pressure,
+        cpu_throttling,
+        partial,
+        errors: failures.clone(),
     };
 
-    save_results(&results, &args.results_dir).await?;
+    let file_name = archive::file_name(
+        &args.archive.name_template,
+        metrics::ResultKind::Performance,
+        &results.timestamp,
+        &results.metadata,
+    )?;
+    let saved_path = save_results(&results, &targets, &args.results_dir, &file_name).await?;
+    if let Some(path) = outliers::save(&slow, &saved_path).await? {
+        println!(
+            "{} {} outlier(s) logged to {}",
+            "🔎".dimmed(),
+            slow.len(),
+            path.display()
+        );
+    }
+    if partial {
+        // Partial results stay out of the archive, the store and the gate.
+        return Ok(results::Run {
+            results,
+            path: saved_path,
+            partial,
+            gate_passed: true,
+            errors: failures,
+        });
+    }
+    if let Err(e) = archive::finalize(
+        &args.results_dir,
+        metrics::ResultKind::Performance,
+        &args.archive,
+    )
+    .await
+    {
+        tracing::warn!("Failed to update results archive: {:#}", e);
+    }
+
+    let document = serde_json::to_value(&results)?;
+    let store_path = (!args.no_store).then(|| {
+        args.store
+            .clone()
+            .unwrap_or_else(|| store::default_path(&args.results_dir))
+    });
+    store::record(
+        store_path.as_deref(),
+        metrics::ResultKind::Performance,
+        &saved_path,
+        &document,
+    );
+    influx::export(&args.influx, metrics::ResultKind::Performance, &document).await;
 
-    println!("\n{}", "🎉 Benchmark completed!".green().bold());
+    let result_metrics = metrics::flatten(&document);
+    let gate_passed = gate::check(&rules, &result_metrics)?;
 
-    Ok(())
+    let mut artifact = saved_path.display().to_string();
+    if let Some(destination) = &args.upload {
+        let date = time_utils::format_date(SystemTime::now());
+        match upload::upload(destination, &saved_path, &date).await {
+            Ok(uri) => artifact = uri,
+            Err(e) => tracing::warn!("{:#}", e),
+        }
+    }
+
+    let values = |metric: fn(&PerformanceMetrics) -> f64| -> Vec<f64> {
+        targets
+            .iter()
+            .map(|target| scenario_average(&results.results[&target.name], metric))
+            .collect()
+    };
+    let summary = notify::Summary {
+        title,
+        timestamp: results.timestamp.clone(),
+        targets: targets.clone(),
+        deltas: vec![
+            notify::Delta {
+                label: "Avg response time".to_string(),
+                unit: units::Unit::Milliseconds,
+                values: values(|m| m.avg),
+                lower_is_better: true,
+            },
+            notify::Delta {
+                label: "P95 response time".to_string(),
+                unit: units::Unit::Milliseconds,
+                values: values(|m| m.p95),
+                lower_is_better: true,
+            },
+        ],
+        gate_passed: (!rules.is_empty()).then_some(gate_passed),
+        artifact: args.artifact_url.clone().unwrap_or(artifact),
+    };
+
+    notify::notify(args.notify_webhook.as_deref(), &summary).await;
+    if let Err(e) = publish::publish(&args.github, "benchmarks/performance", &summary).await {
+        tracing::warn!("{:#}", e);
+    }
+
+    args.hooks.after_run(&saved_path).await?;
+    output::finish(&args.output, &summary.to_text(), &document)?;
+    stream::emit(
+        "run_finished",
+        json!({
+            "results": saved_path,
+            "artifact": summary.artifact,
+            "gatePassed": gate_passed,
+        }),
+    );
+
+    if gate_passed {
+        println!("\n{}", "🎉 Benchmark completed!".green().bold());
+    }
+
+    Ok(results::Run {
+        results,
+        path: saved_path,
+        partial,
+        gate_passed,
+        errors: failures,
+    })
 }
@@ -0,0 +1,189 @@
+use crate::errors::{Error, ErrorKind};
+use crate::run_metadata::HostLoad;
+use anyhow::Result;
+use clap::ValueEnum;
+use colored::Colorize;
+
+#[cfg(target_os = "linux")]
+const CPU_SAMPLE: std::time::Duration = std::time::Duration::from_secs(1);
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct PreflightArgs {
+    #[arg(
+        long,
+        value_enum,
+        value_name = "POLICY",
+        default_value_t = BusyPolicy::Warn,
+        help = "What to do when the host is already busy before the run starts"
+    )]
+    pub host_busy: BusyPolicy,
+    #[arg(
+        long,
+        value_name = "PCT",
+        default_value = "25",
+        help = "CPU use above which the host counts as busy"
+    )]
+    pub max_host_cpu: f64,
+    #[arg(
+        long,
+        value_name = "PCT",
+        default_value = "90",
+        help = "Memory use above which the host counts as busy"
+    )]
+    pub max_host_memory: f64,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BusyPolicy {
+    /// Warn and mark the results with `hostLoad.busy`.
+    Warn,
+    /// Stop before starting anything.
+    Refuse,
+    /// Skip the check.
+    Ignore,
+}
+
+impl BusyPolicy {
+    pub fn name(self) -> &'static str {
+        match self {
+            BusyPolicy::Warn => "warn",
+            BusyPolicy::Refuse => "refuse",
+            BusyPolicy::Ignore => "ignore",
+        }
+    }
+}
+
+/// Total and idle jiffies from the aggregate `cpu` line of /proc/stat.
+#[cfg(target_os = "linux")]
+fn cpu_times() -> Option<(u64, u64)> {
+    let stat = std::fs::read_to_string("/proc/stat").ok()?;
+    let line = stat.lines().find(|line| line.starts_with("cpu "))?;
+    let fields: Vec<u64> = line
+        .split_whitespace()
+        .skip(1)
+        .filter_map(|field| field.parse().ok())
+        .collect();
+    // idle + iowait
+    let idle = fields.get(3)? + fields.get(4).copied().unwrap_or(0);
+    Some((fields.iter().sum(), idle))
+}
+
+#[cfg(target_os = "linux")]
+async fn cpu_pct() -> Option<f64> {
+    let (total_before, idle_before) = cpu_times()?;
+    tokio::time::sleep(CPU_SAMPLE).await;
+    let (total_after, idle_after) = cpu_times()?;
+    let total = total_after.saturating_sub(total_before);
+    if total == 0 {
+        return None;
+    }
+    let idle = idle_after.saturating_sub(idle_before);
+    Some(100.0 * (total - idle.min(total)) as f64 / total as f64)
+}
+
+#[cfg(not(target_os = "linux"))]
+async fn cpu_pct() -> Option<f64> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn memory_used_pct() -> Option<f64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let field = |name: &str| -> Option<f64> {
+        let line = meminfo.lines().find(|line| line.starts_with(name))?;
+        line.split_whitespace().nth(1)?.parse().ok()
+    };
+    let total = field("MemTotal:")?;
+    let available = field("MemAvailable:")?;
+    (total > 0.0).then(|| 100.0 * (1.0 - available / total))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn memory_used_pct() -> Option<f64> {
+    None
+}
+
+#[cfg(unix)]
+fn load_avg() -> Vec<f64> {
+    let mut loads = [0.0; 3];
+    let read = unsafe { libc::getloadavg(loads.as_mut_ptr(), 3) };
+    loads.iter().take(read.max(0) as usize).copied().collect()
+}
+
+#[cfg(not(unix))]
+fn load_avg() -> Vec<f64> {
+    Vec::new()
+}
+
+/// Samples CPU, memory and load average before anything is started. A busy
+/// host is reported, and refused with `--host-busy refuse`.
+#[tracing::instrument(name = "preflight", skip_all)]
+pub async fn check(args: &PreflightArgs) -> Result<Option<HostLoad>> {
+    if args.host_busy == BusyPolicy::Ignore {
+        return Ok(None);
+    }
+
+    let mut load = HostLoad {
+        cpu_pct: cpu_pct().await,
+        memory_used_pct: memory_used_pct(),
+        load_avg: load_avg(),
+        busy: false,
+    };
+
+    let mut reasons = Vec::new();
+    if let Some(cpu) = load.cpu_pct.filter(|cpu| *cpu > args.max_host_cpu) {
+        reasons.push(format!(
+            "CPU is {:.0}% busy (limit {}%)",
+            cpu, args.max_host_cpu
+        ));
+    }
+    if let Some(memory) = load
+        .memory_used_pct
+        .filter(|memory| *memory > args.max_host_memory)
+    {
+        reasons.push(format!(
+            "{:.0}% of memory is in use (limit {}%)",
+            memory, args.max_host_memory
+        ));
+    }
+    load.busy = !reasons.is_empty();
+
+    let mut parts = Vec::new();
+    if let Some(cpu) = load.cpu_pct {
+        parts.push(format!("CPU {:.0}%", cpu));
+    }
+    if let Some(memory) = load.memory_used_pct {
+        parts.push(format!("memory {:.0}%", memory));
+    }
+    if !load.load_avg.is_empty() {
+        let averages: Vec<String> = load
+            .load_avg
+            .iter()
+            .map(|avg| format!("{:.2}", avg))
+            .collect();
+        parts.push(format!("load {}", averages.join(" ")));
+    }
+    if !parts.is_empty() {
+        println!("{} Host: {}", "🖥️".dimmed(), parts.join(", "));
+    }
+
+    if load.busy {
+        let reasons = reasons.join("; ");
+        if args.host_busy == BusyPolicy::Refuse {
+            return Err(Error::new(
+                ErrorKind::HostBusy,
+                format!(
+                    "The host is busy: {}. Wait for it to settle or pass --host-busy warn",
+                    reasons
+                ),
+            )
+            .into());
+        }
+        tracing::warn!(
+            "The host is busy: {}. Results are marked with hostLoad.busy",
+            reasons
+        );
+    }
+
+    Ok(Some(load))
+}
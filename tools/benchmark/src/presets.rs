@@ -0,0 +1,88 @@
+use clap::ValueEnum;
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct PresetArgs {
+    #[arg(
+        long,
+        value_enum,
+        value_name = "PROFILE",
+        help = "Scale warmup, request counts, durations, and repetitions together; flags and the settings file win over it"
+    )]
+    pub profile: Option<Preset>,
+}
+
+impl PresetArgs {
+    /// The flags that make another benchmark use the same profile.
+    pub fn forward(&self) -> Vec<std::ffi::OsString> {
+        self.profile
+            .iter()
+            .flat_map(|preset| ["--profile".into(), preset.name().into()])
+            .collect()
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    /// A sanity check in a couple of minutes.
+    Quick,
+    /// The defaults.
+    Standard,
+    /// Numbers worth publishing: more samples, longer windows, repeated runs.
+    Thorough,
+}
+
+impl Preset {
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::Quick => "quick",
+            Preset::Standard => "standard",
+            Preset::Thorough => "thorough",
+        }
+    }
+
+    /// The flags the profile sets for `benchmark`, by long name.
+    pub fn settings(self, benchmark: &str) -> &'static [(&'static str, &'static str)] {
+        match (benchmark, self) {
+            ("performance", Preset::Quick) => {
+                &[("warmup", "10"), ("requests", "10"), ("repetitions", "1")]
+            }
+            ("performance", Preset::Standard) => {
+                &[("warmup", "50"), ("requests", "20"), ("repetitions", "1")]
+            }
+            ("performance", Preset::Thorough) => {
+                &[("warmup", "100"), ("requests", "100"), ("repetitions", "5")]
+            }
+            ("load-test", Preset::Quick) => &[("duration", "10"), ("step-duration", "5")],
+            ("load-test", Preset::Standard) => &[("duration", "30"), ("step-duration", "10")],
+            ("load-test", Preset::Thorough) => &[("duration", "120"), ("step-duration", "30")],
+            ("build-times", Preset::Quick | Preset::Standard) => &[("runs", "1")],
+            ("build-times", Preset::Thorough) => &[("runs", "5")],
+            ("images", Preset::Quick) => &[("starts", "2")],
+            ("images", Preset::Standard) => &[("starts", "5")],
+            ("images", Preset::Thorough) => &[("starts", "10")],
+            ("browser", Preset::Quick) => &[("runs", "2")],
+            ("browser", Preset::Standard) => &[("runs", "5")],
+            ("browser", Preset::Thorough) => &[("runs", "10")],
+            // `full` passes the profile on, so its steps fill in the rest.
+            ("full", Preset::Quick) => &[
+                ("warmup", "10"),
+                ("requests", "10"),
+                ("repetitions", "1"),
+                ("duration", "10"),
+            ],
+            ("full", Preset::Standard) => &[
+                ("warmup", "50"),
+                ("requests", "20"),
+                ("repetitions", "1"),
+                ("duration", "30"),
+            ],
+            ("full", Preset::Thorough) => &[
+                ("warmup", "100"),
+                ("requests", "100"),
+                ("repetitions", "5"),
+                ("duration", "120"),
+            ],
+            _ => &[],
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use crate::errors::{Error, ErrorKind};
+use crate::targets::Target;
+use anyhow::Result;
+use colored::Colorize;
+use std::time::{Duration, Instant};
+
+const DEFAULT_MANAGED_TIMEOUT_SECS: u64 = 60;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ProbeArgs {
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Path requested to check that a server is ready [default: the target's healthPath, or /]"
+    )]
+    pub health_path: Option<String>,
+    #[arg(
+        long,
+        value_name = "CODE",
+        help = "Status the health check must return [default: any 2xx]"
+    )]
+    pub expect_status: Option<u16>,
+    #[arg(
+        long,
+        value_name = "TEXT",
+        help = "Text the health check response body must contain"
+    )]
+    pub expect_body: Option<String>,
+    #[arg(
+        long,
+        value_name = "MS",
+        default_value = "500",
+        help = "Delay between health check attempts"
+    )]
+    pub probe_interval: u64,
+    #[arg(
+        long,
+        value_name = "SECS",
+        help = "How long to wait for a server to become ready [default: 60 with --manage-servers, otherwise a single check]"
+    )]
+    pub ready_timeout: Option<u64>,
+}
+
+impl ProbeArgs {
+    pub fn url(&self, target: &Target, base_url: &str) -> String {
+        let path = self
+            .health_path
+            .as_deref()
+            .or(target.health_path.as_deref())
+            .unwrap_or("/")
+            .trim_start_matches('/');
+        format!("{}/{}", base_url, path)
+    }
+
+    pub fn timeout(&self, managed: bool) -> Duration {
+        let default = if managed {
+            DEFAULT_MANAGED_TIMEOUT_SECS
+        } else {
+            0
+        };
+        Duration::from_secs(self.ready_timeout.unwrap_or(default))
+    }
+}
+
+pub async fn probe(client: &reqwest::Client, url: &str, args: &ProbeArgs) -> Result<()> {
+    let response = client.get(url).timeout(REQUEST_TIMEOUT).send().await?;
+    let status = response.status();
+
+    match args.expect_status {
+        Some(expected) if status.as_u16() != expected => {
+            anyhow::bail!("expected status {}, got {}", expected, status)
+        }
+        None if !status.is_success() => anyhow::bail!("got status {}", status),
+        _ => {}
+    }
+
+    if let Some(expected) = &args.expect_body {
+        let body = response.text().await?;
+        if !body.contains(expected.as_str()) {
+            anyhow::bail!("response body does not contain {:?}", expected);
+        }
+    }
+
+    Ok(())
+}
+
+/// Polls the health check until it passes or the timeout runs out.
+/// `check_alive` runs before every attempt so callers can give up early,
+/// e.g. when a managed server process has already exited.
+#[tracing::instrument(name = "wait_until_ready", skip_all, fields(target = %target.name))]
+pub async fn wait_until_ready(
+    target: &Target,
+    base_url: &str,
+    args: &ProbeArgs,
+    managed: bool,
+    mut check_alive: impl FnMut() -> Result<()>,
+) -> Result<()> {
+    let name = target.label();
+    let url = args.url(target, base_url);
+    let timeout = args.timeout(managed);
+    let interval = Duration::from_millis(args.probe_interval);
+    let client = reqwest::Client::new();
+    let started = Instant::now();
+    let mut attempts = 0;
+
+    loop {
+        check_alive()?;
+        attempts += 1;
+
+        match probe(&client, &url, args).await {
+            Ok(()) => {
+                if attempts == 1 {
+                    println!("{} {} server is responding", "✅".green(), name);
+                } else {
+                    println!(
+                        "{} {} server is ready after {:.1}s ({} attempts)",
+                        "✅".green(),
+                        name,
+                        started.elapsed().as_secs_f64(),
+                        attempts
+                    );
+                }
+                return Ok(());
+            }
+            Err(e) if started.elapsed() + interval > timeout => {
+                return Err(Error::new(
+                    ErrorKind::ServerUnreachable,
+                    format!(
+                        "{} server is not ready at {} after {} attempt{}: {:#}",
+                        name,
+                        url,
+                        attempts,
+                        if attempts == 1 { "" } else { "s" },
+                        e
+                    ),
+                )
+                .target(&target.name)
+                .into());
+            }
+            Err(e) => {
+                tracing::debug!("{} not ready yet: {:#}", name, e);
+            }
+        }
+
+        tokio::time::sleep(interval).await;
+    }
+}
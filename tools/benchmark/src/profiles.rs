@@ -0,0 +1,274 @@
+use crate::units;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tokio::fs;
+
+const HOTSPOT_COUNT: usize = 5;
+
+#[derive(Debug, Clone)]
+pub struct ProfileSpec {
+    framework: String,
+    path: PathBuf,
+}
+
+impl FromStr for ProfileSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (framework, path) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected FRAMEWORK=PATH, got '{}'", s))?;
+
+        if framework.is_empty() {
+            return Err(format!("missing target name in '{}'", s));
+        }
+        Ok(ProfileSpec {
+            framework: framework.to_string(),
+            path: PathBuf::from(path),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProfileTool {
+    Perf,
+    Flamegraph,
+    V8CpuProfile,
+    V8Log,
+    Other,
+}
+
+impl ProfileTool {
+    fn label(self) -> &'static str {
+        match self {
+            ProfileTool::Perf => "perf",
+            ProfileTool::Flamegraph => "flamegraph",
+            ProfileTool::V8CpuProfile => "V8 CPU profile",
+            ProfileTool::V8Log => "V8 log",
+            ProfileTool::Other => "file",
+        }
+    }
+
+    fn detect(path: &Path) -> Self {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+
+        if name.ends_with(".cpuprofile") {
+            ProfileTool::V8CpuProfile
+        } else if name.ends_with(".svg") {
+            ProfileTool::Flamegraph
+        } else if name.starts_with("perf") && (name.ends_with(".data") || name.contains(".data.")) {
+            ProfileTool::Perf
+        } else if name.starts_with("isolate-") && name.ends_with(".log") {
+            ProfileTool::V8Log
+        } else {
+            ProfileTool::Other
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Hotspot {
+    pub function: String,
+    pub self_pct: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileArtifact {
+    pub framework: String,
+    pub tool: ProfileTool,
+    pub path: String,
+    pub size_bytes: units::Bytes,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub stats: BTreeMap<String, f64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hotspots: Vec<Hotspot>,
+}
+
+fn cpuprofile_summary(json: &Value) -> (BTreeMap<String, f64>, Vec<Hotspot>) {
+    let mut stats = BTreeMap::new();
+
+    let nodes = json["nodes"].as_array().cloned().unwrap_or_default();
+    let samples = json["samples"].as_array().cloned().unwrap_or_default();
+    stats.insert("nodes".to_string(), nodes.len() as f64);
+    stats.insert("samples".to_string(), samples.len() as f64);
+
+    if let (Some(start), Some(end)) = (json["startTime"].as_f64(), json["endTime"].as_f64()) {
+        stats.insert("durationMs".to_string(), (end - start) / 1000.0);
+    }
+
+    let names: HashMap<u64, String> = nodes
+        .iter()
+        .filter_map(|node| {
+            let id = node["id"].as_u64()?;
+            let name = node["callFrame"]["functionName"]
+                .as_str()
+                .unwrap_or_default();
+            let name = if name.is_empty() { "(anonymous)" } else { name };
+            Some((id, name.to_string()))
+        })
+        .collect();
+
+    let mut self_samples: HashMap<&str, usize> = HashMap::new();
+    for id in samples.iter().filter_map(Value::as_u64) {
+        if let Some(name) = names.get(&id) {
+            *self_samples.entry(name.as_str()).or_default() += 1;
+        }
+    }
+
+    let mut hotspots: Vec<Hotspot> = self_samples
+        .into_iter()
+        .filter(|(name, _)| !matches!(*name, "(idle)" | "(program)" | "(root)"))
+        .map(|(name, count)| Hotspot {
+            function: name.to_string(),
+            self_pct: count as f64 / samples.len().max(1) as f64 * 100.0,
+        })
+        .collect();
+    hotspots.sort_by(|a, b| b.self_pct.total_cmp(&a.self_pct));
+    hotspots.truncate(HOTSPOT_COUNT);
+
+    (stats, hotspots)
+}
+
+fn flamegraph_samples(svg: &str) -> Option<f64> {
+    let start = svg.find("<title>all (")? + "<title>all (".len();
+    let digits: String = svg[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == ',')
+        .filter(|c| *c != ',')
+        .collect();
+    digits.parse().ok()
+}
+
+async fn summarize(
+    tool: ProfileTool,
+    path: &Path,
+) -> Result<(BTreeMap<String, f64>, Vec<Hotspot>)> {
+    Ok(match tool {
+        ProfileTool::V8CpuProfile => {
+            let contents = fs::read_to_string(path).await?;
+            let json: Value = serde_json::from_str(&contents)
+                .with_context(|| format!("Failed to parse CPU profile {}", path.display()))?;
+            cpuprofile_summary(&json)
+        }
+        ProfileTool::Flamegraph => {
+            let svg = fs::read_to_string(path).await?;
+            let stats = flamegraph_samples(&svg)
+                .map(|samples| BTreeMap::from([("samples".to_string(), samples)]))
+                .unwrap_or_default();
+            (stats, Vec::new())
+        }
+        ProfileTool::V8Log => {
+            let log = fs::read_to_string(path).await?;
+            let ticks = log.lines().filter(|line| line.starts_with("tick,")).count();
+            (
+                BTreeMap::from([("ticks".to_string(), ticks as f64)]),
+                Vec::new(),
+            )
+        }
+        ProfileTool::Perf | ProfileTool::Other => (BTreeMap::new(), Vec::new()),
+    })
+}
+
+pub async fn describe(framework: &str, path: &Path) -> Result<ProfileArtifact> {
+    let metadata = fs::metadata(path)
+        .await
+        .with_context(|| format!("Profile artifact {} not found", path.display()))?;
+    let tool = ProfileTool::detect(path);
+    let (stats, hotspots) = summarize(tool, path).await?;
+    let path = fs::canonicalize(path)
+        .await
+        .unwrap_or_else(|_| path.to_path_buf());
+
+    Ok(ProfileArtifact {
+        framework: framework.to_string(),
+        tool,
+        path: path.display().to_string(),
+        size_bytes: units::Bytes(metadata.len()),
+        stats,
+        hotspots,
+    })
+}
+
+/// Moves a profile recorded during the run from the temp directory to `path`
+/// and describes it.
+pub async fn keep(framework: &str, recorded: &Path, path: &Path) -> Option<ProfileArtifact> {
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir).await;
+    }
+    let saved = match fs::rename(recorded, path).await {
+        Ok(()) => Ok(()),
+        // The temp directory may be on another file system.
+        Err(_) => fs::copy(recorded, path).await.map(|_| ()),
+    };
+    let _ = fs::remove_file(recorded).await;
+    if let Err(e) = saved {
+        tracing::warn!(
+            "Failed to save the profile of {} to {}: {}",
+            framework,
+            path.display(),
+            e
+        );
+        return None;
+    }
+    match describe(framework, path).await {
+        Ok(artifact) => Some(artifact),
+        Err(e) => {
+            tracing::warn!("{:#}", e);
+            None
+        }
+    }
+}
+
+pub async fn collect(specs: &[ProfileSpec]) -> Vec<ProfileArtifact> {
+    let mut artifacts = Vec::new();
+    for spec in specs {
+        match describe(&spec.framework, &spec.path).await {
+            Ok(artifact) => artifacts.push(artifact),
+            Err(e) => tracing::warn!("{:#}", e),
+        }
+    }
+    artifacts
+}
+
+pub fn display(artifacts: &[ProfileArtifact]) {
+    if artifacts.is_empty() {
+        return;
+    }
+
+    println!("\n{}", "🔬 Profiles".bold());
+    for artifact in artifacts {
+        let stats = artifact
+            .stats
+            .iter()
+            .map(|(name, value)| format!("{} {:.0}", name, value))
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!(
+            "  {} {} {} ({}{}{})",
+            artifact.framework.bold(),
+            artifact.tool.label(),
+            artifact.path,
+            artifact.size_bytes,
+            if stats.is_empty() { "" } else { ", " },
+            stats
+        );
+        for hotspot in &artifact.hotspots {
+            println!(
+                "    {:>5.1}%  {}",
+                hotspot.self_pct,
+                hotspot.function.dimmed()
+            );
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressFinish, ProgressState, ProgressStyle};
+use std::fmt::Write;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+static HIDDEN: AtomicBool = AtomicBool::new(false);
+
+/// Turns progress bars off for this process, e.g. while per-request logging
+/// or NDJSON events would break them up.
+pub fn hide() {
+    HIDDEN.store(true, Ordering::SeqCst);
+}
+
+/// Bars go where the human-readable output goes, and only to a terminal;
+/// `--quiet` and `--json` send that to /dev/null, which hides them too.
+fn draw_target() -> ProgressDrawTarget {
+    if HIDDEN.load(Ordering::SeqCst) {
+        ProgressDrawTarget::hidden()
+    } else {
+        ProgressDrawTarget::stdout()
+    }
+}
+
+fn style(template: &str) -> ProgressStyle {
+    ProgressStyle::with_template(template)
+        .expect("valid progress template")
+        .with_key("rate", |state: &ProgressState, w: &mut dyn Write| {
+            let _ = write!(w, "{:.0} req/s", state.per_sec());
+        })
+        .progress_chars("━╸─")
+}
+
+/// A bar counting `len` requests, with their current rate and the time left.
+/// Call `inc(1)` per request; it clears itself when finished or dropped.
+pub fn requests(message: &str, len: u64) -> ProgressBar {
+    ProgressBar::with_draw_target(Some(len), draw_target())
+        .with_style(style(
+            "  {msg:8} {bar:30.cyan/dim} {pos}/{len} · {rate} · ETA {eta}",
+        ))
+        .with_message(message.to_string())
+        .with_finish(ProgressFinish::AndClear)
+}
+
+/// Runs `future` with a bar that fills up over `expected`, with the time
+/// left, or a spinner with the elapsed time when it is unknown.
+pub async fn timed<F: Future>(future: F, message: &str, expected: Option<Duration>) -> F::Output {
+    let bar = match expected {
+        Some(expected) => {
+            ProgressBar::with_draw_target(Some(expected.as_millis() as u64), draw_target())
+                .with_style(style("  {msg} {bar:30.cyan/dim} {elapsed} · ETA {eta}"))
+        }
+        None => ProgressBar::with_draw_target(None, draw_target())
+            .with_style(style("  {spinner:.cyan} {msg} {elapsed}")),
+    }
+    .with_message(message.to_string())
+    .with_finish(ProgressFinish::AndClear);
+
+    let started = Instant::now();
+    let mut interval = tokio::time::interval(Duration::from_millis(200));
+    tokio::pin!(future);
+    loop {
+        tokio::select! {
+            output = &mut future => {
+                bar.finish_and_clear();
+                return output;
+            }
+            _ = interval.tick() => {
+                let elapsed = started.elapsed().as_millis() as u64;
+                match bar.length() {
+                    // Past the estimate, the bar stays full until the work is done.
+                    Some(len) => bar.set_position(elapsed.min(len)),
+                    None => bar.tick(),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use crate::notify::Summary;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde_json::json;
+
+const GITHUB_API_URL: &str = "https://api.github.com";
+
+#[derive(clap::Args, Debug)]
+pub struct GitHubArgs {
+    #[arg(long, value_name = "OWNER/REPO", env = "GITHUB_REPOSITORY")]
+    pub github_repo: Option<String>,
+    #[arg(long, env = "GITHUB_TOKEN", hide_env_values = true)]
+    pub github_token: Option<String>,
+    #[arg(long, value_name = "NUMBER")]
+    pub github_pr: Option<u64>,
+    #[arg(long, value_name = "SHA")]
+    pub github_sha: Option<String>,
+    #[arg(long, env = "GITHUB_API_URL", default_value = GITHUB_API_URL)]
+    pub github_api_url: String,
+}
+
+fn github_request(
+    client: &reqwest::Client,
+    args: &GitHubArgs,
+    token: &str,
+    path: &str,
+) -> reqwest::RequestBuilder {
+    client
+        .post(format!(
+            "{}{}",
+            args.github_api_url.trim_end_matches('/'),
+            path
+        ))
+        .bearer_auth(token)
+        .header("Accept", "application/vnd.github+json")
+        .header("X-GitHub-Api-Version", "2022-11-28")
+        .header("User-Agent", "rari-benchmark")
+}
+
+async fn send(request: reqwest::RequestBuilder, what: &str) -> Result<()> {
+    let response = request
+        .send()
+        .await
+        .with_context(|| format!("Failed to {}", what))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!(
+            "Failed to {}: GitHub returned {} {}",
+            what,
+            status,
+            body.trim()
+        );
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument(name = "publish", skip(args, summary))]
+pub async fn publish(args: &GitHubArgs, context: &str, summary: &Summary) -> Result<()> {
+    if args.github_pr.is_none() && args.github_sha.is_none() {
+        return Ok(());
+    }
+
+    let repo = args
+        .github_repo
+        .as_deref()
+        .context("--github-repo is required to publish to GitHub")?;
+    let token = args
+        .github_token
+        .as_deref()
+        .context("--github-token (or GITHUB_TOKEN) is required to publish to GitHub")?;
+
+    let client = reqwest::Client::new();
+
+    if let Some(pr) = args.github_pr {
+        let request = github_request(
+            &client,
+            args,
+            token,
+            &format!("/repos/{}/issues/{}/comments", repo, pr),
+        )
+        .json(&json!({ "body": summary.to_markdown() }));
+        send(request, "post PR comment").await?;
+        println!("{} Posted summary to {}#{}", "💬".dimmed(), repo, pr);
+    }
+
+    if let Some(sha) = &args.github_sha {
+        let (state, description) = match summary.gate_passed {
+            Some(false) => ("failure", "Regression thresholds failed"),
+            Some(true) => ("success", "Regression thresholds passed"),
+            None => ("success", "Benchmark completed"),
+        };
+
+        let mut status = json!({
+            "state": state,
+            "context": context,
+            "description": description,
+        });
+        if summary.artifact.starts_with("https://") || summary.artifact.starts_with("http://") {
+            status["target_url"] = json!(summary.artifact);
+        }
+
+        let request = github_request(
+            &client,
+            args,
+            token,
+            &format!("/repos/{}/statuses/{}", repo, sha),
+        )
+        .json(&status);
+        send(request, "set commit status").await?;
+        println!(
+            "{} Set {} status on {} to {}",
+            "✔️".dimmed(),
+            context,
+            &sha[..sha.len().min(7)],
+            state
+        );
+    }
+
+    Ok(())
+}
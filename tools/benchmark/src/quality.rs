@@ -0,0 +1,221 @@
+use crate::run_metadata::HostLoad;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+
+/// Upper bounds of an A, B, and C for the worst coefficient of variation.
+const CV_PCT: [f64; 3] = [5.0, 10.0, 20.0];
+/// Upper bounds of an A, B, and C for the share of failed requests.
+const ERROR_PCT: [f64; 3] = [0.0, 0.1, 1.0];
+/// Upper bounds of an A, B, and C for CPU time taken by other virtual machines.
+const STEAL_PCT: [f64; 3] = [1.0, 5.0, 10.0];
+/// Lower bounds of an A, B, and C for the fewest samples behind a measurement.
+const SAMPLES: [usize; 3] = [100, 50, 20];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Grade {
+    A,
+    B,
+    C,
+    D,
+}
+
+impl Grade {
+    const ALL: [Grade; 4] = [Grade::A, Grade::B, Grade::C, Grade::D];
+
+    /// A for a value within the first bound, B within the second, and so on.
+    fn at_most(value: f64, bounds: [f64; 3]) -> Self {
+        Grade::ALL[bounds.iter().take_while(|bound| value > **bound).count()]
+    }
+
+    fn at_least(value: usize, bounds: [usize; 3]) -> Self {
+        Grade::ALL[bounds.iter().take_while(|bound| value < **bound).count()]
+    }
+
+    pub fn meaning(self) -> &'static str {
+        match self {
+            Grade::A => "solid",
+            Grade::B => "good",
+            Grade::C => "rough, compare with care",
+            Grade::D => "not fit to quote",
+        }
+    }
+}
+
+/// One measurement of one target, as the grade sees it.
+pub struct Measurement {
+    pub cv_pct: Option<f64>,
+    pub samples: usize,
+    pub requests: usize,
+    pub errors: usize,
+}
+
+/// How far a run's numbers can be trusted, and what held it back.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Quality {
+    pub grade: Grade,
+    /// The noisiest measurement's coefficient of variation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_cv_pct: Option<f64>,
+    /// Failed requests across every measurement.
+    pub error_pct: f64,
+    /// The fewest samples behind any measurement.
+    pub min_samples: usize,
+    /// CPU time the hypervisor gave to other machines while measuring.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub steal_pct: Option<f64>,
+    /// The host was already busy before the run; see `metadata.hostLoad`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub host_busy: bool,
+    /// Why the grade is not an A.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reasons: Vec<String>,
+}
+
+impl Quality {
+    /// Grades a run by its worst measurement: the lowest of the grades for
+    /// noise, failed requests, sample count, and host load. A partial run is
+    /// a D.
+    pub fn grade(
+        measurements: &[Measurement],
+        host_load: Option<&HostLoad>,
+        steal_pct: Option<f64>,
+        partial: bool,
+    ) -> Self {
+        let max_cv_pct = measurements
+            .iter()
+            .filter_map(|measurement| measurement.cv_pct)
+            .max_by(f64::total_cmp);
+        let requests: usize = measurements.iter().map(|m| m.requests).sum();
+        let errors: usize = measurements.iter().map(|m| m.errors).sum();
+        let error_pct = if requests == 0 {
+            0.0
+        } else {
+            errors as f64 / requests as f64 * 100.0
+        };
+        let min_samples = measurements.iter().map(|m| m.samples).min().unwrap_or(0);
+        let host_busy = host_load.is_some_and(|load| load.busy);
+
+        let mut grades = vec![
+            (
+                max_cv_pct.map_or(Grade::A, |cv| Grade::at_most(cv, CV_PCT)),
+                format!("CV up to {:.1}%", max_cv_pct.unwrap_or_default()),
+            ),
+            (
+                Grade::at_most(error_pct, ERROR_PCT),
+                format!("{:.2}% of requests failed", error_pct),
+            ),
+            (
+                Grade::at_least(min_samples, SAMPLES),
+                format!("as few as {} samples per measurement", min_samples),
+            ),
+            (
+                steal_pct.map_or(Grade::A, |steal| Grade::at_most(steal, STEAL_PCT)),
+                format!(
+                    "{:.1}% of CPU time stolen by other machines while measuring",
+                    steal_pct.unwrap_or_default()
+                ),
+            ),
+        ];
+        if host_busy {
+            grades.push((Grade::C, "the host was busy before the run".to_string()));
+        }
+        if partial {
+            grades.push((Grade::D, "the run was cut short".to_string()));
+        }
+
+        let grade = grades
+            .iter()
+            .map(|(grade, _)| *grade)
+            .max()
+            .unwrap_or(Grade::A);
+        let reasons = grades
+            .into_iter()
+            .filter(|(grade, _)| *grade > Grade::A)
+            .map(|(grade, reason)| format!("{} ({:?})", reason, grade))
+            .collect();
+        Quality {
+            grade,
+            max_cv_pct,
+            error_pct,
+            min_samples,
+            steal_pct,
+            host_busy,
+            reasons,
+        }
+    }
+
+    pub fn print(&self) {
+        let headline = format!(
+            "🏅 Run quality: {:?} ({})",
+            self.grade,
+            self.grade.meaning()
+        );
+        println!(
+            "\n{}",
+            match self.grade {
+                Grade::A | Grade::B => headline.green().bold(),
+                Grade::C => headline.yellow().bold(),
+                Grade::D => headline.red().bold(),
+            }
+        );
+        for reason in &self.reasons {
+            println!("  {} {}", "•".dimmed(), reason);
+        }
+        if self.grade == Grade::D {
+            println!(
+                "  {}",
+                "Don't quote these numbers as definitive; fix the above and run again".red()
+            );
+        }
+    }
+}
+
+/// CPU steal over a stretch of the run, from /proc/stat. Only virtual
+/// machines have any.
+pub struct StealMeter {
+    #[cfg(target_os = "linux")]
+    start: Option<(u64, u64)>,
+}
+
+impl StealMeter {
+    #[cfg(target_os = "linux")]
+    pub fn start() -> Self {
+        StealMeter {
+            start: steal_times(),
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn start() -> Self {
+        StealMeter {}
+    }
+
+    /// Stolen CPU time since `start`, in percent of all CPU time.
+    #[cfg(target_os = "linux")]
+    pub fn finish(self) -> Option<f64> {
+        let (total_before, steal_before) = self.start?;
+        let (total_after, steal_after) = steal_times()?;
+        let total = total_after.saturating_sub(total_before);
+        (total > 0).then(|| steal_after.saturating_sub(steal_before) as f64 / total as f64 * 100.0)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn finish(self) -> Option<f64> {
+        None
+    }
+}
+
+/// Total and steal jiffies from the aggregate `cpu` line of /proc/stat.
+#[cfg(target_os = "linux")]
+fn steal_times() -> Option<(u64, u64)> {
+    let stat = std::fs::read_to_string("/proc/stat").ok()?;
+    let line = stat.lines().find(|line| line.starts_with("cpu "))?;
+    let fields: Vec<u64> = line
+        .split_whitespace()
+        .skip(1)
+        .filter_map(|field| field.parse().ok())
+        .collect();
+    // user nice system idle iowait irq softirq steal
+    Some((fields.iter().sum(), *fields.get(7)?))
+}
@@ -0,0 +1,138 @@
+use anyhow::Result;
+use clap::Parser;
+use colored::Colorize;
+use metrics::ResultKind;
+use rari_benchmark::{errors, logging, metrics, schema, store, time_utils};
+use std::path::PathBuf;
+use store::{QueryFilter, Store};
+use tabled::{Table, Tabled};
+
+#[derive(Parser, Debug)]
+#[command(name = "query")]
+#[command(about = "Query the SQLite store of historical benchmark runs")]
+struct Args {
+    #[arg(long, default_value = "results")]
+    results_dir: PathBuf,
+    #[arg(long, value_name = "FILE")]
+    store: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Import result files from the results directory before querying"
+    )]
+    import: bool,
+    #[arg(short, long, value_enum)]
+    kind: Option<ResultKind>,
+    #[arg(long, value_name = "DATE")]
+    since: Option<String>,
+    #[arg(long, value_name = "DATE")]
+    until: Option<String>,
+    #[arg(short, long)]
+    framework: Option<String>,
+    #[arg(short, long)]
+    scenario: Option<String>,
+    #[arg(short, long)]
+    metric: Option<String>,
+    #[arg(short, long)]
+    limit: Option<usize>,
+    #[arg(long)]
+    json: bool,
+    #[command(flatten)]
+    log: logging::LogArgs,
+}
+
+#[derive(Tabled)]
+struct QueryRow {
+    #[tabled(rename = "Timestamp")]
+    timestamp: String,
+    #[tabled(rename = "Kind")]
+    kind: String,
+    #[tabled(rename = "Framework")]
+    framework: String,
+    #[tabled(rename = "Scenario")]
+    scenario: String,
+    #[tabled(rename = "Metric")]
+    metric: String,
+    #[tabled(rename = "Value")]
+    value: String,
+}
+
+async fn import_results(store: &mut Store, results_dir: &std::path::Path) -> Result<usize> {
+    let mut imported = 0;
+    for result in metrics::scan_results(results_dir).await? {
+        if store.has_source(&result.path)? {
+            continue;
+        }
+        let document = schema::read_json(&result.path).await?;
+        store.record_run(result.kind, &result.path, &document)?;
+        imported += 1;
+    }
+    Ok(imported)
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run().await {
+        errors::exit(e);
+    }
+}
+
+async fn run() -> Result<()> {
+    let args = Args::parse();
+    logging::init(&args.log, 0)?;
+
+    let store_path = args
+        .store
+        .clone()
+        .unwrap_or_else(|| store::default_path(&args.results_dir));
+    let mut store = Store::open(&store_path)?;
+
+    if args.import {
+        let imported = import_results(&mut store, &args.results_dir).await?;
+        if !args.json {
+            println!(
+                "{} Imported {} result file(s) into {}",
+                "📥".dimmed(),
+                imported,
+                store_path.display()
+            );
+        }
+    }
+
+    let rows = store.query(&QueryFilter {
+        kind: args.kind,
+        since: args.since,
+        until: args.until,
+        framework: args.framework,
+        scenario: args.scenario,
+        metric: args.metric,
+        limit: args.limit,
+    })?;
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+        return Ok(());
+    }
+
+    if rows.is_empty() {
+        println!("{}", "No matching metrics".dimmed());
+        return Ok(());
+    }
+
+    let count = rows.len();
+    let table_rows: Vec<QueryRow> = rows
+        .into_iter()
+        .map(|row| QueryRow {
+            timestamp: time_utils::to_local(&row.timestamp),
+            kind: row.kind,
+            framework: row.framework.unwrap_or_default(),
+            scenario: row.scenario.unwrap_or_default(),
+            metric: row.metric,
+            value: format!("{:.3}", row.value),
+        })
+        .collect();
+
+    println!("{}", Table::new(table_rows));
+    println!("{}", format!("{} row(s)", count).dimmed());
+
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct RandomArgs {
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Seed for everything random in the run, the --shuffle order and --jitter delays, to reproduce it [default: random; recorded with the results]"
+    )]
+    pub seed: Option<u64>,
+}
+
+impl RandomArgs {
+    /// The seed given, or a fresh one to record with the results.
+    pub fn resolve(&self) -> u64 {
+        self.seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos() as u64
+        })
+    }
+}
+
+/// splitmix64, so a seed gives the same numbers on every platform and
+/// version.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    /// Stream `n` of `seed`, independent of the others, for randomness that
+    /// must not depend on what else drew from the run's seed first.
+    pub fn stream(seed: u64, n: u64) -> Self {
+        Rng::new(Rng::new(seed ^ Rng::new(n).next_u64()).next_u64())
+    }
+
+    /// A new stream drawn from this one, e.g. one per connection.
+    pub fn fork(&mut self) -> Self {
+        Rng::new(self.next_u64())
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number from `0..=max`.
+    pub fn up_to(&mut self, max: u64) -> u64 {
+        self.next_u64() % max.saturating_add(1)
+    }
+
+    /// Fisher-Yates.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.up_to(i as u64) as usize);
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use crate::errors::{Error, ErrorKind};
+use crate::units::Millis;
+use anyhow::Result;
+use reqwest::header::{self, HeaderMap};
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Instant;
+
+/// How many redirects `follow` takes before giving up, as browsers roughly do.
+const FOLLOW_LIMIT: usize = 10;
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct RedirectArgs {
+    #[arg(
+        long,
+        value_name = "POLICY",
+        default_value = "follow",
+        help = "Redirects every scenario follows, unless a --script scenario sets its own: follow, none (time the redirect itself), or limit=N"
+    )]
+    pub redirects: RedirectPolicy,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum RedirectPolicy {
+    /// Up to 10 redirects, timed through to the final response.
+    #[default]
+    Follow,
+    /// The redirect response is the measurement.
+    None,
+    /// Up to N redirects; one more fails the request.
+    Limit(usize),
+}
+
+impl RedirectPolicy {
+    fn limit(self) -> usize {
+        match self {
+            RedirectPolicy::Follow => FOLLOW_LIMIT,
+            RedirectPolicy::None => 0,
+            RedirectPolicy::Limit(limit) => limit,
+        }
+    }
+
+    /// Whether a response with `status` is a successful measurement.
+    pub fn accepts(self, status: StatusCode) -> bool {
+        status.is_success() || (self == RedirectPolicy::None && status.is_redirection())
+    }
+}
+
+impl FromStr for RedirectPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "follow" => Ok(RedirectPolicy::Follow),
+            "none" => Ok(RedirectPolicy::None),
+            _ => s
+                .strip_prefix("limit=")
+                .unwrap_or(s)
+                .parse()
+                .map(RedirectPolicy::Limit)
+                .map_err(|_| format!("expected follow, none, or limit=N, got {:?}", s)),
+        }
+    }
+}
+
+impl TryFrom<String> for RedirectPolicy {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for RedirectPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RedirectPolicy::Follow => write!(f, "follow"),
+            RedirectPolicy::None => write!(f, "none"),
+            RedirectPolicy::Limit(limit) => write!(f, "limit={}", limit),
+        }
+    }
+}
+
+/// One redirect a request followed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hop {
+    pub status: u16,
+    pub from: String,
+    pub to: String,
+}
+
+/// The redirects the measured requests of a scenario went through.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Redirects {
+    /// The chain of the first redirected request.
+    pub chain: Vec<Hop>,
+    /// How many measured requests were redirected.
+    pub redirected: usize,
+    /// Average time from sending a redirected request to sending the request
+    /// for its final URL, included in the scenario's times.
+    pub added_ms: Millis,
+}
+
+impl Redirects {
+    /// The chain as `/ → 308 /en/`, with URLs on the first one's origin
+    /// shortened to their paths.
+    pub fn describe(&self) -> String {
+        let Some(first) = self.chain.first() else {
+            return String::new();
+        };
+        let origin = reqwest::Url::parse(&first.from)
+            .ok()
+            .map(|url| url.origin().ascii_serialization());
+        let short = |url: &str| match &origin {
+            Some(origin) => url.strip_prefix(origin.as_str()).unwrap_or(url).to_string(),
+            None => url.to_string(),
+        };
+        let mut described = short(&first.from);
+        for hop in &self.chain {
+            described.push_str(&format!(" → {} {}", hop.status, short(&hop.to)));
+        }
+        described
+    }
+}
+
+/// What a request went through before its final response.
+pub struct Sent {
+    pub response: reqwest::Response,
+    pub hops: Vec<Hop>,
+    /// Time spent on the redirects, before the final request went out.
+    pub added: Millis,
+}
+
+/// Sends `request` with `client`, which must not follow redirects itself,
+/// following them as `policy` allows. A 307 or 308 repeats the request as it
+/// was; any other redirect becomes a GET.
+pub async fn send(
+    client: &reqwest::Client,
+    request: reqwest::RequestBuilder,
+    policy: RedirectPolicy,
+) -> Result<Sent> {
+    let mut request = request.build()?;
+    let start = Instant::now();
+    let mut hops = Vec::new();
+    let mut added = Millis::default();
+    loop {
+        let repeated = request.try_clone();
+        let mut headers = request.headers().clone();
+        let response = client.execute(request).await?;
+        let status = response.status();
+        let location = response
+            .headers()
+            .get(header::LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .and_then(|location| response.url().join(location).ok());
+        let Some(location) = location.filter(|_| status.is_redirection()) else {
+            return Ok(Sent {
+                response,
+                hops,
+                added,
+            });
+        };
+        if policy == RedirectPolicy::None {
+            return Ok(Sent {
+                response,
+                hops,
+                added,
+            });
+        }
+        if hops.len() == policy.limit() {
+            return Err(Error::new(
+                ErrorKind::RequestsFailed,
+                format!(
+                    "more than {} redirect(s), the last to {}",
+                    policy.limit(),
+                    location
+                ),
+            )
+            .into());
+        }
+        hops.push(Hop {
+            status: status.as_u16(),
+            from: response.url().to_string(),
+            to: location.to_string(),
+        });
+        added = Millis::since(start);
+        request = match repeated {
+            Some(mut repeated)
+                if matches!(
+                    status,
+                    StatusCode::TEMPORARY_REDIRECT | StatusCode::PERMANENT_REDIRECT
+                ) =>
+            {
+                *repeated.url_mut() = location;
+                repeated
+            }
+            _ => {
+                strip_body_headers(&mut headers);
+                let mut get = reqwest::Request::new(Method::GET, location);
+                *get.headers_mut() = headers;
+                get
+            }
+        };
+    }
+}
+
+fn strip_body_headers(headers: &mut HeaderMap) {
+    for name in [
+        header::CONTENT_TYPE,
+        header::CONTENT_LENGTH,
+        header::CONTENT_ENCODING,
+    ] {
+        headers.remove(name);
+    }
+}
@@ -0,0 +1,550 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use colored::Colorize;
+use metrics::{ResultFile, ResultKind};
+use rari_benchmark::{
+    errors, influx, logging, metrics, schema, stats, targets, time_utils, tolerance, units,
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tabled::builder::Builder;
+use tokio::fs;
+use units::Unit;
+
+#[derive(Parser, Debug)]
+#[command(name = "report")]
+#[command(about = "Build reports from saved benchmark results")]
+struct Args {
+    #[command(subcommand)]
+    command: ReportCommand,
+    #[command(flatten)]
+    log: logging::LogArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum ReportCommand {
+    #[command(
+        about = "Merge the latest load, performance, build, image, and browser results into one report"
+    )]
+    Combine {
+        #[arg(long, default_value = "results")]
+        results_dir: PathBuf,
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[arg(
+            long,
+            value_name = "PCT",
+            default_value_t = tolerance::DEFAULT_TIE_PCT,
+            help = "Differences within ±PCT% are reported as a tie"
+        )]
+        tie_threshold: f64,
+    },
+    #[command(
+        about = "Export saved results as InfluxDB line protocol (stdout unless a target is given)"
+    )]
+    Influx {
+        #[arg(long, default_value = "results")]
+        results_dir: PathBuf,
+        #[arg(long, value_name = "DATE")]
+        since: Option<String>,
+        #[command(flatten)]
+        influx: influx::InfluxArgs,
+    },
+}
+
+#[derive(Debug, Serialize)]
+struct CombinedReport {
+    timestamp: String,
+    sources: BTreeMap<String, PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    loadtest: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    performance: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    buildtimes: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    images: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    browser: Option<Value>,
+    verdict: Verdict,
+}
+
+#[derive(Debug, Serialize)]
+struct Verdict {
+    winner: String,
+    tie_threshold_pct: f64,
+    wins: BTreeMap<String, usize>,
+    ties: usize,
+    checks: Vec<VerdictCheck>,
+}
+
+#[derive(Debug, Serialize)]
+struct VerdictCheck {
+    category: String,
+    metric: String,
+    values: BTreeMap<String, f64>,
+    /// Difference of the first target against each of the others.
+    difference_pct: BTreeMap<String, f64>,
+    winner: String,
+    #[serde(skip)]
+    unit: Unit,
+    #[serde(skip)]
+    lower_is_better: bool,
+}
+
+struct CheckSpec {
+    kind: ResultKind,
+    label: &'static str,
+    suffix: &'static str,
+    unit: Unit,
+}
+
+const CHECKS: [CheckSpec; 19] = [
+    CheckSpec {
+        kind: ResultKind::BuildTimes,
+        label: "Build time",
+        suffix: "duration_ms",
+        unit: Unit::Milliseconds,
+    },
+    CheckSpec {
+        kind: ResultKind::Performance,
+        label: "Avg response time",
+        suffix: "avg",
+        unit: Unit::Milliseconds,
+    },
+    CheckSpec {
+        kind: ResultKind::Performance,
+        label: "P95 response time",
+        suffix: "p95",
+        unit: Unit::Milliseconds,
+    },
+    CheckSpec {
+        kind: ResultKind::LoadTest,
+        label: "Throughput",
+        suffix: "requests.average",
+        unit: Unit::RequestsPerSec,
+    },
+    CheckSpec {
+        kind: ResultKind::LoadTest,
+        label: "Mean latency",
+        suffix: "latency.mean",
+        unit: Unit::Milliseconds,
+    },
+    CheckSpec {
+        kind: ResultKind::LoadTest,
+        label: "P95 latency",
+        suffix: "latency.p95",
+        unit: Unit::Milliseconds,
+    },
+    CheckSpec {
+        kind: ResultKind::LoadTest,
+        label: "Errors",
+        suffix: "errors",
+        unit: Unit::Count,
+    },
+    CheckSpec {
+        kind: ResultKind::LoadTest,
+        label: "Requests per CPU-second",
+        suffix: "requestsPerCpuSecond",
+        unit: Unit::Count,
+    },
+    CheckSpec {
+        kind: ResultKind::LoadTest,
+        label: "Max sustainable throughput",
+        suffix: "capacity.maxSustainableRps",
+        unit: Unit::RequestsPerSec,
+    },
+    CheckSpec {
+        kind: ResultKind::Images,
+        label: "Image size",
+        suffix: "sizeBytes",
+        unit: Unit::Bytes,
+    },
+    CheckSpec {
+        kind: ResultKind::Images,
+        label: "Cold start",
+        suffix: "coldStart.p50",
+        unit: Unit::Milliseconds,
+    },
+    CheckSpec {
+        kind: ResultKind::Browser,
+        label: "First Contentful Paint",
+        suffix: "fcp.p50",
+        unit: Unit::Milliseconds,
+    },
+    CheckSpec {
+        kind: ResultKind::Browser,
+        label: "Largest Contentful Paint",
+        suffix: "lcp.p50",
+        unit: Unit::Milliseconds,
+    },
+    CheckSpec {
+        kind: ResultKind::Browser,
+        label: "Time to Interactive",
+        suffix: "tti.p50",
+        unit: Unit::Milliseconds,
+    },
+    CheckSpec {
+        kind: ResultKind::Browser,
+        label: "Hydration",
+        suffix: "hydration.p50",
+        unit: Unit::Milliseconds,
+    },
+    CheckSpec {
+        kind: ResultKind::Browser,
+        label: "Lighthouse score",
+        suffix: "lighthouse.score",
+        unit: Unit::Count,
+    },
+    CheckSpec {
+        kind: ResultKind::Browser,
+        label: "Soft navigation",
+        suffix: "softNav.p50",
+        unit: Unit::Milliseconds,
+    },
+    CheckSpec {
+        kind: ResultKind::Browser,
+        label: "P95 page load under concurrency",
+        suffix: "loadTime.p95",
+        unit: Unit::Milliseconds,
+    },
+    CheckSpec {
+        kind: ResultKind::Browser,
+        label: "Browser page loads per second",
+        suffix: "pagesPerSec",
+        unit: Unit::RequestsPerSec,
+    },
+];
+
+fn mean_matching(metrics: &BTreeMap<String, f64>, framework: &str, suffix: &str) -> Option<f64> {
+    let prefix = format!("{}.", framework);
+    let dotted_suffix = format!(".{}", suffix);
+    let values: Vec<f64> = metrics
+        .iter()
+        .filter(|(path, _)| {
+            path.strip_prefix(&prefix)
+                .is_some_and(|rest| rest == suffix || rest.ends_with(&dotted_suffix))
+        })
+        .map(|(_, value)| *value)
+        .collect();
+    stats::mean(&values)
+}
+
+fn build_verdict(
+    latest: &BTreeMap<ResultKind, ResultFile>,
+    targets: &[String],
+    tie_pct: f64,
+) -> Verdict {
+    let mut checks = Vec::new();
+
+    for spec in &CHECKS {
+        let Some(result) = latest.get(&spec.kind) else {
+            continue;
+        };
+        let measured: Vec<(&String, f64)> = targets
+            .iter()
+            .filter_map(|target| {
+                Some((target, mean_matching(&result.metrics, target, spec.suffix)?))
+            })
+            .collect();
+        let Some(((subject, subject_value), others)) = measured.split_first() else {
+            continue;
+        };
+        if others.is_empty() {
+            continue;
+        }
+
+        let lower_is_better = metrics::lower_is_better(&format!("{}.{}", subject, spec.suffix));
+        let values: Vec<f64> = measured.iter().map(|(_, value)| *value).collect();
+        let winner = match tolerance::best(&values, lower_is_better, tie_pct) {
+            Some(index) => measured[index].0.clone(),
+            None => "tie".to_string(),
+        };
+
+        checks.push(VerdictCheck {
+            category: spec.kind.title().to_string(),
+            metric: spec.label.to_string(),
+            values: measured
+                .iter()
+                .map(|(target, value)| ((*target).clone(), *value))
+                .collect(),
+            difference_pct: others
+                .iter()
+                .map(|(target, value)| {
+                    (
+                        (*target).clone(),
+                        tolerance::difference_pct(*subject_value, *value),
+                    )
+                })
+                .collect(),
+            winner,
+            unit: spec.unit,
+            lower_is_better,
+        });
+    }
+
+    let mut wins: BTreeMap<String, usize> =
+        targets.iter().map(|target| (target.clone(), 0)).collect();
+    for check in &checks {
+        if let Some(count) = wins.get_mut(&check.winner) {
+            *count += 1;
+        }
+    }
+    let ties = checks.iter().filter(|c| c.winner == "tie").count();
+
+    let most = wins.values().copied().max().unwrap_or_default();
+    let leaders: Vec<&String> = wins
+        .iter()
+        .filter(|(_, count)| **count == most)
+        .map(|(target, _)| target)
+        .collect();
+    let winner = match leaders.as_slice() {
+        [leader] if most > 0 => (*leader).clone(),
+        _ => "tie".to_string(),
+    };
+
+    Verdict {
+        winner,
+        tie_threshold_pct: tie_pct,
+        wins,
+        ties,
+        checks,
+    }
+}
+
+/// Results only record target names, so the built-in targets get their usual
+/// icon and label back from the defaults.
+fn builtin_target(name: &str) -> Option<targets::Target> {
+    targets::defaults()
+        .into_iter()
+        .find(|target| target.name == name)
+}
+
+fn target_label(name: &str) -> String {
+    builtin_target(name).map_or_else(|| name.to_string(), |target| target.label().to_string())
+}
+
+fn display_target(name: &str) -> String {
+    builtin_target(name).map_or_else(|| name.to_string(), |target| target.display())
+}
+
+fn display_winner(winner: &str) -> String {
+    if winner == "tie" {
+        tolerance::TIE.to_string()
+    } else {
+        display_target(winner)
+    }
+}
+
+fn display_verdict(verdict: &Verdict, targets: &[String], tie_pct: f64) {
+    println!("\n{}", "🏆 Overall Verdict".bold());
+
+    if verdict.checks.is_empty() {
+        println!("  {}", "No comparable metrics found".dimmed());
+        return;
+    }
+
+    let others = targets.len().saturating_sub(1);
+    let mut header = vec!["Category".to_string(), "Metric".to_string()];
+    header.extend(targets.iter().map(|target| display_target(target)));
+    if others == 1 {
+        header.push("Difference".to_string());
+    } else {
+        header.extend(
+            targets
+                .iter()
+                .skip(1)
+                .map(|target| format!("vs {}", target_label(target))),
+        );
+    }
+    header.push("Winner".to_string());
+
+    let mut builder = Builder::default();
+    builder.push_record(header);
+    for check in &verdict.checks {
+        let mut row = vec![check.category.clone(), check.metric.clone()];
+        row.extend(targets.iter().map(|target| {
+            check
+                .values
+                .get(target)
+                .map_or_else(|| "—".to_string(), |value| check.unit.format(*value))
+        }));
+        let subject = check.values.get(&targets[0]);
+        row.extend(targets.iter().skip(1).map(|target| {
+            match (
+                subject,
+                check.values.get(target),
+                check.difference_pct.get(target),
+            ) {
+                (Some(subject), Some(other), Some(diff)) => tolerance::paint(
+                    tolerance::classify(*subject, *other, check.lower_is_better, tie_pct),
+                    &format!("{:+.1}%", diff),
+                )
+                .to_string(),
+                _ => "—".to_string(),
+            }
+        }));
+        row.push(display_winner(&check.winner));
+        builder.push_record(row);
+    }
+
+    println!("\n{}", builder.build());
+    let headline = if verdict.winner == "tie" {
+        format!("{} overall", display_winner(&verdict.winner))
+    } else {
+        format!(
+            "{} wins {} of {} checks",
+            display_winner(&verdict.winner),
+            verdict.wins[&verdict.winner],
+            verdict.checks.len()
+        )
+    };
+    let tally: Vec<String> = targets
+        .iter()
+        .map(|target| {
+            format!(
+                "{} {}",
+                verdict.wins.get(target).copied().unwrap_or_default(),
+                target_label(target)
+            )
+        })
+        .collect();
+    println!(
+        "\n  {} ({}, {} tied)",
+        headline.bold(),
+        tally.join(", "),
+        verdict.ties
+    );
+}
+
+async fn combine(results_dir: &Path, output: Option<PathBuf>, tie_pct: f64) -> Result<()> {
+    println!("{}", "📋 Combined Report".cyan().bold());
+
+    let mut latest: BTreeMap<ResultKind, ResultFile> = BTreeMap::new();
+    for result in metrics::scan_results(results_dir).await? {
+        latest.insert(result.kind, result);
+    }
+
+    if latest.is_empty() {
+        anyhow::bail!("No benchmark results found in {}", results_dir.display());
+    }
+
+    let mut sources = BTreeMap::new();
+    let mut documents = BTreeMap::new();
+    for kind in ResultKind::ALL {
+        match latest.get(&kind) {
+            Some(result) => {
+                println!(
+                    "{} {}: {}",
+                    "📄".dimmed(),
+                    kind.title(),
+                    result.path.display()
+                );
+                sources.insert(kind.prefix().to_string(), result.path.clone());
+                documents.insert(kind, schema::read_json(&result.path).await?);
+            }
+            None => tracing::warn!("{}: no results found", kind.title()),
+        }
+    }
+
+    let mut modes: Vec<&str> = latest
+        .values()
+        .filter_map(|result| result.mode.as_deref())
+        .collect();
+    modes.sort();
+    modes.dedup();
+    if modes.len() > 1 {
+        tracing::warn!(
+            "Results were recorded in different modes ({})",
+            modes.join(", ")
+        );
+    }
+
+    let mut targets: Vec<String> = Vec::new();
+    for document in documents.values() {
+        for name in metrics::target_names(document) {
+            if !targets.contains(&name) {
+                targets.push(name);
+            }
+        }
+    }
+
+    let verdict = build_verdict(&latest, &targets, tie_pct);
+    display_verdict(&verdict, &targets, tie_pct);
+
+    let now = SystemTime::now();
+    let report = CombinedReport {
+        timestamp: time_utils::format_timestamp(now),
+        sources,
+        loadtest: documents.remove(&ResultKind::LoadTest),
+        performance: documents.remove(&ResultKind::Performance),
+        buildtimes: documents.remove(&ResultKind::BuildTimes),
+        images: documents.remove(&ResultKind::Images),
+        browser: documents.remove(&ResultKind::Browser),
+        verdict,
+    };
+
+    let output = output.unwrap_or_else(|| {
+        results_dir.join(format!("report-{}.json", time_utils::format_date(now)))
+    });
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    let json = format!("{}\n", serde_json::to_string_pretty(&report)?);
+    fs::write(&output, json).await?;
+
+    println!("\n{} Report saved to {}", "💾".dimmed(), output.display());
+
+    Ok(())
+}
+
+async fn export_influx(
+    results_dir: &Path,
+    since: Option<&str>,
+    args: &influx::InfluxArgs,
+) -> Result<()> {
+    let mut lines = Vec::new();
+    for result in metrics::scan_results(results_dir).await? {
+        if since.is_some_and(|since| result.date.as_str() < since) {
+            continue;
+        }
+        let document = schema::read_json(&result.path).await?;
+        lines.push(influx::to_line_protocol(result.kind, &document));
+    }
+    let lines = lines.join("\n");
+
+    if args.influx_file.is_none() && args.influx_url.is_none() {
+        println!("{}", lines);
+        return Ok(());
+    }
+
+    influx::write(args, &lines).await
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run().await {
+        errors::exit(e);
+    }
+}
+
+async fn run() -> Result<()> {
+    let args = Args::parse();
+    logging::init(&args.log, 0)?;
+
+    match args.command {
+        ReportCommand::Combine {
+            results_dir,
+            output,
+            tie_threshold,
+        } => combine(&results_dir, output, tie_threshold).await,
+        ReportCommand::Influx {
+            results_dir,
+            since,
+            influx,
+        } => export_influx(&results_dir, since.as_deref(), &influx).await,
+    }
+}
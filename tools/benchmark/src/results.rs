@@ -0,0 +1,59 @@
+use crate::{errors, gate, interrupt};
+use anyhow::Result;
+use colored::Colorize;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// Writes `results` as pretty JSON to `results_dir/file_name`, creating the
+/// directory, and returns the path.
+#[tracing::instrument(name = "save", skip_all)]
+pub async fn save<T: Serialize>(
+    results: &T,
+    results_dir: &Path,
+    file_name: &str,
+) -> Result<PathBuf> {
+    fs::create_dir_all(results_dir).await?;
+
+    let filename = results_dir.join(file_name);
+    let json = format!("{}\n", serde_json::to_string_pretty(results)?);
+    fs::write(&filename, json).await?;
+
+    println!(
+        "\n{} Results saved to {}",
+        "💾".dimmed(),
+        filename.display()
+    );
+
+    Ok(filename)
+}
+
+/// A finished benchmark run.
+#[derive(Debug)]
+pub struct Run<T> {
+    pub results: T,
+    /// Where the results were saved.
+    pub path: PathBuf,
+    /// Interrupted with Ctrl-C; partial results skip the archive, the store,
+    /// and the gate.
+    pub partial: bool,
+    /// Whether the results passed `--fail-if` and `--thresholds`.
+    pub gate_passed: bool,
+    /// What failed along the way, as saved in the results' `errors`.
+    pub errors: Vec<errors::RunError>,
+}
+
+impl<T> Run<T> {
+    /// The exit code of a benchmark binary that ends with this run, unless 0.
+    pub fn exit_code(&self) -> Option<i32> {
+        if let Some(code) = errors::exit_code(&self.errors) {
+            Some(code)
+        } else if self.partial {
+            Some(interrupt::EXIT_CODE)
+        } else if !self.gate_passed {
+            Some(gate::REGRESSION_EXIT_CODE)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use crate::interrupt;
+use crate::targets::{self, Target};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tokio::process::Command;
+
+static RUNNING: Mutex<Option<(PathBuf, PathBuf)>> = Mutex::new(None);
+
+const NAMES: [&str; 2] = ["base", "head"];
+
+struct Revision {
+    reference: String,
+    sha: String,
+    path: PathBuf,
+}
+
+/// Temporary git worktrees of two revisions, removed again on drop or Ctrl-C.
+pub struct Worktrees {
+    repo: PathBuf,
+    root: PathBuf,
+    revisions: Vec<Revision>,
+}
+
+async fn git(repo: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .output()
+        .await
+        .context("Failed to run git. Is it installed?")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn remove_blocking(repo: &Path, root: &Path) {
+    for name in NAMES {
+        let _ = std::process::Command::new("git")
+            .arg("-C")
+            .arg(repo)
+            .args(["worktree", "remove", "--force"])
+            .arg(root.join(name))
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status();
+    }
+    let _ = std::fs::remove_dir_all(root);
+    let _ = std::process::Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["worktree", "prune"])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status();
+}
+
+async fn install(path: &Path, command: &str) -> Result<()> {
+    let mut parts = command.split_whitespace();
+    let Some(program) = parts.next() else {
+        return Ok(());
+    };
+    println!("{} {} in {}", "📦".dimmed(), command, path.display());
+    let output = Command::new(program)
+        .args(parts)
+        .current_dir(path)
+        .output()
+        .await
+        .with_context(|| format!("Failed to run `{}`", command))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "`{}` failed in {} ({}): {}",
+            command,
+            path.display(),
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// The commit each of `refs` names in `repo`, in order.
+pub async fn resolve(repo: &Path, refs: &[String]) -> Result<Vec<String>> {
+    let mut shas = Vec::new();
+    for reference in refs {
+        let sha = git(
+            repo,
+            &[
+                "rev-parse",
+                "--verify",
+                &format!("{}^{{commit}}", reference),
+            ],
+        )
+        .await
+        .with_context(|| format!("Unknown revision '{}'", reference))?;
+        shas.push(sha);
+    }
+    Ok(shas)
+}
+
+/// Checks out `refs` (base first, then head) of `repo` into temporary
+/// worktrees and installs their dependencies with `install_command`.
+pub async fn checkout(repo: &Path, refs: &[String], install_command: &str) -> Result<Worktrees> {
+    let repo = std::fs::canonicalize(repo)
+        .with_context(|| format!("Failed to resolve {}", repo.display()))?;
+    let root =
+        std::env::temp_dir().join(format!("rari-benchmark-revisions-{}", std::process::id()));
+
+    interrupt::on_interrupt("removing worktrees", || {
+        if let Some((repo, root)) = RUNNING.lock().unwrap().take() {
+            remove_blocking(&repo, &root);
+        }
+    });
+
+    *RUNNING.lock().unwrap() = Some((repo.clone(), root.clone()));
+    let mut worktrees = Worktrees {
+        repo,
+        root,
+        revisions: Vec::new(),
+    };
+
+    let shas = resolve(&worktrees.repo, refs).await?;
+    for ((name, reference), sha) in NAMES.iter().zip(refs).zip(shas) {
+        let path = worktrees.root.join(name);
+        git(
+            &worktrees.repo,
+            &["worktree", "add", "--detach", &path.to_string_lossy(), &sha],
+        )
+        .await?;
+        println!(
+            "{} Checked out {} ({}) to {}",
+            "🌿".dimmed(),
+            reference,
+            &sha[..sha.len().min(8)],
+            path.display()
+        );
+        worktrees.revisions.push(Revision {
+            reference: reference.clone(),
+            sha,
+            path,
+        });
+    }
+
+    for revision in &worktrees.revisions {
+        install(&revision.path, install_command).await?;
+    }
+
+    Ok(worktrees)
+}
+
+impl Worktrees {
+    /// One target per revision, both based on the built-in rari target and
+    /// pointing at `app_dir` inside their worktree.
+    fn targets(&self, app_dir: &Path) -> Vec<Target> {
+        let defaults = targets::defaults();
+        self.revisions
+            .iter()
+            .zip(NAMES)
+            .zip(&defaults)
+            .map(|((revision, name), default)| Target {
+                name: name.to_string(),
+                label: Some(format!(
+                    "rari@{} ({})",
+                    revision.reference,
+                    &revision.sha[..revision.sha.len().min(8)]
+                )),
+                icon: None,
+                dir: revision.path.join(app_dir),
+                port: default.port,
+                ..defaults[0].clone()
+            })
+            .collect()
+    }
+
+    /// Writes the revision targets to a targets file for the benchmark steps.
+    pub fn write_targets(&self, app_dir: &Path) -> Result<PathBuf> {
+        let path = self.root.join("targets.json");
+        let json = serde_json::json!({ "targets": self.targets(app_dir) });
+        std::fs::write(&path, serde_json::to_string_pretty(&json)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+impl Drop for Worktrees {
+    fn drop(&mut self) {
+        println!(
+            "{} Removing worktrees in {}",
+            "🌿".dimmed(),
+            self.root.display()
+        );
+        remove_blocking(&self.repo, &self.root);
+        RUNNING.lock().unwrap().take();
+    }
+}
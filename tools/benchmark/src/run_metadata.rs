@@ -0,0 +1,183 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tokio::process::Command;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_sha: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_branch: Option<String>,
+    #[serde(default)]
+    pub git_dirty: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fs_cache: Option<String>,
+    /// Per-target environment overrides from the targets file.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub target_env: BTreeMap<String, BTreeMap<String, String>>,
+    /// Per-target CPU pinning and nice values, e.g. `cpus 0-3, nice -5`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub target_scheduling: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_cpus: Option<String>,
+    /// Base URLs of targets that were benchmarked remotely.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub target_urls: BTreeMap<String, String>,
+    /// Seed command and endpoints run before measuring.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub seed: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host_load: Option<HostLoad>,
+    /// Host the servers were started on with `--ssh`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_host: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<NetworkProfile>,
+    /// The `--seed` of everything random in the run, given or drawn.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub random_seed: Option<u64>,
+    /// The `--profile` preset the run was scaled with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_version: Option<String>,
+    /// Per-target versions of the apps' runtime dependencies, e.g. `next`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub target_versions: BTreeMap<String, BTreeMap<String, String>>,
+    /// Per-target framework and version, e.g. `next 15.1.0`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub framework_versions: BTreeMap<String, String>,
+    #[serde(default)]
+    pub tool_version: String,
+}
+
+/// How busy the host was just before the run started.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostLoad {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_pct: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_used_pct: Option<f64>,
+    /// 1, 5 and 15 minute load averages.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub load_avg: Vec<f64>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub busy: bool,
+}
+
+/// Network conditions simulated between the load generator and the servers.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkProfile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preset: Option<String>,
+    pub rtt_ms: u64,
+    pub jitter_ms: u64,
+    /// Per-connection bandwidth in kbit/s; unlimited when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_kbps: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upload_kbps: Option<u64>,
+}
+
+async fn git(repo_dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_dir)
+        .args(args)
+        .output()
+        .await
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+pub async fn collect(repo_dir: &Path, label: Option<String>) -> RunMetadata {
+    let git_sha = git(repo_dir, &["rev-parse", "HEAD"]).await;
+    let git_branch = git(repo_dir, &["rev-parse", "--abbrev-ref", "HEAD"])
+        .await
+        .filter(|branch| branch != "HEAD");
+    let git_dirty = git(repo_dir, &["status", "--porcelain", "--", "apps"])
+        .await
+        .is_some_and(|status| !status.is_empty());
+
+    RunMetadata {
+        label,
+        git_sha,
+        git_branch,
+        git_dirty,
+        hostname: hostname::get()
+            .ok()
+            .map(|name| name.to_string_lossy().into_owned()),
+        mode: None,
+        fs_cache: None,
+        target_env: BTreeMap::new(),
+        target_scheduling: BTreeMap::new(),
+        client_cpus: None,
+        target_urls: BTreeMap::new(),
+        seed: Vec::new(),
+        host_load: None,
+        ssh_host: None,
+        network: None,
+        random_seed: None,
+        profile: None,
+        node_version: None,
+        target_versions: BTreeMap::new(),
+        framework_versions: BTreeMap::new(),
+        tool_version: env!("CARGO_PKG_VERSION").to_string(),
+    }
+}
+
+impl RunMetadata {
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(label) = &self.label {
+            parts.push(label.clone());
+        }
+        if let Some(sha) = &self.git_sha {
+            let short = &sha[..sha.len().min(8)];
+            let dirty = if self.git_dirty { "-dirty" } else { "" };
+            match &self.git_branch {
+                Some(branch) => parts.push(format!("{}@{}{}", branch, short, dirty)),
+                None => parts.push(format!("{}{}", short, dirty)),
+            }
+        }
+        if let Some(hostname) = &self.hostname {
+            parts.push(hostname.clone());
+        }
+        if let Some(mode) = &self.mode {
+            parts.push(format!("{} mode", mode));
+        }
+        if let Some(fs_cache) = &self.fs_cache {
+            parts.push(format!("fs cache: {}", fs_cache));
+        }
+        if let Some(network) = &self.network {
+            parts.push(format!(
+                "network {}",
+                network.preset.as_deref().unwrap_or("shaped")
+            ));
+        }
+        if let Some(profile) = &self.profile {
+            parts.push(format!("{} profile", profile));
+        }
+        if let Some(node_version) = &self.node_version {
+            parts.push(format!("node {}", node_version));
+        }
+        parts.extend(self.framework_versions.values().cloned());
+        parts.push(format!("v{}", self.tool_version));
+        parts.join(" · ")
+    }
+}
@@ -0,0 +1,80 @@
+use crate::config;
+use colored::Colorize;
+
+/// z for a two-sided 95% confidence level.
+const CONFIDENCE_Z: f64 = 1.96;
+/// z for 80% power, the usual chance of catching a difference that is there.
+const POWER_Z: f64 = 0.8416;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct SampleSizeArgs {
+    #[arg(
+        long,
+        value_name = "PCT",
+        default_value_t = 5.0,
+        value_parser = config::positive,
+        help = "Smallest difference between targets, in percent, the samples should be able to detect; the run says how many that takes"
+    )]
+    pub detect: f64,
+    #[arg(
+        long,
+        help = "Keep measuring a scenario until it has the samples --detect needs, up to --max-requests"
+    )]
+    pub extend_samples: bool,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 1000,
+        requires = "extend_samples",
+        help = "Most requests --extend-samples measures per scenario"
+    )]
+    pub max_requests: usize,
+}
+
+impl SampleSizeArgs {
+    /// Samples per target that tell apart two targets whose means differ by
+    /// `--detect`, given the coefficient of variation seen so far.
+    pub fn needed(&self, cv_pct: f64) -> usize {
+        needed(cv_pct, self.detect)
+    }
+
+    /// How many more requests `--extend-samples` should measure after
+    /// `samples` successful ones out of `requests`, or 0 to stop.
+    pub fn more(&self, cv_pct: Option<f64>, samples: usize, requests: usize) -> usize {
+        let Some(cv_pct) = cv_pct.filter(|_| self.extend_samples) else {
+            return 0;
+        };
+        self.needed(cv_pct)
+            .min(self.max_requests)
+            .saturating_sub(samples)
+            .min(self.max_requests.saturating_sub(requests))
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "{}% difference at 95% confidence and 80% power",
+            self.detect
+        )
+    }
+}
+
+/// Samples per group for a two-sample comparison of means that differ by
+/// `detect_pct`, for measurements varying by `cv_pct`.
+pub fn needed(cv_pct: f64, detect_pct: f64) -> usize {
+    let ratio = cv_pct / detect_pct;
+    (2.0 * (CONFIDENCE_Z + POWER_Z).powi(2) * ratio * ratio)
+        .ceil()
+        .max(2.0) as usize
+}
+
+/// One line on a measurement with too few samples.
+pub fn warning(cv_pct: f64, samples: usize, needed: usize, detect_pct: f64) -> String {
+    format!(
+        "  {} CV {:.1}%: {} samples, {} needed to detect a {}% difference",
+        "⚠️".yellow(),
+        cv_pct,
+        samples,
+        needed,
+        detect_pct
+    )
+}
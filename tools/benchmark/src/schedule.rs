@@ -0,0 +1,245 @@
+use anyhow::Result;
+use clap::Parser;
+use colored::Colorize;
+use rari_benchmark::{errors, gate, logging, sibling, time_utils};
+use std::ffi::OsString;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::process::Command;
+
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+#[derive(Parser, Debug)]
+#[command(name = "schedule")]
+#[command(
+    about = "Run the full benchmark pipeline on a recurring schedule, storing every run and applying the regression gate"
+)]
+struct Args {
+    #[arg(
+        long,
+        value_name = "DURATION",
+        required_unless_present = "at",
+        conflicts_with = "at",
+        help = "Start a run every DURATION, e.g. 30m, 6h or 1d (the first one right away)"
+    )]
+    every: Option<Every>,
+    #[arg(
+        long,
+        value_name = "HH:MM",
+        help = "Start a run once a day at this time (UTC)"
+    )]
+    at: Option<TimeOfDay>,
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Stop after N runs [default: run until interrupted]"
+    )]
+    runs: Option<u64>,
+    #[command(flatten)]
+    log: logging::LogArgs,
+    #[arg(
+        last = true,
+        value_name = "FULL_ARGS",
+        help = "Arguments passed to `full` on every run, e.g. -- --load-thresholds load.thresholds"
+    )]
+    full_args: Vec<OsString>,
+}
+
+#[derive(Debug, Clone)]
+struct Every {
+    interval: Duration,
+    text: String,
+}
+
+impl FromStr for Every {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let number: u64 = number
+            .parse()
+            .map_err(|_| format!("expected a duration like 30m, 6h or 1d, got '{}'", s))?;
+        let unit_secs = match unit {
+            "s" => 1,
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => DAY_SECS,
+            _ => return Err(format!("unknown unit '{}' (use s, m, h or d)", unit)),
+        };
+        let secs = number
+            .checked_mul(unit_secs)
+            .ok_or_else(|| format!("interval '{}' is too long", s))?;
+        if secs == 0 {
+            return Err("the interval must be longer than zero".to_string());
+        }
+        Ok(Every {
+            interval: Duration::from_secs(secs),
+            text: s.to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TimeOfDay {
+    secs: u64,
+}
+
+impl FromStr for TimeOfDay {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("expected HH:MM, got '{}'", s);
+        let (hours, minutes) = s.split_once(':').ok_or_else(invalid)?;
+        let hours: u64 = hours.parse().map_err(|_| invalid())?;
+        let minutes: u64 = minutes.parse().map_err(|_| invalid())?;
+        if hours > 23 || minutes > 59 {
+            return Err(invalid());
+        }
+        Ok(TimeOfDay {
+            secs: hours * 3600 + minutes * 60,
+        })
+    }
+}
+
+impl TimeOfDay {
+    /// Time left until the next occurrence; zero means now.
+    fn until_next(self) -> Duration {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        Duration::from_secs((self.secs + DAY_SECS - now % DAY_SECS) % DAY_SECS)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Passed,
+    Regressed,
+    Failed,
+}
+
+async fn run_full(binary: &Path, args: &[OsString]) -> Outcome {
+    let status = Command::new(binary).args(args).status().await;
+    match status {
+        Ok(status) if status.success() => Outcome::Passed,
+        Ok(status) if status.code() == Some(gate::REGRESSION_EXIT_CODE) => Outcome::Regressed,
+        Ok(status) => {
+            println!("{} full exited with {}", "❌".red(), status);
+            Outcome::Failed
+        }
+        Err(e) => {
+            println!("{} Failed to run {}: {}", "❌".red(), binary.display(), e);
+            Outcome::Failed
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run().await {
+        errors::exit(e);
+    }
+}
+
+async fn run() -> Result<()> {
+    let args = Args::parse();
+    logging::init(&args.log, 0)?;
+    // Every run logs the way the schedule does.
+    let mut full_args = args.log.forward();
+    full_args.extend(args.full_args.iter().cloned());
+    let binary = sibling::binary("full")?;
+
+    let schedule = match (&args.every, args.at) {
+        (Some(every), _) => format!("every {}", every.text),
+        (None, Some(at)) => format!(
+            "daily at {:02}:{:02} UTC",
+            at.secs / 3600,
+            at.secs % 3600 / 60
+        ),
+        (None, None) => unreachable!("clap requires --every or --at"),
+    };
+    println!(
+        "{}",
+        format!("⏰ Scheduled benchmarks, {}", schedule)
+            .cyan()
+            .bold()
+    );
+
+    let mut outcomes = Vec::new();
+    let mut next = Instant::now();
+    loop {
+        let wait = match args.at {
+            // A run that finishes within its own start second must not repeat.
+            Some(at) => match at.until_next() {
+                wait if wait.is_zero() && !outcomes.is_empty() => Duration::from_secs(DAY_SECS),
+                wait => wait,
+            },
+            None => next.saturating_duration_since(Instant::now()),
+        };
+        if !wait.is_zero() {
+            println!(
+                "{} Next run at {}",
+                "💤".dimmed(),
+                time_utils::format_local(SystemTime::now() + wait)
+            );
+            tokio::time::sleep(wait).await;
+        }
+
+        let started = Instant::now();
+        if let Some(every) = &args.every {
+            next = started + every.interval;
+        }
+        println!(
+            "\n{}",
+            format!(
+                "▶ Scheduled run {} at {}",
+                outcomes.len() + 1,
+                time_utils::format_local(SystemTime::now())
+            )
+            .cyan()
+            .bold()
+        );
+
+        let outcome = run_full(&binary, &full_args).await;
+        let minutes = started.elapsed().as_secs_f64() / 60.0;
+        match outcome {
+            Outcome::Passed => println!("{} Run passed in {:.1} min", "✅".green(), minutes),
+            Outcome::Regressed => println!(
+                "{} Run finished in {:.1} min but the regression gate failed",
+                "🚨".red(),
+                minutes
+            ),
+            Outcome::Failed => println!("{} Run failed after {:.1} min", "❌".red(), minutes),
+        }
+        outcomes.push(outcome);
+
+        if args.runs == Some(outcomes.len() as u64) {
+            break;
+        }
+        if args.every.is_some() && Instant::now() > next {
+            tracing::warn!("The run took longer than --every; starting the next one now");
+        }
+    }
+
+    let count = |outcome| outcomes.iter().filter(|o| **o == outcome).count();
+    println!(
+        "\n{} {} runs: {} passed, {} regressed, {} failed",
+        "📋".dimmed(),
+        outcomes.len(),
+        count(Outcome::Passed),
+        count(Outcome::Regressed),
+        count(Outcome::Failed)
+    );
+    if count(Outcome::Failed) > 0 {
+        anyhow::bail!("{} scheduled run(s) failed", count(Outcome::Failed));
+    }
+    if count(Outcome::Regressed) > 0 {
+        std::process::exit(gate::REGRESSION_EXIT_CODE);
+    }
+
+    Ok(())
+}
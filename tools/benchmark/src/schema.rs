@@ -0,0 +1,40 @@
+use crate::errors::{Error, ErrorKind};
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::path::Path;
+use tokio::fs;
+
+pub use rari_benchmark_results::schema::{VERSION, migrate, parse, parse_as, version};
+
+/// Reads a results file of any version as a current document.
+pub async fn read_json(path: &Path) -> Result<Value> {
+    let contents = fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut json: Value = serde_json::from_str(&contents).with_context(|| {
+        Error::new(
+            ErrorKind::ParseError,
+            format!("Failed to parse {}", path.display()),
+        )
+    })?;
+    migrate(&mut json).with_context(|| {
+        Error::new(
+            ErrorKind::ParseError,
+            format!("Failed to read {}", path.display()),
+        )
+    })?;
+    Ok(json)
+}
+
+/// Reads a results file of any version into the current results type, e.g.
+/// `performance::BenchmarkResults`.
+pub async fn read<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let json = read_json(path).await?;
+    serde_json::from_value(json).with_context(|| {
+        Error::new(
+            ErrorKind::ParseError,
+            format!("Failed to read {}", path.display()),
+        )
+    })
+}
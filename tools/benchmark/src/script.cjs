@@ -0,0 +1,75 @@
+// Run by `performance --script <FILE>` with the file as its only argument.
+// Loads the scenario module and answers the benchmark over stdin and stdout,
+// one JSON object per line.
+const path = require('node:path')
+const process = require('node:process')
+const readline = require('node:readline')
+const { pathToFileURL } = require('node:url')
+
+// stdout carries the replies, so the module's logging goes to stderr.
+console.log = console.error
+console.info = console.error
+
+function describe(error) {
+  return error instanceof Error ? error.message : String(error)
+}
+
+async function handle(scenarios, message) {
+  if (message.op === 'list') {
+    return {
+      scenarios: scenarios.map((scenario, index) => ({
+        name: scenario.name ?? `Script scenario ${index + 1}`,
+        validates: typeof scenario.validate === 'function',
+        redirects: scenario.redirects === undefined ? undefined : String(scenario.redirects),
+      })),
+    }
+  }
+
+  const scenario = scenarios[message.scenario]
+  if (message.op === 'request') {
+    const request = typeof scenario.request === 'function'
+      ? await scenario.request({ target: message.target, baseUrl: message.baseUrl, index: message.index })
+      : { path: scenario.path ?? '/' }
+    return typeof request === 'string' ? { path: request } : request
+  }
+  if (message.op === 'validate') {
+    try {
+      const valid = await scenario.validate({
+        target: message.target,
+        status: message.status,
+        headers: message.headers,
+        body: message.body,
+      })
+      if (valid === false)
+        return { invalid: 'rejected by validate()' }
+      return typeof valid === 'string' ? { invalid: valid } : {}
+    }
+    catch (error) {
+      return { invalid: describe(error) }
+    }
+  }
+  throw new Error(`unknown op ${message.op}`)
+}
+
+async function main() {
+  const loaded = await import(pathToFileURL(path.resolve(process.argv[1])).href)
+  const module = loaded.default ?? loaded
+  const scenarios = module.scenarios ?? [module]
+
+  const lines = readline.createInterface({ input: process.stdin })
+  for await (const line of lines) {
+    let reply
+    try {
+      reply = await handle(scenarios, JSON.parse(line))
+    }
+    catch (error) {
+      reply = { error: describe(error) }
+    }
+    process.stdout.write(`${JSON.stringify(reply)}\n`)
+  }
+}
+
+main().catch((error) => {
+  console.error(error)
+  process.exit(1)
+})
@@ -0,0 +1,173 @@
+use crate::errors::{self, Error, ErrorKind};
+use crate::redirects::RedirectPolicy;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+
+const DRIVER: &str = include_str!("script.cjs");
+
+/// One scenario the script defines.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScriptScenario {
+    pub name: String,
+    /// Whether the script checks each response.
+    pub validates: bool,
+    /// The scenario's own redirect policy, over `--redirects`.
+    #[serde(default)]
+    pub redirects: Option<RedirectPolicy>,
+}
+
+/// What the script's `request()` asked for.
+#[derive(Debug, Deserialize)]
+pub struct ScriptRequest {
+    pub path: String,
+    #[serde(default)]
+    pub method: Option<String>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub body: Option<String>,
+}
+
+/// A JavaScript scenario module, run by Node.js for as long as the benchmark
+/// needs it. The module exports `scenarios`, a list of `{ name, request,
+/// validate }`, or a single one of them.
+pub struct Script {
+    path: PathBuf,
+    child: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+    pub scenarios: Vec<ScriptScenario>,
+}
+
+impl Script {
+    pub async fn start(path: &Path) -> Result<Self> {
+        if !path.is_file() {
+            return Err(Error::new(
+                ErrorKind::InvalidConfig,
+                format!("Script {} not found", path.display()),
+            )
+            .into());
+        }
+        let mut child = Command::new("node")
+            .arg("-e")
+            .arg(DRIVER)
+            .arg(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| errors::spawn_error(e, "Failed to run node for --script"))?;
+        let stdin = child.stdin.take().context("No stdin for the script")?;
+        let stdout = child.stdout.take().context("No stdout for the script")?;
+
+        let mut script = Script {
+            path: path.to_path_buf(),
+            child,
+            stdin,
+            stdout: BufReader::new(stdout).lines(),
+            scenarios: Vec::new(),
+        };
+        let listed = script.call(json!({ "op": "list" })).await?;
+        script.scenarios = serde_json::from_value(listed["scenarios"].clone())
+            .with_context(|| format!("{}: invalid scenarios", path.display()))?;
+        if script.scenarios.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidConfig,
+                format!("{} defines no scenarios", path.display()),
+            )
+            .into());
+        }
+        Ok(script)
+    }
+
+    async fn call(&mut self, message: Value) -> Result<Value> {
+        let mut line = serde_json::to_string(&message)?;
+        line.push('\n');
+        self.stdin.write_all(line.as_bytes()).await?;
+        self.stdin.flush().await?;
+
+        let Some(reply) = self.stdout.next_line().await? else {
+            let status = self.child.wait().await?;
+            return Err(Error::new(
+                ErrorKind::ToolFailed,
+                format!("{} exited with {}", self.path.display(), status),
+            )
+            .into());
+        };
+        let reply: Value = serde_json::from_str(&reply).with_context(|| {
+            Error::new(
+                ErrorKind::ParseError,
+                format!("{}: unexpected output {:?}", self.path.display(), reply),
+            )
+        })?;
+        if let Some(error) = reply["error"].as_str() {
+            return Err(Error::new(
+                ErrorKind::ToolFailed,
+                format!("{}: {}", self.path.display(), error),
+            )
+            .into());
+        }
+        Ok(reply)
+    }
+
+    /// The `index`-th request of a scenario against one target.
+    pub async fn request(
+        &mut self,
+        scenario: usize,
+        target: &str,
+        base_url: &str,
+        index: usize,
+    ) -> Result<ScriptRequest> {
+        let reply = self
+            .call(json!({
+                "op": "request",
+                "scenario": scenario,
+                "target": target,
+                "baseUrl": base_url,
+                "index": index,
+            }))
+            .await?;
+        let request: ScriptRequest = serde_json::from_value(reply).with_context(|| {
+            format!(
+                "{}: request() must return a path or {{ path, method, headers, body }}",
+                self.path.display()
+            )
+        })?;
+        if !request.path.starts_with('/') {
+            anyhow::bail!(
+                "{}: request() returned {:?}, which does not start with /",
+                self.path.display(),
+                request.path
+            );
+        }
+        Ok(request)
+    }
+
+    /// Why the script rejected a response, if it did.
+    pub async fn validate(
+        &mut self,
+        scenario: usize,
+        target: &str,
+        status: u16,
+        headers: BTreeMap<String, String>,
+        body: &str,
+    ) -> Result<Option<String>> {
+        let reply = self
+            .call(json!({
+                "op": "validate",
+                "scenario": scenario,
+                "target": target,
+                "status": status,
+                "headers": headers,
+                "body": body,
+            }))
+            .await?;
+        Ok(reply["invalid"].as_str().map(str::to_string))
+    }
+}
@@ -0,0 +1,89 @@
+use crate::errors::{Error, ErrorKind};
+use crate::hooks;
+use crate::targets::Target;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::time::Duration;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct SeedArgs {
+    #[arg(
+        long,
+        value_name = "COMMAND",
+        help = "Shell command run once before measuring to load the same data for every target"
+    )]
+    pub seed_command: Option<String>,
+    #[arg(
+        long = "seed-endpoint",
+        value_name = "PATH",
+        help = "Path POSTed to every target once before measuring, e.g. /api/seed (repeatable)"
+    )]
+    pub seed_endpoints: Vec<String>,
+}
+
+impl SeedArgs {
+    pub fn is_empty(&self) -> bool {
+        self.seed_command.is_none() && self.seed_endpoints.is_empty()
+    }
+
+    /// Runs the seed command, then posts every seed endpoint to every target.
+    /// Targets must already be up.
+    #[tracing::instrument(name = "seed", skip_all)]
+    pub async fn run(&self, targets: &[Target]) -> Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        println!("\n{}", "🌱 Seeding data".bold());
+
+        if let Some(command) = &self.seed_command {
+            let urls = targets
+                .iter()
+                .map(|target| format!("{}={}", target.name, target.base_url()))
+                .collect::<Vec<_>>()
+                .join(" ");
+            hooks::run("seed", command, &[("BENCHMARK_TARGET_URLS", urls)]).await?;
+        }
+
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()?;
+        for target in targets {
+            for endpoint in &self.seed_endpoints {
+                let url = format!("{}/{}", target.base_url(), endpoint.trim_start_matches('/'));
+                let response = client.post(&url).send().await.with_context(|| {
+                    Error::new(
+                        ErrorKind::ServerUnreachable,
+                        format!("Failed to seed {}", url),
+                    )
+                    .target(&target.name)
+                })?;
+                let status = response.status();
+                if !status.is_success() {
+                    return Err(Error::new(
+                        ErrorKind::RequestsFailed,
+                        format!("Seeding {} returned {}", url, status),
+                    )
+                    .target(&target.name)
+                    .into());
+                }
+                println!("  {} {} {}", "✅".green(), target.label(), url.dimmed());
+            }
+        }
+        Ok(())
+    }
+
+    /// What was seeded, for the run metadata.
+    pub fn describe(&self) -> Vec<String> {
+        self.seed_command
+            .iter()
+            .cloned()
+            .chain(
+                self.seed_endpoints
+                    .iter()
+                    .map(|endpoint| format!("POST {}", endpoint)),
+            )
+            .collect()
+    }
+}
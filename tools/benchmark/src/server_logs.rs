@@ -0,0 +1,171 @@
+use crate::targets::Target;
+use crate::time_utils;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// Case-insensitive markers of a line that reports a server-side failure.
+const ERROR_MARKERS: [&str; 5] = ["error", "exception", "panic", "fatal", "unhandled"];
+const MAX_EXCERPT_LINES: usize = 20;
+const MAX_LINE_CHARS: usize = 300;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerLog {
+    pub path: PathBuf,
+    pub started_at: String,
+    pub lines: usize,
+    pub error_lines: usize,
+    /// Error lines per second since `startedAt`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors_per_second: Vec<usize>,
+    /// The first error lines, with the time they were read.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excerpt: Vec<LogLine>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogLine {
+    pub at: String,
+    pub line: String,
+}
+
+pub struct LogWatcher {
+    stop: watch::Sender<bool>,
+    task: JoinHandle<BTreeMap<String, ServerLog>>,
+}
+
+fn is_error_line(line: &str) -> bool {
+    let line = line.to_lowercase();
+    ERROR_MARKERS.iter().any(|marker| line.contains(marker))
+}
+
+struct Tail {
+    name: String,
+    offset: u64,
+    partial: String,
+    log: ServerLog,
+}
+
+impl Tail {
+    /// Reads the complete lines appended since the last call.
+    fn read(&mut self, started: Instant) {
+        let Ok(mut file) = std::fs::File::open(&self.log.path) else {
+            return;
+        };
+        let mut appended = Vec::new();
+        if file.seek(SeekFrom::Start(self.offset)).is_err()
+            || file.read_to_end(&mut appended).is_err()
+        {
+            return;
+        }
+        self.offset += appended.len() as u64;
+        self.partial.push_str(&String::from_utf8_lossy(&appended));
+
+        let Some(end) = self.partial.rfind('\n') else {
+            return;
+        };
+        let complete: String = self.partial.drain(..=end).collect();
+        let second = started.elapsed().as_secs() as usize;
+        for line in complete.lines().filter(|line| !line.trim().is_empty()) {
+            self.log.lines += 1;
+            if !is_error_line(line) {
+                continue;
+            }
+            self.log.error_lines += 1;
+            if self.log.errors_per_second.len() <= second {
+                self.log.errors_per_second.resize(second + 1, 0);
+            }
+            self.log.errors_per_second[second] += 1;
+            if self.log.excerpt.len() < MAX_EXCERPT_LINES {
+                self.log.excerpt.push(LogLine {
+                    at: time_utils::format_timestamp(SystemTime::now()),
+                    line: line.trim_end().chars().take(MAX_LINE_CHARS).collect(),
+                });
+            }
+        }
+    }
+}
+
+impl LogWatcher {
+    /// Follows each `(target name, log file)` from its current end, counting
+    /// lines and error lines until `finish`.
+    pub fn start(logs: Vec<(String, PathBuf)>) -> Self {
+        let started_at = time_utils::format_timestamp(SystemTime::now());
+        let mut tails: Vec<Tail> = logs
+            .into_iter()
+            .map(|(name, path)| Tail {
+                name,
+                offset: std::fs::metadata(&path).map_or(0, |metadata| metadata.len()),
+                partial: String::new(),
+                log: ServerLog {
+                    path,
+                    started_at: started_at.clone(),
+                    ..ServerLog::default()
+                },
+            })
+            .collect();
+        let (stop, mut stopped) = watch::channel(false);
+        let task = tokio::spawn(async move {
+            let started = Instant::now();
+            loop {
+                let stopping = tokio::select! {
+                    _ = stopped.changed() => true,
+                    _ = tokio::time::sleep(Duration::from_secs(1)) => false,
+                };
+                for tail in &mut tails {
+                    tail.read(started);
+                }
+                if stopping {
+                    break;
+                }
+            }
+            tails
+                .into_iter()
+                .map(|tail| (tail.name, tail.log))
+                .collect()
+        });
+        LogWatcher { stop, task }
+    }
+
+    pub async fn finish(self) -> BTreeMap<String, ServerLog> {
+        let _ = self.stop.send(true);
+        self.task.await.unwrap_or_default()
+    }
+}
+
+pub fn display(logs: &BTreeMap<String, ServerLog>, targets: &[Target]) {
+    if logs.is_empty() {
+        return;
+    }
+
+    println!("\n{}", "📜 Server Logs".bold());
+    for target in targets {
+        let Some(log) = logs.get(&target.name) else {
+            continue;
+        };
+        if log.error_lines == 0 {
+            println!(
+                "  {}: {} lines, no errors",
+                target.label().bold(),
+                log.lines
+            );
+            continue;
+        }
+        println!(
+            "  {}: {} lines, {} error lines {}",
+            target.label().bold(),
+            log.lines,
+            log.error_lines.to_string().yellow(),
+            format!("(see {})", log.path.display()).dimmed()
+        );
+        for line in log.excerpt.iter().take(3) {
+            println!("     {} {}", line.at.dimmed(), line.line);
+        }
+    }
+}
@@ -0,0 +1,585 @@
+use crate::affinity;
+use crate::cache::{self, FsCache};
+use crate::errors::{self, Error, ErrorKind};
+use crate::hooks::HookArgs;
+use crate::interrupt;
+use crate::limits::{self, Cgroup, CpuThrottling, MemoryLimitMode, MemoryPressure};
+use crate::probe::{self, ProbeArgs};
+use crate::targets::{self, Target};
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use colored::Colorize;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+static RUNNING: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ServerArgs {
+    #[arg(
+        long,
+        help = "Start every target before benchmarking and stop them afterwards"
+    )]
+    pub manage_servers: bool,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = Mode::Prod,
+        help = "Benchmark the dev servers or production servers (managed servers are built first if needed)"
+    )]
+    pub mode: Mode,
+    #[arg(
+        long,
+        value_name = "DIR",
+        default_value = ".",
+        requires = "manage_servers",
+        help = "Directory that target directories are relative to"
+    )]
+    pub apps_dir: PathBuf,
+    #[arg(
+        long,
+        requires = "manage_servers",
+        help = "Start the apps on free ports instead of their configured ports"
+    )]
+    pub auto_ports: bool,
+    #[arg(
+        long,
+        value_enum,
+        value_name = "POLICY",
+        requires = "manage_servers",
+        help = "Warm or drop the OS file cache before each build and server start"
+    )]
+    pub fs_cache: Option<FsCache>,
+    #[arg(
+        long,
+        value_name = "MB",
+        requires = "manage_servers",
+        help = "Cap each managed server's memory to see how it degrades when memory is tight"
+    )]
+    pub memory_limit: Option<u64>,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = MemoryLimitMode::Cgroup,
+        requires = "memory_limit",
+        help = "Enforce --memory-limit with a cgroup on the whole process tree or with V8's heap limit"
+    )]
+    pub memory_limit_mode: MemoryLimitMode,
+    #[arg(
+        long,
+        value_name = "CPUS",
+        requires = "manage_servers",
+        help = "Cap each managed server at this many CPUs' worth of time with a cgroup, e.g. 1 for a single vCPU"
+    )]
+    pub cpu_limit: Option<f64>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Dev,
+    Prod,
+}
+
+impl Mode {
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Dev => "dev",
+            Mode::Prod => "prod",
+        }
+    }
+
+    pub fn command(self, target: &Target) -> &str {
+        match self {
+            Mode::Dev => &target.dev,
+            Mode::Prod => &target.start,
+        }
+    }
+}
+
+struct ManagedServer {
+    name: String,
+    target: String,
+    child: Child,
+    log_path: PathBuf,
+    cgroup: Option<Cgroup>,
+}
+
+pub struct ManagedServers {
+    servers: Vec<ManagedServer>,
+    memory_limit: Option<(u64, MemoryLimitMode)>,
+    cpu_limit: Option<f64>,
+}
+
+const LOCAL_HOSTS: [&str; 4] = ["localhost:", "127.0.0.1:", "0.0.0.0:", "[::1]:"];
+
+fn free_port() -> Result<u16> {
+    let listener =
+        std::net::TcpListener::bind(("127.0.0.1", 0)).context("Failed to find a free port")?;
+    Ok(listener.local_addr()?.port())
+}
+
+fn port_in_use(port: u16) -> bool {
+    std::net::TcpStream::connect(("127.0.0.1", port)).is_ok()
+}
+
+fn strip_ansi(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
+
+/// Finds the first local URL a server printed on startup, e.g. vite's
+/// `Local: http://localhost:5173/` or Next.js' `- Local: http://localhost:3001`.
+fn announced_port(log: &str) -> Option<u16> {
+    let log = strip_ansi(log);
+    log.lines().find_map(|line| {
+        LOCAL_HOSTS.iter().find_map(|host| {
+            let (_, rest) = line.split_once(host)?;
+            let digits: String = rest.chars().take_while(char::is_ascii_digit).collect();
+            digits.parse().ok()
+        })
+    })
+}
+
+/// Waits until the server either prints the port it bound or starts accepting
+/// connections on the port it was given.
+async fn discover_port(
+    server: &mut ManagedServer,
+    port: u16,
+    trust_port: bool,
+    timeout: Duration,
+) -> Result<u16> {
+    let started = Instant::now();
+
+    loop {
+        if let Some(status) = server.child.try_wait()? {
+            return Err(Error::new(
+                ErrorKind::ServerFailed,
+                format!("{} server exited with {}", server.name, status),
+            )
+            .target(&server.target)
+            .into());
+        }
+        let log = std::fs::read_to_string(&server.log_path).unwrap_or_default();
+        if let Some(announced) = announced_port(&log) {
+            if announced != port {
+                println!(
+                    "{} {} is listening on port {} instead of {}",
+                    "🔌".dimmed(),
+                    server.name,
+                    announced,
+                    port
+                );
+            }
+            return Ok(announced);
+        }
+        if trust_port && port_in_use(port) {
+            return Ok(port);
+        }
+        if started.elapsed() >= timeout {
+            return Err(Error::new(
+                ErrorKind::ServerFailed,
+                format!(
+                    "{} server did not report a listening port within {}s",
+                    server.name,
+                    timeout.as_secs()
+                ),
+            )
+            .target(&server.target)
+            .into());
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+}
+
+fn spawn(
+    target: &Target,
+    apps_dir: &Path,
+    port: u16,
+    mode: Mode,
+    cpus: &[usize],
+    cgroup: Option<Cgroup>,
+) -> Result<ManagedServer> {
+    let app_dir = apps_dir.join(&target.dir);
+    let command_line = mode.command(target);
+    let mut parts = command_line.split_whitespace();
+    let program = parts
+        .next()
+        .with_context(|| format!("{} has an empty command", target.name))?;
+
+    let log_path = std::env::temp_dir().join(format!(
+        "rari-benchmark-{}-{}.log",
+        target.name,
+        std::process::id()
+    ));
+    let log = File::create(&log_path)
+        .with_context(|| format!("Failed to create {}", log_path.display()))?;
+
+    let mut command = Command::new(program);
+    command
+        .args(parts)
+        .current_dir(&app_dir)
+        .envs(&target.env)
+        .env("PORT", port.to_string())
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // Own process group so the whole pnpm -> node tree can be signalled at once.
+        command.process_group(0);
+    }
+    affinity::apply(&mut command, cpus, target.nice)
+        .with_context(|| format!("{}: failed to set CPU affinity or priority", target.name))?;
+    if let Some(cgroup) = &cgroup {
+        cgroup.apply(&mut command);
+    }
+
+    let child = command.spawn().map_err(|e| {
+        errors::spawn_error(
+            e,
+            format!("Failed to run `{}` in {}", command_line, app_dir.display()),
+        )
+    })?;
+    RUNNING.lock().unwrap().push(child.id());
+
+    println!(
+        "{} Started {} (pid {}), logging to {}",
+        "🚀".dimmed(),
+        target.label(),
+        child.id(),
+        log_path.display()
+    );
+    if !target.env.is_empty() {
+        println!("   {} {}", "Env:".dimmed(), target.describe_env());
+    }
+    if let Some(scheduling) = target.describe_scheduling() {
+        println!("   {} {}", "Scheduling:".dimmed(), scheduling);
+    }
+
+    Ok(ManagedServer {
+        name: target.label().to_string(),
+        target: target.name.clone(),
+        child,
+        log_path,
+        cgroup,
+    })
+}
+
+/// A production server needs a build when its bundle directory is missing;
+/// targets without one are always rebuilt.
+fn needs_build(target: &Target, apps_dir: &Path) -> bool {
+    match &target.bundle_dir {
+        Some(bundle_dir) => !apps_dir.join(&target.dir).join(bundle_dir).exists(),
+        None => true,
+    }
+}
+
+async fn build(target: &Target, apps_dir: &Path) -> Result<()> {
+    let app_dir = apps_dir.join(&target.dir);
+    println!(
+        "{} Building {} for production: {}",
+        "🔨".dimmed(),
+        target.label(),
+        target.build
+    );
+
+    let mut parts = target.build.split_whitespace();
+    let program = parts
+        .next()
+        .with_context(|| format!("{} has an empty build command", target.name))?;
+    let output = tokio::process::Command::new(program)
+        .args(parts)
+        .current_dir(&app_dir)
+        .env("NODE_ENV", "production")
+        .envs(&target.env)
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .map_err(|e| {
+            errors::spawn_error(
+                e,
+                format!("Failed to run `{}` in {}", target.build, app_dir.display()),
+            )
+        })?;
+
+    if !output.status.success() {
+        return Err(Error::new(
+            ErrorKind::BuildFailed,
+            format!(
+                "{} build failed ({}): {}",
+                target.label(),
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        )
+        .target(&target.name)
+        .into());
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn signal_group(pid: u32, signal: libc::c_int) {
+    unsafe {
+        libc::kill(-(pid as libc::pid_t), signal);
+    }
+}
+
+fn kill_registered() {
+    let pids = std::mem::take(&mut *RUNNING.lock().unwrap());
+    for pid in pids {
+        #[cfg(unix)]
+        signal_group(pid, libc::SIGKILL);
+        #[cfg(not(unix))]
+        let _ = pid;
+    }
+}
+
+/// Waits for servers someone else started, and fails with a hint on how to
+/// start the first one that never answers.
+pub async fn require_running(targets: &[Target], probe_args: &ProbeArgs, mode: Mode) -> Result<()> {
+    for target in targets {
+        probe::wait_until_ready(target, &target.base_url(), probe_args, false, || Ok(()))
+            .await
+            .with_context(|| {
+                if target.is_remote() {
+                    format!(
+                        "Check that {} is reachable from this machine",
+                        target.base_url()
+                    )
+                } else {
+                    format!(
+                        "Please start the {} server with: cd {} && {}",
+                        target.label(),
+                        target.dir.display(),
+                        mode.command(target)
+                    )
+                }
+            })?;
+    }
+    Ok(())
+}
+
+/// Starts every target and waits for it to answer, updating each target's
+/// port to the one its server actually listens on. The servers are stopped
+/// when the returned handle is dropped, on panic, or on Ctrl-C.
+#[tracing::instrument(name = "start_servers", skip_all)]
+pub async fn start(
+    args: &ServerArgs,
+    probe_args: &ProbeArgs,
+    hooks: &HookArgs,
+    targets: &mut [Target],
+) -> Result<ManagedServers> {
+    interrupt::on_interrupt("stopping servers", kill_registered);
+
+    let cpus = targets
+        .iter()
+        .map(|target| match &target.cpus {
+            Some(spec) => {
+                affinity::parse_cpus(spec).with_context(|| format!("{}: invalid cpus", target.name))
+            }
+            None => Ok(Vec::new()),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut managed = ManagedServers {
+        servers: Vec::new(),
+        memory_limit: args
+            .memory_limit
+            .map(|limit_mb| (limit_mb, args.memory_limit_mode)),
+        cpu_limit: args.cpu_limit,
+    };
+    if let Some(cpus) = args.cpu_limit
+        && cpus <= 0.0
+    {
+        return Err(Error::new(
+            ErrorKind::InvalidConfig,
+            format!("--cpu-limit must be more than 0, got {}", cpus),
+        )
+        .into());
+    }
+
+    for (target, cpus) in targets.iter_mut().zip(&cpus) {
+        if target.is_remote() {
+            println!(
+                "{} {} is remote ({}), not starting it",
+                "🌐".dimmed(),
+                target.label(),
+                target.base_url()
+            );
+            probe::wait_until_ready(target, &target.base_url(), probe_args, false, || Ok(()))
+                .await?;
+            continue;
+        }
+        let port = if args.auto_ports {
+            free_port()?
+        } else {
+            target.port
+        };
+        // A port that already answers belongs to someone else, so only trust
+        // the server's own announcement in that case.
+        let trust_port = !port_in_use(port);
+        if !trust_port {
+            tracing::warn!(
+                "Port {} is already in use; pass --auto-ports to pick a free one",
+                port
+            );
+        }
+
+        let app_dir = args.apps_dir.join(&target.dir);
+        if args.mode == Mode::Prod && needs_build(target, &args.apps_dir) {
+            cache::prepare(args.fs_cache, &app_dir).await?;
+            hooks.before_build(target, &app_dir).await?;
+            build(target, &args.apps_dir).await?;
+        }
+        cache::prepare(args.fs_cache, &app_dir).await?;
+
+        let cgroup_memory = match managed.memory_limit {
+            Some((limit_mb, MemoryLimitMode::Cgroup)) => Some(limit_mb),
+            Some((limit_mb, MemoryLimitMode::Heap)) => {
+                limits::limit_heap(target, limit_mb);
+                None
+            }
+            None => None,
+        };
+        let cgroup = if cgroup_memory.is_some() || managed.cpu_limit.is_some() {
+            Some(
+                Cgroup::create(&target.name, cgroup_memory, managed.cpu_limit)
+                    .with_context(|| format!("{}: failed to set resource limits", target.name))?,
+            )
+        } else {
+            None
+        };
+        let mut server = spawn(target, &args.apps_dir, port, args.mode, cpus, cgroup)?;
+        let ready = async {
+            let port =
+                discover_port(&mut server, port, trust_port, probe_args.timeout(true)).await?;
+            probe::wait_until_ready(target, &targets::local_url(port), probe_args, true, || {
+                match server.child.try_wait()? {
+                    Some(status) => Err(Error::new(
+                        ErrorKind::ServerFailed,
+                        format!("{} server exited with {}", target.label(), status),
+                    )
+                    .target(&target.name)
+                    .into()),
+                    None => Ok(()),
+                }
+            })
+            .await?;
+            Ok::<_, anyhow::Error>(port)
+        }
+        .await;
+        let ready = ready.with_context(|| {
+            format!(
+                "{} server did not start (see {})",
+                target.label(),
+                server.log_path.display()
+            )
+        });
+        managed.servers.push(server);
+        target.port = ready?;
+    }
+
+    Ok(managed)
+}
+
+impl ManagedServers {
+    /// Each server's target name and the file its output is logged to.
+    pub fn logs(&self) -> Vec<(String, PathBuf)> {
+        self.servers
+            .iter()
+            .map(|server| (server.target.clone(), server.log_path.clone()))
+            .collect()
+    }
+
+    /// Each server's target name and the pid of the process it was started as.
+    pub fn pids(&self) -> BTreeMap<String, u32> {
+        self.servers
+            .iter()
+            .map(|server| (server.target.clone(), server.child.id()))
+            .collect()
+    }
+
+    /// How each server fared under `--memory-limit`; empty without one.
+    pub fn memory_pressure(&mut self) -> BTreeMap<String, MemoryPressure> {
+        let Some((limit_mb, mode)) = self.memory_limit else {
+            return BTreeMap::new();
+        };
+        self.servers
+            .iter_mut()
+            .map(|server| {
+                let cgroup = server.cgroup.as_ref();
+                let pressure = MemoryPressure {
+                    limit_mb,
+                    mode,
+                    oom_kills: cgroup.and_then(Cgroup::oom_kills),
+                    peak_bytes: cgroup.and_then(Cgroup::peak_bytes),
+                    exited: matches!(server.child.try_wait(), Ok(Some(_))),
+                };
+                (server.target.clone(), pressure)
+            })
+            .collect()
+    }
+
+    /// How often each server hit its `--cpu-limit`; empty without one.
+    pub fn cpu_throttling(&self) -> BTreeMap<String, CpuThrottling> {
+        self.servers
+            .iter()
+            .filter_map(|server| {
+                let throttling = server.cgroup.as_ref()?.throttling()?;
+                Some((server.target.clone(), throttling))
+            })
+            .collect()
+    }
+
+    /// Stops the servers explicitly; needed before `std::process::exit`,
+    /// which skips destructors.
+    pub fn stop(&mut self) {
+        for mut server in self.servers.drain(..) {
+            let pid = server.child.id();
+
+            #[cfg(unix)]
+            {
+                signal_group(pid, libc::SIGTERM);
+                let deadline = Instant::now() + Duration::from_secs(5);
+                while Instant::now() < deadline {
+                    if matches!(server.child.try_wait(), Ok(Some(_))) {
+                        break;
+                    }
+                    std::thread::sleep(Duration::from_millis(100));
+                }
+                signal_group(pid, libc::SIGKILL);
+            }
+            #[cfg(not(unix))]
+            let _ = server.child.kill();
+
+            let _ = server.child.wait();
+            RUNNING.lock().unwrap().retain(|running| *running != pid);
+            println!("{} Stopped {} server", "🛑".dimmed(), server.name);
+        }
+    }
+}
+
+impl Drop for ManagedServers {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -0,0 +1,24 @@
+use crate::errors::{Error, ErrorKind};
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+
+/// Path of another benchmark binary built next to the current one.
+pub fn binary(name: &str) -> Result<PathBuf> {
+    let exe = std::env::current_exe().context("Failed to locate the current executable")?;
+    let dir = exe
+        .parent()
+        .context("Current executable has no parent directory")?;
+    let path = dir.join(format!("{}{}", name, std::env::consts::EXE_SUFFIX));
+    if !path.exists() {
+        return Err(Error::new(
+            ErrorKind::ToolMissing,
+            format!(
+                "{} not found next to {}; build all benchmark binaries first",
+                path.display(),
+                exe.display()
+            ),
+        )
+        .into());
+    }
+    Ok(path)
+}
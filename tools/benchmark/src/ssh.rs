@@ -0,0 +1,400 @@
+use crate::containers::{ContainerStats, StatsSampler};
+use crate::errors::{self, Error, ErrorKind};
+use crate::interrupt;
+use crate::probe::{self, ProbeArgs};
+use crate::servers::Mode;
+use crate::targets::Target;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tokio::sync::watch;
+
+/// Host and process groups of the servers started over SSH, for Ctrl-C.
+static RUNNING: Mutex<Option<(String, Vec<u32>)>> = Mutex::new(None);
+
+/// CPU ticks and resident pages per process group.
+type GroupUsage = BTreeMap<u32, (f64, f64)>;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct SshArgs {
+    #[arg(
+        long,
+        value_name = "[USER@]HOST",
+        conflicts_with = "manage_servers",
+        help = "Start every target on this host over SSH and send the load from this machine"
+    )]
+    pub ssh: Option<String>,
+    #[arg(
+        long,
+        value_name = "DIR",
+        default_value = ".",
+        requires = "ssh",
+        help = "Remote directory that target directories are relative to"
+    )]
+    pub ssh_dir: String,
+    #[arg(
+        long,
+        value_name = "HOST",
+        requires = "ssh",
+        help = "Address the load is sent to [default: the --ssh host]"
+    )]
+    pub ssh_address: Option<String>,
+}
+
+pub struct SshEnv {
+    host: String,
+    servers: Vec<(String, u32)>,
+    clock_ticks: f64,
+    page_size: f64,
+}
+
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Options shared by every connection, so they reuse the session opened by
+/// `open_master` when there is one.
+fn ssh_args(host: &str) -> Vec<String> {
+    let control_path = std::env::temp_dir().join(format!("rb-ssh-{}", std::process::id()));
+    vec![
+        "-o".to_string(),
+        "BatchMode=yes".to_string(),
+        "-o".to_string(),
+        format!("ControlPath={}", control_path.display()),
+        host.to_string(),
+    ]
+}
+
+/// Opens a background master connection. Its stdio is detached up front;
+/// otherwise the master would keep the first command's output pipe open.
+async fn open_master(host: &str) -> Result<()> {
+    let status = Command::new("ssh")
+        .args([
+            "-f",
+            "-N",
+            "-o",
+            "ControlMaster=yes",
+            "-o",
+            "ControlPersist=yes",
+        ])
+        .args(ssh_args(host))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await
+        .map_err(|e| errors::spawn_error(e, "Failed to run ssh. Is OpenSSH installed?"))?;
+    if !status.success() {
+        return Err(Error::new(
+            ErrorKind::ServerUnreachable,
+            format!(
+                "Could not connect to {} over SSH ({}); check that `ssh {}` works without a password prompt",
+                host, status, host
+            ),
+        )
+        .into());
+    }
+    Ok(())
+}
+
+async fn remote(host: &str, script: &str) -> Result<String> {
+    let output = Command::new("ssh")
+        .args(ssh_args(host))
+        .arg(script)
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .map_err(|e| errors::spawn_error(e, "Failed to run ssh. Is OpenSSH installed?"))?;
+    if !output.status.success() {
+        return Err(Error::new(
+            ErrorKind::ToolFailed,
+            format!(
+                "`{}` on {} failed ({}): {}",
+                script,
+                host,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        )
+        .into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn stop_blocking(host: &str, groups: &[u32]) {
+    if groups.is_empty() {
+        return;
+    }
+    let script = groups
+        .iter()
+        .map(|group| {
+            format!(
+                "kill -TERM -{group} 2>/dev/null; \
+                 for i in $(seq 50); do kill -0 -{group} 2>/dev/null || break; sleep 0.1; done; \
+                 kill -KILL -{group} 2>/dev/null"
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("; ");
+    let _ = std::process::Command::new("ssh")
+        .args(ssh_args(host))
+        .arg(format!("{}; true", script))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
+/// The address load is sent to: `--ssh-address`, or the `--ssh` host
+/// without its user.
+fn address(args: &SshArgs, host: &str) -> String {
+    args.ssh_address
+        .clone()
+        .unwrap_or_else(|| host.rsplit('@').next().unwrap_or(host).to_string())
+}
+
+/// Builds (in prod mode, when the bundle directory is missing) and starts
+/// every target on the `--ssh` host, then points each target at that host
+/// and waits until it is ready. The servers are stopped when the returned
+/// handle is dropped or on Ctrl-C.
+#[tracing::instrument(name = "ssh_up", skip_all)]
+pub async fn up(
+    args: &SshArgs,
+    mode: Mode,
+    probe_args: &ProbeArgs,
+    targets: &mut [Target],
+) -> Result<SshEnv> {
+    let host = args.ssh.clone().context("--ssh was not given")?;
+    let address = address(args, &host);
+
+    interrupt::on_interrupt("stopping remote servers", || {
+        if let Some((host, groups)) = RUNNING.lock().unwrap().take() {
+            stop_blocking(&host, &groups);
+        }
+    });
+
+    println!("{} Connecting to {} over SSH", "🔐".dimmed(), host);
+    open_master(&host).await?;
+    let config = remote(&host, "getconf CLK_TCK; getconf PAGESIZE").await?;
+    let mut values = config
+        .lines()
+        .filter_map(|line| line.trim().parse::<f64>().ok());
+    let mut env = SshEnv {
+        host: host.clone(),
+        servers: Vec::new(),
+        clock_ticks: values.next().unwrap_or(100.0),
+        page_size: values.next().unwrap_or(4096.0),
+    };
+    *RUNNING.lock().unwrap() = Some((host.clone(), Vec::new()));
+
+    for target in targets.iter_mut() {
+        if target.is_remote() {
+            println!(
+                "{} {} is remote ({}), not starting it",
+                "🌐".dimmed(),
+                target.label(),
+                target.base_url()
+            );
+            continue;
+        }
+
+        let dir = format!(
+            "{}/{}",
+            args.ssh_dir.trim_end_matches('/'),
+            target.dir.display()
+        );
+        let env_vars: String = target
+            .env
+            .iter()
+            .map(|(key, value)| format!(" {}={}", key, quote(value)))
+            .collect();
+
+        if mode == Mode::Prod {
+            let built = match &target.bundle_dir {
+                Some(bundle_dir) => remote(
+                    &host,
+                    &format!(
+                        "test -d {}/{} && echo yes || true",
+                        quote(&dir),
+                        quote(&bundle_dir.display().to_string())
+                    ),
+                )
+                .await?
+                .contains("yes"),
+                None => false,
+            };
+            if !built {
+                println!(
+                    "{} Building {} on {}: {}",
+                    "🔨".dimmed(),
+                    target.label(),
+                    host,
+                    target.build
+                );
+                remote(
+                    &host,
+                    &format!(
+                        "cd {} && env NODE_ENV=production{} {}",
+                        quote(&dir),
+                        env_vars,
+                        target.build
+                    ),
+                )
+                .await
+                .with_context(|| format!("{} build failed", target.label()))?;
+            }
+        }
+
+        let mut scheduling = String::new();
+        if let Some(cpus) = &target.cpus {
+            scheduling.push_str(&format!("taskset -c {} ", quote(cpus)));
+        }
+        if let Some(nice) = target.nice {
+            scheduling.push_str(&format!("nice -n {} ", nice));
+        }
+        let log = format!(
+            "/tmp/rari-benchmark-{}-{}.log",
+            target.name,
+            std::process::id()
+        );
+        // setsid makes the server lead its own process group, so the whole
+        // pnpm -> node tree can be signalled at once.
+        let started = remote(
+            &host,
+            &format!(
+                "cd {} && env PORT={}{} setsid {}{} > {} 2>&1 < /dev/null & echo $!",
+                quote(&dir),
+                target.port,
+                env_vars,
+                scheduling,
+                mode.command(target),
+                log
+            ),
+        )
+        .await?;
+        let group: u32 = started
+            .lines()
+            .last()
+            .and_then(|pid| pid.trim().parse().ok())
+            .with_context(|| format!("Could not start {} on {}", target.label(), host))?;
+        env.servers.push((target.name.clone(), group));
+        if let Some((_, groups)) = RUNNING.lock().unwrap().as_mut() {
+            groups.push(group);
+        }
+        println!(
+            "{} Started {} on {} (pid {}), logging to {}",
+            "🚀".dimmed(),
+            target.label(),
+            host,
+            group,
+            log
+        );
+
+        target.url = Some(format!("http://{}:{}", address, target.port));
+        probe::wait_until_ready(target, &target.base_url(), probe_args, true, || Ok(()))
+            .await
+            .with_context(|| {
+                format!(
+                    "{} server did not start (see {} on {})",
+                    target.label(),
+                    log,
+                    host
+                )
+            })?;
+    }
+
+    Ok(env)
+}
+
+/// Total CPU ticks and resident pages of every process in each group.
+async fn read_groups(host: &str, groups: &[u32]) -> Option<GroupUsage> {
+    let list = groups
+        .iter()
+        .map(|group| group.to_string())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let script = format!(
+        "for g in {}; do for p in $(pgrep -g $g); do echo \"$g $(cut -d' ' -f14,15,24 /proc/$p/stat 2>/dev/null)\"; done; done",
+        list
+    );
+    let output = remote(host, &script).await.ok()?;
+
+    let mut usage = GroupUsage::new();
+    for line in output.lines() {
+        let fields: Vec<f64> = line
+            .split_whitespace()
+            .filter_map(|field| field.parse().ok())
+            .collect();
+        if let [group, user, system, rss] = fields[..] {
+            let entry = usage.entry(group as u32).or_default();
+            entry.0 += user + system;
+            entry.1 += rss;
+        }
+    }
+    Some(usage)
+}
+
+impl SshEnv {
+    /// Samples CPU and memory of every remote server over the SSH connection
+    /// until `StatsSampler::finish`.
+    pub fn sample_stats(&self) -> StatsSampler {
+        let host = self.host.clone();
+        let servers = self.servers.clone();
+        let clock_ticks = self.clock_ticks;
+        let page_size = self.page_size;
+        let (stop, mut stopped) = watch::channel(false);
+        let task = tokio::spawn(async move {
+            let groups: Vec<u32> = servers.iter().map(|(_, group)| *group).collect();
+            let mut stats: BTreeMap<String, ContainerStats> = BTreeMap::new();
+            let mut previous: Option<(Instant, GroupUsage)> = None;
+            loop {
+                if let Some(usage) = read_groups(&host, &groups).await {
+                    let now = Instant::now();
+                    if let Some((then, before)) = &previous {
+                        let elapsed = now.duration_since(*then).as_secs_f64();
+                        for (name, group) in &servers {
+                            let Some((ticks, pages)) = usage.get(group) else {
+                                continue;
+                            };
+                            let ticks_before = before.get(group).map_or(0.0, |usage| usage.0);
+                            let cpu_pct =
+                                100.0 * (ticks - ticks_before).max(0.0) / clock_ticks / elapsed;
+                            stats
+                                .entry(name.clone())
+                                .or_default()
+                                .add(cpu_pct, pages * page_size);
+                        }
+                    }
+                    previous = Some((now, usage));
+                }
+                tokio::select! {
+                    _ = stopped.changed() => break,
+                    _ = tokio::time::sleep(Duration::from_secs(1)) => {}
+                }
+            }
+            stats
+        });
+        StatsSampler { stop, task }
+    }
+}
+
+impl Drop for SshEnv {
+    fn drop(&mut self) {
+        let groups: Vec<u32> = self.servers.iter().map(|(_, group)| *group).collect();
+        stop_blocking(&self.host, &groups);
+        for (name, _) in &self.servers {
+            println!("{} Stopped {} on {}", "🛑".dimmed(), name, self.host);
+        }
+        RUNNING.lock().unwrap().take();
+        let _ = std::process::Command::new("ssh")
+            .args(["-O", "exit"])
+            .args(ssh_args(&self.host))
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+}
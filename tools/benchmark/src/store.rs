@@ -0,0 +1,223 @@
+use crate::metrics::{self, ResultKind};
+use anyhow::{Context, Result};
+use rusqlite::{Connection, params};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+pub struct Store {
+    conn: Connection,
+}
+
+#[derive(Debug, Default)]
+pub struct QueryFilter {
+    pub kind: Option<ResultKind>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub framework: Option<String>,
+    pub scenario: Option<String>,
+    pub metric: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MetricRow {
+    pub timestamp: String,
+    pub kind: String,
+    pub framework: Option<String>,
+    pub scenario: Option<String>,
+    pub metric: String,
+    pub value: f64,
+}
+
+pub fn default_path(results_dir: &Path) -> PathBuf {
+    results_dir.join("benchmarks.db")
+}
+
+fn split_metric(kind: ResultKind, path: &str) -> (Option<String>, Option<String>, String) {
+    let mut segments = path.splitn(3, '.');
+    let first = segments.next().unwrap_or_default();
+    let rest: Vec<&str> = segments.collect();
+
+    // staticAssets, largePayload, conditional, and compression:
+    // <key>.<target>.<payload>.<metric>
+    if kind == ResultKind::Performance
+        && matches!(
+            first,
+            "staticAssets" | "largePayload" | "conditional" | "compression"
+        )
+        && let [target, rest] = rest.as_slice()
+        && let Some((asset, metric)) = rest.split_once('.')
+    {
+        return (
+            Some(target.to_string()),
+            Some(asset.to_string()),
+            metric.to_string(),
+        );
+    }
+
+    // mutation.<target>.<metric>, filed under the scenario it measures
+    if kind == ResultKind::Performance
+        && first == "mutation"
+        && let [target, metric] = rest.as_slice()
+    {
+        return (
+            Some(target.to_string()),
+            Some("Form submission".to_string()),
+            metric.to_string(),
+        );
+    }
+
+    if metrics::NON_TARGET_KEYS.contains(&first) || rest.is_empty() {
+        return (None, None, path.to_string());
+    }
+
+    if kind == ResultKind::Performance && rest.len() == 2 {
+        return (
+            Some(first.to_string()),
+            Some(rest[0].to_string()),
+            rest[1].to_string(),
+        );
+    }
+
+    (Some(first.to_string()), None, rest.join("."))
+}
+
+impl Store {
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open results store {}", path.display()))?;
+
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS runs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                kind TEXT NOT NULL,
+                timestamp TEXT NOT NULL,
+                source TEXT NOT NULL,
+                document TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS metrics (
+                run_id INTEGER NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
+                framework TEXT,
+                scenario TEXT,
+                metric TEXT NOT NULL,
+                value REAL NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS runs_timestamp ON runs(timestamp);
+            CREATE INDEX IF NOT EXISTS metrics_lookup ON metrics(framework, scenario, metric);",
+        )
+        .context("Failed to initialize results store")?;
+
+        Ok(Store { conn })
+    }
+
+    pub fn has_source(&self, source: &Path) -> Result<bool> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM runs WHERE source = ?1",
+            params![source.display().to_string()],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    pub fn record_run(&mut self, kind: ResultKind, source: &Path, document: &Value) -> Result<i64> {
+        let timestamp = document["timestamp"].as_str().unwrap_or_default();
+
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO runs (kind, timestamp, source, document) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                kind.prefix(),
+                timestamp,
+                source.display().to_string(),
+                document.to_string()
+            ],
+        )?;
+        let run_id = tx.last_insert_rowid();
+
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO metrics (run_id, framework, scenario, metric, value)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for (path, value) in metrics::flatten(document) {
+                let (framework, scenario, metric) = split_metric(kind, &path);
+                insert.execute(params![run_id, framework, scenario, metric, value])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(run_id)
+    }
+
+    pub fn query(&self, filter: &QueryFilter) -> Result<Vec<MetricRow>> {
+        let mut sql = String::from(
+            "SELECT r.timestamp, r.kind, m.framework, m.scenario, m.metric, m.value
+             FROM metrics m JOIN runs r ON r.id = m.run_id WHERE 1 = 1",
+        );
+        let mut values: Vec<String> = Vec::new();
+
+        let mut conditions: Vec<(&str, String)> = Vec::new();
+        if let Some(kind) = filter.kind {
+            conditions.push(("r.kind = ?", kind.prefix().to_string()));
+        }
+        if let Some(since) = &filter.since {
+            conditions.push(("r.timestamp >= ?", since.clone()));
+        }
+        if let Some(until) = &filter.until {
+            conditions.push(("substr(r.timestamp, 1, length(?)) <= ?", until.clone()));
+        }
+        if let Some(framework) = &filter.framework {
+            conditions.push(("m.framework = ?", framework.clone()));
+        }
+        if let Some(scenario) = &filter.scenario {
+            conditions.push(("m.scenario LIKE ?", format!("%{}%", scenario)));
+        }
+        if let Some(metric) = &filter.metric {
+            conditions.push(("m.metric LIKE ?", format!("%{}%", metric)));
+        }
+
+        for (clause, value) in conditions {
+            values.push(value);
+            let placeholder = format!("?{}", values.len());
+            sql.push_str(&format!(" AND {}", clause.replace('?', &placeholder)));
+        }
+
+        sql.push_str(" ORDER BY r.timestamp, r.kind, m.framework, m.scenario, m.metric");
+        if let Some(limit) = filter.limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(values.iter()), |row| {
+            Ok(MetricRow {
+                timestamp: row.get(0)?,
+                kind: row.get(1)?,
+                framework: row.get(2)?,
+                scenario: row.get(3)?,
+                metric: row.get(4)?,
+                value: row.get(5)?,
+            })
+        })?;
+
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to query results store")
+    }
+}
+
+#[tracing::instrument(name = "store", skip_all)]
+pub fn record(store_path: Option<&Path>, kind: ResultKind, source: &Path, document: &Value) {
+    let Some(store_path) = store_path else {
+        return;
+    };
+
+    let recorded =
+        Store::open(store_path).and_then(|mut store| store.record_run(kind, source, document));
+    if let Err(e) = recorded {
+        tracing::warn!("Failed to record run in {}: {:#}", store_path.display(), e);
+    }
+}
@@ -0,0 +1,56 @@
+use crate::{output, time_utils};
+use serde_json::{Value, json};
+use std::future::Future;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant, SystemTime};
+
+static STARTED: OnceLock<Instant> = OnceLock::new();
+
+pub fn enable() {
+    let _ = STARTED.set(Instant::now());
+}
+
+pub fn enabled() -> bool {
+    STARTED.get().is_some()
+}
+
+pub fn emit(event: &str, data: Value) {
+    let Some(started) = STARTED.get() else {
+        return;
+    };
+
+    let mut line = json!({
+        "event": event,
+        "timestamp": time_utils::format_timestamp(SystemTime::now()),
+        "elapsedMs": started.elapsed().as_millis() as u64,
+    });
+    if let (Some(line), Value::Object(data)) = (line.as_object_mut(), data) {
+        line.extend(data);
+    }
+
+    output::write_line(&line.to_string());
+}
+
+pub async fn with_progress<F: Future>(
+    future: F,
+    mut progress: impl FnMut(u64) -> Value,
+) -> F::Output {
+    if !enabled() {
+        return future.await;
+    }
+
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+    interval.tick().await;
+    tokio::pin!(future);
+
+    let mut elapsed_secs = 0;
+    loop {
+        tokio::select! {
+            output = &mut future => return output,
+            _ = interval.tick() => {
+                elapsed_secs += 1;
+                emit("progress", progress(elapsed_secs));
+            }
+        }
+    }
+}
@@ -0,0 +1,435 @@
+use crate::adapters::{self, FrameworkAdapter};
+use crate::config;
+use crate::errors::{self, Error, ErrorKind};
+use crate::metrics;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Target {
+    pub name: String,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// Adapter (`rari` or `nextjs`) that fills in the commands, `bundleDir`,
+    /// and `healthPath` the target leaves out.
+    #[serde(default)]
+    pub framework: Option<String>,
+    pub dir: PathBuf,
+    pub port: u16,
+    #[serde(default)]
+    pub start: String,
+    #[serde(default)]
+    pub dev: String,
+    #[serde(default)]
+    pub build: String,
+    #[serde(default)]
+    pub bundle_dir: Option<PathBuf>,
+    /// Path the readiness probe requests unless `--health-path` is given.
+    #[serde(default)]
+    pub health_path: Option<String>,
+    #[serde(default)]
+    pub compose_service: Option<String>,
+    /// Extra environment for the target's build and server commands. A `PORT`
+    /// entry replaces `port`.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// CPUs the managed server is pinned to, e.g. `2-3` (Linux only).
+    #[serde(default)]
+    pub cpus: Option<String>,
+    /// Nice value of the managed server, from -20 (highest priority) to 19.
+    #[serde(default)]
+    pub nice: Option<i32>,
+    /// Base URL of an already running server, e.g. a staging deployment. Such
+    /// a target is never started, built, or looked up on localhost.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Dockerfile the `images` benchmark builds, relative to `dir`.
+    #[serde(default)]
+    pub dockerfile: Option<PathBuf>,
+    /// Prebuilt image the `images` benchmark pulls instead of building one.
+    #[serde(default)]
+    pub image: Option<String>,
+    /// Paths `performance --static-assets` requests, keyed `js`, `css`, or
+    /// `image`. Missing kinds are found from the homepage.
+    #[serde(default)]
+    pub assets: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TargetsFile {
+    targets: Vec<Target>,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct TargetArgs {
+    #[arg(
+        long = "targets",
+        value_name = "FILE",
+        help = "JSON or TOML file listing the frameworks to benchmark [default: rari and Next.js]"
+    )]
+    pub targets_file: Option<PathBuf>,
+    #[arg(
+        long = "port",
+        value_name = "NAME=PORT",
+        help = "Override the port of a target (repeatable)"
+    )]
+    pub ports: Vec<PortOverride>,
+    #[arg(
+        long = "url",
+        value_name = "NAME=URL",
+        help = "Benchmark a target at a remote base URL instead of localhost (repeatable)"
+    )]
+    pub urls: Vec<UrlOverride>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PortOverride {
+    name: String,
+    port: u16,
+}
+
+impl std::fmt::Display for PortOverride {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}={}", self.name, self.port)
+    }
+}
+
+impl FromStr for PortOverride {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, port) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected NAME=PORT, got '{}'", s))?;
+        let port = port
+            .parse()
+            .map_err(|_| format!("invalid port '{}'", port))?;
+        Ok(PortOverride {
+            name: name.to_string(),
+            port,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UrlOverride {
+    name: String,
+    url: String,
+}
+
+impl std::fmt::Display for UrlOverride {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}={}", self.name, self.url)
+    }
+}
+
+impl FromStr for UrlOverride {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, url) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected NAME=URL, got '{}'", s))?;
+        if !is_http_url(url) {
+            return Err(format!(
+                "invalid URL '{}' (expected http:// or https://)",
+                url
+            ));
+        }
+        Ok(UrlOverride {
+            name: name.to_string(),
+            url: url.to_string(),
+        })
+    }
+}
+
+fn is_http_url(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+/// The base URL of a server listening on `port` on this machine.
+pub fn local_url(port: u16) -> String {
+    format!("http://localhost:{}", port)
+}
+
+impl Target {
+    pub fn label(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.name)
+    }
+
+    pub fn display(&self) -> String {
+        match &self.icon {
+            Some(icon) => format!("{} {}", icon, self.label()),
+            None => self.label().to_string(),
+        }
+    }
+
+    /// Base URL without a trailing slash: the remote `url` if set, otherwise
+    /// localhost on `port`.
+    pub fn base_url(&self) -> String {
+        match &self.url {
+            Some(url) => url.trim_end_matches('/').to_string(),
+            None => local_url(self.port),
+        }
+    }
+
+    pub fn is_remote(&self) -> bool {
+        self.url.is_some()
+    }
+
+    pub fn compose_service(&self) -> &str {
+        self.compose_service.as_deref().unwrap_or(&self.name)
+    }
+
+    /// `KEY=value` pairs for logging which environment a target ran with.
+    pub fn describe_env(&self) -> String {
+        self.env
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// `cpus 2-3, nice -5`-style description of the server's scheduling, if set.
+    pub fn describe_scheduling(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if let Some(cpus) = &self.cpus {
+            parts.push(format!("cpus {}", cpus));
+        }
+        if let Some(nice) = self.nice {
+            parts.push(format!("nice {}", nice));
+        }
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+}
+
+pub fn defaults() -> Vec<Target> {
+    adapters::ADAPTERS
+        .iter()
+        .map(|adapter| adapter.target())
+        .collect()
+}
+
+impl Target {
+    pub fn adapter(&self) -> Option<&'static dyn FrameworkAdapter> {
+        adapters::find(self.framework.as_deref()?)
+    }
+
+    /// Fills what the target leaves unset from its framework's adapter, or
+    /// with the default pnpm commands when it names none.
+    fn resolve(&mut self) {
+        let adapter = self.adapter();
+        let commands = [
+            (
+                &mut self.start,
+                adapter.map_or(adapters::DEFAULT_START, |a| a.start_command()),
+            ),
+            (
+                &mut self.dev,
+                adapter.map_or(adapters::DEFAULT_DEV, |a| a.dev_command()),
+            ),
+            (
+                &mut self.build,
+                adapter.map_or(adapters::DEFAULT_BUILD, |a| a.build_command()),
+            ),
+        ];
+        for (command, default) in commands {
+            if command.is_empty() {
+                *command = default.to_string();
+            }
+        }
+        if let Some(adapter) = adapter {
+            if self.bundle_dir.is_none() {
+                self.bundle_dir = adapter.bundle_dir();
+            }
+            if self.health_path.is_none() {
+                self.health_path = Some(adapter.health_path().to_string());
+            }
+        }
+    }
+}
+
+fn validate(targets: &[Target]) -> Result<()> {
+    if targets.len() < 2 {
+        anyhow::bail!("At least two targets are needed for a comparison");
+    }
+
+    for (index, target) in targets.iter().enumerate() {
+        let valid_name = !target.name.is_empty()
+            && target
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid_name {
+            anyhow::bail!(
+                "Invalid target name '{}' (use letters, digits, '-' and '_')",
+                target.name
+            );
+        }
+        if let Some(framework) = &target.framework
+            && adapters::find(framework).is_none()
+        {
+            anyhow::bail!(
+                "{}: unknown framework '{}' (known: {})",
+                target.name,
+                framework,
+                adapters::keys().join(", ")
+            );
+        }
+        if metrics::NON_TARGET_KEYS.contains(&target.name.as_str()) {
+            anyhow::bail!("'{}' is reserved and cannot be a target name", target.name);
+        }
+        if targets[..index].iter().any(|t| t.name == target.name) {
+            anyhow::bail!("Duplicate target name '{}'", target.name);
+        }
+        if let Some(url) = &target.url
+            && !is_http_url(url)
+        {
+            anyhow::bail!(
+                "{}: invalid URL '{}' (expected http:// or https://)",
+                target.name,
+                url
+            );
+        }
+        if let Some(nice) = target.nice
+            && !(-20..=19).contains(&nice)
+        {
+            anyhow::bail!(
+                "{}: nice must be between -20 and 19, got {}",
+                target.name,
+                nice
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Loads the targets file (or the built-in rari and Next.js targets) and
+/// applies `--port` overrides. The first target is the one every other
+/// target is compared against.
+pub fn load(args: &TargetArgs) -> Result<Vec<Target>> {
+    read(args).map_err(|e| errors::classify(e, ErrorKind::InvalidConfig))
+}
+
+fn read(args: &TargetArgs) -> Result<Vec<Target>> {
+    let mut targets = match &args.targets_file {
+        Some(path) => config::read_file::<TargetsFile>(path)?.targets,
+        None => defaults(),
+    };
+    validate(&targets)?;
+
+    for target in &mut targets {
+        target.resolve();
+        if let Some(port) = target.env.remove("PORT") {
+            target.port = port
+                .parse()
+                .with_context(|| format!("{}: invalid PORT '{}'", target.name, port))?;
+        }
+    }
+
+    for PortOverride { name, port } in &args.ports {
+        let target = targets
+            .iter_mut()
+            .find(|target| &target.name == name)
+            .with_context(|| format!("--port: unknown target '{}'", name))?;
+        target.port = *port;
+    }
+
+    for UrlOverride { name, url } in &args.urls {
+        let target = targets
+            .iter_mut()
+            .find(|target| &target.name == name)
+            .with_context(|| format!("--url: unknown target '{}'", name))?;
+        target.url = Some(url.clone());
+    }
+
+    Ok(targets)
+}
+
+/// Drops remote targets for benchmarks that need the app on disk, such as
+/// builds.
+pub fn local(targets: Vec<Target>) -> Result<Vec<Target>> {
+    let (remote, local): (Vec<Target>, Vec<Target>) =
+        targets.into_iter().partition(Target::is_remote);
+    for target in &remote {
+        println!(
+            "{} Skipping {}: it is remote ({})",
+            "🌐".dimmed(),
+            target.label(),
+            target.base_url()
+        );
+    }
+    if local.len() < 2 {
+        return Err(Error::new(
+            ErrorKind::InvalidConfig,
+            "At least two local targets are needed for a comparison",
+        )
+        .into());
+    }
+    Ok(local)
+}
+
+pub fn title(targets: &[Target]) -> String {
+    targets
+        .iter()
+        .map(Target::label)
+        .collect::<Vec<_>>()
+        .join(" vs ")
+}
+
+/// Environment overrides of the targets that have any, keyed by target name.
+pub fn env(targets: &[Target]) -> BTreeMap<String, BTreeMap<String, String>> {
+    targets
+        .iter()
+        .filter(|target| !target.env.is_empty())
+        .map(|target| (target.name.clone(), target.env.clone()))
+        .collect()
+}
+
+/// Base URLs of the remote targets, keyed by target name.
+pub fn remote_urls(targets: &[Target]) -> BTreeMap<String, String> {
+    targets
+        .iter()
+        .filter(|target| target.is_remote())
+        .map(|target| (target.name.clone(), target.base_url()))
+        .collect()
+}
+
+/// Scheduling settings of the targets that have any, keyed by target name.
+pub fn scheduling(targets: &[Target]) -> BTreeMap<String, String> {
+    targets
+        .iter()
+        .filter_map(|target| Some((target.name.clone(), target.describe_scheduling()?)))
+        .collect()
+}
+
+pub fn names(targets: &[Target]) -> Vec<String> {
+    targets.iter().map(|target| target.name.clone()).collect()
+}
+
+/// `"🦀 rari:"`-style headings padded to a common width, one per target.
+pub fn headings(targets: &[Target]) -> Vec<String> {
+    // Icons are emoji, which terminals draw two columns wide.
+    let width = |target: &Target| {
+        target.label().chars().count() + 1 + target.icon.as_ref().map_or(0, |_| 3)
+    };
+    let widest = targets.iter().map(width).max().unwrap_or_default() + 2;
+    targets
+        .iter()
+        .map(|target| {
+            format!(
+                "{}:{}",
+                target.display(),
+                " ".repeat(widest - width(target))
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,44 @@
+const SPARK_BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+pub fn sparkline(values: &[Option<f64>]) -> String {
+    let present: Vec<f64> = values.iter().flatten().copied().collect();
+    if present.is_empty() {
+        return " ".repeat(values.len());
+    }
+
+    let min = present.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = present.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let range = max - min;
+
+    values
+        .iter()
+        .map(|value| match value {
+            Some(v) if range > 0.0 => {
+                let level = ((v - min) / range * (SPARK_BLOCKS.len() - 1) as f64).round();
+                SPARK_BLOCKS[level as usize]
+            }
+            Some(_) => SPARK_BLOCKS[SPARK_BLOCKS.len() / 2],
+            None => ' ',
+        })
+        .collect()
+}
+
+pub fn histogram(buckets: &[(String, u64)], width: usize) -> Vec<String> {
+    let max_count = buckets.iter().map(|(_, count)| *count).max().unwrap_or(0);
+    if max_count == 0 {
+        return Vec::new();
+    }
+
+    buckets
+        .iter()
+        .map(|(label, count)| {
+            let bar_len = (*count as f64 / max_count as f64 * width as f64).round() as usize;
+            let bar = if *count > 0 && bar_len == 0 {
+                "▏".to_string()
+            } else {
+                "█".repeat(bar_len)
+            };
+            format!("{:>10} │{:<width$} {}", label, bar, count, width = width)
+        })
+        .collect()
+}
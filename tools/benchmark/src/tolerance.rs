@@ -0,0 +1,25 @@
+use colored::{ColoredString, Colorize};
+
+pub use rari_benchmark_results::tolerance::{
+    DEFAULT_TIE_PCT, Outcome, TIE, best, classify, difference_pct,
+};
+
+/// `text` in the colour of `outcome`: green when the subject is better.
+pub fn paint(outcome: Outcome, text: &str) -> ColoredString {
+    match outcome {
+        Outcome::SubjectBetter => text.green(),
+        Outcome::Tie => text.yellow(),
+        Outcome::OtherBetter => text.red(),
+    }
+}
+pub fn tie_line(diff: f64, tie_pct: f64, other: &str) -> String {
+    format!(
+        "  {} {}",
+        "🤝".yellow(),
+        format!(
+            "Within ±{}% of {} ({:+.1}%), effectively a tie",
+            tie_pct, other, diff
+        )
+        .yellow()
+    )
+}
@@ -0,0 +1,393 @@
+use anyhow::Result;
+use clap::Parser;
+use colored::Colorize;
+use metrics::{ResultFile, ResultKind};
+use rari_benchmark::{errors, logging, metrics, term_charts, units};
+use rari_benchmark_results::change_points;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use tabled::{Table, Tabled};
+use tokio::fs;
+
+#[derive(Parser, Debug)]
+#[command(name = "trends")]
+#[command(about = "Show how benchmark metrics drift across saved results")]
+struct Args {
+    #[arg(long, default_value = "results")]
+    results_dir: PathBuf,
+    #[arg(short, long, value_enum)]
+    kind: Option<ResultKind>,
+    #[arg(short, long)]
+    metric: Option<String>,
+    #[arg(
+        long,
+        value_name = "MODE",
+        help = "Only include results recorded in this mode (dev or prod)"
+    )]
+    mode: Option<String>,
+    #[arg(
+        long,
+        value_name = "PCT",
+        default_value_t = 5.0,
+        help = "Smallest shift in a metric's level, in percent, reported as a change point"
+    )]
+    min_shift: f64,
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    #[command(flatten)]
+    log: logging::LogArgs,
+}
+
+#[derive(Debug, Serialize)]
+struct TrendReport {
+    sources: Vec<PathBuf>,
+    /// When each run was recorded, so that two runs on one day keep a column
+    /// each.
+    timestamps: Vec<String>,
+    series: BTreeMap<String, BTreeMap<String, Vec<Option<f64>>>>,
+    changes: Vec<Change>,
+}
+
+/// A run after which a metric stayed at a different level.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Change {
+    kind: String,
+    metric: String,
+    date: String,
+    timestamp: String,
+    path: PathBuf,
+    git_sha: Option<String>,
+    /// The run before, so `git log previousSha..gitSha` lists the suspects.
+    previous_sha: Option<String>,
+    before: f64,
+    after: f64,
+    change_pct: f64,
+    regression: bool,
+}
+
+#[derive(Tabled)]
+struct TrendRow {
+    #[tabled(rename = "Metric")]
+    metric: String,
+    #[tabled(rename = "Trend")]
+    trend: String,
+    #[tabled(rename = "Runs")]
+    runs: usize,
+    #[tabled(rename = "First")]
+    first: String,
+    #[tabled(rename = "Latest")]
+    latest: String,
+    #[tabled(rename = "Change")]
+    change: String,
+}
+
+fn included(metric: &str, metric_filter: Option<&str>) -> bool {
+    !metrics::is_config_metric(metric) && metric_filter.is_none_or(|filter| metric.contains(filter))
+}
+
+fn build_report(
+    results: &[ResultFile],
+    metric_filter: Option<&str>,
+    min_shift: f64,
+) -> TrendReport {
+    let timestamps: Vec<String> = results
+        .iter()
+        .map(|r| r.timestamp.clone())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    let mut series: BTreeMap<String, BTreeMap<String, Vec<Option<f64>>>> = BTreeMap::new();
+
+    for result in results {
+        let index = timestamps.binary_search(&result.timestamp).unwrap();
+        let kind_series = series.entry(result.kind.prefix().to_string()).or_default();
+
+        for (metric, value) in &result.metrics {
+            if !included(metric, metric_filter) {
+                continue;
+            }
+            kind_series
+                .entry(metric.clone())
+                .or_insert_with(|| vec![None; timestamps.len()])[index] = Some(*value);
+        }
+    }
+
+    TrendReport {
+        sources: results.iter().map(|r| r.path.clone()).collect(),
+        timestamps,
+        series,
+        changes: find_changes(results, metric_filter, min_shift),
+    }
+}
+
+/// Change points in every metric's history, run by run rather than by date,
+/// so two runs on one day are told apart.
+fn find_changes(
+    results: &[ResultFile],
+    metric_filter: Option<&str>,
+    min_shift: f64,
+) -> Vec<Change> {
+    let mut changes = Vec::new();
+    for kind in ResultKind::ALL {
+        let runs: Vec<&ResultFile> = results.iter().filter(|r| r.kind == kind).collect();
+        let names: BTreeSet<&String> = runs
+            .iter()
+            .flat_map(|r| r.metrics.keys())
+            .filter(|metric| included(metric, metric_filter))
+            .collect();
+
+        for metric in names {
+            let history: Vec<(&ResultFile, f64)> = runs
+                .iter()
+                .filter_map(|r| Some((*r, *r.metrics.get(metric)?)))
+                .collect();
+            let values: Vec<f64> = history.iter().map(|(_, value)| *value).collect();
+            if values.iter().all(|v| *v == 0.0) {
+                continue;
+            }
+
+            for change in change_points::detect(&values, min_shift) {
+                let (run, _) = history[change.index];
+                let (previous, _) = history[change.index - 1];
+                changes.push(Change {
+                    kind: kind.prefix().to_string(),
+                    metric: metric.clone(),
+                    date: run.date.clone(),
+                    timestamp: run.timestamp.clone(),
+                    path: run.path.clone(),
+                    git_sha: run.git_sha.clone(),
+                    previous_sha: previous.git_sha.clone(),
+                    before: change.before,
+                    after: change.after,
+                    change_pct: change.change_pct,
+                    regression: if metrics::lower_is_better(metric) {
+                        change.change_pct > 0.0
+                    } else {
+                        change.change_pct < 0.0
+                    },
+                });
+            }
+        }
+    }
+    changes
+}
+
+fn format_value(metric: &str, value: f64) -> String {
+    if let Some(unit) = units::Unit::for_metric(metric) {
+        unit.format(value)
+    } else if value.abs() >= 1000.0 {
+        format!("{:.0}", value)
+    } else {
+        format!("{:.2}", value)
+    }
+}
+
+fn display_trends(report: &TrendReport) {
+    for kind in ResultKind::ALL {
+        let Some(kind_series) = report.series.get(kind.prefix()) else {
+            continue;
+        };
+
+        let kind_dates: Vec<&str> = report
+            .timestamps
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| kind_series.values().any(|values| values[*i].is_some()))
+            .map(|(_, timestamp)| timestamp.get(..10).unwrap_or(timestamp))
+            .collect();
+
+        println!(
+            "\n{} {} ({} runs, {} → {})",
+            "📈".bold(),
+            kind.title().bold(),
+            kind_dates.len(),
+            kind_dates.first().copied().unwrap_or("?"),
+            kind_dates.last().copied().unwrap_or("?")
+        );
+
+        let mut rows = Vec::new();
+        for (metric, values) in kind_series {
+            let present: Vec<f64> = values.iter().flatten().copied().collect();
+            if present.iter().all(|v| *v == 0.0) {
+                continue;
+            }
+
+            let first = present[0];
+            let latest = present[present.len() - 1];
+            let change = if first != 0.0 {
+                let pct = ((latest - first) / first) * 100.0;
+                let worse = if metrics::lower_is_better(metric) {
+                    pct > 0.0
+                } else {
+                    pct < 0.0
+                };
+                let marker = if pct.abs() < 0.05 {
+                    ""
+                } else if worse {
+                    " (worse)"
+                } else {
+                    " (better)"
+                };
+                format!("{:+.1}%{}", pct, marker)
+            } else {
+                "n/a".to_string()
+            };
+
+            rows.push(TrendRow {
+                metric: metric.clone(),
+                trend: term_charts::sparkline(values),
+                runs: present.len(),
+                first: format_value(metric, first),
+                latest: format_value(metric, latest),
+                change,
+            });
+        }
+
+        if rows.is_empty() {
+            println!("  {}", "No varying metrics recorded".dimmed());
+        } else {
+            println!("{}", Table::new(rows));
+        }
+
+        display_changes(
+            report
+                .changes
+                .iter()
+                .filter(|change| change.kind == kind.prefix()),
+        );
+    }
+}
+
+fn short_sha(sha: Option<&String>) -> &str {
+    sha.map_or("?", |sha| sha.get(..7).unwrap_or(sha))
+}
+
+/// Change points grouped by the run that brought them.
+fn display_changes<'a>(changes: impl Iterator<Item = &'a Change>) {
+    let mut by_run: BTreeMap<(&str, &Path), Vec<&Change>> = BTreeMap::new();
+    for change in changes {
+        by_run
+            .entry((change.timestamp.as_str(), change.path.as_path()))
+            .or_default()
+            .push(change);
+    }
+    if by_run.is_empty() {
+        return;
+    }
+
+    println!("\n  {}", "🔀 Change points".bold());
+    for ((_, path), changes) in &by_run {
+        let first = changes[0];
+        println!(
+            "  {} {}..{} {}",
+            first.date,
+            short_sha(first.previous_sha.as_ref()),
+            short_sha(first.git_sha.as_ref()),
+            format!("({})", path.display()).dimmed()
+        );
+        for change in changes {
+            let line = format!(
+                "{} → {} ({:+.1}%)",
+                format_value(&change.metric, change.before),
+                format_value(&change.metric, change.after),
+                change.change_pct
+            );
+            if change.regression {
+                println!("    {} {}  {}", "▲".red(), change.metric, line.red());
+            } else {
+                println!("    {} {}  {}", "▼".green(), change.metric, line.green());
+            }
+        }
+    }
+    println!(
+        "  {}",
+        "List the commits behind a shift with git log <before>..<after>".dimmed()
+    );
+}
+
+async fn export_report(report: &TrendReport, output: &Path) -> Result<()> {
+    let is_csv = output.extension().is_some_and(|ext| ext == "csv");
+
+    let contents = if is_csv {
+        let mut csv = format!("kind,metric,{}\n", report.timestamps.join(","));
+        for (kind, kind_series) in &report.series {
+            for (metric, values) in kind_series {
+                let cells: Vec<String> = values
+                    .iter()
+                    .map(|v| v.map_or(String::new(), |v| v.to_string()))
+                    .collect();
+                csv.push_str(&format!(
+                    "{},\"{}\",{}\n",
+                    kind,
+                    metric.replace('"', "\"\""),
+                    cells.join(",")
+                ));
+            }
+        }
+        csv
+    } else {
+        format!("{}\n", serde_json::to_string_pretty(report)?)
+    };
+
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    fs::write(output, contents).await?;
+
+    println!("\n{} Trends saved to {}", "💾".dimmed(), output.display());
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run().await {
+        errors::exit(e);
+    }
+}
+
+async fn run() -> Result<()> {
+    let args = Args::parse();
+    logging::init(&args.log, 0)?;
+
+    println!("{}", "📈 rari vs Next.js Benchmark Trends".cyan().bold());
+
+    let results: Vec<ResultFile> = metrics::scan_results(&args.results_dir)
+        .await?
+        .into_iter()
+        .filter(|r| args.kind.is_none_or(|kind| r.kind == kind))
+        .filter(|r| args.mode.is_none() || r.mode == args.mode)
+        .collect();
+
+    if results.is_empty() {
+        println!(
+            "\n{} No results found in {}",
+            "❌".red(),
+            args.results_dir.display()
+        );
+        return Ok(());
+    }
+
+    let mut modes: Vec<&str> = results.iter().filter_map(|r| r.mode.as_deref()).collect();
+    modes.sort();
+    modes.dedup();
+    if modes.len() > 1 {
+        tracing::warn!(
+            "Results mix {} modes; pass --mode to look at one of them",
+            modes.join(" and ")
+        );
+    }
+
+    let report = build_report(&results, args.metric.as_deref(), args.min_shift);
+    display_trends(&report);
+
+    let output = args
+        .output
+        .unwrap_or_else(|| args.results_dir.join("trends.json"));
+    export_report(&report, &output).await?;
+
+    Ok(())
+}
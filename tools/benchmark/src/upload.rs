@@ -0,0 +1,140 @@
+use crate::archive;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tokio::process::Command;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Provider {
+    S3,
+    Gcs,
+}
+
+#[derive(Debug, Clone)]
+pub struct Destination {
+    provider: Provider,
+    bucket: String,
+    prefix: String,
+}
+
+impl FromStr for Destination {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (provider, rest) = if let Some(rest) = s.strip_prefix("s3://") {
+            (Provider::S3, rest)
+        } else if let Some(rest) = s.strip_prefix("gs://") {
+            (Provider::Gcs, rest)
+        } else {
+            return Err(format!(
+                "unsupported upload destination '{}' (expected s3://bucket/prefix or gs://bucket/prefix)",
+                s
+            ));
+        };
+
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        if bucket.is_empty() {
+            return Err(format!("missing bucket name in '{}'", s));
+        }
+
+        Ok(Destination {
+            provider,
+            bucket: bucket.to_string(),
+            prefix: prefix.trim_matches('/').to_string(),
+        })
+    }
+}
+
+impl Destination {
+    fn scheme(&self) -> &'static str {
+        match self.provider {
+            Provider::S3 => "s3",
+            Provider::Gcs => "gs",
+        }
+    }
+
+    fn uri_for(&self, date: &str, file_name: &str) -> String {
+        let date_path = date.replace('-', "/");
+        if self.prefix.is_empty() {
+            format!(
+                "{}://{}/{}/{}",
+                self.scheme(),
+                self.bucket,
+                date_path,
+                file_name
+            )
+        } else {
+            format!(
+                "{}://{}/{}/{}/{}",
+                self.scheme(),
+                self.bucket,
+                self.prefix,
+                date_path,
+                file_name
+            )
+        }
+    }
+}
+
+async fn artifact_files(results_path: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = vec![results_path.to_path_buf()];
+    files.extend(archive::artifacts(results_path).await?);
+    files.sort();
+    Ok(files)
+}
+
+async fn copy(destination: &Destination, file: &Path, uri: &str) -> Result<()> {
+    let mut command = match destination.provider {
+        Provider::S3 => {
+            let mut command = Command::new("aws");
+            command.args(["s3", "cp", "--only-show-errors"]);
+            command
+        }
+        Provider::Gcs => {
+            let mut command = Command::new("gcloud");
+            command.args(["storage", "cp", "--quiet"]);
+            command
+        }
+    };
+
+    let output =
+        command
+            .arg(file)
+            .arg(uri)
+            .output()
+            .await
+            .with_context(|| match destination.provider {
+                Provider::S3 => "Failed to run the aws CLI (is it installed?)",
+                Provider::Gcs => "Failed to run the gcloud CLI (is it installed?)",
+            })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Upload of {} failed: {}", file.display(), stderr.trim());
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument(name = "upload", skip_all)]
+pub async fn upload(destination: &Destination, results_path: &Path, date: &str) -> Result<String> {
+    let files = artifact_files(results_path).await?;
+    let mut results_uri = String::new();
+
+    for file in &files {
+        let file_name = file
+            .file_name()
+            .and_then(|n| n.to_str())
+            .context("Invalid artifact file name")?;
+        let uri = destination.uri_for(date, file_name);
+        copy(destination, file, &uri).await?;
+        println!("{} Uploaded {}", "☁️".dimmed(), uri);
+
+        if file == results_path {
+            results_uri = uri;
+        }
+    }
+
+    Ok(results_uri)
+}
@@ -0,0 +1,121 @@
+use crate::targets::Target;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+use tokio::fs;
+use tokio::process::Command;
+
+/// `node --version` of the Node.js on `PATH`, e.g. `v22.12.0`.
+pub async fn node() -> Option<String> {
+    let output = Command::new("node").arg("--version").output().await.ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!version.is_empty()).then_some(version)
+}
+
+async fn read_json(path: &Path) -> Option<Value> {
+    let contents = fs::read_to_string(path).await.ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// Resolved versions of an importer's dependencies in a pnpm lockfile, with
+/// peer suffixes such as `(react@19.2.7)` removed.
+fn pnpm_lock_versions(lockfile: &str, importer: &str) -> BTreeMap<String, String> {
+    let mut versions = BTreeMap::new();
+    let lines = lockfile
+        .lines()
+        .skip_while(|line| *line != "importers:")
+        .skip_while(|line| line.trim_end() != format!("  {}:", importer))
+        .skip(1);
+    let mut package = None;
+    for line in lines {
+        let indent = line.len() - line.trim_start().len();
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        match indent {
+            0..=2 => break,
+            6 => package = Some(line.trim_end_matches(':').trim_matches('\'').to_string()),
+            8 => {
+                if let (Some(name), Some(version)) = (&package, line.strip_prefix("version: ")) {
+                    let version = version.trim_matches('\'');
+                    let version = version.split('(').next().unwrap_or(version);
+                    if !version.starts_with("link:") {
+                        versions.insert(name.clone(), version.to_string());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    versions
+}
+
+/// Versions of the runtime dependencies (`dependencies` in package.json) of
+/// every local target, e.g. `rari`, `next` and `react`. Each version comes from
+/// the installed package, then the pnpm lockfile, then the package.json
+/// specifier when that is a plain version.
+pub async fn packages(
+    apps_dir: &Path,
+    targets: &[Target],
+) -> BTreeMap<String, BTreeMap<String, String>> {
+    let lockfile = fs::read_to_string(apps_dir.join("pnpm-lock.yaml"))
+        .await
+        .unwrap_or_default();
+
+    let mut all = BTreeMap::new();
+    for target in targets.iter().filter(|target| !target.is_remote()) {
+        let app_dir = apps_dir.join(&target.dir);
+        let Some(manifest) = read_json(&app_dir.join("package.json")).await else {
+            continue;
+        };
+        let Some(dependencies) = manifest["dependencies"].as_object() else {
+            continue;
+        };
+        let importer = target.dir.to_string_lossy().replace('\\', "/");
+        let locked = pnpm_lock_versions(&lockfile, importer.trim_end_matches('/'));
+
+        let mut versions = BTreeMap::new();
+        for (name, specifier) in dependencies {
+            let installed =
+                read_json(&app_dir.join("node_modules").join(name).join("package.json"))
+                    .await
+                    .and_then(|package| package["version"].as_str().map(str::to_string));
+            let declared = specifier.as_str().filter(|specifier| {
+                specifier.starts_with(|c: char| c.is_ascii_digit() || c == '^' || c == '~')
+            });
+            if let Some(version) = installed
+                .or_else(|| locked.get(name).cloned())
+                .or_else(|| declared.map(str::to_string))
+            {
+                versions.insert(name.clone(), version);
+            }
+        }
+        if !versions.is_empty() {
+            all.insert(target.name.clone(), versions);
+        }
+    }
+    all
+}
+
+/// Each target's framework and version, e.g. `next 15.1.0`, from the
+/// dependency versions [`packages`] found for it.
+pub fn frameworks(
+    targets: &[Target],
+    packages: &BTreeMap<String, BTreeMap<String, String>>,
+) -> BTreeMap<String, String> {
+    targets
+        .iter()
+        .filter_map(|target| {
+            let adapter = target.adapter()?;
+            let version = adapter.version(packages.get(&target.name)?)?;
+            Some((
+                target.name.clone(),
+                format!("{} {}", adapter.package(), version),
+            ))
+        })
+        .collect()
+}
@@ -0,0 +1,106 @@
+use crate::{stats, tolerance};
+use serde::{Deserialize, Serialize};
+
+/// How many standard errors apart the means on either side of a change
+/// point must be, about a 1% chance for a shift that is only noise.
+pub const MIN_T: f64 = 3.0;
+
+/// Runs on each side of a change point, the fewest that have a spread.
+const MIN_RUNS: usize = 2;
+
+/// Where the level of a series shifted.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangePoint {
+    /// The first value after the shift.
+    pub index: usize,
+    /// Mean of the values since the previous change point.
+    pub before: f64,
+    /// Mean of the values until the next change point.
+    pub after: f64,
+    pub change_pct: f64,
+}
+
+/// The points where the mean of `values` shifted by at least
+/// `min_shift_pct` percent and by at least [`MIN_T`] standard errors, in
+/// order, found by binary segmentation.
+pub fn detect(values: &[f64], min_shift_pct: f64) -> Vec<ChangePoint> {
+    let mut found = Vec::new();
+    segment(values, 0, min_shift_pct, &mut found);
+    found.sort_by_key(|change| change.index);
+
+    // Each split was measured against everything on its sides; report the
+    // levels between neighbouring change points instead.
+    let bounds: Vec<usize> = std::iter::once(0)
+        .chain(found.iter().map(|change| change.index))
+        .chain(std::iter::once(values.len()))
+        .collect();
+    for (change, bounds) in found.iter_mut().zip(bounds.windows(3)) {
+        change.before = stats::mean(&values[bounds[0]..bounds[1]]).unwrap_or(change.before);
+        change.after = stats::mean(&values[bounds[1]..bounds[2]]).unwrap_or(change.after);
+        change.change_pct = tolerance::difference_pct(change.after, change.before);
+    }
+    found
+}
+
+/// Splits `values`, which start at `offset` in the whole series, at its most
+/// significant shift, then each side of it in turn.
+fn segment(values: &[f64], offset: usize, min_shift_pct: f64, found: &mut Vec<ChangePoint>) {
+    let Some((index, t)) = best_split(values) else {
+        return;
+    };
+    let (before, after) = values.split_at(index);
+    let (Some(before_mean), Some(after_mean)) = (stats::mean(before), stats::mean(after)) else {
+        return;
+    };
+    let change_pct = tolerance::difference_pct(after_mean, before_mean);
+    if t < MIN_T || change_pct.abs() < min_shift_pct {
+        return;
+    }
+    found.push(ChangePoint {
+        index: offset + index,
+        before: before_mean,
+        after: after_mean,
+        change_pct,
+    });
+    segment(before, offset, min_shift_pct, found);
+    segment(after, offset + index, min_shift_pct, found);
+}
+
+/// The split of `values` whose sides' means are the most standard errors
+/// apart, and how many.
+fn best_split(values: &[f64]) -> Option<(usize, f64)> {
+    (MIN_RUNS..=values.len().saturating_sub(MIN_RUNS))
+        .map(|index| {
+            let (before, after) = values.split_at(index);
+            (index, welch_t(before, after))
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+/// Welch's t for the difference between the means of `a` and `b`, infinite
+/// when neither varies but they differ.
+fn welch_t(a: &[f64], b: &[f64]) -> f64 {
+    let (Some(mean_a), Some(mean_b)) = (stats::mean(a), stats::mean(b)) else {
+        return 0.0;
+    };
+    let difference = (mean_a - mean_b).abs();
+    let standard_error =
+        (sample_variance(a) / a.len() as f64 + sample_variance(b) / b.len() as f64).sqrt();
+    if standard_error == 0.0 {
+        if difference == 0.0 {
+            0.0
+        } else {
+            f64::INFINITY
+        }
+    } else {
+        difference / standard_error
+    }
+}
+
+/// Bessel-corrected, since a handful of runs is a sample of the runs there
+/// could have been.
+fn sample_variance(values: &[f64]) -> f64 {
+    let n = values.len() as f64;
+    stats::variance(values).map_or(0.0, |variance| variance * n / (n - 1.0))
+}
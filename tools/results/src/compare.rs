@@ -0,0 +1,112 @@
+use crate::{metrics, units};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Change {
+    Regression,
+    Improvement,
+    Unchanged,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricDelta {
+    pub metric: String,
+    pub baseline: Option<f64>,
+    pub candidate: Option<f64>,
+    pub change_pct: Option<f64>,
+    pub change: Change,
+}
+
+/// Which metrics to compare, and how far one has to move to count.
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Smallest change, in percent, that is a regression or an improvement.
+    pub threshold: f64,
+    /// Only metrics whose name contains this.
+    pub metric: Option<String>,
+    /// Include configuration, summary, and all-zero metrics.
+    pub all: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            threshold: 1.0,
+            metric: None,
+            all: false,
+        }
+    }
+}
+
+/// Every metric of either flattened document (see [`metrics::flatten`]) with
+/// how it changed from `baseline` to `candidate`, sorted by name.
+pub fn deltas(
+    baseline: &BTreeMap<String, f64>,
+    candidate: &BTreeMap<String, f64>,
+    options: &Options,
+) -> Vec<MetricDelta> {
+    let mut names: Vec<&String> = baseline.keys().chain(candidate.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    names
+        .into_iter()
+        .filter(|name| options.all || !metrics::is_config_metric(name))
+        .filter(|name| {
+            options
+                .metric
+                .as_ref()
+                .is_none_or(|m| name.contains(m.as_str()))
+        })
+        .filter_map(|name| {
+            let before = baseline.get(name).copied();
+            let after = candidate.get(name).copied();
+
+            if !options.all && before.unwrap_or(0.0) == 0.0 && after.unwrap_or(0.0) == 0.0 {
+                return None;
+            }
+
+            let change_pct = match (before, after) {
+                (Some(b), Some(a)) if b != 0.0 => Some(((a - b) / b) * 100.0),
+                _ => None,
+            };
+
+            let change = match change_pct {
+                Some(pct) if pct.abs() >= options.threshold => {
+                    let worse = if metrics::lower_is_better(name) {
+                        pct > 0.0
+                    } else {
+                        pct < 0.0
+                    };
+                    if worse {
+                        Change::Regression
+                    } else {
+                        Change::Improvement
+                    }
+                }
+                _ => Change::Unchanged,
+            };
+
+            Some(MetricDelta {
+                metric: name.clone(),
+                baseline: before,
+                candidate: after,
+                change_pct,
+                change,
+            })
+        })
+        .collect()
+}
+
+/// A metric's value in its unit, or plain when it has none.
+pub fn format_value(metric: &str, value: Option<f64>) -> String {
+    match value {
+        Some(v) if let Some(unit) = units::Unit::for_metric(metric) => unit.format(v),
+        Some(v) if v.abs() >= 1000.0 => format!("{:.0}", v),
+        Some(v) => format!("{:.2}", v),
+        None => "—".to_string(),
+    }
+}
@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+
+/// How large a Cliff's delta is, by Romano et al.'s thresholds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Magnitude {
+    Negligible,
+    Small,
+    Medium,
+    Large,
+}
+
+impl Magnitude {
+    pub fn of(cliffs_delta: f64) -> Self {
+        match cliffs_delta.abs() {
+            d if d < 0.147 => Magnitude::Negligible,
+            d if d < 0.33 => Magnitude::Small,
+            d if d < 0.474 => Magnitude::Medium,
+            _ => Magnitude::Large,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Magnitude::Negligible => "negligible",
+            Magnitude::Small => "small",
+            Magnitude::Medium => "medium",
+            Magnitude::Large => "large",
+        }
+    }
+}
+
+/// How two samples' whole distributions differ, beyond their means.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Distribution {
+    /// The Kolmogorov–Smirnov statistic, the largest gap between the two
+    /// empirical CDFs: 0 for identical samples, 1 for ones that don't overlap.
+    pub ks: f64,
+    /// The asymptotic chance of a gap at least that large between samples
+    /// from the same distribution.
+    pub ks_p_value: f64,
+    /// Cliff's delta, P(subject > other) − P(subject < other): -1 when every
+    /// subject value is below every other value, 1 when above.
+    pub cliffs_delta: f64,
+    pub magnitude: Magnitude,
+}
+
+impl Distribution {
+    /// Compares `subject` with `other`, or `None` if either is empty.
+    pub fn compare(subject: &[f64], other: &[f64]) -> Option<Self> {
+        if subject.is_empty() || other.is_empty() {
+            return None;
+        }
+        let mut subject = subject.to_vec();
+        let mut other = other.to_vec();
+        subject.sort_by(f64::total_cmp);
+        other.sort_by(f64::total_cmp);
+        let ks = ks_statistic(&subject, &other);
+        let cliffs_delta = cliffs_delta(&subject, &other);
+        Some(Distribution {
+            ks,
+            ks_p_value: ks_p_value(ks, subject.len(), other.len()),
+            cliffs_delta,
+            magnitude: Magnitude::of(cliffs_delta),
+        })
+    }
+
+    /// Whether the distributions differ at the 5% level.
+    pub fn differs(&self) -> bool {
+        self.ks_p_value < 0.05
+    }
+}
+
+/// The largest gap between the empirical CDFs of two sorted samples.
+fn ks_statistic(a: &[f64], b: &[f64]) -> f64 {
+    let (mut i, mut j) = (0, 0);
+    let mut largest: f64 = 0.0;
+    while i < a.len() && j < b.len() {
+        // Step past every copy of the smaller value on both sides, so ties
+        // move the two CDFs together.
+        let value = a[i].min(b[j]);
+        while i < a.len() && a[i] <= value {
+            i += 1;
+        }
+        while j < b.len() && b[j] <= value {
+            j += 1;
+        }
+        largest = largest.max((i as f64 / a.len() as f64 - j as f64 / b.len() as f64).abs());
+    }
+    largest
+}
+
+/// The Kolmogorov distribution's tail at `d` for samples of `n` and `m`,
+/// with Stephens' correction for small samples.
+fn ks_p_value(d: f64, n: usize, m: usize) -> f64 {
+    let effective = (n * m) as f64 / (n + m) as f64;
+    let lambda = (effective.sqrt() + 0.12 + 0.11 / effective.sqrt()) * d;
+    if lambda < 0.2 {
+        return 1.0;
+    }
+    let sum: f64 = (1..=100)
+        .map(|k| {
+            let k = k as f64;
+            let sign = if k as u64 % 2 == 1 { 1.0 } else { -1.0 };
+            sign * (-2.0 * k * k * lambda * lambda).exp()
+        })
+        .sum();
+    (2.0 * sum).clamp(0.0, 1.0)
+}
+
+/// Cliff's delta of two sorted samples, in O(n log m).
+fn cliffs_delta(subject: &[f64], other: &[f64]) -> f64 {
+    let dominance: i64 = subject
+        .iter()
+        .map(|&value| {
+            let below = other.partition_point(|&o| o < value);
+            let above = other.len() - other.partition_point(|&o| o <= value);
+            below as i64 - above as i64
+        })
+        .sum();
+    dominance as f64 / (subject.len() * other.len()) as f64
+}
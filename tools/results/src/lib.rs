@@ -0,0 +1,20 @@
+//! The format of benchmark results and how they compare, with nothing but
+//! serde underneath, so anything that reads results (the benchmark binaries,
+//! or the dashboard built for `wasm32-unknown-unknown`) parses and diffs
+//! them with the same code.
+//!
+//! A results file's contents become comparable metrics with
+//! [`schema::parse`] and [`metrics::flatten`], and two of those become
+//! regressions and improvements with [`compare::deltas`]. A metric's values
+//! over many runs become the runs where it shifted with
+//! [`change_points::detect`]. Two runs' times become how their whole
+//! distributions differ with [`distribution::Distribution::compare`].
+
+pub mod change_points;
+pub mod compare;
+pub mod distribution;
+pub mod metrics;
+pub mod schema;
+pub mod stats;
+pub mod tolerance;
+pub mod units;
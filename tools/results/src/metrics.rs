@@ -0,0 +1,178 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Top-level keys of a results document that are not target names.
+pub const NON_TARGET_KEYS: [&str; 22] = [
+    "compression",
+    "conditional",
+    "config",
+    "containers",
+    "cpuThrottling",
+    "errors",
+    "footprint",
+    "largePayload",
+    "memoryPressure",
+    "metadata",
+    "mutation",
+    "overhead",
+    "partial",
+    "profiles",
+    "quality",
+    "serverLogs",
+    "serverStats",
+    "staticAssets",
+    "summary",
+    "targets",
+    "timestamp",
+    "version",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum ResultKind {
+    #[cfg_attr(feature = "clap", value(name = "load"))]
+    LoadTest,
+    #[cfg_attr(feature = "clap", value(name = "perf"))]
+    Performance,
+    #[cfg_attr(feature = "clap", value(name = "build"))]
+    BuildTimes,
+    #[cfg_attr(feature = "clap", value(name = "images"))]
+    Images,
+    #[cfg_attr(feature = "clap", value(name = "browser"))]
+    Browser,
+}
+
+impl ResultKind {
+    pub const ALL: [ResultKind; 5] = [
+        ResultKind::LoadTest,
+        ResultKind::Performance,
+        ResultKind::BuildTimes,
+        ResultKind::Images,
+        ResultKind::Browser,
+    ];
+
+    pub fn prefix(self) -> &'static str {
+        match self {
+            ResultKind::LoadTest => "loadtest",
+            ResultKind::Performance => "performance",
+            ResultKind::BuildTimes => "buildtimes",
+            ResultKind::Images => "images",
+            ResultKind::Browser => "browser",
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            ResultKind::LoadTest => "Load Test",
+            ResultKind::Performance => "Performance",
+            ResultKind::BuildTimes => "Build Times",
+            ResultKind::Images => "Container Images",
+            ResultKind::Browser => "Browser Lab Metrics",
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?;
+        if !name.ends_with(".json") {
+            return None;
+        }
+        Self::ALL
+            .into_iter()
+            .find(|kind| name.starts_with(&format!("{}-", kind.prefix())))
+    }
+
+    pub fn from_json(json: &Value) -> Option<Self> {
+        let subject = &json[target_names(json).first()?.as_str()];
+        if subject["requests"].is_object() {
+            Some(ResultKind::LoadTest)
+        } else if subject["duration_ms"].is_number() {
+            Some(ResultKind::BuildTimes)
+        } else if subject["coldStart"].is_object() {
+            Some(ResultKind::Images)
+        } else if json["summary"]["pageLoads"].is_number() {
+            Some(ResultKind::Browser)
+        } else if json["summary"]["testRequests"].is_number() {
+            Some(ResultKind::Performance)
+        } else {
+            None
+        }
+    }
+}
+
+/// Target names of a results document in benchmark order. Documents written
+/// before targets were configurable only have `rari` and `nextjs`.
+pub fn target_names(json: &Value) -> Vec<String> {
+    if let Some(names) = json["targets"].as_array() {
+        return names
+            .iter()
+            .filter_map(|name| name.as_str().map(str::to_string))
+            .collect();
+    }
+
+    let mut names: Vec<String> = json
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(key, value)| value.is_object() && !NON_TARGET_KEYS.contains(&key.as_str()))
+        .map(|(key, _)| key.clone())
+        .collect();
+    if let Some(rari) = names.iter().position(|name| name == "rari") {
+        let rari = names.remove(rari);
+        names.insert(0, rari);
+    }
+    names
+}
+
+pub fn flatten(value: &Value) -> BTreeMap<String, f64> {
+    let mut metrics = BTreeMap::new();
+    flatten_into("", value, &mut metrics);
+    // The schema version is not a measurement.
+    metrics.remove("version");
+    metrics
+}
+
+fn flatten_into(prefix: &str, value: &Value, metrics: &mut BTreeMap<String, f64>) {
+    match value {
+        Value::Number(number) => {
+            if let Some(n) = number.as_f64() {
+                metrics.insert(prefix.to_string(), n);
+            }
+        }
+        Value::Object(map) => {
+            for (key, child) in map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten_into(&path, child, metrics);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn is_config_metric(metric: &str) -> bool {
+    metric.starts_with("config.") || metric.starts_with("summary.")
+}
+
+pub fn lower_is_better(metric: &str) -> bool {
+    let leaf = metric.rsplit('.').next().unwrap_or(metric);
+    !(metric.contains(".requests.")
+        || metric.contains(".throughput.")
+        || matches!(
+            leaf,
+            "successRate"
+                | "requestsPerCpuSecond"
+                | "maxSustainableRps"
+                | "breakingRps"
+                | "pagesPerSec"
+                | "bytesPerSec"
+                | "score"
+                | "notModified"
+                | "ratio"
+                | "samples"
+                | "satisfied"
+        ))
+}
@@ -0,0 +1,90 @@
+use crate::metrics;
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// Version of the results documents this build writes, saved as `version`.
+/// Bump it, and add a step to [`MIGRATIONS`], whenever a change would keep an
+/// older document from reading into the current types.
+pub const VERSION: u32 = 3;
+
+/// Documents written before `version` was recorded.
+const UNVERSIONED: u32 = 1;
+
+/// `MIGRATIONS[n]` upgrades a version `n + 1` document to version `n + 2`.
+const MIGRATIONS: [fn(&mut Value); (VERSION - UNVERSIONED) as usize] =
+    [add_target_list, drop_placeholder_spread];
+
+/// The version a document was written with.
+pub fn version(json: &Value) -> u32 {
+    json["version"]
+        .as_u64()
+        .map_or(UNVERSIONED, |version| version as u32)
+}
+
+/// Upgrades a results document of any earlier version to [`VERSION`] in
+/// place, and returns the version it was written with.
+pub fn migrate(json: &mut Value) -> Result<u32> {
+    let found = version(json);
+    if !json.is_object() {
+        anyhow::bail!("not a results document");
+    }
+    if found > VERSION {
+        anyhow::bail!(
+            "written by a newer rari-benchmark (results version {}; this build reads up to {})",
+            found,
+            VERSION
+        );
+    }
+    for step in &MIGRATIONS[(found.max(UNVERSIONED) - UNVERSIONED) as usize..] {
+        step(json);
+    }
+    json["version"] = VERSION.into();
+    Ok(found)
+}
+
+/// 1 → 2: documents from before targets were configurable have no `targets`
+/// list, only the `rari` and `nextjs` keys.
+fn add_target_list(json: &mut Value) {
+    if !json["targets"].is_array() {
+        json["targets"] = metrics::target_names(json).into();
+    }
+}
+
+/// 2 → 3: the load test wrote zeros for the spread of requests and
+/// throughput and for the latency's standard deviation, before it read them
+/// from oha's per-request records.
+fn drop_placeholder_spread(json: &mut Value) {
+    for target in metrics::target_names(json) {
+        let Some(result) = json
+            .get_mut(&target)
+            .filter(|result| result["requests"].is_object())
+        else {
+            continue;
+        };
+        for (group, keys) in [
+            ("requests", &["stddev", "min", "max"][..]),
+            ("latency", &["stddev"][..]),
+            ("throughput", &["stddev", "min", "max"][..]),
+        ] {
+            if let Some(stats) = result[group].as_object_mut() {
+                for key in keys {
+                    stats.remove(*key);
+                }
+            }
+        }
+    }
+}
+
+/// Parses the contents of a results file of any version as a current document.
+pub fn parse(contents: &str) -> Result<Value> {
+    let mut json: Value = serde_json::from_str(contents)?;
+    migrate(&mut json)?;
+    Ok(json)
+}
+
+/// Parses the contents of a results file of any version into the current
+/// results type.
+pub fn parse_as<T: DeserializeOwned>(contents: &str) -> Result<T> {
+    Ok(serde_json::from_value(parse(contents)?)?)
+}
@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+
+/// The value at `p` (0–1) of already sorted samples, without interpolation.
+pub fn percentile(sorted_data: &[f64], p: f64) -> f64 {
+    let index = (p * (sorted_data.len() - 1) as f64) as usize;
+    sorted_data[index]
+}
+
+/// `None` for no samples.
+pub fn min(samples: &[f64]) -> Option<f64> {
+    samples.iter().copied().reduce(f64::min)
+}
+
+/// `None` for no samples.
+pub fn max(samples: &[f64]) -> Option<f64> {
+    samples.iter().copied().reduce(f64::max)
+}
+
+/// `None` for no samples.
+pub fn mean(samples: &[f64]) -> Option<f64> {
+    if samples.is_empty() {
+        return None;
+    }
+    Some(samples.iter().sum::<f64>() / samples.len() as f64)
+}
+
+/// The middle sample, or the mean of the two middle ones; `None` for no samples.
+pub fn median(samples: &[f64]) -> Option<f64> {
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let middle = sorted.len() / 2;
+    match sorted.len() {
+        0 => None,
+        len if len % 2 == 0 => Some((sorted[middle - 1] + sorted[middle]) / 2.0),
+        _ => Some(sorted[middle]),
+    }
+}
+
+/// Population variance, or `None` with fewer than two samples.
+pub fn variance(samples: &[f64]) -> Option<f64> {
+    if samples.len() < 2 {
+        return None;
+    }
+    let mean = mean(samples)?;
+    Some(samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / samples.len() as f64)
+}
+
+/// Population standard deviation, or `None` with fewer than two samples.
+pub fn stddev(samples: &[f64]) -> Option<f64> {
+    variance(samples).map(f64::sqrt)
+}
+
+/// The mean of ratios, for comparing values on different scales such as the
+/// response times of several pages. `None` for no samples or any that is not
+/// positive.
+pub fn geometric_mean(samples: &[f64]) -> Option<f64> {
+    if samples.is_empty() || samples.iter().any(|&x| x <= 0.0) {
+        return None;
+    }
+    Some((samples.iter().map(|x| x.ln()).sum::<f64>() / samples.len() as f64).exp())
+}
+
+/// Latency of a handful of requests, in milliseconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Latency {
+    pub min: f64,
+    pub avg: f64,
+    pub p50: f64,
+    pub p95: f64,
+    pub max: f64,
+}
+
+impl Latency {
+    /// `None` for no times.
+    pub fn from_times(times: &[f64]) -> Option<Self> {
+        let mut sorted = times.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        Some(Latency {
+            min: *sorted.first()?,
+            avg: mean(&sorted)?,
+            p50: percentile(&sorted, 0.50),
+            p95: percentile(&sorted, 0.95),
+            max: *sorted.last()?,
+        })
+    }
+}
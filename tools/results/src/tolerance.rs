@@ -0,0 +1,63 @@
+pub const DEFAULT_TIE_PCT: f64 = 5.0;
+pub const TIE: &str = "🤝 Tie";
+
+/// Outcome of comparing the subject (the first target) against another target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    SubjectBetter,
+    Tie,
+    OtherBetter,
+}
+
+impl Outcome {
+    pub fn winner<'a>(self, subject: &'a str, other: &'a str) -> &'a str {
+        match self {
+            Outcome::SubjectBetter => subject,
+            Outcome::Tie => TIE,
+            Outcome::OtherBetter => other,
+        }
+    }
+}
+
+pub fn difference_pct(subject: f64, other: f64) -> f64 {
+    if other == 0.0 {
+        0.0
+    } else {
+        ((subject - other) / other) * 100.0
+    }
+}
+
+pub fn classify(subject: f64, other: f64, lower_is_better: bool, tie_pct: f64) -> Outcome {
+    let diff = difference_pct(subject, other);
+    if subject == other || diff.abs() <= tie_pct {
+        Outcome::Tie
+    } else if (diff < 0.0) == lower_is_better {
+        Outcome::SubjectBetter
+    } else {
+        Outcome::OtherBetter
+    }
+}
+
+/// Index of the best value, or `None` when the runner-up is within the tie band.
+pub fn best(values: &[f64], lower_is_better: bool, tie_pct: f64) -> Option<usize> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&a, &b| {
+        let ordering = values[a].total_cmp(&values[b]);
+        if lower_is_better {
+            ordering
+        } else {
+            ordering.reverse()
+        }
+    });
+
+    let (&first, rest) = order.split_first()?;
+    match rest.first() {
+        Some(&second)
+            if classify(values[first], values[second], lower_is_better, tie_pct)
+                == Outcome::Tie =>
+        {
+            None
+        }
+        _ => Some(first),
+    }
+}
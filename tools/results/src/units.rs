@@ -0,0 +1,229 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::{Add, Sub};
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Milliseconds,
+    Bytes,
+    BytesPerSec,
+    RequestsPerSec,
+    Count,
+}
+
+impl Unit {
+    pub fn format(self, value: f64) -> String {
+        match self {
+            Unit::Milliseconds => duration_ms(value),
+            Unit::Bytes => bytes(value),
+            Unit::BytesPerSec => format!("{}/s", bytes(value)),
+            Unit::RequestsPerSec => format!("{:.2} req/s", value),
+            Unit::Count => format!("{:.0}", value),
+        }
+    }
+
+    pub fn for_metric(metric: &str) -> Option<Self> {
+        let leaf = metric.rsplit('.').next().unwrap_or(metric);
+        if metric.contains(".lighthouse.") {
+            match leaf {
+                "score" => Some(Unit::Count),
+                "cumulativeLayoutShift" => None,
+                _ => Some(Unit::Milliseconds),
+            }
+        } else if matches!(
+            leaf,
+            "duration_ms"
+                | "buildMs"
+                | "pullMs"
+                | "addedMs"
+                | "clientSetupMs"
+                | "timerResolutionMs"
+                | "serializationMs"
+        ) || metric.contains(".latency.")
+            || [
+                ".ttfb.",
+                ".fcp.",
+                ".lcp.",
+                ".tti.",
+                ".hydration.",
+                ".softNav.",
+                ".loadTime.",
+                ".submit.",
+                ".render.",
+                ".roundTrip.",
+                ".ci.",
+            ]
+            .iter()
+            .any(|timing| metric.contains(timing))
+        {
+            Some(Unit::Milliseconds)
+        } else if metric.contains(".requests.") {
+            Some(if leaf == "total" {
+                Unit::Count
+            } else {
+                Unit::RequestsPerSec
+            })
+        } else if leaf == "pagesPerSec" {
+            Some(Unit::RequestsPerSec)
+        } else if leaf == "bytesPerSec" || metric.contains(".throughput.") {
+            Some(Unit::BytesPerSec)
+        } else if matches!(
+            leaf,
+            "avgSize"
+                | "bodyBytes"
+                | "bundle_bytes"
+                | "sizeBytes"
+                | "avgMemoryBytes"
+                | "peakMemoryBytes"
+                | "read_bytes"
+                | "written_bytes"
+        ) {
+            Some(Unit::Bytes)
+        } else if matches!(
+            leaf,
+            "errors"
+                | "timeouts"
+                | "chunk_count"
+                | "layers"
+                | "notModified"
+                | "redirected"
+                | "samples"
+                | "samplesNeeded"
+                | "outliers"
+                | "satisfied"
+                | "tolerating"
+                | "frustrated"
+        ) {
+            Some(Unit::Count)
+        } else if (metric.split('.').count() == 3
+            || metric.starts_with("staticAssets.")
+            || metric.starts_with("largePayload."))
+            && matches!(leaf, "min" | "max" | "avg" | "p50" | "p95" | "p99")
+        {
+            Some(Unit::Milliseconds)
+        } else {
+            None
+        }
+    }
+}
+
+/// Writes whole numbers without a fraction, as results store counts and
+/// round times.
+pub fn serialize_float_as_int_if_whole<S>(value: &f64, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    if value.fract() == 0.0 {
+        serializer.serialize_i64(*value as i64)
+    } else {
+        serializer.serialize_f64(*value)
+    }
+}
+
+pub fn duration_ms(ms: f64) -> String {
+    let magnitude = ms.abs();
+    if magnitude >= 1000.0 {
+        format!("{:.2}s", ms / 1000.0)
+    } else if magnitude >= 1.0 || ms == 0.0 {
+        format!("{:.2}ms", ms)
+    } else {
+        format!("{:.0}µs", ms * 1000.0)
+    }
+}
+
+/// In decimal units, as the labels say: 1 kB is 1000 bytes.
+pub fn bytes(bytes: f64) -> String {
+    let magnitude = bytes.abs();
+    if magnitude >= 1e6 {
+        format!("{:.2} MB", bytes / 1e6)
+    } else if magnitude >= 1e3 {
+        format!("{:.2} kB", bytes / 1e3)
+    } else {
+        format!("{:.0} B", bytes)
+    }
+}
+
+/// A duration in milliseconds, the unit every results file stores times in.
+/// Serializes as the bare number, whole numbers without a fraction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd, Deserialize)]
+#[serde(transparent)]
+pub struct Millis(pub f64);
+
+impl Millis {
+    pub fn from_secs(secs: f64) -> Self {
+        Millis(secs * 1000.0)
+    }
+
+    pub fn from_duration(duration: Duration) -> Self {
+        Millis::from_secs(duration.as_secs_f64())
+    }
+
+    /// The time since `start`. Not on `wasm32-unknown-unknown`, which has no
+    /// clock.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn since(start: Instant) -> Self {
+        Millis::from_duration(start.elapsed())
+    }
+
+    pub fn as_secs(self) -> f64 {
+        self.0 / 1000.0
+    }
+
+    pub fn as_duration(self) -> Duration {
+        Duration::from_secs_f64(self.as_secs().max(0.0))
+    }
+
+    pub fn total_cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl Serialize for Millis {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_float_as_int_if_whole(&self.0, serializer)
+    }
+}
+
+impl fmt::Display for Millis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&duration_ms(self.0))
+    }
+}
+
+/// A size in bytes, never kB: results store sizes in bytes and only
+/// formatting scales them.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct Bytes(pub u64);
+
+impl Bytes {
+    pub fn as_f64(self) -> f64 {
+        self.0 as f64
+    }
+}
+
+impl fmt::Display for Bytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&bytes(self.as_f64()))
+    }
+}
+
+impl Add for Bytes {
+    type Output = Bytes;
+    fn add(self, other: Bytes) -> Bytes {
+        Bytes(self.0 + other.0)
+    }
+}
+
+/// Saturating, since a counter read twice can go backwards.
+impl Sub for Bytes {
+    type Output = Bytes;
+    fn sub(self, other: Bytes) -> Bytes {
+        Bytes(self.0.saturating_sub(other.0))
+    }
+}